@@ -243,7 +243,7 @@ impl<'a> GameAppState<'a> {
                         RenderTarget::Window(event.window),
                         WindowProperties {
                             size: UVec2::ZERO,
-                            scale_factor: 1.0,
+                            scale_factor: event.scale_factor,
                             state: window.clone(),
                         },
                     );
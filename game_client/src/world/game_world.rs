@@ -1,5 +1,7 @@
 use std::time::{Duration, Instant};
 
+use ahash::HashSet;
+
 use game_common::components::actions::ActionId;
 use game_common::components::Transform;
 use game_common::entity::EntityId;
@@ -26,6 +28,20 @@ use super::RemoteError;
 const DRIFT_RESYNC_DURATION: Duration = Duration::from_secs(1);
 
 /// The client-side simulation state of the game world.
+///
+/// Snapshots from the server are rendered with a delay of
+/// [`interpolation_frames`](crate::config::Network::interpolation_frames), so remote entities
+/// always move between states that the server has already confirmed.
+///
+/// Entities controlled by the local player are instead marked as
+/// [locally-predicted](Self::set_predicted). Inputs for these entities are applied locally as
+/// soon as they are sent and are not delayed. Every frame the predicted state is reconciled
+/// with the server: it is rebuilt from the newest server snapshot and all inputs that the server
+/// has not acknowledged yet are replayed on top of it. If the server disagrees with a prediction,
+/// e.g. because a movement was rejected, the entity is corrected to the authoritative state
+/// once the snapshot containing the input is rendered and the input is no longer replayed.
+///
+/// The host entity is marked as locally-predicted when it is spawned.
 #[derive(Debug)]
 pub struct GameWorld {
     conn: ServerConnection,
@@ -40,6 +56,10 @@ pub struct GameWorld {
     newest_state: WorldState,
     /// The newest state from the server with locally predicted inputs applied.
     predicted_state: WorldState,
+    /// Entities whose inputs are predicted locally.
+    predicted_entities: HashSet<EntityId>,
+    /// Whether client-side prediction is enabled.
+    prediction: bool,
 
     interval: Interval,
     server_tick_rate: ServerTickRate,
@@ -63,6 +83,8 @@ impl GameWorld {
             physics_pipeline: game_physics::Pipeline::new(),
             event_queue: EventQueue::new(),
             predicted_state: WorldState::new(),
+            predicted_entities: HashSet::default(),
+            prediction: config.network.prediction,
             interval: Interval::new(Duration::from_secs(1) / config.timestep),
             server_tick_rate: ServerTickRate::new(config.timestep),
             statistics: Statistics::default(),
@@ -143,6 +165,10 @@ impl GameWorld {
         self.next_frame_counter.update();
         self.conn.set_cf(self.game_tick.current_control_frame);
 
+        self.statistics.snapshot_delay = self
+            .snapshot_frame()
+            .map(|cf| frame_difference(server_cf, cf));
+
         self.statistics.rtt = self.conn.rtt();
         self.statistics.input_buffer_len = self.conn.input_buffer.len();
 
@@ -153,6 +179,32 @@ impl GameWorld {
         &self.statistics
     }
 
+    /// Returns the server [`ControlFrame`] of the snapshot that is currently being rendered.
+    ///
+    /// Returns `None` if no snapshot has been processed yet, i.e. while the interpolation buffer
+    /// is still being filled.
+    pub fn snapshot_frame(&self) -> Option<ControlFrame> {
+        self.predicted_state.snapshot_frame()
+    }
+
+    /// Marks the entity as locally-predicted.
+    ///
+    /// Inputs for a locally-predicted entity are applied immediately and reconciled against
+    /// server snapshots. Inputs for all other entities only take effect once they are confirmed
+    /// by the server. Has no effect if client-side prediction is disabled.
+    pub fn set_predicted(&mut self, entity: EntityId, predicted: bool) {
+        if predicted {
+            self.predicted_entities.insert(entity);
+        } else {
+            self.predicted_entities.remove(&entity);
+        }
+    }
+
+    /// Returns `true` if the entity is locally-predicted.
+    pub fn is_predicted(&self, entity: EntityId) -> bool {
+        self.prediction && self.predicted_entities.contains(&entity)
+    }
+
     fn process_frame(&mut self, cf: ControlFrame, cmd_buffer: &mut CommandBuffer) {
         let _span = trace_span!("GameWorld::process_frame").entered();
 
@@ -168,6 +220,7 @@ impl GameWorld {
                         };
 
                         self.newest_state.world.despawn(id);
+                        self.predicted_entities.remove(&id);
                     }
                     DataMessageBody::SpawnHost(msg) => {
                         let Some(id) = self.server_entities.get(msg.entity) else {
@@ -175,6 +228,7 @@ impl GameWorld {
                             continue;
                         };
 
+                        self.predicted_entities.insert(id);
                         cmd_buffer.push(Command::SpawnHost(id));
                     }
                     DataMessageBody::EntityComponentAdd(msg) => {
@@ -257,6 +311,7 @@ impl GameWorld {
             }
         }
 
+        self.newest_state.control_frame = Some(cf);
         self.apply_predicted_inputs(cf);
     }
 
@@ -270,23 +325,41 @@ impl GameWorld {
         self.predicted_state = self.newest_state.clone();
 
         for msg in self.conn.input_buffer.iter() {
+            let entity = match &msg.body {
+                DataMessageBody::EntityTranslate(msg) => msg.entity,
+                DataMessageBody::EntityRotate(msg) => msg.entity,
+                DataMessageBody::EntityAction(msg) => msg.entity,
+                _ => {
+                    // Should never be sent from the client.
+                    if cfg!(debug_assertions) {
+                        unreachable!();
+                    }
+
+                    continue;
+                }
+            };
+
+            // Inputs for all other entities only take effect once the
+            // server confirms them.
+            let id = self.server_entities.get(entity).unwrap();
+            if !self.is_predicted(id) {
+                continue;
+            }
+
             match &msg.body {
                 DataMessageBody::EntityTranslate(msg) => {
-                    let id = self.server_entities.get(msg.entity).unwrap();
                     let mut transform: Transform =
                         self.predicted_state.world.get_typed(id).unwrap();
                     transform.translation = msg.translation;
                     self.predicted_state.world.insert_typed(id, transform);
                 }
                 DataMessageBody::EntityRotate(msg) => {
-                    let id = self.server_entities.get(msg.entity).unwrap();
                     let mut transform: Transform =
                         self.predicted_state.world.get_typed(id).unwrap();
                     transform.rotation = msg.rotation;
                     self.predicted_state.world.insert_typed(id, transform);
                 }
                 DataMessageBody::EntityAction(msg) => {
-                    let id = self.server_entities.get(msg.entity).unwrap();
                     self.event_queue.push(Event::Action(ActionEvent {
                         entity: id,
                         invoker: id,
@@ -294,12 +367,7 @@ impl GameWorld {
                         data: msg.bytes.clone(),
                     }));
                 }
-                _ => {
-                    // Should never be sent from the client.
-                    if cfg!(debug_assertions) {
-                        unreachable!();
-                    }
-                }
+                _ => unreachable!(),
             }
         }
     }
@@ -429,12 +497,23 @@ fn compute_compensation(server_tick_rate: &ServerTickRate, drift: i32) -> Durati
     catchup_time.checked_div(ups).unwrap_or_default()
 }
 
+/// Returns the number of control frames from `rhs` to `lhs`.
+///
+/// Control frames wrap around, so the shortest distance between both frames is returned.
+fn frame_difference(lhs: ControlFrame, rhs: ControlFrame) -> i32 {
+    i32::from((lhs - rhs).0 as i16)
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Statistics {
     pub ups: UpdateCounter,
     pub input_buffer_len: usize,
     pub rtt: Duration,
     pub drift: i32,
+    /// Number of control frames the rendered snapshot is behind the predicted server frame.
+    ///
+    /// Negative if the rendered snapshot is ahead of the predicted server frame.
+    pub snapshot_delay: Option<i32>,
 }
 
 #[cfg(test)]
@@ -442,7 +521,9 @@ mod tests {
 
     use std::time::Duration;
 
-    use super::{compute_compensation, ServerTickRate};
+    use game_common::world::control_frame::ControlFrame;
+
+    use super::{compute_compensation, frame_difference, ServerTickRate};
 
     #[test]
     fn test_compute_compensation() {
@@ -452,4 +533,20 @@ mod tests {
         let output = compute_compensation(&tick_rate, drift);
         assert_eq!(output, Duration::from_millis(2));
     }
+
+    #[test]
+    fn frame_difference_wraps() {
+        assert_eq!(frame_difference(ControlFrame(10), ControlFrame(4)), 6);
+        assert_eq!(frame_difference(ControlFrame(4), ControlFrame(10)), -6);
+
+        assert_eq!(
+            frame_difference(ControlFrame(2), ControlFrame(u16::MAX - 1)),
+            4
+        );
+        assert_eq!(
+            frame_difference(ControlFrame(u16::MAX - 1), ControlFrame(2)),
+            -4
+        );
+        assert_eq!(frame_difference(ControlFrame(0), ControlFrame(u16::MAX)), 1);
+    }
 }
@@ -1,14 +1,26 @@
+use game_common::world::control_frame::ControlFrame;
 use game_common::world::World;
 
 #[derive(Clone, Debug, Default)]
 pub struct WorldState {
     pub world: World,
+    /// The server control frame of the newest snapshot applied to this state.
+    pub(super) control_frame: Option<ControlFrame>,
 }
 
 impl WorldState {
     pub fn new() -> Self {
         Self {
             world: World::new(),
+            control_frame: None,
         }
     }
+
+    /// Returns the server [`ControlFrame`] of the snapshot that this state represents.
+    ///
+    /// Returns `None` if no snapshot has been applied yet.
+    #[inline]
+    pub fn snapshot_frame(&self) -> Option<ControlFrame> {
+        self.control_frame
+    }
 }
@@ -0,0 +1,85 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use game_common::components::{GlobalTransform, RigidBody, RigidBodyKind, Transform};
+use game_common::world::{QueryParams, QueryWrapper, World};
+
+/// The number of entities in the world.
+const NUM_ENTITIES: usize = 100_000;
+
+/// Creates a world where every entity has a `Transform`, every second entity has a
+/// `GlobalTransform` and every tenth entity has a `RigidBody`.
+fn build_world() -> World {
+    let mut world = World::new();
+
+    for index in 0..NUM_ENTITIES {
+        let entity = world.spawn();
+        world.insert_typed(entity, Transform::IDENTITY);
+
+        if index % 2 == 0 {
+            world.insert_typed(entity, GlobalTransform(Transform::IDENTITY));
+        }
+
+        if index % 10 == 0 {
+            world.insert_typed(entity, RigidBody::new(RigidBodyKind::Dynamic));
+        }
+    }
+
+    world
+}
+
+/// Visits every entity and fetches the components `Q` through a per-entity lookup.
+///
+/// This is how queries were executed before components were stored in archetypes and serves as
+/// the baseline for [`World::query`].
+fn scan<Q>(world: &World)
+where
+    Q: QueryParams,
+{
+    for entity in world.entities() {
+        if let Some(query) = Q::fetch(world.components(entity)) {
+            black_box((entity, query));
+        }
+    }
+}
+
+fn run_bench(c: &mut Criterion) {
+    let world = build_world();
+
+    let mut group = c.benchmark_group("world query");
+
+    group.bench_function("entities", |b| {
+        b.iter(|| {
+            for entity in world.entities() {
+                black_box(entity);
+            }
+        });
+    });
+
+    group.bench_function("Transform", |b| {
+        b.iter(|| {
+            for entity in world.query::<Transform>() {
+                black_box(entity);
+            }
+        });
+    });
+
+    group.bench_function("(Transform, RigidBody)", |b| {
+        b.iter(|| {
+            for entity in world.query::<QueryWrapper<(Transform, RigidBody)>>() {
+                black_box(entity);
+            }
+        });
+    });
+
+    group.bench_function("Transform (scan)", |b| {
+        b.iter(|| scan::<Transform>(&world));
+    });
+
+    group.bench_function("(Transform, RigidBody) (scan)", |b| {
+        b.iter(|| scan::<QueryWrapper<(Transform, RigidBody)>>(&world));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, run_bench);
+criterion_main!(benches);
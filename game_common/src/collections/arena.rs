@@ -300,6 +300,8 @@ impl<'a, T> AllocateEntry<'a, T> {
 
     /// Write the value into the slot.
     pub fn write(self, value: T) {
+        self.arena.len += 1;
+
         // If index == len we must create a new slot.
         if self.key.index as usize == self.arena.entries.len() {
             self.arena.entries.push(Entry::Occupied(OccupiedEntry {
@@ -716,4 +718,20 @@ mod tests {
         let keys = (0..16).map(|index| arena.insert(index)).collect::<Vec<_>>();
         assert_eq!(arena.keys().collect::<Vec<_>>(), keys);
     }
+
+    #[test]
+    fn arena_allocate_remove() {
+        let mut arena = Arena::new();
+
+        for index in 0..16 {
+            let entry = arena.allocate();
+            let key = entry.key();
+            entry.write(index);
+
+            assert_eq!(arena.len(), 1);
+            assert_eq!(*arena.get(key).unwrap(), index);
+            assert_eq!(arena.remove(key).unwrap(), index);
+            assert_eq!(arena.len(), 0);
+        }
+    }
 }
@@ -44,6 +44,21 @@ impl Ray {
     }
 }
 
+/// The axis-aligned bounding box of an empty set of points as `(min, max)`.
+///
+/// The minimum is positive and the maximum negative infinity, so the box contains no points and
+/// merging it with any other box yields the other box.
+pub const EMPTY_AABB: (Vec3, Vec3) = (Vec3::INFINITY, Vec3::NEG_INFINITY);
+
+/// Returns the axis-aligned bounding box containing all `points` as `(min, max)`.
+///
+/// Returns [`EMPTY_AABB`] if `points` is empty.
+pub fn compute_aabb(points: &[Vec3]) -> (Vec3, Vec3) {
+    points.iter().fold(EMPTY_AABB, |(min, max), point| {
+        (min.min(*point), max.max(*point))
+    })
+}
+
 /// Extension trait for objects representing an 3D rotation.
 pub trait RotationExt: private::Sealed {
     /// The orientation representing the front.
@@ -103,7 +118,7 @@ mod tests {
 
     use glam::{Quat, Vec3};
 
-    use crate::math::RotationExt;
+    use crate::math::{compute_aabb, RotationExt, EMPTY_AABB};
 
     #[test]
     fn rotation_ext_consts() {
@@ -114,4 +129,25 @@ mod tests {
         assert_eq!(Quat::TOP, Quat::from_axis_angle(Vec3::X, FRAC_PI_2));
         assert_eq!(Quat::BOTTOM, Quat::from_axis_angle(Vec3::X, -FRAC_PI_2));
     }
+
+    #[test]
+    fn compute_aabb_points() {
+        let points = [
+            Vec3::new(1.0, -2.0, 0.5),
+            Vec3::new(-1.0, 3.0, 0.0),
+            Vec3::new(0.0, 0.0, 4.0),
+        ];
+
+        assert_eq!(
+            compute_aabb(&points),
+            (Vec3::new(-1.0, -2.0, 0.0), Vec3::new(1.0, 3.0, 4.0))
+        );
+    }
+
+    #[test]
+    fn compute_aabb_empty() {
+        let (min, max) = compute_aabb(&[]);
+        assert_eq!((min, max), EMPTY_AABB);
+        assert!(min.cmpgt(max).all());
+    }
 }
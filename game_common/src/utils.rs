@@ -1,3 +1,4 @@
+pub mod endian;
 pub mod exclusive;
 pub mod vec_ext;
 
@@ -0,0 +1,184 @@
+//! The byte order of all binary formats.
+//!
+//! All binary formats (data files, models, ...) store multi-byte values in [`BYTE_ORDER`],
+//! independent of the byte order of the host. All encoders and decoders must go through this
+//! module instead of using the `to_*_bytes`/`from_*_bytes` methods of the primitives directly,
+//! so that the byte order cannot diverge between formats.
+
+use std::borrow::Cow;
+
+use bytemuck::Pod;
+
+/// The byte order of multi-byte values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    /// The byte order of the target.
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Self = Self::Little;
+    /// The byte order of the target.
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Self = Self::Big;
+}
+
+/// The byte order of all binary formats.
+pub const BYTE_ORDER: Endianness = Endianness::Little;
+
+/// A primitive that can be converted from and to bytes in [`BYTE_ORDER`].
+pub trait Primitive: Copy {
+    type Bytes: AsRef<[u8]>;
+
+    /// Returns the bytes of `self` in [`BYTE_ORDER`].
+    fn to_bytes(self) -> Self::Bytes;
+
+    /// Creates a value from its bytes in [`BYTE_ORDER`].
+    fn from_bytes(bytes: Self::Bytes) -> Self;
+}
+
+macro_rules! primitive_impls {
+    ($($id:ident),*$(,)?) => {
+        $(
+            impl Primitive for $id {
+                type Bytes = [u8; size_of::<$id>()];
+
+                #[inline]
+                fn to_bytes(self) -> Self::Bytes {
+                    match BYTE_ORDER {
+                        Endianness::Little => self.to_le_bytes(),
+                        Endianness::Big => self.to_be_bytes(),
+                    }
+                }
+
+                #[inline]
+                fn from_bytes(bytes: Self::Bytes) -> Self {
+                    match BYTE_ORDER {
+                        Endianness::Little => Self::from_le_bytes(bytes),
+                        Endianness::Big => Self::from_be_bytes(bytes),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+primitive_impls! {
+    u8,
+    u16,
+    u32,
+    u64,
+    i8,
+    i16,
+    i32,
+    i64,
+    f32,
+    f64,
+}
+
+/// Reinterprets `bytes` in [`BYTE_ORDER`] as a slice of `T`, where `T` consists only of scalars
+/// of type `S` (e.g. `Vec3` consists of `f32`s).
+///
+/// If the byte order of the host is [`BYTE_ORDER`] and `bytes` is correctly aligned, `bytes` is
+/// borrowed, otherwise the values are copied.
+///
+/// # Panics
+///
+/// Panics if the length of `bytes` is not a multiple of the size of `T` or if the size of `T` is
+/// not a multiple of the size of `S`.
+pub fn cast_slice<S, T>(bytes: &[u8]) -> Cow<'_, [T]>
+where
+    S: Primitive + Pod,
+    T: Pod,
+{
+    assert_eq!(bytes.len() % size_of::<T>(), 0);
+    assert_eq!(size_of::<T>() % size_of::<S>(), 0);
+
+    if BYTE_ORDER == Endianness::NATIVE {
+        if let Ok(slice) = bytemuck::try_cast_slice(bytes) {
+            return Cow::Borrowed(slice);
+        }
+    }
+
+    let mut values: Vec<T> = bytemuck::pod_collect_to_vec(bytes);
+    convert(
+        bytemuck::cast_slice_mut(&mut values),
+        size_of::<S>(),
+        BYTE_ORDER,
+        Endianness::NATIVE,
+    );
+    Cow::Owned(values)
+}
+
+/// Returns the bytes of `values` in [`BYTE_ORDER`], where `T` consists only of scalars of type
+/// `S`.
+///
+/// This is the inverse of [`cast_slice`].
+pub fn to_bytes<S, T>(values: &[T]) -> Cow<'_, [u8]>
+where
+    S: Primitive + Pod,
+    T: Pod,
+{
+    assert_eq!(size_of::<T>() % size_of::<S>(), 0);
+
+    let bytes: &[u8] = bytemuck::cast_slice(values);
+    if BYTE_ORDER == Endianness::NATIVE {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut bytes = bytes.to_vec();
+    convert(&mut bytes, size_of::<S>(), Endianness::NATIVE, BYTE_ORDER);
+    Cow::Owned(bytes)
+}
+
+/// Converts the scalars of `size` bytes in `bytes` from the byte order `from` to `to`.
+fn convert(bytes: &mut [u8], size: usize, from: Endianness, to: Endianness) {
+    if from == to {
+        return;
+    }
+
+    for scalar in bytes.chunks_exact_mut(size) {
+        scalar.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{convert, Endianness, Primitive};
+
+    #[test]
+    fn primitive_is_little_endian() {
+        assert_eq!(0x1234_5678u32.to_bytes(), [0x78, 0x56, 0x34, 0x12]);
+        assert_eq!(u32::from_bytes([0x78, 0x56, 0x34, 0x12]), 0x1234_5678);
+        assert_eq!(f32::from_bytes([0x00, 0x00, 0x80, 0x3f]), 1.0);
+    }
+
+    #[test]
+    fn cast_slice_roundtrip() {
+        let values = [1.0f32, -2.5, 3.75];
+        let bytes = super::to_bytes::<f32, f32>(&values);
+        assert_eq!(&bytes[0..4], &[0x00, 0x00, 0x80, 0x3f]);
+
+        let output = super::cast_slice::<f32, f32>(&bytes);
+        assert_eq!(&*output, &values);
+    }
+
+    #[test]
+    fn convert_big_endian_host() {
+        // Simulates decoding on a big-endian host.
+        let mut bytes = [0x78, 0x56, 0x34, 0x12, 0x00, 0x00, 0x80, 0x3f];
+        convert(&mut bytes, 4, Endianness::Little, Endianness::Big);
+
+        assert_eq!(
+            u32::from_be_bytes(bytes[0..4].try_into().unwrap()),
+            0x1234_5678
+        );
+        assert_eq!(f32::from_be_bytes(bytes[4..8].try_into().unwrap()), 1.0);
+
+        let mut bytes = [0x78, 0x56, 0x34, 0x12];
+        convert(&mut bytes, 4, Endianness::Little, Endianness::Little);
+        assert_eq!(bytes, [0x78, 0x56, 0x34, 0x12]);
+    }
+}
@@ -5,6 +5,12 @@ use game_wasm::entity::EntityId;
 
 use super::World;
 
+/// The default maximum depth up to which an entity hierarchy is traversed.
+///
+/// Entities that are nested deeper below the starting entity are ignored. This bounds the work
+/// done for pathologically deep hierarchies.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
 /// Returns a list of entities with updated transform.
 pub fn update_global_transform(world: &mut World) -> Vec<EntityId> {
     let mut transforms = HashMap::new();
@@ -34,6 +40,14 @@ pub fn update_global_transform(world: &mut World) -> Vec<EntityId> {
         };
 
         for child in world.children(entity) {
+            // An entity that already has a parent was either reached through
+            // multiple parents or is part of a cycle. Visiting it again would
+            // never terminate for cycles.
+            if parents.contains_key(&child) {
+                tracing::warn!("entity {:?} has multiple parents", child);
+                continue;
+            }
+
             parents.insert(child, entity);
             queued.push_back(child);
         }
@@ -98,4 +112,32 @@ mod tests {
             Vec3::splat(10.0)
         );
     }
+
+    #[test]
+    fn hierarchy_compute_transform_cyclic() {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        let child0 = world.spawn();
+        let child1 = world.spawn();
+        for entity in [root, child0, child1] {
+            world.insert_typed(entity, Transform::from_translation(Vec3::splat(1.0)));
+        }
+
+        // root -> child0 -> child1 -> child0
+        world.insert_typed(root, Children::from_iter([child0]));
+        world.insert_typed(child0, Children::from_iter([child1]));
+        world.insert_typed(child1, Children::from_iter([child0]));
+
+        update_global_transform(&mut world);
+
+        assert_eq!(
+            world
+                .get_typed::<GlobalTransform>(child1)
+                .unwrap()
+                .0
+                .translation,
+            Vec3::splat(3.0)
+        );
+    }
 }
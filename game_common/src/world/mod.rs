@@ -27,11 +27,13 @@ pub mod hierarchy;
 pub mod interaction;
 pub mod snapshot;
 pub mod source;
+mod storage;
 pub mod terrain;
 pub mod time;
 pub mod world;
 
-use std::collections::VecDeque;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
@@ -43,11 +45,14 @@ use game_wasm::components::Component;
 use game_wasm::encoding::{BinaryReader, BinaryWriter, Decode};
 use game_wasm::hierarchy::Children;
 use game_wasm::resource::RuntimeResourceId;
+pub use storage::{ComponentsIter, ComponentsRef};
 
-use crate::components::components::{Components, RawComponent};
+use crate::components::components::RawComponent;
 use crate::entity::EntityId;
 use crate::record::RecordReference;
 
+use self::storage::{Archetype, Storage};
+
 pub enum Error<T>
 where
     T: Component,
@@ -86,11 +91,25 @@ where
     }
 }
 
+/// A collection of entities and their components.
+///
+/// All iterators over the entities of a `World` ([`iter`], [`entities`] and [`query`]) yield the
+/// entities in ascending [`EntityId`] order. This order only depends on the set of entities in
+/// the `World`, not on the order of insertion or removal, making iteration reproducible across
+/// runs.
+///
+/// [`iter`]: Self::iter
+/// [`entities`]: Self::entities
+/// [`query`]: Self::query
+///
+/// Components are stored in archetypes: All entities with the same set of components store their
+/// components contiguously, one column for every component. Queries only visit the entities that
+/// have all requested components. Adding a component to or removing a component from an entity
+/// moves the entity to a different archetype.
 #[derive(Clone, Debug, Default)]
 pub struct World {
-    entities: HashSet<EntityId>,
     next_entity_id: u64,
-    components: HashMap<EntityId, Components>,
+    storage: Storage,
     resources: HashMap<RuntimeResourceId, Arc<[u8]>>,
     next_resource_id: u64,
 }
@@ -98,8 +117,7 @@ pub struct World {
 impl World {
     pub fn new() -> Self {
         Self {
-            entities: HashSet::default(),
-            components: HashMap::default(),
+            storage: Storage::default(),
             next_entity_id: 0,
             resources: HashMap::new(),
             next_resource_id: 0,
@@ -107,21 +125,19 @@ impl World {
     }
 
     pub fn len(&self) -> usize {
-        self.entities.len()
+        self.storage.len()
     }
 
     pub fn spawn(&mut self) -> EntityId {
         let id = EntityId::from_raw(self.next_entity_id);
         self.next_entity_id += 1;
 
-        self.entities.insert(id);
-        self.components.insert(id, Components::default());
+        self.storage.spawn(id);
         id
     }
 
     pub fn spawn_with_id(&mut self, id: EntityId) {
-        self.entities.insert(id);
-        self.components.insert(id, Components::default());
+        self.storage.spawn(id);
     }
 
     pub fn despawn(&mut self, id: EntityId) {
@@ -142,25 +158,20 @@ impl World {
                 }
             }
 
-            if self.entities.remove(&entity) {
+            if self.storage.despawn(entity) {
                 f(entity);
             }
-
-            self.components.remove(&entity);
         }
     }
 
     pub fn insert(&mut self, id: EntityId, component_id: RecordReference, component: RawComponent) {
-        assert!(self.entities.contains(&id));
-        self.components
-            .entry(id)
-            .or_default()
-            .insert(component_id, component);
+        assert!(self.contains(id));
+        self.storage.insert(id, component_id, component);
     }
 
     pub fn get(&self, id: EntityId, component_id: RecordReference) -> Option<&RawComponent> {
-        self.components
-            .get(&id)
+        self.storage
+            .get(id)
             .and_then(|components| components.get(component_id))
     }
 
@@ -169,15 +180,11 @@ impl World {
         id: EntityId,
         component_id: RecordReference,
     ) -> Option<&mut RawComponent> {
-        self.components
-            .get_mut(&id)
-            .and_then(|components| components.get_mut(component_id))
+        self.storage.get_mut(id, component_id)
     }
 
     pub fn remove(&mut self, id: EntityId, component_id: RecordReference) -> Option<RawComponent> {
-        self.components
-            .get_mut(&id)
-            .and_then(|components| components.remove(component_id))
+        self.storage.remove(id, component_id)
     }
 
     pub fn insert_typed<T: Component>(&mut self, entity: EntityId, component: T) {
@@ -194,38 +201,54 @@ impl World {
         T::decode(reader).map_err(Error::Decode)
     }
 
+    /// Returns an iterator over all entities in ascending [`EntityId`] order.
     pub fn iter(&self) -> Iter<'_> {
         Iter {
-            inner: self.entities.iter(),
+            inner: ArchetypeMerge::new(self.storage.archetypes_with(&[])),
         }
     }
 
-    pub fn components(&self, entity: EntityId) -> &Components {
-        self.components.get(&entity).unwrap()
+    /// Returns all components of the `entity`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `entity` does not exist.
+    pub fn components(&self, entity: EntityId) -> ComponentsRef<'_> {
+        self.storage.get(entity).unwrap()
     }
 
     pub fn contains(&self, id: EntityId) -> bool {
-        self.entities.contains(&id)
+        self.storage.contains(id)
     }
 
+    /// Returns an iterator over all entities with the components `Q` in ascending [`EntityId`]
+    /// order.
     pub fn query<Q>(&self) -> Query<'_, Q>
     where
         Q: QueryParams,
     {
         Query {
-            iter: self.entities.iter(),
-            components: &self.components,
+            inner: ArchetypeMerge::new(self.storage.archetypes_with(Q::COMPONENTS)),
             _marker: PhantomData,
         }
     }
 
+    /// Returns an iterator over all entities in ascending [`EntityId`] order.
     pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
-        self.entities.iter().copied()
+        self.iter()
+    }
+
+    /// Returns an iterator over all entities that have all `components` in ascending
+    /// [`EntityId`] order.
+    pub fn entities_with(
+        &self,
+        components: &[RecordReference],
+    ) -> impl Iterator<Item = EntityId> + '_ {
+        ArchetypeMerge::new(self.storage.archetypes_with(components)).map(|(entity, _)| entity)
     }
 
     pub fn clear(&mut self) {
-        self.entities.clear();
-        self.components.clear();
+        self.storage.clear();
     }
 
     pub fn append(&mut self, other: World) -> EntityId {
@@ -243,11 +266,11 @@ impl World {
             spawn_queue.extend(other.children(entity));
         }
 
-        for (entity, components) in other.components {
+        for entity in other.entities() {
             let entity_id = *entity_keys.get(&entity).unwrap();
             entities.insert(entity, entity_id);
 
-            for (id, component) in components.iter() {
+            for (id, component) in other.components(entity).iter() {
                 if id == Children::ID {
                     let reader = BinaryReader::new(
                         component.as_bytes().to_vec(),
@@ -285,9 +308,7 @@ impl World {
     }
 
     fn children(&self, parent: EntityId) -> Vec<EntityId> {
-        let components = self.components.get(&parent).unwrap();
-
-        let Some(component) = components.get(Children::ID) else {
+        let Some(component) = self.get(parent, Children::ID) else {
             return Vec::new();
         };
 
@@ -303,23 +324,21 @@ impl World {
         // pointing at them.
         let mut non_root_entities = HashSet::new();
 
-        for (_, components) in &self.components {
-            let Some(component) = components.get(Children::ID) else {
-                continue;
-            };
+        for entity in self.entities_with(&[Children::ID]) {
+            let component = self.get(entity, Children::ID).unwrap();
 
             let fields = component.fields();
             let reader = BinaryReader::new(component.as_bytes().to_vec(), fields.to_vec().into());
             let children = Children::decode(reader).unwrap();
 
             for id in children.get() {
-                if self.entities.contains(id) {
+                if self.contains(*id) {
                     non_root_entities.insert(*id);
                 }
             }
         }
 
-        let mut root = Vec::with_capacity(self.entities.len() - non_root_entities.len());
+        let mut root = Vec::with_capacity(self.len() - non_root_entities.len());
         for entity in self.entities() {
             if !non_root_entities.contains(&entity) {
                 root.push(entity);
@@ -357,26 +376,98 @@ impl World {
 }
 
 pub struct Iter<'a> {
-    inner: std::collections::hash_set::Iter<'a, EntityId>,
+    inner: ArchetypeMerge<'a>,
 }
 
 impl<'a> Iterator for Iter<'a> {
     type Item = EntityId;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().copied()
+        self.inner.next().map(|(entity, _)| entity)
+    }
+}
+
+/// Iterates over the entities of multiple [`Archetype`]s in ascending [`EntityId`] order.
+struct ArchetypeMerge<'a> {
+    cursors: Vec<Cursor<'a>>,
+    /// The next entity of every cursor that still has entities left, with the index of the
+    /// cursor.
+    heap: BinaryHeap<Reverse<(EntityId, usize)>>,
+}
+
+impl<'a> ArchetypeMerge<'a> {
+    fn new(archetypes: impl Iterator<Item = &'a Archetype>) -> Self {
+        let cursors: Vec<_> = archetypes
+            .map(|archetype| Cursor {
+                archetype,
+                rows: archetype.sorted_rows(),
+                next: 0,
+            })
+            .collect();
+
+        let heap = cursors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cursor)| Some(Reverse((cursor.peek()?.0, index))))
+            .collect();
+
+        Self { cursors, heap }
+    }
+}
+
+impl<'a> Iterator for ArchetypeMerge<'a> {
+    type Item = (EntityId, ComponentsRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((entity, index)) = self.heap.pop()?;
+
+        let cursor = &mut self.cursors[index];
+        let (_, row) = cursor.peek().unwrap();
+        cursor.next += 1;
+
+        if let Some((next, _)) = cursor.peek() {
+            self.heap.push(Reverse((next, index)));
+        }
+
+        Some((entity, cursor.archetype.components(row)))
+    }
+}
+
+/// The position of an [`ArchetypeMerge`] within a single [`Archetype`].
+struct Cursor<'a> {
+    archetype: &'a Archetype,
+    /// The rows in ascending [`EntityId`] order, `None` if the rows are already sorted.
+    rows: Option<&'a [usize]>,
+    next: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Returns the next entity and its row.
+    fn peek(&self) -> Option<(EntityId, usize)> {
+        let row = match self.rows {
+            Some(rows) => *rows.get(self.next)?,
+            None => self.next,
+        };
+
+        let entity = *self.archetype.entities().get(row)?;
+        Some((entity, row))
     }
 }
 
 pub trait QueryParams: Sized {
-    fn fetch(components: &Components) -> Option<Self>;
+    /// The components that an entity must have to match the query.
+    const COMPONENTS: &'static [RecordReference];
+
+    fn fetch(components: ComponentsRef<'_>) -> Option<Self>;
 }
 
 impl<T> QueryParams for T
 where
     T: Component,
 {
-    fn fetch(components: &Components) -> Option<Self> {
+    const COMPONENTS: &'static [RecordReference] = &[T::ID];
+
+    fn fetch(components: ComponentsRef<'_>) -> Option<Self> {
         let component = components.get(T::ID)?;
         let reader = BinaryReader::new(
             component.as_bytes().to_vec(),
@@ -387,8 +478,7 @@ where
 }
 
 pub struct Query<'a, T> {
-    iter: std::collections::hash_set::Iter<'a, EntityId>,
-    components: &'a HashMap<EntityId, Components>,
+    inner: ArchetypeMerge<'a>,
     _marker: PhantomData<fn() -> T>,
 }
 
@@ -417,7 +507,9 @@ where
     C0: Component,
     C1: Component,
 {
-    fn fetch(components: &Components) -> Option<Self> {
+    const COMPONENTS: &'static [RecordReference] = &[C0::ID, C1::ID];
+
+    fn fetch(components: ComponentsRef<'_>) -> Option<Self> {
         let c0 = components.get(C0::ID)?;
         let c1 = components.get(C1::ID)?;
         let r0 = BinaryReader::new(c0.as_bytes().to_vec(), c0.fields().to_vec().into());
@@ -432,7 +524,9 @@ where
     C1: Component,
     C2: Component,
 {
-    fn fetch(components: &Components) -> Option<Self> {
+    const COMPONENTS: &'static [RecordReference] = &[C0::ID, C1::ID, C2::ID];
+
+    fn fetch(components: ComponentsRef<'_>) -> Option<Self> {
         let c0 = components.get(C0::ID)?;
         let c1 = components.get(C1::ID)?;
         let c2 = components.get(C2::ID)?;
@@ -455,13 +549,10 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            let entity = self.iter.next()?;
-            let Some(components) = self.components.get(entity) else {
-                continue;
-            };
+            let (entity, components) = self.inner.next()?;
 
             if let Some(query) = T::fetch(components) {
-                return Some((*entity, query));
+                return Some((entity, query));
             };
         }
     }
@@ -470,8 +561,11 @@ where
 #[cfg(test)]
 mod tests {
     use game_wasm::components::builtin::Transform;
+    use game_wasm::components::Component;
     use game_wasm::hierarchy::Children;
 
+    use crate::entity::EntityId;
+
     use super::World;
 
     #[test]
@@ -489,4 +583,59 @@ mod tests {
             Transform::default()
         );
     }
+
+    fn build_world() -> World {
+        let mut world = World::new();
+        let entities: Vec<_> = (0..64).map(|_| world.spawn()).collect();
+        for entity in entities.iter().step_by(3) {
+            world.despawn(*entity);
+        }
+
+        world.spawn_with_id(EntityId::from_raw(1000));
+        world.spawn_with_id(EntityId::from_raw(500));
+        world.spawn();
+        world.despawn(entities[4]);
+        world.spawn_with_id(entities[3]);
+        world
+    }
+
+    #[test]
+    fn world_iter_order() {
+        let world = build_world();
+
+        let entities: Vec<_> = world.entities().collect();
+        let mut sorted = entities.clone();
+        sorted.sort();
+        assert_eq!(entities, sorted);
+        assert_eq!(world.iter().collect::<Vec<_>>(), entities);
+
+        for _ in 0..8 {
+            assert_eq!(build_world().entities().collect::<Vec<_>>(), entities);
+        }
+    }
+
+    #[test]
+    fn world_query_across_archetypes() {
+        let mut world = World::new();
+        let entities: Vec<_> = (0..16).map(|_| world.spawn()).collect();
+
+        // Spread the entities over multiple archetypes in an order that
+        // doesn't match their ids.
+        for (index, entity) in entities.iter().enumerate().rev() {
+            world.insert_typed(*entity, Transform::default());
+            if index % 3 == 0 {
+                world.insert_typed(*entity, Children::new());
+            }
+        }
+        world.remove(entities[3], Children::ID);
+
+        let queried: Vec<_> = world.query::<Transform>().map(|(id, _)| id).collect();
+        assert_eq!(queried, entities);
+
+        let with_children: Vec<_> = world.entities_with(&[Children::ID]).collect();
+        assert_eq!(
+            with_children,
+            [0, 6, 9, 12, 15].map(|index| entities[index])
+        );
+    }
 }
@@ -0,0 +1,530 @@
+//! Archetype-based component storage
+//!
+//! All entities with the same set of components share an [`Archetype`]. An archetype stores the
+//! components in columns, one column for every component type, so all components of the same
+//! type within an archetype are stored contiguously. Iterating over all entities with a set of
+//! components only needs to visit the archetypes containing these components and scan their
+//! columns linearly.
+//!
+//! Rows are removed by swapping in the last row, so the rows of an archetype are in no particular
+//! order. Iterating in ascending [`EntityId`] order sorts the rows of an archetype lazily and
+//! merges the archetypes.
+
+use std::cmp::Ordering;
+use std::iter::FusedIterator;
+use std::sync::OnceLock;
+
+use ahash::HashMap;
+
+use crate::components::components::{Components, RawComponent};
+use crate::entity::EntityId;
+use crate::record::RecordReference;
+
+#[derive(Clone, Debug, Default)]
+pub(super) struct Storage {
+    archetypes: Vec<Archetype>,
+    /// The index of the archetype for a set of components sorted by [`cmp_component`].
+    archetype_ids: HashMap<Box<[RecordReference]>, usize>,
+    /// The archetype and row of every entity.
+    entities: HashMap<EntityId, Location>,
+}
+
+impl Storage {
+    /// Inserts a new entity without any components.
+    ///
+    /// If the entity already exists, all its components are removed.
+    pub(super) fn spawn(&mut self, id: EntityId) {
+        self.despawn(id);
+
+        let archetype = self.archetype_id(&[]);
+        let row = self.archetypes[archetype].entities.len();
+        self.archetypes[archetype].push_entity(id);
+        self.entities.insert(id, Location { archetype, row });
+    }
+
+    /// Removes the entity with all its components. Returns `true` if the entity existed.
+    pub(super) fn despawn(&mut self, id: EntityId) -> bool {
+        match self.entities.remove(&id) {
+            Some(location) => {
+                let archetype = &mut self.archetypes[location.archetype];
+                for column in archetype.columns.iter_mut() {
+                    column.swap_remove(location.row);
+                }
+                self.swap_remove_entity(location);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub(super) fn contains(&self, id: EntityId) -> bool {
+        self.entities.contains_key(&id)
+    }
+
+    pub(super) fn get(&self, id: EntityId) -> Option<ComponentsRef<'_>> {
+        let location = self.entities.get(&id)?;
+        Some(self.archetypes[location.archetype].components(location.row))
+    }
+
+    pub(super) fn get_mut(
+        &mut self,
+        id: EntityId,
+        component_id: RecordReference,
+    ) -> Option<&mut RawComponent> {
+        let location = *self.entities.get(&id)?;
+        let archetype = &mut self.archetypes[location.archetype];
+        let column = archetype.column(component_id)?;
+        Some(&mut archetype.columns[column][location.row])
+    }
+
+    /// Inserts a component on an existing entity, replacing the previous component with the same
+    /// id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entity does not exist.
+    pub(super) fn insert(
+        &mut self,
+        id: EntityId,
+        component_id: RecordReference,
+        component: RawComponent,
+    ) {
+        let location = *self.entities.get(&id).unwrap();
+
+        let archetype = &mut self.archetypes[location.archetype];
+        if let Some(column) = archetype.column(component_id) {
+            archetype.columns[column][location.row] = component;
+            return;
+        }
+
+        let target = match archetype.insert_edges.get(&component_id) {
+            Some(target) => *target,
+            None => {
+                let mut components = archetype.components.to_vec();
+                let index = components
+                    .binary_search_by(|id| cmp_component(id, &component_id))
+                    .unwrap_err();
+                components.insert(index, component_id);
+
+                let target = self.archetype_id(&components);
+                self.archetypes[location.archetype]
+                    .insert_edges
+                    .insert(component_id, target);
+                target
+            }
+        };
+
+        self.move_entity(id, location, target, Some((component_id, component)));
+    }
+
+    pub(super) fn remove(
+        &mut self,
+        id: EntityId,
+        component_id: RecordReference,
+    ) -> Option<RawComponent> {
+        let location = *self.entities.get(&id)?;
+
+        let archetype = &mut self.archetypes[location.archetype];
+        let index = archetype.column(component_id)?;
+
+        let target = match archetype.remove_edges.get(&component_id) {
+            Some(target) => *target,
+            None => {
+                let mut components = archetype.components.to_vec();
+                components.remove(index);
+
+                let target = self.archetype_id(&components);
+                self.archetypes[location.archetype]
+                    .remove_edges
+                    .insert(component_id, target);
+                target
+            }
+        };
+
+        self.move_entity(id, location, target, None)
+    }
+
+    /// Returns all archetypes that contain all `components`.
+    pub(super) fn archetypes_with<'a, 'b>(
+        &'a self,
+        components: &'b [RecordReference],
+    ) -> impl Iterator<Item = &'a Archetype> + 'b
+    where
+        'a: 'b,
+    {
+        self.archetypes.iter().filter(|archetype| {
+            !archetype.entities.is_empty()
+                && components.iter().all(|id| archetype.column(*id).is_some())
+        })
+    }
+
+    pub(super) fn clear(&mut self) {
+        // Keep the archetypes around, the same sets of components
+        // are likely to be used again.
+        for archetype in &mut self.archetypes {
+            archetype.entities.clear();
+            for column in archetype.columns.iter_mut() {
+                column.clear();
+            }
+
+            archetype.sorted = true;
+            archetype.order.take();
+        }
+
+        self.entities.clear();
+    }
+
+    /// Returns the index of the archetype with the given sorted `components`, creating it if it
+    /// does not exist.
+    fn archetype_id(&mut self, components: &[RecordReference]) -> usize {
+        if let Some(id) = self.archetype_ids.get(components) {
+            return *id;
+        }
+
+        let id = self.archetypes.len();
+        self.archetypes.push(Archetype {
+            columns: components.iter().map(|_| Vec::new()).collect(),
+            components: components.into(),
+            entities: Vec::new(),
+            sorted: true,
+            order: OnceLock::new(),
+            insert_edges: HashMap::default(),
+            remove_edges: HashMap::default(),
+        });
+        self.archetype_ids.insert(components.into(), id);
+        id
+    }
+
+    /// Moves the entity at `location` into the `target` archetype, which must have the same
+    /// components except the `inserted` component or one removed component.
+    ///
+    /// Returns the removed component.
+    fn move_entity(
+        &mut self,
+        id: EntityId,
+        location: Location,
+        target: usize,
+        inserted: Option<(RecordReference, RawComponent)>,
+    ) -> Option<RawComponent> {
+        debug_assert_ne!(location.archetype, target);
+
+        let (source, dest) = if location.archetype < target {
+            let (lhs, rhs) = self.archetypes.split_at_mut(target);
+            (&mut lhs[location.archetype], &mut rhs[0])
+        } else {
+            let (lhs, rhs) = self.archetypes.split_at_mut(location.archetype);
+            (&mut rhs[0], &mut lhs[target])
+        };
+
+        let mut removed = None;
+        for (component_id, column) in source.components.iter().zip(source.columns.iter_mut()) {
+            let component = column.swap_remove(location.row);
+            match dest.column(*component_id) {
+                Some(index) => dest.columns[index].push(component),
+                None => removed = Some(component),
+            }
+        }
+
+        if let Some((component_id, component)) = inserted {
+            let index = dest.column(component_id).unwrap();
+            dest.columns[index].push(component);
+        }
+
+        let row = dest.entities.len();
+        dest.push_entity(id);
+
+        self.swap_remove_entity(location);
+        self.entities.insert(
+            id,
+            Location {
+                archetype: target,
+                row,
+            },
+        );
+
+        removed
+    }
+
+    /// Removes the entity at `location` from the entities of its archetype, after its components
+    /// have been removed from all columns.
+    fn swap_remove_entity(&mut self, location: Location) {
+        let archetype = &mut self.archetypes[location.archetype];
+        archetype.entities.swap_remove(location.row);
+        archetype.order.take();
+
+        // The last row was moved into the removed row.
+        if let Some(moved) = archetype.entities.get(location.row) {
+            self.entities.get_mut(moved).unwrap().row = location.row;
+            archetype.sorted = false;
+        }
+
+        if archetype.entities.len() <= 1 {
+            archetype.sorted = true;
+        }
+    }
+}
+
+/// The position of an entity within the [`Storage`].
+#[derive(Copy, Clone, Debug)]
+struct Location {
+    archetype: usize,
+    row: usize,
+}
+
+/// The entities of a unique set of components.
+#[derive(Clone, Debug)]
+pub(super) struct Archetype {
+    /// The components of the archetype, sorted by [`cmp_component`].
+    components: Box<[RecordReference]>,
+    /// The entity of every row.
+    entities: Vec<EntityId>,
+    /// The column for every component in `components`.
+    columns: Box<[Vec<RawComponent>]>,
+    /// Whether the `entities` are sorted in ascending order.
+    sorted: bool,
+    /// The rows in ascending [`EntityId`] order, computed when first needed if the `entities`
+    /// are not `sorted`.
+    order: OnceLock<Box<[usize]>>,
+    /// The archetypes that entities move to when a component is inserted.
+    insert_edges: HashMap<RecordReference, usize>,
+    /// The archetypes that entities move to when a component is removed.
+    remove_edges: HashMap<RecordReference, usize>,
+}
+
+impl Archetype {
+    /// Returns the entity of every row.
+    pub(super) fn entities(&self) -> &[EntityId] {
+        &self.entities
+    }
+
+    /// Returns the rows sorted by their [`EntityId`], or `None` if the rows are already sorted.
+    pub(super) fn sorted_rows(&self) -> Option<&[usize]> {
+        if self.sorted {
+            return None;
+        }
+
+        Some(self.order.get_or_init(|| {
+            let mut rows: Box<[usize]> = (0..self.entities.len()).collect();
+            rows.sort_unstable_by_key(|row| self.entities[*row]);
+            rows
+        }))
+    }
+
+    pub(super) fn column(&self, component_id: RecordReference) -> Option<usize> {
+        self.components
+            .binary_search_by(|id| cmp_component(id, &component_id))
+            .ok()
+    }
+
+    /// Returns the components in the `row`.
+    pub(super) fn components(&self, row: usize) -> ComponentsRef<'_> {
+        ComponentsRef {
+            archetype: self,
+            row,
+        }
+    }
+
+    /// Appends a new row for the entity after its components were pushed to all columns.
+    fn push_entity(&mut self, id: EntityId) {
+        debug_assert!(self
+            .columns
+            .iter()
+            .all(|column| column.len() == self.entities.len() + 1));
+
+        // Entities are usually spawned with increasing ids, which keeps the
+        // rows sorted.
+        if let Some(last) = self.entities.last() {
+            self.sorted &= *last < id;
+        }
+
+        self.entities.push(id);
+        self.order.take();
+    }
+}
+
+/// A reference to all components of an entity.
+#[derive(Copy, Clone, Debug)]
+pub struct ComponentsRef<'a> {
+    archetype: &'a Archetype,
+    row: usize,
+}
+
+impl<'a> ComponentsRef<'a> {
+    pub fn len(&self) -> usize {
+        self.archetype.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, component_id: RecordReference) -> Option<&'a RawComponent> {
+        let column = self.archetype.column(component_id)?;
+        Some(&self.archetype.columns[column][self.row])
+    }
+
+    pub fn iter(&self) -> ComponentsIter<'a> {
+        ComponentsIter {
+            archetype: self.archetype,
+            row: self.row,
+            column: 0,
+        }
+    }
+
+    /// Clones all components into an owned [`Components`] collection.
+    pub fn to_components(&self) -> Components {
+        let mut components = Components::new();
+        for (id, component) in self.iter() {
+            components.insert(id, component.clone());
+        }
+        components
+    }
+}
+
+/// An iterator over all components of an entity.
+#[derive(Clone, Debug)]
+pub struct ComponentsIter<'a> {
+    archetype: &'a Archetype,
+    row: usize,
+    column: usize,
+}
+
+impl<'a> Iterator for ComponentsIter<'a> {
+    type Item = (RecordReference, &'a RawComponent);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = *self.archetype.components.get(self.column)?;
+        let component = &self.archetype.columns[self.column][self.row];
+        self.column += 1;
+        Some((id, component))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.archetype.components.len() - self.column;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for ComponentsIter<'a> {}
+
+impl<'a> FusedIterator for ComponentsIter<'a> {}
+
+/// The order of components within an [`Archetype`].
+fn cmp_component(lhs: &RecordReference, rhs: &RecordReference) -> Ordering {
+    bytemuck::bytes_of(lhs).cmp(bytemuck::bytes_of(rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::components::RawComponent;
+    use crate::entity::EntityId;
+    use crate::record::{ModuleId, RecordId, RecordReference};
+
+    use super::Storage;
+
+    fn component_id(record: u32) -> RecordReference {
+        RecordReference {
+            module: ModuleId::CORE,
+            record: RecordId(record),
+        }
+    }
+
+    fn component(byte: u8) -> RawComponent {
+        RawComponent::new(vec![byte], vec![])
+    }
+
+    #[test]
+    fn storage_move_between_archetypes() {
+        let mut storage = Storage::default();
+        let entity = EntityId::from_raw(0);
+        storage.spawn(entity);
+
+        storage.insert(entity, component_id(1), component(1));
+        storage.insert(entity, component_id(0), component(0));
+        // Replaces the component without moving the entity.
+        storage.insert(entity, component_id(1), component(2));
+
+        let components = storage.get(entity).unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components.get(component_id(0)), Some(&component(0)));
+        assert_eq!(components.get(component_id(1)), Some(&component(2)));
+
+        assert_eq!(storage.remove(entity, component_id(0)), Some(component(0)));
+        assert_eq!(storage.remove(entity, component_id(0)), None);
+
+        let components = storage.get(entity).unwrap();
+        assert_eq!(components.len(), 1);
+        assert_eq!(components.get(component_id(1)), Some(&component(2)));
+    }
+
+    #[test]
+    fn storage_archetype_sorted_rows() {
+        let mut storage = Storage::default();
+        for id in [5, 1, 3, 0, 4, 2] {
+            let entity = EntityId::from_raw(id);
+            storage.spawn(entity);
+            storage.insert(entity, component_id(0), component(id as u8));
+        }
+
+        storage.despawn(EntityId::from_raw(3));
+
+        let archetypes: Vec<_> = storage.archetypes_with(&[component_id(0)]).collect();
+        assert_eq!(archetypes.len(), 1);
+
+        let archetype = archetypes[0];
+        let rows = archetype.sorted_rows().unwrap();
+        let entities: Vec<_> = rows.iter().map(|row| archetype.entities()[*row]).collect();
+        assert_eq!(entities, [0, 1, 2, 4, 5].map(EntityId::from_raw));
+
+        for (row, entity) in archetype.entities().iter().enumerate() {
+            assert_eq!(
+                archetype.components(row).get(component_id(0)),
+                Some(&component(entity.into_raw() as u8))
+            );
+        }
+    }
+
+    #[test]
+    fn storage_swap_remove_rows() {
+        let mut storage = Storage::default();
+        let entities: Vec<_> = (0..32).map(EntityId::from_raw).collect();
+        for entity in &entities {
+            storage.spawn(*entity);
+            storage.insert(*entity, component_id(0), component(entity.into_raw() as u8));
+        }
+
+        // Entities spawned in ascending order keep the rows sorted.
+        let archetype = storage.archetypes_with(&[component_id(0)]).next().unwrap();
+        assert!(archetype.sorted_rows().is_none());
+
+        // Move every other entity into another archetype and despawn some,
+        // which moves the last rows into the freed rows.
+        for entity in entities.iter().step_by(2) {
+            storage.insert(*entity, component_id(1), component(0));
+        }
+        for entity in entities.iter().step_by(3) {
+            storage.despawn(*entity);
+        }
+        storage.remove(entities[4], component_id(1));
+
+        assert_eq!(storage.len(), 21);
+        for entity in &entities {
+            let Some(components) = storage.get(*entity) else {
+                assert_eq!(entity.into_raw() % 3, 0);
+                continue;
+            };
+
+            assert_eq!(
+                components.get(component_id(0)),
+                Some(&component(entity.into_raw() as u8))
+            );
+
+            let has_component_1 = entity.into_raw() % 2 == 0 && *entity != entities[4];
+            assert_eq!(components.get(component_id(1)).is_some(), has_component_1);
+        }
+    }
+}
@@ -14,13 +14,47 @@ pub struct CommandDescriptor {
 pub enum ServerCommand {
     Uptime,
     Clients,
+    /// Halts the server tick.
+    Pause,
+    /// Continues the server tick after a [`Pause`].
+    ///
+    /// [`Pause`]: Self::Pause
+    Resume,
+    /// Pauses the server tick and advances it by the given number of control frames.
+    Step(u32),
 }
 
 impl ServerCommand {
     pub fn parse(tokens: &[Token<'_>]) -> Result<Self, ParseError> {
-        match tokens.first() {
-            Some(Token::Ident("uptime")) => Ok(Self::Uptime),
-            Some(Token::Ident("clients")) => Ok(Self::Clients),
+        match tokens.split_first() {
+            Some((&Token::Ident("uptime"), _)) => Ok(Self::Uptime),
+            Some((&Token::Ident("clients"), _)) => Ok(Self::Clients),
+            Some((&Token::Ident("pause"), _)) => Ok(Self::Pause),
+            Some((&Token::Ident("resume"), _)) => Ok(Self::Resume),
+            Some((&Token::Ident("step"), mut tokens)) => {
+                // `step` without any arguments advances a single frame.
+                if tokens.is_empty() {
+                    return Ok(Self::Step(1));
+                }
+
+                let Ok(args) = parse_parens(&mut tokens) else {
+                    return Err(ParseError::Msg(
+                        "missing step count, expected step(n)".to_owned(),
+                    ));
+                };
+
+                match args {
+                    [] => Ok(Self::Step(1)),
+                    [Token::Literal(Literal::I64(n))] => match u32::try_from(*n) {
+                        Ok(n) => Ok(Self::Step(n)),
+                        Err(_) => Err(ParseError::Msg(format!("invalid step count {}", n))),
+                    },
+                    [token, ..] => Err(ParseError::Msg(format!(
+                        "expected step count, found {}",
+                        token.kind()
+                    ))),
+                }
+            }
             _ => Err(ParseError::Empty),
         }
     }
@@ -35,6 +69,18 @@ impl ServerCommand {
                 name: "clients",
                 description: "List all clients currently connected to the server",
             },
+            CommandDescriptor {
+                name: "pause",
+                description: "Pause the server tick",
+            },
+            CommandDescriptor {
+                name: "resume",
+                description: "Resume the paused server tick",
+            },
+            CommandDescriptor {
+                name: "step",
+                description: "Pause the server tick and advance it by n control frames",
+            },
         ]
     }
 }
@@ -304,7 +350,7 @@ pub enum TokenizeError {
 
 #[cfg(test)]
 mod tests {
-    use super::{tokenize, Literal, Token};
+    use super::{tokenize, Literal, ParseError, ServerCommand, Token};
 
     #[test]
     fn tokenize_idents_whitespace_separated() {
@@ -366,4 +412,29 @@ mod tests {
 
         assert_eq!(tokenize(input).unwrap(), output);
     }
+
+    #[test]
+    fn parse_server_step() {
+        let cases = [
+            ("step", Some(1)),
+            ("step()", Some(1)),
+            ("step(5)", Some(5)),
+            ("step(x)", None),
+            ("step(-1)", None),
+            ("step(", None),
+            ("step 5", None),
+        ];
+
+        for (input, output) in cases {
+            let tokens = tokenize(input).unwrap();
+            match ServerCommand::parse(&tokens) {
+                Ok(ServerCommand::Step(n)) => assert_eq!(Some(n), output, "{}", input),
+                Ok(cmd) => panic!("unexpected command {:?} for {}", cmd, input),
+                // Malformed arguments must be reported instead of falling back
+                // to an unknown command error.
+                Err(ParseError::Msg(_)) => assert_eq!(output, None, "{}", input),
+                Err(ParseError::Empty) => panic!("no error message for {}", input),
+            }
+        }
+    }
 }
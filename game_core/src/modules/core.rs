@@ -1,13 +1,13 @@
 //! Builtin core module data
 
 use game_common::components::{
-    Collider, DirectionalLight, GlobalTransform, MeshInstance, PointLight, RigidBody, SpotLight,
-    Transform,
+    Collider, CollisionGroups, DirectionalLight, GlobalTransform, MeshInstance, PointLight,
+    RigidBody, SpotLight, Transform,
 };
 use game_common::record::ModuleId;
 use game_common::reflection::{
     ComponentDescriptor, EnumField, EnumFieldVariant, Field, FieldIndex, FieldKind, FloatField,
-    RecordDescriptor,
+    IntegerField, RecordDescriptor,
 };
 use game_data::record::{Record, RecordKind};
 use game_wasm::components::Component;
@@ -47,6 +47,7 @@ pub fn load_core() -> ModuleData {
         PointLight,
         SpotLight,
         Collider,
+        CollisionGroups,
         RigidBody,
         MeshInstance,
     };
@@ -368,6 +369,34 @@ impl Descriptor for Collider {
     }
 }
 
+impl Descriptor for CollisionGroups {
+    fn descriptor() -> ComponentDescriptor {
+        let fields = vec![
+            Field {
+                name: "Memberships".to_owned(),
+                kind: FieldKind::Int(IntegerField {
+                    bits: 32,
+                    is_signed: false,
+                    min: None,
+                    max: None,
+                }),
+            },
+            Field {
+                name: "Filter".to_owned(),
+                kind: FieldKind::Int(IntegerField {
+                    bits: 32,
+                    is_signed: false,
+                    min: None,
+                    max: None,
+                }),
+            },
+        ];
+        let root = vec![FieldIndex::from_raw(0), FieldIndex::from_raw(1)];
+
+        ComponentDescriptor::new(fields, root).unwrap()
+    }
+}
+
 impl Descriptor for RigidBody {
     fn descriptor() -> ComponentDescriptor {
         let fields = vec![
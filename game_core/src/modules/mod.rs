@@ -93,7 +93,12 @@ pub fn load_scripts(executor: &mut Executor, modules: &Modules) {
             let mut buf = Vec::new();
             file.read_to_end(&mut buf).unwrap();
 
-            let handle = match executor.load(&buf) {
+            let id = RecordReference {
+                module: module.id,
+                record: record.id,
+            };
+
+            let handle = match executor.load_record(id, &buf) {
                 Ok(handle) => handle,
                 Err(err) => {
                     tracing::error!(
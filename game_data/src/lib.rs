@@ -1,4 +1,7 @@
 //! Types and (de)serializiers for data files.
+//!
+//! All multi-byte values are encoded in the byte order defined by [`game_common::utils::endian`]
+//! (little-endian), independent of the host.
 
 pub mod header;
 pub mod loader;
@@ -13,6 +16,7 @@ use std::string::FromUtf8Error;
 
 use bytes::{Buf, BufMut};
 use game_common::module::Module;
+use game_common::utils::endian::Primitive;
 use header::{Header, HeaderError};
 use record::{Record, RecordError};
 use thiserror::Error;
@@ -60,7 +64,7 @@ macro_rules! int_impls {
                 fn encode<B>(&self, buf: B)
                     where B: BufMut,
                 {
-                    self.to_le_bytes().encode(buf);
+                    Primitive::to_bytes(*self).encode(buf);
                 }
             }
 
@@ -77,7 +81,7 @@ macro_rules! int_impls {
                         expected: err.expected,
                     })?;
 
-                    Ok(Self::from_le_bytes(bytes))
+                    Ok(Primitive::from_bytes(bytes))
                 }
             }
         )*
@@ -347,8 +351,14 @@ impl Decode for DataBuffer {
 
 #[cfg(test)]
 mod tests {
+    use game_common::module::{Dependency, Module, ModuleId, Version};
+    use game_common::record::RecordId;
 
-    use super::{Decode, Encode};
+    use crate::header::Header;
+    use crate::record::{Record, RecordKind};
+    use crate::varint::VarU64;
+
+    use super::{DataBuffer, Decode, Encode};
 
     #[test]
     fn test_array_decode() {
@@ -384,6 +394,10 @@ mod tests {
         let buf = 1234u32.to_le_bytes();
         assert_eq!(u32::decode(&buf[..]).unwrap(), 1234);
 
+        // Must decode the same on any host.
+        let buf = [0x78, 0x56, 0x34, 0x12];
+        assert_eq!(u32::decode(&buf[..]).unwrap(), 0x1234_5678);
+
         let buf = [0; 5];
         assert_eq!(u32::decode(&buf[..]).unwrap(), 0);
 
@@ -417,4 +431,91 @@ mod tests {
 
         assert_eq!(buf, output);
     }
+
+    fn create_data_buffer() -> DataBuffer {
+        let mut buffer = DataBuffer::new(Module {
+            id: ModuleId::CORE,
+            name: String::from("test"),
+            version: Version::new(1, 2, 3),
+            dependencies: vec![Dependency {
+                id: ModuleId::CORE,
+                name: Some(String::from("dep")),
+                version: Version::new(0, 0, 0),
+            }],
+        });
+
+        for (id, kind) in [
+            RecordKind::COMPONENT,
+            RecordKind::PREFAB,
+            RecordKind::SCRIPT,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            buffer.records.push(Record {
+                id: RecordId(id as u32),
+                kind,
+                name: format!("record {}", id),
+                description: String::from("description"),
+                data: (0..id as u8 * 16).collect(),
+            });
+        }
+
+        buffer
+    }
+
+    #[test]
+    fn test_data_buffer_decode_truncated() {
+        let mut buf = Vec::new();
+        create_data_buffer().encode(&mut buf);
+
+        let res = DataBuffer::decode(&buf[..]).unwrap();
+        assert_eq!(res.records.len(), 3);
+
+        for len in 0..buf.len() {
+            DataBuffer::decode(&buf[..len]).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_header_decode_truncated() {
+        let mut buf = Vec::new();
+        create_data_buffer().header.encode(&mut buf);
+
+        Header::decode(&buf[..]).unwrap();
+
+        for len in 0..buf.len() {
+            Header::decode(&buf[..len]).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_record_decode_truncated() {
+        for record in create_data_buffer().records {
+            let mut buf = Vec::new();
+            record.encode(&mut buf);
+
+            Record::decode(&buf[..]).unwrap();
+
+            for len in 0..buf.len() {
+                Record::decode(&buf[..len]).unwrap_err();
+            }
+        }
+    }
+
+    #[test]
+    fn test_list_decode_length_exceeds_input() {
+        let mut buf = Vec::new();
+        VarU64(u64::MAX >> 1).encode(&mut buf);
+        buf.extend_from_slice(&[0; 16]);
+
+        Vec::<u8>::decode(&buf[..]).unwrap_err();
+        String::decode(&buf[..]).unwrap_err();
+    }
+
+    #[test]
+    fn test_varint_decode_overflow() {
+        let buf = [0xFF; 16];
+        VarU64::decode(&buf[..]).unwrap_err();
+    }
 }
@@ -376,7 +376,7 @@ impl WorldWindowState {
     }
 
     pub fn components(&self, entity: EntityId) -> Components {
-        self.state.world.components(entity).clone()
+        self.state.world.components(entity).to_components()
     }
 
     pub fn pop_event(&mut self) -> Option<WorldEvent> {
@@ -6,6 +6,8 @@ var<storage> lines: array<Vertex>;
 
 struct Vertex {
     position: vec3<f32>,
+    // Non-zero if `position` is already in normalized device coordinates.
+    screen_space: u32,
     color: vec4<f32>,
 }
 
@@ -29,7 +31,11 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     let vertex = lines[in.instance_index * 2u + in.vertex_index];
 
     var out: VertexOutput;
-    out.clip_position = camera.view_proj * vec4<f32>(vertex.position, 1.0);
+    if vertex.screen_space != 0u {
+        out.clip_position = vec4<f32>(vertex.position, 1.0);
+    } else {
+        out.clip_position = camera.view_proj * vec4<f32>(vertex.position, 1.0);
+    }
     out.color = vertex.color;
 
     return out;
@@ -17,7 +17,7 @@ use glam::Vec3;
 use parking_lot::Mutex;
 use parking_lot::RwLock;
 use render::pipeline::GizmoPass;
-use render::DrawCommand;
+use render::{DrawCommand, DrawCommands, TextCommand};
 
 const GIZMO_PASS: NodeLabel = NodeLabel::new("GIZMO_PASS");
 
@@ -26,32 +26,33 @@ const GIZMO_PASS: NodeLabel = NodeLabel::new("GIZMO_PASS");
 pub struct Gizmos {
     camera: Arc<Mutex<Option<Camera>>>,
     /// Elements that are currently being rendered.
-    current: Arc<RwLock<Vec<DrawCommand>>>,
+    current: Arc<RwLock<DrawCommands>>,
     /// Elements queued for the next render submission.
-    next: Mutex<Vec<DrawCommand>>,
+    next: Mutex<DrawCommands>,
 }
 
 impl Gizmos {
     /// Creates a new `Gizmos` renderer.
     pub fn new(renderer: &mut Renderer) -> Self {
-        let elements = Arc::new(RwLock::new(Vec::new()));
+        let elements = Arc::new(RwLock::new(DrawCommands::default()));
         let camera = Arc::new(Mutex::new(None));
 
-        let node = GizmoPass::new(renderer.device(), elements.clone(), camera.clone());
-        let mut graph = renderer.graph_mut();
-        graph.add_node(GIZMO_PASS, node);
-        graph.add_node_dependency(GIZMO_PASS, FINAL_RENDER_PASS);
+        let pass = GizmoPass::new(renderer.device(), elements.clone(), camera.clone());
+        renderer.add_pass(GIZMO_PASS, pass, &[FINAL_RENDER_PASS]);
 
         Self {
             current: elements,
             camera,
-            next: Mutex::new(Vec::new()),
+            next: Mutex::new(DrawCommands::default()),
         }
     }
 
     /// Draw a line from `start` to `end`.
     pub fn line(&self, start: Vec3, end: Vec3, color: Color) {
-        self.next.lock().push(DrawCommand { start, end, color });
+        self.next
+            .lock()
+            .lines
+            .push(DrawCommand { start, end, color });
     }
 
     pub fn sphere(&self, center: Vec3, radius: f32, color: Color) {
@@ -60,6 +61,54 @@ impl Gizmos {
         self.circle(center, Vec3::Z, radius, color);
     }
 
+    /// Draws a capsule at `center` with the given `rotation`.
+    ///
+    /// The capsule consists of two hemispheres with the given `radius` that are `2 * half_height`
+    /// apart along the axis of the capsule. Without any `rotation` the capsule axis is `Vec3::Y`.
+    /// This matches the capsule colliders in `game_physics`: a capsule with [`Axis::Y`] is drawn
+    /// with `Quat::IDENTITY`, while [`Axis::X`] and [`Axis::Z`] require an additional rotation
+    /// that maps `Vec3::Y` onto the respective axis.
+    ///
+    /// `rotation` must be normalized.
+    ///
+    /// [`Axis::X`]: game_common::components::Axis::X
+    /// [`Axis::Y`]: game_common::components::Axis::Y
+    /// [`Axis::Z`]: game_common::components::Axis::Z
+    pub fn capsule(
+        &self,
+        center: Vec3,
+        rotation: Quat,
+        half_height: f32,
+        radius: f32,
+        color: Color,
+    ) {
+        debug_assert!(rotation.is_normalized());
+
+        let axis = rotation * Vec3::Y;
+        let top = center + axis * half_height;
+        let bottom = center - axis * half_height;
+
+        // The rings where the hemispheres meet the cylinder.
+        self.circle(top, axis, radius, color);
+        self.circle(bottom, axis, radius, color);
+
+        // `arc` draws in the XZ plane starting at `Vec3::X`. Rotate the
+        // arcs so that they pass through the capsule axis, once in the
+        // XY and once in the ZY plane.
+        for (hemisphere, direction) in [(top, 1.0), (bottom, -1.0)] {
+            let xy = Quat::from_rotation_x(direction * PI / 2.0);
+            let zy = Quat::from_rotation_y(-PI / 2.0) * xy;
+
+            self.arc(hemisphere, rotation * xy, PI, radius, color);
+            self.arc(hemisphere, rotation * zy, PI, radius, color);
+        }
+
+        for offset in [Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z] {
+            let offset = rotation * offset * radius;
+            self.line(bottom + offset, top + offset, color);
+        }
+    }
+
     /// Draws a circle at `center` with the given `radius`.
     ///
     /// `normal` must be normalized.
@@ -82,7 +131,7 @@ impl Gizmos {
             let end =
                 center + Quat::from_axis_angle(normal, STEP_ANGLE * (index + 1) as f32) * forward;
 
-            cmds.push(DrawCommand { start, end, color });
+            cmds.lines.push(DrawCommand { start, end, color });
         }
     }
 
@@ -111,10 +160,26 @@ impl Gizmos {
             let end =
                 center + Quat::from_axis_angle(normal, step_angle * (index + 1) as f32) * forward;
 
-            cmds.push(DrawCommand { start, end, color });
+            cmds.lines.push(DrawCommand { start, end, color });
         }
     }
 
+    /// Draws a text label at the world `position`.
+    ///
+    /// The label is drawn in screen space with a fixed size, always facing the camera and
+    /// horizontally centered on `position`. Labels with a `position` behind the camera are not
+    /// drawn.
+    ///
+    /// Only ASCII letters, digits and common punctuation are supported. Lowercase letters are
+    /// drawn as uppercase letters and unsupported characters are drawn as a box.
+    pub fn text(&self, position: Vec3, text: &str, color: Color) {
+        self.next.lock().texts.push(TextCommand {
+            position,
+            text: text.to_owned(),
+            color,
+        });
+    }
+
     /// Update the camera position from which the gizmo renderer draws 3D objects.
     pub fn update_camera(&self, camera: Camera) {
         *self.camera.lock() = Some(camera);
@@ -134,3 +199,51 @@ impl Gizmos {
         next.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use game_common::components::Color;
+    use glam::{Quat, Vec3};
+    use parking_lot::{Mutex, RwLock};
+
+    use crate::render::DrawCommands;
+    use crate::Gizmos;
+
+    fn create_gizmos() -> Gizmos {
+        Gizmos {
+            camera: Arc::new(Mutex::new(None)),
+            current: Arc::new(RwLock::new(DrawCommands::default())),
+            next: Mutex::new(DrawCommands::default()),
+        }
+    }
+
+    #[test]
+    fn capsule_on_surface() {
+        let gizmos = create_gizmos();
+
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let rotation = Quat::from_rotation_z(0.7) * Quat::from_rotation_x(0.3);
+        let half_height = 2.0;
+        let radius = 0.5;
+        gizmos.capsule(center, rotation, half_height, radius, Color::WHITE);
+
+        let axis = rotation * Vec3::Y;
+        let mut max_height = 0.0f32;
+
+        for line in &gizmos.next.lock().lines {
+            for point in [line.start, line.end] {
+                let height = (point - center).dot(axis);
+                max_height = max_height.max(height.abs());
+
+                // Distance to the line segment along the capsule axis.
+                let segment = center + axis * height.clamp(-half_height, half_height);
+                let distance = point.distance(segment);
+                assert!((distance - radius).abs() < 1e-4);
+            }
+        }
+
+        assert!((max_height - (half_height + radius)).abs() < 1e-4);
+    }
+}
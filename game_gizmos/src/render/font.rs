@@ -0,0 +1,143 @@
+//! A minimal stroke font for drawing text using lines.
+//!
+//! Every glyph is defined on a grid of [`GLYPH_WIDTH`] x [`GLYPH_HEIGHT`] units with the origin
+//! at the bottom-left. A glyph consists of a list of strokes separated by spaces, every stroke
+//! being a polyline of points encoded as two digits `xy`.
+
+use glam::Vec2;
+
+/// The width of a glyph in grid units.
+pub(crate) const GLYPH_WIDTH: f32 = 4.0;
+
+/// The height of a glyph in grid units.
+pub(crate) const GLYPH_HEIGHT: f32 = 6.0;
+
+/// The horizontal distance between the origins of two glyphs in grid units.
+pub(crate) const GLYPH_ADVANCE: f32 = GLYPH_WIDTH + 2.0;
+
+/// The glyph drawn for characters without a glyph.
+const FALLBACK: &str = "0006464000";
+
+const GLYPHS: &[(char, &str)] = &[
+    ('A', "0004264440 0343"),
+    ('B', "00063645443303 3342413000"),
+    ('C', "461605010040"),
+    ('D', "00063645413000"),
+    ('E', "46060040 0333"),
+    ('F', "460600 0333"),
+    ('G', "45361605010030414323"),
+    ('H', "0006 4046 0343"),
+    ('I', "1636 2620 1030"),
+    ('J', "4641301001"),
+    ('K', "0006 4602 1340"),
+    ('L', "060040"),
+    ('M', "0006234640"),
+    ('N', "00064046"),
+    ('O', "100105163645413010"),
+    ('P', "00063645443303"),
+    ('Q', "100105163645413010 2240"),
+    ('R', "00063645443303 2340"),
+    ('S', "453616050413334241301001"),
+    ('T', "0646 2620"),
+    ('U', "060110304146"),
+    ('V', "062046"),
+    ('W', "0610233046"),
+    ('X', "0046 0640"),
+    ('Y', "062346 2320"),
+    ('Z', "06460040"),
+    ('0', "100105163645413010 0145"),
+    ('1', "152620 1030"),
+    ('2', "05163645440040"),
+    ('3', "0516364544334241301001 1333"),
+    ('4', "30360242"),
+    ('5', "4606033342413000"),
+    ('6', "4536160501103041423303"),
+    ('7', "064610"),
+    ('8', "1304051636454433130201103041423233"),
+    ('9', "4313040516364541301001"),
+    ('.', "2021"),
+    (',', "2110"),
+    (':', "2122 2425"),
+    ('-', "1333"),
+    ('_', "0040"),
+    ('+', "1333 2224"),
+    ('=', "1232 1434"),
+    ('(', "36141230"),
+    (')', "16343210"),
+    ('[', "36161030"),
+    (']', "16363010"),
+    ('/', "0046"),
+    ('\\', "0640"),
+    ('?', "05163645442322 2021"),
+    ('!', "2622 2021"),
+    ('\'', "2624"),
+    ('"', "1614 3634"),
+    ('<', "460340"),
+    ('>', "064300"),
+    ('*', "2125 0244 0442"),
+    ('#', "1016 3036 0242 0444"),
+    ('%', "0046 0605 4041"),
+];
+
+/// Returns an iterator over the line segments of the glyph for `c` in grid units.
+///
+/// Lowercase characters are drawn using their uppercase glyphs. Whitespace has no line segments.
+pub(crate) fn glyph_lines(c: char) -> impl Iterator<Item = (Vec2, Vec2)> {
+    let strokes = if c.is_whitespace() {
+        ""
+    } else {
+        let c = c.to_ascii_uppercase();
+        GLYPHS
+            .iter()
+            .find(|(glyph, _)| *glyph == c)
+            .map(|(_, strokes)| *strokes)
+            .unwrap_or(FALLBACK)
+    };
+
+    strokes.split(' ').flat_map(|stroke| {
+        let points = stroke
+            .as_bytes()
+            .chunks_exact(2)
+            .map(|point| Vec2::new(f32::from(point[0] - b'0'), f32::from(point[1] - b'0')));
+
+        points.clone().zip(points.skip(1))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glyph_lines, FALLBACK, GLYPHS, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+    #[test]
+    fn glyphs_valid() {
+        for (c, strokes) in GLYPHS.iter().copied().chain([('\0', FALLBACK)]) {
+            for stroke in strokes.split(' ') {
+                assert!(
+                    stroke.len() >= 4,
+                    "{:?}: stroke {:?} has no line",
+                    c,
+                    stroke
+                );
+                assert!(
+                    stroke.len() % 2 == 0,
+                    "{:?}: invalid stroke {:?}",
+                    c,
+                    stroke
+                );
+            }
+
+            for (start, end) in glyph_lines(c) {
+                for point in [start, end] {
+                    assert!(point.x <= GLYPH_WIDTH && point.y <= GLYPH_HEIGHT, "{:?}", c);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn glyph_lines_lowercase() {
+        assert!(glyph_lines('a').eq(glyph_lines('A')));
+        assert_eq!(glyph_lines(' ').count(), 0);
+        assert_eq!(glyph_lines('-').count(), 1);
+    }
+}
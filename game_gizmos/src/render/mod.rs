@@ -1,3 +1,4 @@
+pub(crate) mod font;
 pub(crate) mod pipeline;
 
 use game_common::components::Color;
@@ -9,3 +10,23 @@ pub(crate) struct DrawCommand {
     pub(crate) end: Vec3,
     pub(crate) color: Color,
 }
+
+#[derive(Clone, Debug)]
+pub(crate) struct TextCommand {
+    pub(crate) position: Vec3,
+    pub(crate) text: String,
+    pub(crate) color: Color,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DrawCommands {
+    pub(crate) lines: Vec<DrawCommand>,
+    pub(crate) texts: Vec<TextCommand>,
+}
+
+impl DrawCommands {
+    pub(crate) fn clear(&mut self) {
+        self.lines.clear();
+        self.texts.clear();
+    }
+}
@@ -2,9 +2,10 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use bytemuck::{Pod, Zeroable};
-use game_render::camera::{Camera, CameraUniform};
-use game_render::graph::{Node, RenderContext};
+use game_render::camera::{Camera, CameraUniform, OPENGL_TO_WGPU};
+use game_render::pass::{PassContext, RenderPass};
 use game_tracing::trace_span;
+use glam::{Mat4, UVec2, Vec2, Vec3};
 use parking_lot::{Mutex, RwLock};
 use wgpu::util::{BufferInitDescriptor, DeviceExt, DrawIndirectArgs};
 use wgpu::{
@@ -17,10 +18,14 @@ use wgpu::{
     ShaderStages, StoreOp, TextureFormat, VertexState,
 };
 
-use super::DrawCommand;
+use super::font::{self, GLYPH_ADVANCE, GLYPH_HEIGHT};
+use super::{DrawCommands, TextCommand};
 
 const SHADER: &str = include_str!("../../shaders/line.wgsl");
 
+/// The height of text labels in pixels.
+const TEXT_HEIGHT: f32 = 12.0;
+
 pub struct GizmoPipeline {
     bind_group_layout: BindGroupLayout,
     pipeline_layout: PipelineLayout,
@@ -112,14 +117,14 @@ impl GizmoPipeline {
 pub struct GizmoPass {
     pipeline: GizmoPipeline,
     camera: Arc<Mutex<Option<Camera>>>,
-    elements: Arc<RwLock<Vec<DrawCommand>>>,
+    elements: Arc<RwLock<DrawCommands>>,
     vertex_buffer: Mutex<Vec<Vertex>>,
 }
 
 impl GizmoPass {
     pub(crate) fn new(
         device: &Device,
-        elements: Arc<RwLock<Vec<DrawCommand>>>,
+        elements: Arc<RwLock<DrawCommands>>,
         camera: Arc<Mutex<Option<Camera>>>,
     ) -> Self {
         Self {
@@ -130,36 +135,50 @@ impl GizmoPass {
         }
     }
 
-    fn update_buffers(&self) {
+    fn update_buffers(&self, camera: &Camera, size: UVec2) {
         let cmds = self.elements.read();
 
         let mut vertex_buffer = self.vertex_buffer.lock();
         vertex_buffer.clear();
 
-        for cmd in &*cmds {
+        for cmd in &cmds.lines {
             vertex_buffer.push(Vertex {
                 position: cmd.start.to_array(),
                 color: cmd.color.as_rgba(),
-                _pad0: 0,
+                screen_space: 0,
             });
             vertex_buffer.push(Vertex {
                 position: cmd.end.to_array(),
                 color: cmd.color.as_rgba(),
-                _pad0: 0,
+                screen_space: 0,
             });
         }
+
+        if !cmds.texts.is_empty() {
+            let view_proj = OPENGL_TO_WGPU
+                * camera.projection.projection_matrix()
+                * Mat4::look_to_rh(
+                    camera.transform.translation,
+                    camera.transform.rotation * -Vec3::Z,
+                    camera.transform.rotation * Vec3::Y,
+                );
+
+            for cmd in &cmds.texts {
+                push_text(&mut vertex_buffer, cmd, view_proj, size);
+            }
+        }
     }
 }
 
-impl Node for GizmoPass {
-    fn render(&self, ctx: &mut RenderContext<'_, '_>) {
+impl RenderPass for GizmoPass {
+    fn render(&self, ctx: &mut PassContext<'_>) {
         let _span = trace_span!("GizmoPass::render").entered();
 
         let Some(camera) = *self.camera.lock() else {
             return;
         };
 
-        self.update_buffers();
+        self.update_buffers(&camera, ctx.size());
         let vertex_buffer = self.vertex_buffer.lock();
 
         // Don't start a render pass with 0 vertices, this will cause problems
@@ -168,7 +187,7 @@ impl Node for GizmoPass {
             return;
         }
 
-        let camera_buffer = ctx.device.create_buffer_init(&BufferInitDescriptor {
+        let camera_buffer = ctx.device().create_buffer_init(&BufferInitDescriptor {
             label: None,
             contents: bytemuck::cast_slice(&[CameraUniform::new(
                 camera.transform,
@@ -177,13 +196,13 @@ impl Node for GizmoPass {
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
         });
 
-        let vertices = ctx.device.create_buffer_init(&BufferInitDescriptor {
+        let vertices = ctx.device().create_buffer_init(&BufferInitDescriptor {
             label: None,
             contents: bytemuck::cast_slice(&vertex_buffer),
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
         });
 
-        let bg = ctx.device.create_bind_group(&BindGroupDescriptor {
+        let bg = ctx.device().create_bind_group(&BindGroupDescriptor {
             label: None,
             layout: &self.pipeline.bind_group_layout,
             entries: &[
@@ -198,7 +217,7 @@ impl Node for GizmoPass {
             ],
         });
 
-        let indirect_buffer = ctx.device.create_buffer_init(&BufferInitDescriptor {
+        let indirect_buffer = ctx.device().create_buffer_init(&BufferInitDescriptor {
             label: None,
             contents: DrawIndirectArgs {
                 vertex_count: 2,
@@ -211,19 +230,20 @@ impl Node for GizmoPass {
         });
 
         let mut pipelines = self.pipeline.pipelines.lock();
-        let render_pipeline = match pipelines.get(&ctx.format) {
+        let render_pipeline = match pipelines.get(&ctx.format()) {
             Some(pl) => pl,
             None => {
-                let pl = self.pipeline.build_pipeline(ctx.format, ctx.device);
-                pipelines.insert(ctx.format, pl);
-                pipelines.get(&ctx.format).unwrap()
+                let pl = self.pipeline.build_pipeline(ctx.format(), ctx.device());
+                pipelines.insert(ctx.format(), pl);
+                pipelines.get(&ctx.format()).unwrap()
             }
         };
 
-        let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
+        let target = ctx.target();
+        let mut render_pass = ctx.encoder().begin_render_pass(&RenderPassDescriptor {
             label: Some("gizmo_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: ctx.target,
+                view: target,
                 resolve_target: None,
                 ops: Operations {
                     load: LoadOp::Load,
@@ -242,10 +262,46 @@ impl Node for GizmoPass {
     }
 }
 
+/// Pushes the lines of a text label in normalized device coordinates.
+fn push_text(vertex_buffer: &mut Vec<Vertex>, cmd: &TextCommand, view_proj: Mat4, size: UVec2) {
+    let clip = view_proj * cmd.position.extend(1.0);
+
+    // The position is behind the camera. Projecting it would mirror the label
+    // onto the screen.
+    if clip.w <= 0.0 {
+        return;
+    }
+
+    let ndc = clip.truncate() / clip.w;
+
+    // Converts from glyph grid units into NDC.
+    let scale = TEXT_HEIGHT / GLYPH_HEIGHT * 2.0 / size.as_vec2();
+
+    let width = cmd.text.chars().count() as f32 * GLYPH_ADVANCE;
+    let mut origin = Vec2::new(-width / 2.0, 0.0);
+
+    for c in cmd.text.chars() {
+        for (start, end) in font::glyph_lines(c) {
+            for point in [start, end] {
+                let position = ndc.truncate() + (origin + point) * scale;
+
+                vertex_buffer.push(Vertex {
+                    position: position.extend(ndc.z).to_array(),
+                    color: cmd.color.as_rgba(),
+                    screen_space: 1,
+                });
+            }
+        }
+
+        origin.x += GLYPH_ADVANCE;
+    }
+}
+
 #[derive(Copy, Clone, Debug, Zeroable, Pod)]
 #[repr(C)]
 struct Vertex {
     position: [f32; 3],
-    _pad0: u32,
+    /// Whether `position` is already in normalized device coordinates.
+    screen_space: u32,
     color: [f32; 4],
 }
@@ -1,14 +1,12 @@
 use std::marker::PhantomData;
 
-pub type Positions = [f32; 3];
 pub type Normals = [f32; 3];
 pub type Tangents = [f32; 4];
-pub type Uvs = [f32; 2];
 
 use bytes::Buf;
 use gltf::Accessor;
 
-use crate::{EofError, GltfStagingData};
+use crate::{EofError, Error, GltfStagingData};
 
 pub trait Item: Sized + Copy {
     fn from_slice(buf: &[u8]) -> Self;
@@ -46,6 +44,36 @@ impl Item for u16 {
     }
 }
 
+impl Item for i8 {
+    fn from_slice(buf: &[u8]) -> Self {
+        buf[0] as i8
+    }
+
+    fn is_valid(self, min: Option<Self>, max: Option<Self>) -> bool {
+        match (min, max) {
+            (Some(min), Some(max)) => self >= min && self <= max,
+            (Some(min), None) => self >= min,
+            (None, Some(max)) => self <= max,
+            (None, None) => true,
+        }
+    }
+}
+
+impl Item for i16 {
+    fn from_slice(mut buf: &[u8]) -> Self {
+        buf.get_i16_le()
+    }
+
+    fn is_valid(self, min: Option<Self>, max: Option<Self>) -> bool {
+        match (min, max) {
+            (Some(min), Some(max)) => self >= min && self <= max,
+            (Some(min), None) => self >= min,
+            (None, Some(max)) => self <= max,
+            (None, None) => true,
+        }
+    }
+}
+
 impl Item for u32 {
     fn from_slice(mut buf: &[u8]) -> Self {
         buf.get_u32_le()
@@ -253,6 +281,40 @@ where
     }
 }
 
+/// An integer component type that can be normalized to a float.
+pub trait Normalized: Item {
+    /// Converts the normalized integer into a float.
+    ///
+    /// Unsigned integers are mapped to `[0.0, 1.0]`, signed integers to `[-1.0, 1.0]`.
+    fn to_f32(self) -> f32;
+}
+
+// The conversions follow the normalization rules of the glTF 2.0 specification.
+// Signed integers are clamped since the minimum value would be slightly below -1.0.
+impl Normalized for u8 {
+    fn to_f32(self) -> f32 {
+        f32::from(self) / 255.0
+    }
+}
+
+impl Normalized for u16 {
+    fn to_f32(self) -> f32 {
+        f32::from(self) / 65535.0
+    }
+}
+
+impl Normalized for i8 {
+    fn to_f32(self) -> f32 {
+        (f32::from(self) / 127.0).max(-1.0)
+    }
+}
+
+impl Normalized for i16 {
+    fn to_f32(self) -> f32 {
+        (f32::from(self) / 32767.0).max(-1.0)
+    }
+}
+
 pub struct ItemReader<'a, T>
 where
     T: Item,
@@ -270,13 +332,11 @@ where
         semantic: &'static str,
         accessor: &Accessor<'_>,
         data: &'a GltfStagingData,
-    ) -> Result<Self, EofError> {
+    ) -> Result<Self, Error> {
         let view = accessor.view().unwrap();
         let buffer = view.buffer();
 
-        let buffer = data
-            .buffer(buffer.source(), view.offset(), view.length())
-            .unwrap();
+        let buffer = data.buffer(buffer.source(), view.offset(), view.length())?;
 
         let stride = view.stride().unwrap_or(size_of::<T>());
 
@@ -291,7 +351,8 @@ where
                 semantic,
                 bytes_avail,
                 bytes_required,
-            });
+            }
+            .into());
         };
 
         Ok(Self {
@@ -322,3 +383,29 @@ where
         Some(T::from_slice(item))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Normalized;
+
+    #[test]
+    fn normalized_unsigned() {
+        assert_eq!(0u8.to_f32(), 0.0);
+        assert_eq!(255u8.to_f32(), 1.0);
+        assert_eq!(0u16.to_f32(), 0.0);
+        assert_eq!(65535u16.to_f32(), 1.0);
+    }
+
+    #[test]
+    fn normalized_signed() {
+        assert_eq!(0i8.to_f32(), 0.0);
+        assert_eq!(127i8.to_f32(), 1.0);
+        assert_eq!((-127i8).to_f32(), -1.0);
+        assert_eq!((-128i8).to_f32(), -1.0);
+
+        assert_eq!(0i16.to_f32(), 0.0);
+        assert_eq!(32767i16.to_f32(), 1.0);
+        assert_eq!((-32767i16).to_f32(), -1.0);
+        assert_eq!((-32768i16).to_f32(), -1.0);
+    }
+}
@@ -19,12 +19,13 @@ use std::io::Read;
 use std::ops::Range;
 use std::path::Path;
 
-use accessor::{ItemReader, Normals, Positions, Tangents, Uvs};
+use accessor::{Item, ItemReader, Normalized, Normals, Tangents};
 use base64::alphabet::STANDARD;
 use base64::engine::GeneralPurpose;
 use base64::engine::GeneralPurposeConfig;
 use base64::Engine;
 use game_common::components::{Color, Transform};
+use game_common::math::compute_aabb;
 use game_core::hierarchy::Hierarchy;
 use game_render::texture::{Image, TextureFormat};
 use game_tracing::trace_span;
@@ -32,10 +33,12 @@ use glam::{Quat, UVec2, Vec2, Vec3, Vec4};
 use gltf::accessor::DataType;
 use gltf::accessor::Dimensions;
 use gltf::buffer::Source;
+use gltf::material::PbrSpecularGlossiness;
 use gltf::mesh::Mode;
 use gltf::Material;
 use gltf::Node;
-use gltf::{Accessor, Gltf, Semantic};
+use gltf::{Accessor, Buffer, Gltf, Semantic};
+use image::{Rgba, RgbaImage};
 use mime::InvalidMimeType;
 use mime::MimeType;
 use serde_json::{Number, Value};
@@ -86,6 +89,25 @@ pub struct GltfDecoder {
     gltf: Gltf,
     buffers: HashMap<String, Vec<u8>>,
     external_sources: HashSet<String>,
+    node_layout: NodeLayout,
+    smoothing_angle: Option<f32>,
+}
+
+/// The layout of the [`GltfNode`]s created from the nodes of a glTF scene.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NodeLayout {
+    /// Every primitive of a glTF node becomes a separate [`GltfNode`] with a single `mesh` and
+    /// `material`.
+    ///
+    /// Every glTF node is represented by a placeholder [`GltfNode`] without a mesh, which
+    /// contains the [`GltfNode`]s of its primitives and its children.
+    #[default]
+    Flatten,
+    /// Every glTF node becomes exactly one [`GltfNode`] with all its primitives stored in
+    /// [`GltfNode::primitives`].
+    ///
+    /// The hierarchy of the [`GltfNode`]s is the same as the hierarchy of the glTF nodes.
+    Preserve,
 }
 
 impl GltfDecoder {
@@ -107,13 +129,29 @@ impl GltfDecoder {
         for buffer in gltf.buffers() {
             match buffer.source() {
                 Source::Bin => {
-                    buffers.insert(String::from(""), gltf.blob.clone().unwrap());
+                    // Only the first buffer may refer to the binary chunk of a glb file.
+                    // All other buffers must have an URI.
+                    if buffer.index() != 0 {
+                        return Err(Error::InvalidBinBuffer {
+                            index: buffer.index(),
+                        });
+                    }
+
+                    // A missing binary blob is reported once the buffer is accessed.
+                    if let Some(mut blob) = gltf.blob.clone() {
+                        // The binary chunk may be padded to a multiple of 4 bytes.
+                        validate_buffer_length(&buffer, blob.len())?;
+                        blob.truncate(buffer.length());
+
+                        buffers.insert(String::from(""), blob);
+                    }
                 }
                 Source::Uri(uri) => {
                     if let Some(data) = uri.strip_prefix(BASE64_PREFIX) {
                         let engine = GeneralPurpose::new(&STANDARD, GeneralPurposeConfig::new());
                         let buf = engine.decode(data)?;
 
+                        validate_buffer_length(&buffer, buf.len())?;
                         buffers.insert(uri.to_owned(), buf);
                     } else {
                         external_sources.insert(uri.to_owned());
@@ -141,9 +179,28 @@ impl GltfDecoder {
             gltf,
             buffers,
             external_sources,
+            node_layout: NodeLayout::default(),
+            smoothing_angle: None,
         })
     }
 
+    /// Sets the [`NodeLayout`] used to create the [`GltfNode`]s of all scenes.
+    ///
+    /// Defaults to [`NodeLayout::Flatten`].
+    pub fn set_node_layout(&mut self, layout: NodeLayout) {
+        self.node_layout = layout;
+    }
+
+    /// Sets the angle in radians used to recompute the normals of all meshes.
+    ///
+    /// If `Some`, the normals of all meshes are recomputed using [`GltfMesh::smooth_normals`]
+    /// with the given angle as threshold. If `None` the normals of the glTF file are kept.
+    ///
+    /// Defaults to `None`.
+    pub fn set_smoothing_angle(&mut self, angle: Option<f32>) {
+        self.smoothing_angle = angle;
+    }
+
     pub fn pop_source(&mut self) -> Option<String> {
         self.external_sources.iter().nth(0).cloned()
     }
@@ -196,8 +253,23 @@ impl GltfDecoder {
     pub fn finish(self) -> Result<GltfData, Error> {
         let _span = trace_span!("GltfDecoder::finish").entered();
 
+        // External buffers are only available once all sources were loaded.
+        for buffer in self.gltf.buffers() {
+            if let Source::Uri(uri) = buffer.source() {
+                if let Some(buf) = self.buffers.get(uri) {
+                    validate_buffer_length(&buffer, buf.len())?;
+                }
+            }
+        }
+
         let mut data = GltfStagingData::new(self.buffers);
-        data.finish(self.gltf)?;
+        data.finish(self.gltf, self.node_layout)?;
+
+        if let Some(angle) = self.smoothing_angle {
+            for mesh in data.meshes.values_mut() {
+                mesh.smooth_normals(angle);
+            }
+        }
 
         Ok(GltfData {
             scenes: data.scenes,
@@ -209,6 +281,18 @@ impl GltfDecoder {
     }
 }
 
+fn validate_buffer_length(buffer: &Buffer<'_>, length: usize) -> Result<(), Error> {
+    if length < buffer.length() {
+        return Err(Error::InvalidBufferLength {
+            index: buffer.index(),
+            byte_length: buffer.length(),
+            length,
+        });
+    }
+
+    Ok(())
+}
+
 /// An error that can occur when loading an GLTF file.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -242,6 +326,20 @@ pub enum Error {
     EofReadingBuffer(#[from] EofError),
     #[error("invalid indices: {0}")]
     InvalidIndicies(#[from] InvalidIndices),
+    /// The buffer with the given `uri` was never loaded. An empty `uri` refers to the binary blob
+    /// of a glb file.
+    #[error("missing buffer {uri:?}")]
+    MissingBuffer { uri: String },
+    /// A buffer other than the first buffer refers to the binary blob of a glb file.
+    #[error("buffer {index} refers to the binary blob")]
+    InvalidBinBuffer { index: usize },
+    /// The buffer contains fewer bytes than declared by its `byteLength`.
+    #[error("buffer {index} has length {length}, but declares a byteLength of {byte_length}")]
+    InvalidBufferLength {
+        index: usize,
+        byte_length: usize,
+        length: usize,
+    },
 }
 
 /// An error returned when reaching an eof while accessing a buffer.
@@ -280,16 +378,38 @@ pub struct GltfData {
     pub meshes: HashMap<MeshIndex, GltfMesh>,
     pub materials: HashMap<MaterialIndex, GltfMaterial>,
     pub images: HashMap<TextureIndex, Image>,
+    /// The index of the default scene as declared by the glTF file.
+    ///
+    /// Unlike [`default_scene`] this is `None` if the file declares no default scene, even if it
+    /// contains scenes.
+    ///
+    /// [`default_scene`]: Self::default_scene
     pub default_scene: Option<usize>,
 }
 
 impl GltfData {
     /// Returns the default scene.
+    ///
+    /// If the file declares no default scene the first scene is used instead, like most glTF
+    /// viewers do. Returns `None` only if the file contains no scenes. Use the
+    /// [`default_scene`](Self::default_scene) field to get the declared default scene without
+    /// the fallback.
     pub fn default_scene(&self) -> Option<&GltfScene> {
-        self.default_scene
+        self.default_scene_index()
             .map(|index| self.scenes.get(index).unwrap())
     }
 
+    /// Returns the index of the scene returned by [`default_scene`].
+    ///
+    /// [`default_scene`]: Self::default_scene
+    pub fn default_scene_index(&self) -> Option<usize> {
+        match self.default_scene {
+            Some(index) => Some(index),
+            None if !self.scenes.is_empty() => Some(0),
+            None => None,
+        }
+    }
+
     /// Loads a glTF document from a single file.
     ///
     /// # Errors
@@ -302,8 +422,113 @@ impl GltfData {
     {
         GltfDecoder::from_file(path)?.finish()
     }
+
+    /// Merges all data from `other` into `self`.
+    ///
+    /// The meshes, materials and images of `other` are moved to indices that are not used by
+    /// `self` and the scenes of `other` are appended to the scenes of `self`, with all nodes
+    /// referring to the moved indices. Both inputs share the same default material. The
+    /// [`default_scene`] of `self` is kept.
+    ///
+    /// [`default_scene`]: Self::default_scene
+    pub fn merge(&mut self, other: GltfData) {
+        let _span = trace_span!("GltfData::merge").entered();
+
+        let used_meshes: HashSet<usize> = self.meshes.keys().map(|index| index.mesh).collect();
+        let mut mesh_indices = HashMap::new();
+        let mut next_mesh = 0;
+
+        // All primitives of a glTF mesh must stay together, so only the
+        // mesh part of the `MeshIndex` is rebased.
+        let mut meshes = HashMap::new();
+        for (index, mesh) in other.meshes {
+            let mesh_index = *mesh_indices.entry(index.mesh).or_insert_with(|| {
+                while used_meshes.contains(&next_mesh) {
+                    next_mesh += 1;
+                }
+
+                let mesh_index = next_mesh;
+                next_mesh += 1;
+                mesh_index
+            });
+
+            let new_index = MeshIndex {
+                mesh: mesh_index,
+                primitive: index.primitive,
+            };
+            meshes.insert(index, new_index);
+            self.meshes.insert(new_index, mesh);
+        }
+
+        let mut images = HashMap::new();
+        let mut next_image = 0;
+        for (index, image) in other.images {
+            while self.images.contains_key(&TextureIndex(next_image)) {
+                next_image += 1;
+            }
+
+            images.insert(index, TextureIndex(next_image));
+            self.images.insert(TextureIndex(next_image), image);
+        }
+
+        let mut materials = HashMap::new();
+        let mut next_material = 0;
+        for (index, mut material) in other.materials {
+            for texture in [
+                &mut material.base_color_texture,
+                &mut material.normal_texture,
+                &mut material.metallic_roughness_texture,
+            ] {
+                *texture = texture.map(|index| images[&index]);
+            }
+
+            // The default material is equal for all inputs, so we only
+            // need to keep a single one.
+            if index.0 == DEFAULT_MATERIAL_INDEX {
+                materials.insert(index, index);
+                self.materials.entry(index).or_insert(material);
+                continue;
+            }
+
+            while self.materials.contains_key(&MaterialIndex(next_material)) {
+                next_material += 1;
+            }
+
+            materials.insert(index, MaterialIndex(next_material));
+            self.materials
+                .insert(MaterialIndex(next_material), material);
+        }
+
+        if self.scenes.is_empty() {
+            self.default_scene = other.default_scene;
+        }
+
+        for scene in other.scenes {
+            let nodes = scene.nodes.convert(|node| GltfNode {
+                transform: node.transform,
+                mesh: node.mesh.map(|index| meshes[&index]),
+                material: node.material.map(|index| materials[&index]),
+                name: node.name.clone(),
+                primitives: node
+                    .primitives
+                    .iter()
+                    .map(|primitive| GltfMeshMaterial {
+                        mesh: meshes[&primitive.mesh],
+                        material: materials[&primitive.material],
+                    })
+                    .collect(),
+            });
+
+            self.scenes.push(GltfScene { nodes });
+        }
+    }
 }
 
+/// The [`MaterialIndex`] of the default material used by primitives without a material.
+///
+/// `usize::MAX` should be big enough to never cause it collide with a valid material index.
+const DEFAULT_MATERIAL_INDEX: usize = usize::MAX;
+
 #[derive(Clone, Debug)]
 struct GltfStagingData {
     buffers: HashMap<String, Vec<u8>>,
@@ -326,13 +551,22 @@ impl GltfStagingData {
         }
     }
 
-    pub fn finish(&mut self, gltf: Gltf) -> Result<(), Error> {
+    pub fn finish(&mut self, gltf: Gltf, layout: NodeLayout) -> Result<(), Error> {
         let mut scenes = Vec::new();
 
         if let Some(scene) = gltf.default_scene() {
             self.default_scene = Some(scene.index());
         }
 
+        if layout == NodeLayout::Preserve {
+            for scene in gltf.scenes() {
+                scenes.push(self.load_scene_preserved(&scene)?);
+            }
+
+            self.scenes = scenes;
+            return Ok(());
+        }
+
         for scene in gltf.scenes() {
             let mut nodes = Hierarchy::new();
 
@@ -346,6 +580,7 @@ impl GltfStagingData {
                         mesh: None,
                         material: None,
                         name: None,
+                        primitives: Vec::new(),
                     },
                 );
 
@@ -369,6 +604,7 @@ impl GltfStagingData {
                             mesh: None,
                             material: None,
                             name: None,
+                            primitives: Vec::new(),
                         },
                     );
 
@@ -391,6 +627,39 @@ impl GltfStagingData {
         Ok(())
     }
 
+    /// Loads a scene with [`NodeLayout::Preserve`].
+    fn load_scene_preserved(&mut self, scene: &gltf::Scene<'_>) -> Result<GltfScene, Error> {
+        let mut nodes = Hierarchy::new();
+
+        // Nodes are pushed in reverse so that siblings are appended in the
+        // same order as they appear in the glTF file.
+        let mut queue: Vec<_> = scene.nodes().map(|node| (None, node)).collect();
+        queue.reverse();
+
+        while let Some((parent, node)) = queue.pop() {
+            let primitives = match node.mesh() {
+                Some(mesh) => self.load_node_meshes(mesh)?,
+                None => Vec::new(),
+            };
+
+            let key = nodes.append(
+                parent,
+                GltfNode {
+                    transform: node_transform(&node),
+                    mesh: None,
+                    material: None,
+                    name: node.name().map(|s| s.to_owned()),
+                    primitives,
+                },
+            );
+
+            let children: Vec<_> = node.children().map(|child| (Some(key), child)).collect();
+            queue.extend(children.into_iter().rev());
+        }
+
+        Ok(GltfScene { nodes })
+    }
+
     // Note that in gltf a single node can contain multiple "primitives" which are
     // already formed like a node (with mesh + material). We flatten this hierarchy
     // into a list of nodes instead.
@@ -401,15 +670,7 @@ impl GltfStagingData {
             vec![]
         };
 
-        let (translation, rotation, scale) = node.transform().decomposed();
-        let transform = Transform {
-            translation: Vec3::from_array(translation),
-            rotation: Quat::from_array(rotation),
-            scale: Vec3::from_array(scale),
-        };
-
-        // TODO: Error instead of panicking.
-        assert!(transform.rotation.is_normalized());
+        let transform = node_transform(node);
 
         Ok(meshes
             .into_iter()
@@ -418,6 +679,7 @@ impl GltfStagingData {
                 mesh: Some(primitive.mesh),
                 material: Some(primitive.material),
                 name: node.name().map(|s| s.to_owned()),
+                primitives: Vec::new(),
             })
             .collect())
     }
@@ -488,6 +750,8 @@ impl GltfStagingData {
             self.load_indices(&accessor, &mut mesh.indices)?;
         }
 
+        mesh.aabb = compute_aabb(&mesh.positions);
+
         if !tangents_set {
             //mesh.compute_tangents();
             //todo!()
@@ -501,18 +765,24 @@ impl GltfStagingData {
         Ok(index)
     }
 
-    fn buffer(&self, source: Source<'_>, offset: usize, length: usize) -> Result<&[u8], Error> {
-        let buf = match source {
-            Source::Bin => {
-                let buf = self.buffers.get("").unwrap();
-                buf
-            }
-            Source::Uri(uri) => {
-                let buf = self.buffers.get(uri).unwrap();
-                buf
-            }
+    /// Returns the full buffer with the given `source`.
+    fn source(&self, source: Source<'_>) -> Result<&[u8], Error> {
+        let uri = match source {
+            Source::Bin => "",
+            Source::Uri(uri) => uri,
         };
 
+        match self.buffers.get(uri) {
+            Some(buf) => Ok(buf),
+            None => Err(Error::MissingBuffer {
+                uri: uri.to_owned(),
+            }),
+        }
+    }
+
+    fn buffer(&self, source: Source<'_>, offset: usize, length: usize) -> Result<&[u8], Error> {
+        let buf = self.source(source)?;
+
         match buf.get(offset..offset + length) {
             Some(buf) => Ok(buf),
             None => Err(Error::InvalidBufferView {
@@ -527,20 +797,12 @@ impl GltfStagingData {
         accessor: &Accessor<'_>,
         positions: &mut Vec<Vec3>,
     ) -> Result<(), Error> {
-        let data_type = accessor.data_type();
-        if data_type != DataType::F32 {
-            return Err(Error::InvalidDataType(data_type));
-        }
-
         let dimensions = accessor.dimensions();
         if dimensions != Dimensions::Vec3 {
             return Err(Error::InvalidDimensions(dimensions));
         }
 
-        let reader: ItemReader<'_, Positions> = ItemReader::new("POSITIONS", accessor, self)?;
-        positions.extend(reader.map(Vec3::from_array));
-
-        Ok(())
+        self.load_floats::<3, _>("POSITIONS", accessor, positions, Vec3::from_array)
     }
 
     fn load_normals(&self, accessor: &Accessor<'_>, normals: &mut Vec<Vec3>) -> Result<(), Error> {
@@ -582,20 +844,60 @@ impl GltfStagingData {
     }
 
     fn load_uvs(&self, accessor: &Accessor<'_>, uvs: &mut Vec<Vec2>) -> Result<(), Error> {
-        let data_type = accessor.data_type();
-        if data_type != DataType::F32 {
-            return Err(Error::InvalidDataType(data_type));
-        }
-
         let dimensions = accessor.dimensions();
         if dimensions != Dimensions::Vec2 {
             return Err(Error::InvalidDimensions(dimensions));
         }
 
-        let reader: ItemReader<'_, Uvs> = ItemReader::new("TEXCOORD_0", accessor, self)?;
-        uvs.extend(reader.map(Vec2::from_array));
+        self.load_floats::<2, _>("TEXCOORD_0", accessor, uvs, Vec2::from_array)
+    }
 
-        Ok(())
+    /// Loads an accessor with `N` float components into `out`.
+    ///
+    /// In addition to `F32` components, normalized `U8`, `U16`, `I8` and `I16` components are
+    /// supported and converted into floats.
+    fn load_floats<const N: usize, T>(
+        &self,
+        semantic: &'static str,
+        accessor: &Accessor<'_>,
+        out: &mut Vec<T>,
+        f: impl Fn([f32; N]) -> T,
+    ) -> Result<(), Error>
+    where
+        [f32; N]: Item,
+        [u8; N]: Item,
+        [u16; N]: Item,
+        [i8; N]: Item,
+        [i16; N]: Item,
+    {
+        fn load_normalized<const N: usize, U, T>(
+            semantic: &'static str,
+            accessor: &Accessor<'_>,
+            data: &GltfStagingData,
+            out: &mut Vec<T>,
+            f: impl Fn([f32; N]) -> T,
+        ) -> Result<(), Error>
+        where
+            U: Normalized,
+            [U; N]: Item,
+        {
+            let reader: ItemReader<'_, [U; N]> = ItemReader::new(semantic, accessor, data)?;
+            out.extend(reader.map(|item| f(item.map(U::to_f32))));
+            Ok(())
+        }
+
+        match (accessor.data_type(), accessor.normalized()) {
+            (DataType::F32, _) => {
+                let reader: ItemReader<'_, [f32; N]> = ItemReader::new(semantic, accessor, self)?;
+                out.extend(reader.map(f));
+                Ok(())
+            }
+            (DataType::U8, true) => load_normalized::<N, u8, T>(semantic, accessor, self, out, f),
+            (DataType::U16, true) => load_normalized::<N, u16, T>(semantic, accessor, self, out, f),
+            (DataType::I8, true) => load_normalized::<N, i8, T>(semantic, accessor, self, out, f),
+            (DataType::I16, true) => load_normalized::<N, i16, T>(semantic, accessor, self, out, f),
+            (data_type, _) => Err(Error::InvalidDataType(data_type)),
+        }
     }
 
     fn load_indices(&self, accessor: &Accessor<'_>, indices: &mut Vec<u32>) -> Result<(), Error> {
@@ -607,6 +909,7 @@ impl GltfStagingData {
         }
 
         let alignment = match data_type {
+            DataType::U8 => 1,
             DataType::U16 => 2,
             DataType::U32 => 4,
             _ => return Err(Error::InvalidDataType(data_type)),
@@ -619,6 +922,10 @@ impl GltfStagingData {
         assert!(view.offset() % alignment == 0);
 
         match data_type {
+            DataType::U8 => {
+                let reader: ItemReader<'_, u8> = ItemReader::new("INDICES", accessor, self)?;
+                indices.extend(reader.map(u32::from));
+            }
             DataType::U16 => {
                 let reader: ItemReader<'_, u16> = ItemReader::new("INDICES", accessor, self)?;
                 indices.extend(reader.map(u32::from));
@@ -646,9 +953,6 @@ impl GltfStagingData {
                 return Ok(MaterialIndex(index));
             }
         } else {
-            // `usize::MAX` should be big enough to never cause it collide with
-            // a valid material index.
-            const DEFAULT_MATERIAL_INDEX: usize = usize::MAX;
             if self
                 .materials
                 .contains_key(&MaterialIndex(DEFAULT_MATERIAL_INDEX))
@@ -665,24 +969,39 @@ impl GltfStagingData {
             return Ok(MaterialIndex(DEFAULT_MATERIAL_INDEX));
         }
 
+        let index = material.index().unwrap();
         let alpha_mode = material.alpha_mode();
+        let double_sided = material.double_sided();
 
-        let pbr = material.pbr_metallic_roughness();
-
-        let base_color = pbr.base_color_factor();
-
-        let base_color_texture = if let Some(info) = pbr.base_color_texture() {
+        let normal_texture = if let Some(info) = material.normal_texture() {
             let image = info.texture().source();
 
-            Some(self.load_image(image, TextureFormat::Rgba8UnormSrgb)?)
+            Some(self.load_image(image, TextureFormat::Rgba8Unorm)?)
         } else {
             None
         };
 
-        let normal_texture = if let Some(info) = material.normal_texture() {
+        if let Some(pbr) = material.pbr_specular_glossiness() {
+            tracing::warn!(
+                "material {} uses the legacy specular-glossiness workflow; converting to metallic-roughness",
+                material.name().unwrap_or(&index.to_string()),
+            );
+
+            let mut material =
+                self.load_specular_glossiness(index, pbr, alpha_mode, normal_texture)?;
+            material.double_sided = double_sided;
+            self.materials.insert(MaterialIndex(index), material);
+            return Ok(MaterialIndex(index));
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+
+        let base_color = pbr.base_color_factor();
+
+        let base_color_texture = if let Some(info) = pbr.base_color_texture() {
             let image = info.texture().source();
 
-            Some(self.load_image(image, TextureFormat::Rgba8Unorm)?)
+            Some(self.load_image(image, TextureFormat::Rgba8UnormSrgb)?)
         } else {
             None
         };
@@ -698,8 +1017,6 @@ impl GltfStagingData {
             None
         };
 
-        let index = material.index().unwrap();
-
         self.materials.insert(
             MaterialIndex(index),
             GltfMaterial {
@@ -710,6 +1027,7 @@ impl GltfStagingData {
                 roughness,
                 metallic,
                 metallic_roughness_texture,
+                double_sided,
             },
         );
 
@@ -725,18 +1043,8 @@ impl GltfStagingData {
             return Ok(TextureIndex(image.index()));
         }
 
-        let buf = match image.source() {
-            ImageSource::View { view, mime_type: _ } => {
-                self.buffer(view.buffer().source(), view.offset(), view.length())
-            }
-            ImageSource::Uri { uri, mime_type: _ } => {
-                let len = self.buffers.get(uri).unwrap().len();
-                self.buffer(Source::Uri(uri), 0, len)
-            }
-        }?;
-
         let index = TextureIndex(image.index());
-        let img = image::load_from_memory(buf)?.into_rgba8();
+        let img = self.decode_image(image)?;
         self.images.insert(
             index,
             Image::new(
@@ -747,6 +1055,88 @@ impl GltfStagingData {
         );
         Ok(index)
     }
+
+    fn decode_image(&self, image: gltf::Image<'_>) -> Result<RgbaImage, Error> {
+        let buf = match image.source() {
+            ImageSource::View { view, mime_type: _ } => {
+                self.buffer(view.buffer().source(), view.offset(), view.length())
+            }
+            ImageSource::Uri { uri, mime_type: _ } => self.source(Source::Uri(uri)),
+        }?;
+
+        Ok(image::load_from_memory(buf)?.into_rgba8())
+    }
+
+    /// Loads a material using the `KHR_materials_pbrSpecularGlossiness` extension.
+    ///
+    /// The specular-glossiness parameters are approximated using the metallic-roughness workflow:
+    /// - The diffuse texture is used as the base color texture.
+    /// - The metallic and base color factors are solved from the diffuse and specular factors.
+    ///   Specular colors that are not achievable by a metallic-roughness material are lost.
+    /// - The glossiness (alpha) channel of the specular-glossiness texture is converted into a
+    ///   roughness texture. The specular color of the texture is ignored.
+    fn load_specular_glossiness(
+        &mut self,
+        index: usize,
+        pbr: PbrSpecularGlossiness<'_>,
+        alpha_mode: AlphaMode,
+        normal_texture: Option<TextureIndex>,
+    ) -> Result<GltfMaterial, Error> {
+        let params = material::specular_glossiness_to_metallic_roughness(
+            pbr.diffuse_factor(),
+            pbr.specular_factor(),
+            pbr.glossiness_factor(),
+        );
+
+        let base_color_texture = if let Some(info) = pbr.diffuse_texture() {
+            let image = info.texture().source();
+
+            Some(self.load_image(image, TextureFormat::Rgba8UnormSrgb)?)
+        } else {
+            None
+        };
+
+        let mut roughness = params.roughness;
+        let metallic_roughness_texture = if let Some(info) = pbr.specular_glossiness_texture() {
+            // The converted texture depends on the glossiness factor of the material,
+            // so it cannot be shared with other materials. Indices are allocated
+            // downwards from `usize::MAX` to never collide with a glTF image index.
+            let texture_index = TextureIndex(usize::MAX - index);
+
+            let mut img = self.decode_image(info.texture().source())?;
+            for pixel in img.pixels_mut() {
+                let glossiness = f32::from(pixel[3]) / 255.0 * pbr.glossiness_factor();
+                let roughness = ((1.0 - glossiness).clamp(0.0, 1.0) * 255.0).round() as u8;
+                *pixel = Rgba([0, roughness, u8::MAX, u8::MAX]);
+            }
+
+            self.images.insert(
+                texture_index,
+                Image::new(
+                    UVec2::new(img.width(), img.height()),
+                    TextureFormat::Rgba8UnormSrgb,
+                    img.into_raw(),
+                ),
+            );
+
+            // The roughness is fully contained in the texture.
+            roughness = 1.0;
+            Some(texture_index)
+        } else {
+            None
+        };
+
+        Ok(GltfMaterial {
+            alpha_mode,
+            base_color: Color(params.base_color),
+            base_color_texture,
+            normal_texture,
+            roughness,
+            metallic: params.metallic,
+            metallic_roughness_texture,
+            double_sided: false,
+        })
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Error)]
@@ -934,6 +1324,59 @@ impl ScalarValue {
             _ => Err(InvalidScalar::NoScalar(value)),
         }
     }
+
+    /// Returns the value as a `f64`.
+    ///
+    /// All values are exactly representable as a `f64`.
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Self::U8(val) => val.into(),
+            Self::U16(val) => val.into(),
+            Self::U32(val) => val.into(),
+            Self::I8(val) => val.into(),
+            Self::I16(val) => val.into(),
+            Self::F32(val) => val.into(),
+        }
+    }
+
+    /// Returns the value as a `u64`.
+    ///
+    /// Returns `None` if the value is negative or a float that has a fractional part or is not
+    /// finite.
+    pub fn as_u64(self) -> Option<u64> {
+        match self {
+            Self::U8(val) => Some(val.into()),
+            Self::U16(val) => Some(val.into()),
+            Self::U32(val) => Some(val.into()),
+            Self::I8(val) => val.try_into().ok(),
+            Self::I16(val) => val.try_into().ok(),
+            // `u64::MAX as f32` rounds up to 2^64, which is out of range.
+            Self::F32(val) if val.fract() == 0.0 && val >= 0.0 && val < u64::MAX as f32 => {
+                Some(val as u64)
+            }
+            Self::F32(_) => None,
+        }
+    }
+
+    /// Returns the value as a `i64`.
+    ///
+    /// Returns `None` if the value is a float that has a fractional part or is not finite.
+    pub fn as_i64(self) -> Option<i64> {
+        match self {
+            Self::U8(val) => Some(val.into()),
+            Self::U16(val) => Some(val.into()),
+            Self::U32(val) => Some(val.into()),
+            Self::I8(val) => Some(val.into()),
+            Self::I16(val) => Some(val.into()),
+            // `i64::MIN` is exactly representable, `i64::MAX as f32` rounds up to 2^63.
+            Self::F32(val)
+                if val.fract() == 0.0 && val >= i64::MIN as f32 && val < i64::MAX as f32 =>
+            {
+                Some(val as i64)
+            }
+            Self::F32(_) => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Error)]
@@ -967,5 +1410,59 @@ fn default_material() -> GltfMaterial {
         roughness: 1.0,
         metallic_roughness_texture: None,
         normal_texture: None,
+        double_sided: false,
+    }
+}
+
+fn node_transform(node: &Node<'_>) -> Transform {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    let transform = Transform {
+        translation: Vec3::from_array(translation),
+        rotation: Quat::from_array(rotation),
+        scale: Vec3::from_array(scale),
+    };
+
+    // TODO: Error instead of panicking.
+    assert!(transform.rotation.is_normalized());
+
+    transform
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScalarValue;
+
+    #[test]
+    fn scalar_value_as_f64() {
+        assert_eq!(ScalarValue::U8(u8::MAX).as_f64(), 255.0);
+        assert_eq!(ScalarValue::U32(u32::MAX).as_f64(), 4294967295.0);
+        assert_eq!(ScalarValue::I16(i16::MIN).as_f64(), -32768.0);
+        assert_eq!(ScalarValue::F32(0.5).as_f64(), 0.5);
+    }
+
+    #[test]
+    fn scalar_value_as_u64() {
+        assert_eq!(ScalarValue::U32(u32::MAX).as_u64(), Some(u32::MAX.into()));
+        assert_eq!(ScalarValue::I8(5).as_u64(), Some(5));
+        assert_eq!(ScalarValue::I8(-1).as_u64(), None);
+        assert_eq!(ScalarValue::F32(3.0).as_u64(), Some(3));
+        assert_eq!(ScalarValue::F32(-0.0).as_u64(), Some(0));
+        assert_eq!(ScalarValue::F32(0.5).as_u64(), None);
+        assert_eq!(ScalarValue::F32(-1.0).as_u64(), None);
+        assert_eq!(ScalarValue::F32(u64::MAX as f32).as_u64(), None);
+        assert_eq!(ScalarValue::F32(f32::INFINITY).as_u64(), None);
+        assert_eq!(ScalarValue::F32(f32::NAN).as_u64(), None);
+    }
+
+    #[test]
+    fn scalar_value_as_i64() {
+        assert_eq!(ScalarValue::U32(u32::MAX).as_i64(), Some(u32::MAX.into()));
+        assert_eq!(ScalarValue::I16(i16::MIN).as_i64(), Some(i16::MIN.into()));
+        assert_eq!(ScalarValue::F32(-3.0).as_i64(), Some(-3));
+        assert_eq!(ScalarValue::F32(i64::MIN as f32).as_i64(), Some(i64::MIN));
+        assert_eq!(ScalarValue::F32(i64::MAX as f32).as_i64(), None);
+        assert_eq!(ScalarValue::F32(-0.5).as_i64(), None);
+        assert_eq!(ScalarValue::F32(f32::NEG_INFINITY).as_i64(), None);
+        assert_eq!(ScalarValue::F32(f32::NAN).as_i64(), None);
     }
 }
@@ -1,5 +1,6 @@
 use game_common::components::Color;
 use game_render::pbr::AlphaMode;
+use glam::Vec3;
 
 #[derive(Clone, Debug)]
 pub struct GltfMaterial {
@@ -11,3 +12,70 @@ pub struct GltfMaterial {
     pub metallic: f32,
     pub metallic_roughness_texture: Option<Vec<u8>>,
 }
+
+/// The reflectance of dielectric (non-metallic) materials at normal incidence.
+const DIELECTRIC_SPECULAR: f32 = 0.04;
+
+/// Material parameters of the metallic-roughness workflow.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct MetallicRoughness {
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+/// Converts the parameters of the specular-glossiness workflow (`KHR_materials_pbrSpecularGlossiness`)
+/// into the metallic-roughness workflow.
+///
+/// The specular-glossiness workflow can describe materials that have no metallic-roughness
+/// equivalent (e.g. dielectrics with colored specular reflections), so the result is only an
+/// approximation: the metallic value is solved from the perceived brightness of the diffuse and
+/// specular colors and the base color is blended between the diffuse and specular colors.
+pub(crate) fn specular_glossiness_to_metallic_roughness(
+    diffuse: [f32; 4],
+    specular: [f32; 3],
+    glossiness: f32,
+) -> MetallicRoughness {
+    let diffuse_color = Vec3::new(diffuse[0], diffuse[1], diffuse[2]);
+    let specular = Vec3::from_array(specular);
+
+    let one_minus_specular_strength = 1.0 - specular.max_element();
+    let metallic = solve_metallic(
+        perceived_brightness(diffuse_color),
+        perceived_brightness(specular),
+        one_minus_specular_strength,
+    );
+
+    let base_color_from_diffuse = diffuse_color
+        * (one_minus_specular_strength
+            / (1.0 - DIELECTRIC_SPECULAR)
+            / f32::max(1.0 - metallic, f32::EPSILON));
+    let base_color_from_specular = (specular - Vec3::splat(DIELECTRIC_SPECULAR * (1.0 - metallic)))
+        / f32::max(metallic, f32::EPSILON);
+    let base_color = base_color_from_diffuse
+        .lerp(base_color_from_specular, metallic * metallic)
+        .clamp(Vec3::ZERO, Vec3::ONE);
+
+    MetallicRoughness {
+        base_color: base_color.extend(diffuse[3]).to_array(),
+        metallic,
+        roughness: 1.0 - glossiness,
+    }
+}
+
+fn perceived_brightness(color: Vec3) -> f32 {
+    (color * color).dot(Vec3::new(0.299, 0.587, 0.114)).sqrt()
+}
+
+fn solve_metallic(diffuse: f32, specular: f32, one_minus_specular_strength: f32) -> f32 {
+    if specular < DIELECTRIC_SPECULAR {
+        return 0.0;
+    }
+
+    let a = DIELECTRIC_SPECULAR;
+    let b = diffuse * one_minus_specular_strength / (1.0 - DIELECTRIC_SPECULAR) + specular
+        - 2.0 * DIELECTRIC_SPECULAR;
+    let c = DIELECTRIC_SPECULAR - specular;
+    let discriminant = b * b - 4.0 * a * c;
+    ((-b + discriminant.sqrt()) / (2.0 * a)).clamp(0.0, 1.0)
+}
@@ -1,5 +1,8 @@
+use std::collections::HashMap;
+
 use game_common::components::Transform;
 use game_render::mesh::{Indices, Mesh};
+use glam::Vec3;
 
 use crate::GltfMaterial;
 
@@ -38,3 +41,209 @@ pub(crate) fn validate_mesh(mesh: &Mesh) {
         }
     }
 }
+
+/// Recomputes the normals of a triangle `mesh`, smoothing across edges with an angle of at most
+/// `threshold` radians.
+///
+/// The normal of a vertex is the angle-weighted average of the normals of all triangles sharing
+/// the position of the vertex that deviate at most `threshold` from the normal of the triangle
+/// using the vertex. Vertices at the same position are therefore welded, even if they are
+/// different vertices in the mesh. Vertices that end up with different normals for different
+/// triangles are split and the indices are rewritten.
+///
+/// Tangents are removed since they are no longer valid for the new normals.
+pub(crate) fn smooth_normals(mesh: &mut crate::types::GltfMesh, threshold: f32) {
+    if mesh.indices.is_empty() {
+        mesh.indices = (0..mesh.positions.len() as u32).collect();
+    }
+
+    let min_cos = threshold.cos();
+
+    // The normal of every triangle. Degenerate triangles have a zero normal.
+    let face_normals: Vec<Vec3> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+            (b - a).cross(c - a).normalize_or_zero()
+        })
+        .collect();
+
+    // The interior angle of every triangle corner. Weighting the normals
+    // by the angle makes the result independent of the triangulation.
+    let corner_angles: Vec<f32> = (0..mesh.indices.len())
+        .map(|index| {
+            let first = index - index % 3;
+            let [p, next, prev] = [index, first + (index + 1) % 3, first + (index + 2) % 3]
+                .map(|i| mesh.positions[mesh.indices[i] as usize]);
+            (next - p).angle_between(prev - p)
+        })
+        .collect();
+
+    // All triangle corners at a position.
+    let mut corners_at: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    for (index, vertex) in mesh.indices.iter().enumerate() {
+        let key = mesh.positions[*vertex as usize]
+            .to_array()
+            .map(f32::to_bits);
+        corners_at.entry(key).or_default().push(index);
+    }
+
+    let mut positions = Vec::with_capacity(mesh.positions.len());
+    let mut normals = Vec::with_capacity(mesh.positions.len());
+    let mut uvs = Vec::with_capacity(mesh.uvs.len());
+    let mut indices = Vec::with_capacity(mesh.indices.len());
+
+    // Maps the original vertex and its new normal to the new vertex.
+    let mut vertices: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+
+    for (index, vertex) in mesh.indices.iter().enumerate() {
+        let position = mesh.positions[*vertex as usize];
+        let face_normal = face_normals[index / 3];
+
+        let mut normal = Vec3::ZERO;
+        for corner in &corners_at[&position.to_array().map(f32::to_bits)] {
+            let other = face_normals[corner / 3];
+            if face_normal.dot(other) >= min_cos {
+                normal += other * corner_angles[*corner];
+            }
+        }
+
+        // Degenerate triangles have no normal, keep the original normal
+        // for them if there is one.
+        let normal = match normal.try_normalize() {
+            Some(normal) => normal,
+            None => mesh
+                .normals
+                .get(*vertex as usize)
+                .copied()
+                .unwrap_or(Vec3::ZERO),
+        };
+
+        let new_index = *vertices
+            .entry((*vertex, normal.to_array().map(f32::to_bits)))
+            .or_insert_with(|| {
+                let new_index = positions.len() as u32;
+                positions.push(position);
+                normals.push(normal);
+                if let Some(uv) = mesh.uvs.get(*vertex as usize) {
+                    uvs.push(*uv);
+                }
+
+                new_index
+            });
+        indices.push(new_index);
+    }
+
+    mesh.positions = positions;
+    mesh.normals = normals;
+    mesh.uvs = uvs;
+    mesh.indices = indices;
+    mesh.tangents.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+
+    use crate::types::GltfMesh;
+
+    use super::smooth_normals;
+
+    /// Returns two triangles sharing the edge on the x-axis which are bent by 90 degrees.
+    fn bent_mesh() -> GltfMesh {
+        GltfMesh {
+            positions: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+            uvs: vec![Vec2::ZERO, Vec2::X, Vec2::Y, Vec2::ONE],
+            indices: vec![0, 1, 2, 1, 0, 3],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn smooth_normals_split_hard_edge() {
+        let mut mesh = bent_mesh();
+        smooth_normals(&mut mesh, 30f32.to_radians());
+
+        // Both vertices on the shared edge are split.
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.normals.len(), 6);
+        assert_eq!(mesh.uvs.len(), 6);
+        assert_eq!(mesh.indices.len(), 6);
+
+        for index in &mesh.indices[0..3] {
+            assert_eq!(mesh.normals[*index as usize], Vec3::Z);
+        }
+        for index in &mesh.indices[3..6] {
+            assert_eq!(mesh.normals[*index as usize], Vec3::Y);
+        }
+
+        // The split vertices keep their attributes.
+        assert_eq!(mesh.positions[mesh.indices[3] as usize], Vec3::X);
+        assert_eq!(mesh.uvs[mesh.indices[3] as usize], Vec2::X);
+    }
+
+    #[test]
+    fn smooth_normals_smooth_edge() {
+        let mut mesh = bent_mesh();
+        smooth_normals(&mut mesh, 100f32.to_radians());
+
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 1, 0, 3]);
+
+        let shared = (Vec3::Y + Vec3::Z).normalize();
+        assert!(mesh.normals[0].abs_diff_eq(shared, 1e-6));
+        assert!(mesh.normals[1].abs_diff_eq(shared, 1e-6));
+        assert_eq!(mesh.normals[2], Vec3::Z);
+        assert_eq!(mesh.normals[3], Vec3::Y);
+    }
+
+    #[test]
+    fn smooth_normals_weld_duplicate_vertices() {
+        // Two coplanar triangles that don't share any vertices, but
+        // positions, with incorrect normals.
+        let mut mesh = GltfMesh {
+            positions: vec![
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            normals: vec![Vec3::X; 6],
+            indices: vec![0, 1, 2, 3, 4, 5],
+            ..Default::default()
+        };
+        smooth_normals(&mut mesh, 30f32.to_radians());
+
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.indices, [0, 1, 2, 3, 4, 5]);
+        for normal in &mesh.normals {
+            assert_eq!(*normal, Vec3::Z);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_no_indices() {
+        let mut mesh = bent_mesh();
+        mesh.positions = mesh
+            .indices
+            .iter()
+            .map(|i| mesh.positions[*i as usize])
+            .collect();
+        mesh.uvs.clear();
+        mesh.indices.clear();
+
+        smooth_normals(&mut mesh, 30f32.to_radians());
+
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.indices, [0, 1, 2, 3, 4, 5]);
+        assert!(mesh.uvs.is_empty());
+    }
+}
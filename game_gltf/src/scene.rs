@@ -1,8 +1,186 @@
-use game_core::hierarchy::Hierarchy;
+use std::collections::HashMap;
 
+use game_core::hierarchy::{Hierarchy, Key};
+use glam::{BVec3, Mat4, Vec3};
+
+use crate::types::{GltfMesh, MeshIndex};
 use crate::GltfNode;
 
 #[derive(Clone, Debug)]
 pub struct GltfScene {
     pub nodes: Hierarchy<GltfNode>,
 }
+
+impl GltfScene {
+    /// Returns the axis-aligned bounding box of all meshes in the scene as `(min, max)`.
+    ///
+    /// The bounding box of every mesh is transformed by the world transform of its node, i.e.
+    /// the combined transforms of the node and all its parents. Returns `None` if the scene
+    /// contains no meshes with any positions.
+    pub fn bounds(&self, meshes: &HashMap<MeshIndex, GltfMesh>) -> Option<(Vec3, Vec3)> {
+        let mut bounds = None;
+
+        for (key, _) in self.nodes.iter() {
+            if self.nodes.parent(key).is_none() {
+                self.node_bounds(key, Mat4::IDENTITY, meshes, &mut bounds);
+            }
+        }
+
+        bounds
+    }
+
+    fn node_bounds(
+        &self,
+        key: Key,
+        parent: Mat4,
+        meshes: &HashMap<MeshIndex, GltfMesh>,
+        bounds: &mut Option<(Vec3, Vec3)>,
+    ) {
+        let node = self.nodes.get(key).unwrap();
+        // Accumulate matrices instead of `Transform`s, a rotated child
+        // of a non-uniformly scaled node is sheared, which a `Transform`
+        // cannot represent.
+        let transform = parent * node.transform.compute_matrix();
+
+        let mesh_indices = node
+            .mesh
+            .iter()
+            .chain(node.primitives.iter().map(|primitive| &primitive.mesh));
+        for index in mesh_indices {
+            let Some(mesh) = meshes.get(index) else {
+                continue;
+            };
+
+            let (min, max) = mesh.aabb();
+            // The mesh has no positions.
+            if min.cmpgt(max).any() {
+                continue;
+            }
+
+            for corner in 0..8 {
+                let point = Vec3::select(
+                    BVec3::new(corner & 1 != 0, corner & 2 != 0, corner & 4 != 0),
+                    max,
+                    min,
+                );
+                let point = transform.transform_point3(point);
+
+                *bounds = Some(match *bounds {
+                    Some((min, max)) => (min.min(point), max.max(point)),
+                    None => (point, point),
+                });
+            }
+        }
+
+        if let Some(children) = self.nodes.children(key) {
+            for (child, _) in children {
+                self.node_bounds(child, transform, meshes, bounds);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use game_common::components::Transform;
+    use game_common::math::compute_aabb;
+    use game_core::hierarchy::Hierarchy;
+    use glam::{Quat, Vec3};
+
+    use crate::types::{GltfMesh, MeshIndex};
+    use crate::GltfNode;
+
+    use super::GltfScene;
+
+    const MESH: MeshIndex = MeshIndex {
+        mesh: 0,
+        primitive: 0,
+    };
+
+    fn node(transform: Transform, mesh: Option<MeshIndex>) -> GltfNode {
+        GltfNode {
+            transform,
+            mesh,
+            material: None,
+            name: None,
+            primitives: Vec::new(),
+        }
+    }
+
+    /// Returns a unit cube centered at the origin.
+    fn meshes() -> HashMap<MeshIndex, GltfMesh> {
+        let positions = vec![Vec3::splat(-0.5), Vec3::splat(0.5)];
+        let mesh = GltfMesh {
+            aabb: compute_aabb(&positions),
+            positions,
+            ..Default::default()
+        };
+
+        HashMap::from([(MESH, mesh)])
+    }
+
+    #[test]
+    fn scene_bounds_empty() {
+        let scene = GltfScene {
+            nodes: Hierarchy::new(),
+        };
+        assert_eq!(scene.bounds(&meshes()), None);
+
+        // Nodes without meshes have no bounds.
+        let mut nodes = Hierarchy::new();
+        nodes.append(None, node(Transform::default(), None));
+        let scene = GltfScene { nodes };
+        assert_eq!(scene.bounds(&meshes()), None);
+    }
+
+    #[test]
+    fn scene_bounds_nested() {
+        let mut nodes = Hierarchy::new();
+        let parent = nodes.append(
+            None,
+            node(
+                Transform::from_translation(Vec3::new(10.0, 0.0, 0.0)),
+                Some(MESH),
+            ),
+        );
+        nodes.append(
+            Some(parent),
+            node(
+                Transform::from_translation(Vec3::new(0.0, 5.0, 0.0)),
+                Some(MESH),
+            ),
+        );
+        let scene = GltfScene { nodes };
+
+        assert_eq!(
+            scene.bounds(&meshes()),
+            Some((Vec3::new(9.5, -0.5, -0.5), Vec3::new(10.5, 5.5, 0.5)))
+        );
+    }
+
+    #[test]
+    fn scene_bounds_rotated_child_of_scaled_parent() {
+        let mut nodes = Hierarchy::new();
+        let parent = nodes.append(
+            None,
+            node(Transform::from_scale(Vec3::new(2.0, 1.0, 1.0)), None),
+        );
+        nodes.append(
+            Some(parent),
+            node(
+                Transform::from_rotation(Quat::from_rotation_z(45f32.to_radians())),
+                Some(MESH),
+            ),
+        );
+        let scene = GltfScene { nodes };
+
+        // The rotated cube extends sqrt(0.5) in x and y, the parent
+        // scales x afterwards.
+        let extent = 0.5f32.sqrt();
+        let (min, max) = scene.bounds(&meshes()).unwrap();
+        assert!(min.abs_diff_eq(Vec3::new(-2.0 * extent, -extent, -0.5), 1e-6));
+        assert!(max.abs_diff_eq(Vec3::new(2.0 * extent, extent, 0.5), 1e-6));
+    }
+}
@@ -1,4 +1,5 @@
 use game_common::components::{Color, Transform};
+use game_common::math::EMPTY_AABB;
 use game_core::hierarchy::Hierarchy;
 use glam::{Vec2, Vec3, Vec4};
 use gltf::material::AlphaMode;
@@ -10,10 +11,21 @@ pub struct GltfScene {
 
 #[derive(Clone, Debug)]
 pub struct GltfNode {
+    /// The local transform of the node.
+    ///
+    /// The scale may be non-uniform or negative. Normals of the mesh must be transformed using
+    /// [`Transform::normal_matrix`]. A negative scale mirrors the mesh and reverses the winding
+    /// order of its triangles, see [`Transform::is_mirrored`].
     pub transform: Transform,
     pub mesh: Option<MeshIndex>,
     pub material: Option<MaterialIndex>,
     pub name: Option<String>,
+    /// All primitives of the node.
+    ///
+    /// Only used with [`NodeLayout::Preserve`], otherwise this is always empty.
+    ///
+    /// [`NodeLayout::Preserve`]: crate::NodeLayout::Preserve
+    pub primitives: Vec<GltfMeshMaterial>,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -22,13 +34,48 @@ pub struct GltfMeshMaterial {
     pub material: MaterialIndex,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 pub struct GltfMesh {
     pub positions: Vec<Vec3>,
     pub normals: Vec<Vec3>,
     pub uvs: Vec<Vec2>,
     pub tangents: Vec<Vec4>,
     pub indices: Vec<u32>,
+    pub(crate) aabb: (Vec3, Vec3),
+}
+
+impl GltfMesh {
+    /// Returns the axis-aligned bounding box of the mesh as `(min, max)`.
+    ///
+    /// The bounding box is computed from the `positions` when the mesh is loaded. Returns
+    /// [`EMPTY_AABB`] if the mesh has no positions.
+    #[inline]
+    pub fn aabb(&self) -> (Vec3, Vec3) {
+        self.aabb
+    }
+
+    /// Recomputes the normals of the mesh, smoothing across all edges with an angle of at most
+    /// `threshold` radians.
+    ///
+    /// Normals are welded across edges below the threshold, even if the triangles don't share
+    /// vertices. Vertices on edges above the threshold are split to create hard edges. This
+    /// rewrites the `indices` of the mesh and removes all `tangents`.
+    pub fn smooth_normals(&mut self, threshold: f32) {
+        crate::mesh::smooth_normals(self, threshold);
+    }
+}
+
+impl Default for GltfMesh {
+    fn default() -> Self {
+        Self {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            tangents: Vec::new(),
+            indices: Vec::new(),
+            aabb: EMPTY_AABB,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -40,6 +87,8 @@ pub struct GltfMaterial {
     pub roughness: f32,
     pub metallic: f32,
     pub metallic_roughness_texture: Option<TextureIndex>,
+    /// Whether back faces are rendered. Single-sided materials cull back faces.
+    pub double_sided: bool,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -1,4 +1,4 @@
-use game_gltf::GltfData;
+use game_gltf::{Error, GltfData, GltfDecoder};
 use glam::Vec3;
 
 const POSITIONS: &[[f32; 3]] = &[
@@ -74,4 +74,71 @@ fn validate_output(data: &GltfData) {
         POSITIONS
     );
     assert_eq!(mesh.indices, INDICES);
+    assert_eq!(mesh.aabb(), (Vec3::splat(-1.0), Vec3::splat(1.0)));
+
+    assert_eq!(
+        data.scenes[0].bounds(&data.meshes),
+        Some((Vec3::splat(-1.0), Vec3::splat(1.0)))
+    );
+}
+
+#[test]
+fn gltf_box_missing_buffer() {
+    let bytes = std::fs::read("./tests/gltf_box/gltf_box.gltf").unwrap();
+
+    // Don't push the external buffer into the decoder.
+    let decoder = GltfDecoder::new(&bytes).unwrap();
+
+    match decoder.finish() {
+        Err(Error::MissingBuffer { uri }) => assert_eq!(uri, "gltf_box.bin"),
+        res => panic!("expected missing buffer error, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+fn gltf_box_default_scene_fallback() {
+    let bytes = std::fs::read("./tests/gltf_box/gltf_box_embedded.gltf").unwrap();
+
+    let mut gltf: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    gltf.as_object_mut().unwrap().remove("scene");
+    let bytes = serde_json::to_vec(&gltf).unwrap();
+
+    let data = GltfDecoder::new(&bytes).unwrap().finish().unwrap();
+    assert_eq!(data.default_scene, None);
+    assert_eq!(data.default_scene_index(), Some(0));
+    assert!(data.default_scene().is_some());
+}
+
+#[test]
+fn gltf_no_scenes() {
+    let bytes = br#"{"asset":{"version":"2.0"}}"#;
+
+    let data = GltfDecoder::new(bytes).unwrap().finish().unwrap();
+    assert_eq!(data.default_scene_index(), None);
+    assert!(data.default_scene().is_none());
+}
+
+#[test]
+fn gltf_box_smoothing_angle() {
+    let bytes = std::fs::read("./tests/gltf_box/gltf_box_embedded.gltf").unwrap();
+
+    for (angle, smooth) in [(30f32, false), (100f32, true)] {
+        let mut decoder = GltfDecoder::new(&bytes).unwrap();
+        decoder.set_smoothing_angle(Some(angle.to_radians()));
+        let data = decoder.finish().unwrap();
+
+        let mesh = data.meshes.values().next().unwrap();
+        assert_eq!(mesh.positions.len(), POSITIONS.len());
+        assert_eq!(mesh.normals.len(), POSITIONS.len());
+
+        for (position, normal) in mesh.positions.iter().zip(&mesh.normals) {
+            if smooth {
+                // All faces of a corner are welded.
+                assert!(normal.abs_diff_eq(position.normalize(), 1e-6));
+            } else {
+                // Every vertex has the normal of its face.
+                assert_eq!(normal.abs().max_element(), 1.0);
+            }
+        }
+    }
 }
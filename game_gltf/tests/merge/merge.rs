@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use game_gltf::GltfData;
+
+#[test]
+fn merge_rebases_indices() {
+    let mut data = GltfData::from_file("./tests/basic_material/basic_material.gltf").unwrap();
+    let other = GltfData::from_file("./tests/gltf_box/gltf_box.gltf").unwrap();
+
+    let num_meshes = data.meshes.len() + other.meshes.len();
+    let num_materials = data.materials.len() + other.materials.len();
+    let num_images = data.images.len() + other.images.len();
+    let num_scenes = data.scenes.len() + other.scenes.len();
+    let default_scene = data.default_scene;
+
+    data.merge(other);
+
+    assert_eq!(data.meshes.len(), num_meshes);
+    assert_eq!(data.materials.len(), num_materials);
+    assert_eq!(data.images.len(), num_images);
+    assert_eq!(data.scenes.len(), num_scenes);
+    assert_eq!(data.default_scene, default_scene);
+
+    validate_references(&data);
+
+    // Both scenes have a single object that must refer to a distinct
+    // mesh and material after merging.
+    let objects: Vec<_> = data
+        .scenes
+        .iter()
+        .map(|scene| {
+            let node = scene
+                .nodes
+                .values()
+                .find(|node| node.mesh.is_some())
+                .unwrap();
+            (node.mesh.unwrap(), node.material.unwrap())
+        })
+        .collect();
+    assert_eq!(objects.len(), 2);
+    assert_ne!(objects[0].0, objects[1].0);
+    assert_ne!(objects[0].1, objects[1].1);
+}
+
+#[test]
+fn merge_default_material() {
+    let mut data = GltfData::from_file("./tests/non_uniform_scale/non_uniform_scale.gltf").unwrap();
+    let other =
+        GltfData::from_file("./tests/normalized_attributes/normalized_attributes.gltf").unwrap();
+
+    // Both inputs only use the default material.
+    assert_eq!(data.materials.len(), 1);
+    assert_eq!(other.materials.len(), 1);
+
+    data.merge(other);
+
+    assert_eq!(data.materials.len(), 1);
+    validate_references(&data);
+
+    let materials: HashSet<_> = data
+        .scenes
+        .iter()
+        .flat_map(|scene| scene.nodes.values())
+        .filter_map(|node| node.material)
+        .collect();
+    assert_eq!(materials.len(), 1);
+}
+
+#[test]
+fn merge_into_empty() {
+    let mut data = GltfData::from_file("./tests/gltf_box/gltf_box.gltf").unwrap();
+    let mut empty = data.clone();
+    empty.scenes.clear();
+    empty.meshes.clear();
+    empty.materials.clear();
+    empty.images.clear();
+    empty.default_scene = None;
+
+    let num_meshes = data.meshes.len();
+    let default_scene = data.default_scene;
+
+    empty.merge(data.clone());
+    assert_eq!(empty.meshes.len(), num_meshes);
+    assert_eq!(empty.default_scene, default_scene);
+    validate_references(&empty);
+
+    // Merging with itself must duplicate everything.
+    data.merge(empty);
+    assert_eq!(data.meshes.len(), 2 * num_meshes);
+    validate_references(&data);
+}
+
+fn validate_references(data: &GltfData) {
+    for scene in &data.scenes {
+        for node in scene.nodes.values() {
+            if let Some(mesh) = node.mesh {
+                assert!(data.meshes.contains_key(&mesh));
+            }
+
+            if let Some(material) = node.material {
+                let material = &data.materials[&material];
+
+                for texture in [
+                    material.base_color_texture,
+                    material.normal_texture,
+                    material.metallic_roughness_texture,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    assert!(data.images.contains_key(&texture));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use game_gltf::{Error, GltfData, GltfDecoder};
+use glam::Vec3;
+
+const POSITIONS: &[[f32; 3]] = &[[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+
+const NORMALS: &[[f32; 3]] = &[[0.0, 0.0, 1.0], [0.0, 0.0, 1.0], [0.0, 0.0, 1.0]];
+
+const INDICES: &[u32] = &[0, 1, 2];
+
+/// The glb file contains the positions in the binary chunk (buffer 0), the normals in a data URI
+/// (buffer 1) and the indices in an external file (buffer 2).
+#[test]
+fn multiple_buffers_glb() {
+    let data = GltfData::from_file("./tests/multiple_buffers/multiple_buffers.glb").unwrap();
+
+    assert_eq!(data.scenes.len(), 1);
+
+    let node = data.scenes[0]
+        .nodes
+        .values()
+        .find(|node| node.mesh.is_some())
+        .unwrap();
+
+    let mesh = &data.meshes[&node.mesh.unwrap()];
+    assert_eq!(
+        mesh.positions
+            .iter()
+            .map(Vec3::to_array)
+            .collect::<Vec<_>>(),
+        POSITIONS
+    );
+    assert_eq!(
+        mesh.normals.iter().map(Vec3::to_array).collect::<Vec<_>>(),
+        NORMALS
+    );
+    assert_eq!(mesh.indices, INDICES);
+}
+
+#[test]
+fn multiple_buffers_glb_missing_external_buffer() {
+    let bytes = std::fs::read("./tests/multiple_buffers/multiple_buffers.glb").unwrap();
+
+    // Don't push the external buffer into the decoder.
+    let decoder = GltfDecoder::new(&bytes).unwrap();
+
+    match decoder.finish() {
+        Err(Error::MissingBuffer { uri }) => assert_eq!(uri, "multiple_buffers.bin"),
+        res => panic!("expected missing buffer error, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+fn multiple_buffers_external_buffer_too_short() {
+    let bytes = std::fs::read("./tests/multiple_buffers/multiple_buffers.glb").unwrap();
+
+    let mut decoder = GltfDecoder::new(&bytes).unwrap();
+    let uri = decoder.pop_source().unwrap();
+    decoder.push_source(uri, vec![0; 4]);
+
+    match decoder.finish() {
+        Err(Error::InvalidBufferLength {
+            index,
+            byte_length,
+            length,
+        }) => {
+            assert_eq!(index, 2);
+            assert_eq!(byte_length, 6);
+            assert_eq!(length, 4);
+        }
+        res => panic!(
+            "expected invalid buffer length error, got {:?}",
+            res.map(|_| ())
+        ),
+    }
+}
+
+#[test]
+fn multiple_buffers_bin_buffer_not_first() {
+    let bytes = br#"{
+        "asset": {"version": "2.0"},
+        "buffers": [
+            {"uri": "data:application/octet-stream;base64,AAAAAA==", "byteLength": 4},
+            {"byteLength": 4}
+        ]
+    }"#;
+
+    match GltfDecoder::new(bytes) {
+        Err(Error::InvalidBinBuffer { index }) => assert_eq!(index, 1),
+        res => panic!(
+            "expected invalid bin buffer error, got {:?}",
+            res.map(|_| ())
+        ),
+    }
+}
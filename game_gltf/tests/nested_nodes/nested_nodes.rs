@@ -1,4 +1,4 @@
-use game_gltf::GltfData;
+use game_gltf::{GltfData, GltfDecoder, NodeLayout};
 use glam::Vec3;
 
 #[test]
@@ -19,6 +19,35 @@ fn nested_nodes_glb() {
     validate_output(&data);
 }
 
+#[test]
+fn nested_nodes_preserve_layout() {
+    let bytes = std::fs::read("./tests/nested_nodes/nested_nodes.glb").unwrap();
+    let mut decoder = GltfDecoder::new(&bytes).unwrap();
+    decoder.set_node_layout(NodeLayout::Preserve);
+    let data = decoder.finish().unwrap();
+
+    assert_eq!(data.scenes.len(), 1);
+
+    let nodes = &data.scenes[0].nodes;
+    assert_eq!(nodes.len(), 2);
+
+    let (root_key, root) = nodes
+        .iter()
+        .find(|(key, _)| nodes.parent(*key).is_none())
+        .unwrap();
+    assert_eq!(root.name.as_deref(), Some("Cube"));
+    assert!(root.mesh.is_none());
+    assert_eq!(root.primitives.len(), 1);
+
+    let children: Vec<_> = nodes.children(root_key).unwrap().collect();
+    assert_eq!(children.len(), 1);
+
+    let child = children[0].1;
+    assert_eq!(child.name.as_deref(), Some("Cube.001"));
+    assert_eq!(child.transform.translation, Vec3::new(1.0, 2.0, 3.0));
+    assert_eq!(child.primitives.len(), 1);
+}
+
 fn validate_output(data: &GltfData) {
     assert_eq!(data.scenes.len(), 1);
 
@@ -0,0 +1,60 @@
+use game_gltf::GltfData;
+use glam::Vec3;
+
+#[test]
+fn non_uniform_scale_normals() {
+    let data = GltfData::from_file("./tests/non_uniform_scale/non_uniform_scale.gltf").unwrap();
+    assert_eq!(data.scenes.len(), 1);
+
+    let nodes: Vec<_> = data.scenes[0]
+        .nodes
+        .values()
+        .filter(|node| node.mesh.is_some())
+        .collect();
+    assert_eq!(nodes.len(), 2);
+
+    let scaled = nodes
+        .iter()
+        .find(|node| node.name.as_deref() == Some("Scaled"))
+        .unwrap();
+    assert_eq!(scaled.transform.scale, Vec3::new(4.0, 1.0, 0.25));
+    assert!(!scaled.transform.is_mirrored());
+
+    let mirrored = nodes
+        .iter()
+        .find(|node| node.name.as_deref() == Some("Mirrored"))
+        .unwrap();
+    assert!(mirrored.transform.is_mirrored());
+
+    for node in nodes {
+        let mesh = &data.meshes[&node.mesh.unwrap()];
+        let matrix = node.transform.compute_matrix();
+        let normal_matrix = node.transform.normal_matrix();
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] =
+                [0, 1, 2].map(|i| matrix.transform_point3(mesh.positions[triangle[i] as usize]));
+
+            // The face normal of the transformed triangle. The tetrahedron has
+            // flat faces, so all vertex normals must point in the same direction.
+            let mut face_normal = (b - a).cross(c - a).normalize();
+            if node.transform.is_mirrored() {
+                face_normal = -face_normal;
+            }
+
+            for index in triangle {
+                let normal = normal_matrix * mesh.normals[*index as usize];
+                let normal = normal.normalize();
+
+                assert!((normal.length() - 1.0).abs() < 1e-5);
+                assert!(
+                    normal.dot(face_normal) > 0.999,
+                    "normal {} of node {:?} does not match face normal {}",
+                    normal,
+                    node.name,
+                    face_normal,
+                );
+            }
+        }
+    }
+}
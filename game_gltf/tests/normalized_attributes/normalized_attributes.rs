@@ -0,0 +1,33 @@
+use game_gltf::GltfData;
+use glam::{Vec2, Vec3};
+
+#[test]
+fn normalized_attributes() {
+    let data =
+        GltfData::from_file("./tests/normalized_attributes/normalized_attributes.gltf").unwrap();
+    assert_eq!(data.meshes.len(), 1);
+
+    let mesh = data.meshes.values().next().unwrap();
+
+    // Normalized signed shorts.
+    assert_eq!(
+        mesh.positions,
+        [
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, -16384.0 / 32767.0),
+        ]
+    );
+
+    // Normalized unsigned bytes.
+    assert_eq!(
+        mesh.uvs,
+        [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 128.0 / 255.0),
+        ]
+    );
+
+    assert_eq!(mesh.indices, [0, 1, 2]);
+}
@@ -0,0 +1,69 @@
+use game_gltf::types::GltfMaterial;
+use game_gltf::GltfData;
+use game_render::texture::Image;
+use game_render::texture::TextureFormat;
+use glam::UVec2;
+
+const TEXTURE: &[u8] = include_bytes!("test_texture.png");
+
+#[test]
+fn specular_glossiness_gltf() {
+    let data = GltfData::from_file("./tests/specular_glossiness/specular_glossiness.gltf").unwrap();
+
+    let materials: Vec<&GltfMaterial> = data.scenes[0]
+        .nodes
+        .values()
+        .filter_map(|node| node.material)
+        .map(|index| &data.materials[&index])
+        .collect();
+    assert_eq!(materials.len(), 2);
+
+    // Dielectric material without any textures.
+    let material = materials
+        .iter()
+        .find(|material| material.base_color_texture.is_none())
+        .unwrap();
+    for channel in &material.base_color.0[..3] {
+        assert!((channel - 0.5).abs() < 1e-4);
+    }
+    assert_eq!(material.base_color.0[3], 1.0);
+    assert!(material.metallic.abs() < 1e-4);
+    assert!((material.roughness - 0.75).abs() < 1e-4);
+    assert!(material.metallic_roughness_texture.is_none());
+
+    // Fully specular material with a specular-glossiness texture.
+    let material = materials
+        .iter()
+        .find(|material| material.base_color_texture.is_some())
+        .unwrap();
+    assert!((material.metallic - 1.0).abs() < 1e-4);
+    assert_eq!(material.roughness, 1.0);
+
+    let texture = load_image(TEXTURE);
+    assert_eq!(
+        &data.images[&material.base_color_texture.unwrap()],
+        &texture
+    );
+
+    let roughness = &data.images[&material.metallic_roughness_texture.unwrap()];
+    assert_eq!(roughness.width(), texture.width());
+    assert_eq!(roughness.height(), texture.height());
+    for (src, dst) in texture
+        .as_bytes()
+        .chunks_exact(4)
+        .zip(roughness.as_bytes().chunks_exact(4))
+    {
+        let glossiness = f32::from(src[3]) / 255.0 * 0.5;
+        assert_eq!(dst[1], ((1.0 - glossiness) * 255.0).round() as u8);
+        assert_eq!(dst[2], u8::MAX);
+    }
+}
+
+fn load_image(buf: &[u8]) -> Image {
+    let img = image::load_from_memory(buf).unwrap().into_rgba8();
+    Image::new(
+        UVec2::new(img.width(), img.height()),
+        TextureFormat::Rgba8UnormSrgb,
+        img.into_raw(),
+    )
+}
@@ -0,0 +1,24 @@
+use game_gltf::GltfData;
+use glam::Vec3;
+
+#[test]
+fn u8_indices() {
+    // The indices are stored as unsigned bytes at an odd offset
+    // into the buffer.
+    let data = GltfData::from_file("./tests/u8_indices/u8_indices.gltf").unwrap();
+    assert_eq!(data.meshes.len(), 1);
+
+    let mesh = data.meshes.values().next().unwrap();
+
+    assert_eq!(
+        mesh.positions,
+        [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+        ]
+    );
+
+    assert_eq!(mesh.indices, [0, 1, 2, 2, 1, 3]);
+}
@@ -1,32 +1,39 @@
+use std::borrow::Cow;
+
 use bytes::{Buf, BufMut};
+use game_common::utils::endian;
 use glam::{Vec2, Vec3, Vec4};
 
 use crate::{Decode, Encode};
 
+/// A buffer of tightly packed values.
+///
+/// The values are stored in [`endian::BYTE_ORDER`]. The typed accessors only borrow the bytes if
+/// the host has the same byte order, otherwise the values are converted.
 #[derive(Clone, Debug)]
 pub struct Buffer {
     pub bytes: Vec<u8>,
 }
 
 impl Buffer {
-    pub fn as_positions(&self) -> &[Vec3] {
-        bytemuck::cast_slice(&self.bytes)
+    pub fn as_positions(&self) -> Cow<'_, [Vec3]> {
+        endian::cast_slice::<f32, _>(&self.bytes)
     }
 
-    pub fn as_normals(&self) -> &[Vec3] {
-        bytemuck::cast_slice(&self.bytes)
+    pub fn as_normals(&self) -> Cow<'_, [Vec3]> {
+        endian::cast_slice::<f32, _>(&self.bytes)
     }
 
-    pub fn as_tangents(&self) -> &[Vec4] {
-        bytemuck::cast_slice(&self.bytes)
+    pub fn as_tangents(&self) -> Cow<'_, [Vec4]> {
+        endian::cast_slice::<f32, _>(&self.bytes)
     }
 
-    pub fn as_uvs(&self) -> &[Vec2] {
-        bytemuck::cast_slice(&self.bytes)
+    pub fn as_uvs(&self) -> Cow<'_, [Vec2]> {
+        endian::cast_slice::<f32, _>(&self.bytes)
     }
 
-    pub fn as_indices(&self) -> &[u32] {
-        bytemuck::cast_slice(&self.bytes)
+    pub fn as_indices(&self) -> Cow<'_, [u32]> {
+        endian::cast_slice::<u32, _>(&self.bytes)
     }
 }
 
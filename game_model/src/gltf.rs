@@ -0,0 +1,350 @@
+//! Conversion from glTF
+//!
+//! Requires the `gltf` feature.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+
+use game_common::utils::endian;
+use game_gltf::types::{GltfMaterial, MaterialIndex, MeshIndex, TextureIndex};
+use game_gltf::GltfData;
+use thiserror::Error;
+
+use crate::buffer::Buffer;
+use crate::compression::CompressionScheme;
+use crate::material::{Material, MetallicRoughnessMaterial};
+use crate::mesh::Mesh;
+use crate::textures::{Texture, TextureFormat};
+use crate::{Header, Model, Node, VERSION};
+
+impl Model {
+    /// Converts the [`GltfData`] into a self-contained `Model`.
+    ///
+    /// All scenes of the glTF file are merged into the `Model`. Since a `Model` only contains a
+    /// flat list of nodes, the node hierarchy of the glTF scenes is flattened: every mesh
+    /// primitive of a glTF node becomes a [`Node`] with its transform relative to the scene root.
+    /// glTF nodes without a mesh are dropped.
+    ///
+    /// Meshes, materials and textures shared between multiple nodes are only stored once.
+    ///
+    /// The buffers of the `Model` are stored uncompressed ([`CompressionScheme::None`]) which is
+    /// the fastest to load.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`FromGltfError`] if the glTF data contains more than `u16::MAX - 1` distinct
+    /// buffers, meshes, materials or textures.
+    pub fn from_gltf(gltf: &GltfData) -> Result<Self, FromGltfError> {
+        let mut builder = ModelBuilder::new(gltf);
+
+        for scene in &gltf.scenes {
+            let mut stack: Vec<_> = scene
+                .nodes
+                .iter()
+                .filter(|(key, _)| scene.nodes.parent(*key).is_none())
+                .map(|(key, node)| (key, node.transform))
+                .collect();
+
+            while let Some((key, transform)) = stack.pop() {
+                let node = scene.nodes.get(key).unwrap();
+
+                // Nodes contain either a single mesh or a list of primitives,
+                // depending on the `NodeLayout` used for decoding.
+                let primitives = node
+                    .primitives
+                    .iter()
+                    .map(|primitive| (primitive.mesh, primitive.material))
+                    .chain(node.mesh.zip(node.material));
+
+                for (mesh, material) in primitives {
+                    let mesh = builder.mesh(mesh)?;
+                    let material = builder.material(material)?;
+
+                    builder.model.nodes.push(Node {
+                        transform,
+                        mesh,
+                        material,
+                    });
+                }
+
+                if let Some(children) = scene.nodes.children(key) {
+                    for (child, node) in children {
+                        stack.push((child, transform.mul_transform(node.transform)));
+                    }
+                }
+            }
+        }
+
+        Ok(builder.model)
+    }
+}
+
+/// An error that can occur when converting [`GltfData`] into a [`Model`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Error)]
+pub enum FromGltfError {
+    /// The glTF data contains more distinct elements of a kind than a [`Model`] can index.
+    #[error("index overflow: cannot support more than {} distinct {kind}", u16::MAX - 1)]
+    IndexOverflow { kind: &'static str },
+}
+
+struct ModelBuilder<'a> {
+    gltf: &'a GltfData,
+    model: Model,
+    meshes: HashMap<MeshIndex, u16>,
+    materials: HashMap<MaterialIndex, u16>,
+    /// The indices of the buffers keyed by the hash of their contents.
+    buffers: HashMap<u64, Vec<u16>>,
+    /// The same image may be used as textures with different formats, which must be stored
+    /// separately.
+    textures: HashMap<(TextureIndex, TextureFormat), u16>,
+}
+
+impl<'a> ModelBuilder<'a> {
+    fn new(gltf: &'a GltfData) -> Self {
+        Self {
+            gltf,
+            model: Model {
+                header: Header {
+                    version: VERSION,
+                    compression: CompressionScheme::None,
+                },
+                nodes: Vec::new(),
+                meshes: Vec::new(),
+                materials: Vec::new(),
+                buffers: Vec::new(),
+                textures: Vec::new(),
+            },
+            meshes: HashMap::new(),
+            materials: HashMap::new(),
+            buffers: HashMap::new(),
+            textures: HashMap::new(),
+        }
+    }
+
+    fn mesh(&mut self, index: MeshIndex) -> Result<u16, FromGltfError> {
+        if let Some(index) = self.meshes.get(&index) {
+            return Ok(*index);
+        }
+
+        let gltf = self.gltf;
+        let mesh = &gltf.meshes[&index];
+
+        let positions = self.buffer(&endian::to_bytes::<f32, _>(&mesh.positions))?;
+        let normals = self.buffer(&endian::to_bytes::<f32, _>(&mesh.normals))?;
+        let tangents = self.buffer(&endian::to_bytes::<f32, _>(&mesh.tangents))?;
+        let uvs = self.buffer(&endian::to_bytes::<f32, _>(&mesh.uvs))?;
+        let indices = self.buffer(&endian::to_bytes::<u32, _>(&mesh.indices))?;
+
+        let (min, max) = mesh.aabb();
+
+        let id = next_index(self.model.meshes.len(), "meshes")?;
+        self.model.meshes.push(Mesh {
+            positions,
+            normals,
+            tangents,
+            uvs,
+            indices,
+            min,
+            max,
+        });
+        self.meshes.insert(index, id);
+        Ok(id)
+    }
+
+    fn buffer(&mut self, bytes: &[u8]) -> Result<u16, FromGltfError> {
+        // Identical buffers are common, e.g. if multiple meshes share the
+        // same positions.
+        let hash = self.buffers.hasher().hash_one(bytes);
+        let indices = self.buffers.entry(hash).or_default();
+        if let Some(index) = indices
+            .iter()
+            .find(|index| self.model.buffers[usize::from(**index)].bytes == bytes)
+        {
+            return Ok(*index);
+        }
+
+        let id = next_index(self.model.buffers.len(), "buffers")?;
+        self.model.buffers.push(Buffer {
+            bytes: bytes.to_vec(),
+        });
+        indices.push(id);
+        Ok(id)
+    }
+
+    fn material(&mut self, index: MaterialIndex) -> Result<u16, FromGltfError> {
+        if let Some(index) = self.materials.get(&index) {
+            return Ok(*index);
+        }
+
+        let GltfMaterial {
+            alpha_mode: _,
+            base_color,
+            base_color_texture,
+            normal_texture,
+            roughness,
+            metallic,
+            metallic_roughness_texture,
+            double_sided,
+        } = self.gltf.materials[&index];
+
+        let albedo_texture = base_color_texture
+            .map(|index| self.texture(index, TextureFormat::Rgba8UnormSrgb))
+            .transpose()?;
+        let normal_texture = normal_texture
+            .map(|index| self.texture(index, TextureFormat::Rgba8Unorm))
+            .transpose()?;
+        let metallic_roughness_texture = metallic_roughness_texture
+            .map(|index| self.texture(index, TextureFormat::Rgba8UnormSrgb))
+            .transpose()?;
+
+        let id = next_index(self.model.materials.len(), "materials")?;
+        self.model
+            .materials
+            .push(Material::MetallicRoughness(MetallicRoughnessMaterial {
+                base_color: base_color.0.map(unorm8),
+                roughness: unorm8(roughness),
+                metallic: unorm8(metallic),
+                albedo_texture,
+                normal_texture,
+                metallic_roughness_texture,
+                double_sided,
+            }));
+        self.materials.insert(index, id);
+        Ok(id)
+    }
+
+    fn texture(
+        &mut self,
+        index: TextureIndex,
+        format: TextureFormat,
+    ) -> Result<u16, FromGltfError> {
+        if let Some(index) = self.textures.get(&(index, format)) {
+            return Ok(*index);
+        }
+
+        // Images in `GltfData` are already decoded into RGBA8.
+        let image = &self.gltf.images[&index];
+
+        let id = next_index(self.model.textures.len(), "textures")?;
+        self.model.textures.push(Texture {
+            format,
+            width: image.width(),
+            height: image.height(),
+            bytes: image.as_bytes().to_vec(),
+        });
+        self.textures.insert((index, format), id);
+        Ok(id)
+    }
+}
+
+/// Returns the next index for a list of `len` elements.
+///
+/// `u16::MAX` is reserved as a `None` sentinel.
+fn next_index(len: usize, kind: &'static str) -> Result<u16, FromGltfError> {
+    match u16::try_from(len) {
+        Ok(index) if index != u16::MAX => Ok(index),
+        _ => Err(FromGltfError::IndexOverflow { kind }),
+    }
+}
+
+fn unorm8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use game_gltf::GltfData;
+
+    use crate::material::Material;
+    use crate::textures::TextureFormat;
+    use crate::{Decode, Encode, Model};
+
+    use super::{next_index, FromGltfError, ModelBuilder};
+
+    const BASIC_MATERIAL: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../game_gltf/tests/basic_material/basic_material.glb"
+    );
+
+    fn encode(model: &Model) -> Vec<u8> {
+        let mut buf = Vec::new();
+        model.encode(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn from_gltf_roundtrip() {
+        let gltf = GltfData::from_file(BASIC_MATERIAL).unwrap();
+        let model = Model::from_gltf(&gltf).unwrap();
+
+        assert!(!model.nodes.is_empty());
+        assert_eq!(model.meshes.len(), gltf.meshes.len());
+        assert_eq!(model.materials.len(), 1);
+        assert_eq!(model.textures.len(), 1);
+
+        let Material::MetallicRoughness(material) = &model.materials[0];
+        assert_eq!(material.albedo_texture, Some(0));
+        assert_eq!(model.textures[0].format, TextureFormat::Rgba8UnormSrgb);
+
+        let bytes = encode(&model);
+        let decoded = Model::decode(&bytes[..]).unwrap();
+
+        assert_eq!(decoded.nodes.len(), model.nodes.len());
+        assert_eq!(decoded.meshes.len(), model.meshes.len());
+        assert_eq!(decoded.materials.len(), model.materials.len());
+        assert_eq!(decoded.buffers.len(), model.buffers.len());
+        assert_eq!(decoded.textures.len(), model.textures.len());
+        assert_eq!(encode(&decoded), bytes);
+    }
+
+    #[test]
+    fn from_gltf_texture_with_different_formats() {
+        let mut gltf = GltfData::from_file(BASIC_MATERIAL).unwrap();
+        for material in gltf.materials.values_mut() {
+            material.normal_texture = material.base_color_texture;
+        }
+
+        let model = Model::from_gltf(&gltf).unwrap();
+
+        // The image is used as an sRGB and a linear texture, which must not
+        // share the same texture.
+        let Material::MetallicRoughness(material) = &model.materials[0];
+        let albedo = &model.textures[usize::from(material.albedo_texture.unwrap())];
+        let normal = &model.textures[usize::from(material.normal_texture.unwrap())];
+        assert_eq!(model.textures.len(), 2);
+        assert_eq!(albedo.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(normal.format, TextureFormat::Rgba8Unorm);
+        assert_eq!(albedo.bytes, normal.bytes);
+    }
+
+    #[test]
+    fn model_builder_buffer_dedup() {
+        let gltf = GltfData::from_file(BASIC_MATERIAL).unwrap();
+        let mut builder = ModelBuilder::new(&gltf);
+
+        let a = builder.buffer(&[0, 1, 2, 3]).unwrap();
+        let b = builder.buffer(&[4, 5, 6, 7]).unwrap();
+        assert_ne!(a, b);
+
+        assert_eq!(builder.buffer(&[0, 1, 2, 3]).unwrap(), a);
+        assert_eq!(builder.buffer(&[4, 5, 6, 7]).unwrap(), b);
+        assert_eq!(builder.model.buffers.len(), 2);
+    }
+
+    #[test]
+    fn next_index_overflow() {
+        assert_eq!(next_index(0, "meshes"), Ok(0));
+        assert_eq!(
+            next_index(usize::from(u16::MAX) - 1, "meshes"),
+            Ok(u16::MAX - 1)
+        );
+        assert_eq!(
+            next_index(usize::from(u16::MAX), "meshes"),
+            Err(FromGltfError::IndexOverflow { kind: "meshes" })
+        );
+        assert_eq!(
+            next_index(usize::MAX, "textures"),
+            Err(FromGltfError::IndexOverflow { kind: "textures" })
+        );
+    }
+}
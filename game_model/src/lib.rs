@@ -1,4 +1,7 @@
 //! Model format
+//!
+//! All multi-byte values, including the contents of [`Buffer`]s, are encoded in the byte order
+//! defined by [`game_common::utils::endian`] (little-endian), independent of the host.
 
 pub mod buffer;
 pub mod compression;
@@ -7,10 +10,17 @@ pub mod mesh;
 pub mod parser;
 pub mod textures;
 
+#[cfg(feature = "gltf")]
+mod gltf;
+
+#[cfg(feature = "gltf")]
+pub use gltf::FromGltfError;
+
 use buffer::Buffer;
 use bytes::{Buf, BufMut};
 use compression::CompressionScheme;
 use game_common::components::Transform;
+use game_common::utils::endian::Primitive;
 use glam::{Quat, Vec2, Vec3, Vec4};
 use material::Material;
 use mesh::Mesh;
@@ -18,6 +28,17 @@ use textures::Texture;
 
 pub const MAGIC: [u8; 4] = [0, 0, 0, 0];
 
+/// The version of the format written by this crate.
+///
+/// Files with a different version are rejected by [`Header::decode`].
+///
+/// - `0`: Initial format.
+/// - `1`: [`Mesh`] stores the bounds of its positions.
+/// - `2`: [`MetallicRoughnessMaterial`] stores whether it is double-sided.
+///
+/// [`MetallicRoughnessMaterial`]: material::MetallicRoughnessMaterial
+pub const VERSION: u32 = 2;
+
 pub trait Encode {
     fn encode<B>(&self, buf: B)
     where
@@ -61,6 +82,10 @@ impl Decode for Header {
         assert_eq!(magic, MAGIC);
 
         let version = u32::decode(&mut buf)?;
+        if version != VERSION {
+            return Err(());
+        }
+
         let compression = CompressionScheme::decode(&mut buf)?;
 
         Ok(Self {
@@ -100,7 +125,7 @@ macro_rules! int_impls {
                 fn encode<B>(&self, buf: B)
                     where B: BufMut,
                 {
-                    self.to_le_bytes().encode(buf);
+                    Primitive::to_bytes(*self).encode(buf);
                 }
             }
 
@@ -112,7 +137,7 @@ macro_rules! int_impls {
                     where B: Buf,
                 {
                     let bytes = <[u8; std::mem::size_of::<Self>()]>::decode(buf).unwrap();
-                    Ok(Self::from_le_bytes(bytes))
+                    Ok(Primitive::from_bytes(bytes))
                 }
             }
         )*
@@ -366,3 +391,34 @@ impl Decode for Node {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compression::CompressionScheme;
+    use crate::{Decode, Encode, Header, VERSION};
+
+    #[test]
+    fn header_version() {
+        let mut buf = Vec::new();
+        Header {
+            version: VERSION,
+            compression: CompressionScheme::None,
+        }
+        .encode(&mut buf);
+
+        let header = Header::decode(&buf[..]).unwrap();
+        assert_eq!(header.version, VERSION);
+    }
+
+    #[test]
+    fn header_version_outdated() {
+        let mut buf = Vec::new();
+        Header {
+            version: 0,
+            compression: CompressionScheme::None,
+        }
+        .encode(&mut buf);
+
+        assert!(Header::decode(&buf[..]).is_err());
+    }
+}
@@ -93,6 +93,8 @@ pub struct MetallicRoughnessMaterial {
     pub albedo_texture: Option<u16>,
     pub normal_texture: Option<u16>,
     pub metallic_roughness_texture: Option<u16>,
+    /// Whether back faces are rendered.
+    pub double_sided: bool,
 }
 
 impl Encode for MetallicRoughnessMaterial {
@@ -111,6 +113,8 @@ impl Encode for MetallicRoughnessMaterial {
         albedo_texture.encode(&mut buf);
         normal_texture.encode(&mut buf);
         metallic_roughness_texture.encode(&mut buf);
+
+        u8::from(self.double_sided).encode(&mut buf);
     }
 }
 
@@ -127,6 +131,11 @@ impl Decode for MetallicRoughnessMaterial {
         let albedo_texture = u16::decode(&mut buf)?;
         let normal_texture = u16::decode(&mut buf)?;
         let metallic_roughness_texture = u16::decode(&mut buf)?;
+        let double_sided = match u8::decode(&mut buf)? {
+            0 => false,
+            1 => true,
+            _ => return Err(()),
+        };
 
         Ok(Self {
             base_color,
@@ -147,6 +156,36 @@ impl Decode for MetallicRoughnessMaterial {
             } else {
                 Some(metallic_roughness_texture)
             },
+            double_sided,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Decode, Encode};
+
+    use super::{Material, MetallicRoughnessMaterial};
+
+    #[test]
+    fn material_double_sided() {
+        for double_sided in [false, true] {
+            let material = Material::MetallicRoughness(MetallicRoughnessMaterial {
+                base_color: [255, 0, 0, 255],
+                roughness: 128,
+                metallic: 0,
+                albedo_texture: Some(0),
+                normal_texture: None,
+                metallic_roughness_texture: None,
+                double_sided,
+            });
+
+            let mut buf = Vec::new();
+            material.encode(&mut buf);
+
+            let Material::MetallicRoughness(material) = Material::decode(&buf[..]).unwrap();
+            assert_eq!(material.double_sided, double_sided);
+            assert_eq!(material.albedo_texture, Some(0));
+        }
+    }
+}
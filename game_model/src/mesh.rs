@@ -11,6 +11,23 @@ pub struct Mesh {
     pub tangents: u16,
     pub uvs: u16,
     pub indices: u16,
+    /// The minimum of the axis-aligned bounding box of the positions.
+    pub min: Vec3,
+    /// The maximum of the axis-aligned bounding box of the positions.
+    pub max: Vec3,
+}
+
+impl Mesh {
+    /// Returns the axis-aligned bounding box of the mesh as `(min, max)`.
+    ///
+    /// The bounding box is stored in the model, so it doesn't need to be recomputed from the
+    /// positions when loading. An empty mesh has the [`EMPTY_AABB`].
+    ///
+    /// [`EMPTY_AABB`]: game_common::math::EMPTY_AABB
+    #[inline]
+    pub fn aabb(&self) -> (Vec3, Vec3) {
+        (self.min, self.max)
+    }
 }
 
 impl Encode for Transform {
@@ -53,6 +70,8 @@ impl Encode for Mesh {
         self.tangents.encode(&mut buf);
         self.uvs.encode(&mut buf);
         self.indices.encode(&mut buf);
+        self.min.encode(&mut buf);
+        self.max.encode(&mut buf);
     }
 }
 
@@ -68,6 +87,8 @@ impl Decode for Mesh {
         let tangents = u16::decode(&mut buf)?;
         let uvs = u16::decode(&mut buf)?;
         let indices = u16::decode(&mut buf)?;
+        let min = Vec3::decode(&mut buf)?;
+        let max = Vec3::decode(&mut buf)?;
 
         Ok(Self {
             positions,
@@ -75,6 +96,8 @@ impl Decode for Mesh {
             tangents,
             uvs,
             indices,
+            min,
+            max,
         })
     }
 }
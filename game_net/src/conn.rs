@@ -32,6 +32,11 @@ use crate::proto::{
     Decode, Encode, Flags, Frame, Header, Packet, PacketBody, PacketPosition, PacketType,
     SequenceRange,
 };
+use crate::sequence::AckTracker;
+
+/// The default duration after which a [`Connection`] is closed if no packets are received from
+/// the peer.
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15);
 
 #[derive(Clone, Debug, Error)]
 pub enum Error<E>
@@ -64,7 +69,9 @@ where
 
     state: ConnectionState,
     interval: TickInterval,
-    last_time: Instant,
+    /// The time at which the last packet was received from the peer.
+    last_activity: Instant,
+    idle_timeout: Duration,
 
     packet_queue: VecDeque<Packet>,
     frame_queue: VecDeque<(Frame, ControlFrame, MessageId)>,
@@ -95,6 +102,8 @@ where
     reassembly_buffer: ReassemblyBuffer,
     ack_time_list: AckTimeList,
     rtt: Arc<Mutex<Rtt>>,
+    /// The control frames of our data that the peer acknowledged.
+    ack_tracker: Arc<Mutex<AckTracker>>,
     /// Last Processed control frame
     last_cf: ControlFrame,
     message_out: HashMap<Sequence, MessageId>,
@@ -124,6 +133,7 @@ where
         let (writer, reader) = mpsc::channel(4096);
 
         let rtt = Arc::new(Mutex::new(Rtt::new()));
+        let ack_tracker = Arc::new(Mutex::new(AckTracker::new()));
 
         let mut conn = Self {
             stream,
@@ -133,7 +143,8 @@ where
             packet_queue: VecDeque::new(),
             frame_queue: VecDeque::new(),
             interval: TickInterval::new(),
-            last_time: Instant::now(),
+            last_activity: Instant::now(),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
             next_local_sequence: Sequence::default(),
             next_ack_sequence: Sequence::default(),
             next_peer_sequence: Sequence::default(),
@@ -154,6 +165,7 @@ where
 
             ack_time_list: AckTimeList::new(),
             rtt: rtt.clone(),
+            ack_tracker: ack_tracker.clone(),
             last_cf: ControlFrame(0),
             message_out: HashMap::new(),
             messages_in: HashMap::new(),
@@ -173,10 +185,25 @@ where
                 chan_out: out_tx,
                 rx: Mutex::new(reader),
                 rtt,
+                ack_tracker,
             },
         )
     }
 
+    /// Sets the duration after which the connection is closed if no packets are received from
+    /// the peer. The default is [`DEFAULT_IDLE_TIMEOUT`].
+    ///
+    /// Both sides of a connection periodically send ACKs while connected, which act as
+    /// keepalives. A peer that is alive is therefore never considered idle, even if it does not
+    /// send any data.
+    ///
+    /// When the timeout expires the connection is shut down and resolves to [`Error::Timeout`].
+    /// A listening connection also emits [`ControlMessage::Disconnected`] on its
+    /// [`ConnectionHandle`].
+    pub fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.idle_timeout = timeout;
+    }
+
     fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Error<S::Error>>> {
         let _span = trace_span!("Connection::poll_read").entered();
 
@@ -187,7 +214,7 @@ where
         }
 
         while let Poll::Ready(packet) = self.stream.poll_next_unpin(cx) {
-            self.last_time = Instant::now();
+            self.last_activity = Instant::now();
 
             let Some(packet) = packet else {
                 // `None` means the remote peer has hung up and will
@@ -221,6 +248,10 @@ where
             match msg {
                 Message::Control(ControlMessage::Acknowledge(id, cf)) => {
                     if let Some(seq) = self.messages_in.remove(&id) {
+                        // ACKs are cumulative, we must not acknowledge the
+                        // message if an earlier packet is still missing.
+                        let seq = seq.min(self.last_contiguous_sequence());
+
                         let packet = Packet {
                             header: Header {
                                 packet_type: PacketType::ACK,
@@ -273,6 +304,7 @@ where
             // sequence, but for fragmented commands we track the last sequence
             // of the stream.
             let mut last_seq = Sequence::default();
+            let mut ack_tracker = self.ack_tracker.lock();
 
             fragment_frame(
                 &frame,
@@ -282,6 +314,7 @@ where
                 |packet| {
                     last_seq = packet.header.sequence;
 
+                    ack_tracker.send(cf + self.start_control_frame, last_seq);
                     self.inflight_packets.insert(packet.clone());
                     self.packet_queue.push_back(packet);
                 },
@@ -293,6 +326,15 @@ where
         }
     }
 
+    /// Returns the newest [`Sequence`] for which we have received all packets up to and
+    /// including that sequence.
+    fn last_contiguous_sequence(&self) -> Sequence {
+        match self.loss_list.first() {
+            Some(seq) => seq - 1,
+            None => self.next_peer_sequence - 1,
+        }
+    }
+
     fn init_write(&mut self) -> Result<(), S::Error> {
         self.is_writing = true;
         let packet = self.packet_queue.pop_front().unwrap();
@@ -594,6 +636,9 @@ where
         self.writer
             .try_send(Message::Control(ControlMessage::Ack(control_frame)))
             .unwrap();
+        // The peer has received all data up to the acknowledged packet.
+        self.ack_tracker.lock().ack(sequence);
+
         if let Some(id) = self.message_out.remove(&sequence) {
             self.writer
                 .try_send(Message::Control(ControlMessage::Acknowledge(
@@ -688,8 +733,8 @@ where
         // is not actually necessary.
         // FIXME: It might make sense to replace this with a custom time driver.
         while let Poll::Ready(tick) = self.interval.poll_tick(cx) {
-            if self.last_time.elapsed() >= Duration::from_secs(15) {
-                tracing::info!("closing connection due to timeout");
+            if self.last_activity.elapsed() >= self.idle_timeout {
+                tracing::info!("closing connection due to idle timeout");
 
                 self.shutdown();
                 return Poll::Ready(Err(Error::Timeout));
@@ -697,15 +742,10 @@ where
 
             // Send periodic ACKs while connected.
             if self.state == ConnectionState::Connected && tick.is_ack() {
-                // FIXME: This seems kinda awkward.
-                // What should we actually send? The last received sequence,
-                // last recevied sequence without NAKs, or the last sequence
-                // + 1? The current connection impl only acknowledges messages
-                // if the ACK contains the exact sequence of the last packet,
-                // so if we actually `next_peer_sequence`, but previous packet
-                // was lost, the peer will still acknowledge the message.
-                // This is bad.
-                let sequence = self.next_peer_sequence - 1;
+                // ACKs are cumulative: We only acknowledge the sequences
+                // up to the first lost packet, even if we already received
+                // newer packets.
+                let sequence = self.last_contiguous_sequence();
 
                 let ack_sequence = self.next_ack_sequence.fetch_next();
 
@@ -880,6 +920,7 @@ pub struct ConnectionHandle {
     chan_out: mpsc::Sender<Message>,
     rx: Mutex<mpsc::Receiver<Message>>,
     rtt: Arc<Mutex<Rtt>>,
+    ack_tracker: Arc<Mutex<AckTracker>>,
 }
 
 impl ConnectionHandle {
@@ -926,6 +967,15 @@ impl ConnectionHandle {
     pub fn rtt(&self) -> Duration {
         Duration::from_micros(self.rtt.lock().rtt.into())
     }
+
+    /// Returns the newest [`ControlFrame`] of which the peer has acknowledged the reception of
+    /// data, or `None` if the peer has not acknowledged any data yet.
+    ///
+    /// This is the newest state known to the peer and can be used as a baseline for delta
+    /// updates.
+    pub fn last_acked_cf(&self) -> Option<ControlFrame> {
+        self.ack_tracker.lock().get()
+    }
 }
 
 pub struct ConnectionKey {}
@@ -1051,6 +1101,11 @@ impl LossList {
         self.buffer.push(seq);
     }
 
+    /// Returns the oldest lost sequence.
+    fn first(&self) -> Option<Sequence> {
+        self.buffer.first().copied()
+    }
+
     /// Returns `true` if `seq` was removed.
     fn remove(&mut self, seq: Sequence) -> bool {
         let mut index = 0;
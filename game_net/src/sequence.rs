@@ -1 +1,152 @@
+//! Tracking of the control frames acknowledged by a peer.
 
+use std::collections::VecDeque;
+
+use game_common::world::control_frame::ControlFrame;
+
+use crate::proto::sequence::Sequence;
+
+/// The maximum number of control frames with unacknowledged data tracked by [`AckTracker`].
+///
+/// If the peer falls further behind the oldest control frames are dropped. They can no longer
+/// become the acknowledged frame, but newer frames still can.
+const MAX_PENDING_FRAMES: usize = 8192;
+
+/// Tracks the newest [`ControlFrame`] of which the peer has received all data.
+///
+/// A control frame can span multiple packets and the peer may acknowledge packets out of order.
+/// A control frame is only considered acknowledged once the peer has acknowledged every packet
+/// sent in it and all packets sent before it.
+#[derive(Clone, Debug, Default)]
+pub struct AckTracker {
+    /// The control frames with unacknowledged data and the last [`Sequence`] sent in them, in
+    /// the order they were sent.
+    pending: VecDeque<(ControlFrame, Sequence)>,
+    control_frame: Option<ControlFrame>,
+}
+
+impl AckTracker {
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            control_frame: None,
+        }
+    }
+
+    /// Returns the newest acknowledged [`ControlFrame`], or `None` if the peer has not yet
+    /// acknowledged any frame.
+    #[inline]
+    pub fn get(&self) -> Option<ControlFrame> {
+        self.control_frame
+    }
+
+    /// Records that a data packet with the given `sequence` was sent in the given
+    /// [`ControlFrame`].
+    ///
+    /// Packets must be recorded in the order they were sent.
+    pub fn send(&mut self, control_frame: ControlFrame, sequence: Sequence) {
+        match self.pending.back_mut() {
+            Some((cf, seq)) if *cf == control_frame => *seq = sequence,
+            _ => {
+                if self.pending.len() == MAX_PENDING_FRAMES {
+                    self.pending.pop_front();
+                }
+
+                self.pending.push_back((control_frame, sequence));
+            }
+        }
+    }
+
+    /// Records that the peer has received all packets up to and including `sequence`.
+    ///
+    /// Returns `true` if the acknowledged frame advanced.
+    pub fn ack(&mut self, sequence: Sequence) -> bool {
+        let mut advanced = false;
+
+        while let Some((cf, last)) = self.pending.front() {
+            if *last > sequence {
+                break;
+            }
+
+            self.control_frame = Some(*cf);
+            self.pending.pop_front();
+            advanced = true;
+        }
+
+        advanced
+    }
+
+    /// Forgets all sent packets and the acknowledged frame.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.control_frame = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game_common::world::control_frame::ControlFrame;
+
+    use super::AckTracker;
+    use crate::proto::sequence::Sequence;
+
+    #[test]
+    fn ack_tracker_frame_spans_packets() {
+        let mut tracker = AckTracker::new();
+        tracker.send(ControlFrame(1), Sequence::new(10));
+        tracker.send(ControlFrame(1), Sequence::new(11));
+        tracker.send(ControlFrame(1), Sequence::new(12));
+        tracker.send(ControlFrame(2), Sequence::new(13));
+
+        // Only part of the first frame was received.
+        assert!(!tracker.ack(Sequence::new(11)));
+        assert_eq!(tracker.get(), None);
+
+        assert!(tracker.ack(Sequence::new(12)));
+        assert_eq!(tracker.get(), Some(ControlFrame(1)));
+
+        assert!(tracker.ack(Sequence::new(13)));
+        assert_eq!(tracker.get(), Some(ControlFrame(2)));
+    }
+
+    #[test]
+    fn ack_tracker_out_of_order() {
+        let mut tracker = AckTracker::new();
+        for (cf, seq) in [(1, 1), (2, 2), (3, 3), (4, 4)] {
+            tracker.send(ControlFrame(cf), Sequence::new(seq));
+        }
+
+        assert!(tracker.ack(Sequence::new(3)));
+        assert_eq!(tracker.get(), Some(ControlFrame(3)));
+
+        // An older ACK arriving late never moves the frame back.
+        assert!(!tracker.ack(Sequence::new(1)));
+        assert_eq!(tracker.get(), Some(ControlFrame(3)));
+
+        assert!(tracker.ack(Sequence::new(4)));
+        assert_eq!(tracker.get(), Some(ControlFrame(4)));
+
+        // Duplicate ACKs have no effect.
+        assert!(!tracker.ack(Sequence::new(4)));
+
+        tracker.reset();
+        assert_eq!(tracker.get(), None);
+    }
+
+    #[test]
+    fn ack_tracker_lost_packet() {
+        let mut tracker = AckTracker::new();
+        tracker.send(ControlFrame(1), Sequence::new(1));
+        tracker.send(ControlFrame(2), Sequence::new(2));
+        tracker.send(ControlFrame(3), Sequence::new(3));
+
+        // Packet 2 was lost, so the peer can only acknowledge up to 1 until
+        // the packet was retransmitted.
+        assert!(tracker.ack(Sequence::new(1)));
+        assert_eq!(tracker.get(), Some(ControlFrame(1)));
+
+        assert!(tracker.ack(Sequence::new(3)));
+        assert_eq!(tracker.get(), Some(ControlFrame(3)));
+    }
+}
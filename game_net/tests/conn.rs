@@ -1,8 +1,11 @@
 use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
 
 use game_common::world::control_frame::ControlFrame;
 use game_net::conn::channel::ChannelStream;
-use game_net::conn::{Connection, ConnectionHandle, Listen};
+use game_net::conn::{Connection, ConnectionHandle, Error, Listen};
+use game_net::message::{ControlMessage, Message};
+use game_net::proto::ack::Ack;
 use game_net::proto::handshake::{Handshake, HandshakeFlags, HandshakeType};
 use game_net::proto::sequence::Sequence;
 use game_net::proto::shutdown::{Shutdown, ShutdownReason};
@@ -229,3 +232,90 @@ async fn listener_shutdown() {
     })
     .unwrap();
 }
+
+#[tokio::test]
+async fn idle_timeout() {
+    let (stream, mut tx, mut rx) = create_stream();
+    let (mut conn, handle) = create_connection(stream);
+    conn.set_idle_timeout(Duration::from_millis(200));
+    let conn = tokio::task::spawn(conn);
+
+    do_handshake(&mut tx, &mut rx).await;
+    wait_for_connected(&handle).await;
+
+    let res = tokio::time::timeout(Duration::from_secs(5), conn)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(res, Err(Error::Timeout)));
+
+    assert!(std::iter::from_fn(|| handle.recv())
+        .any(|msg| matches!(msg, Message::Control(ControlMessage::Disconnected))));
+}
+
+#[tokio::test]
+async fn keepalive_prevents_idle_timeout() {
+    let (stream, mut tx, mut rx) = create_stream();
+    let (mut conn, handle) = create_connection(stream);
+    conn.set_idle_timeout(Duration::from_millis(200));
+    let conn = tokio::task::spawn(conn);
+
+    do_handshake(&mut tx, &mut rx).await;
+    wait_for_connected(&handle).await;
+
+    // Send only ACKs for several times the idle timeout.
+    for index in 0..20 {
+        tx.try_send(Packet {
+            header: Header {
+                packet_type: PacketType::ACK,
+                sequence: Sequence::new(0),
+                control_frame: ControlFrame(0),
+                flags: Flags::new(),
+            },
+            body: PacketBody::Ack(Ack {
+                sequence: Sequence::new(0),
+                ack_sequence: Sequence::new(index),
+            }),
+        })
+        .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    assert!(!conn.is_finished());
+    assert!(!std::iter::from_fn(|| handle.recv())
+        .any(|msg| matches!(msg, Message::Control(ControlMessage::Disconnected))));
+
+    drop(handle);
+}
+
+fn create_stream() -> (ChannelStream, mpsc::Sender<Packet>, mpsc::Receiver<Packet>) {
+    let (tx0, rx0) = mpsc::channel(4096);
+    let (tx1, rx1) = mpsc::channel(4096);
+    (ChannelStream::new(tx0, rx1), tx1, rx0)
+}
+
+fn create_connection(
+    stream: ChannelStream,
+) -> (Connection<ChannelStream, Listen>, ConnectionHandle) {
+    Connection::new(
+        stream,
+        ControlFrame(0),
+        ControlFrame(0),
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    )
+}
+
+async fn wait_for_connected(handle: &ConnectionHandle) {
+    loop {
+        if matches!(
+            handle.recv(),
+            Some(Message::Control(ControlMessage::Connected()))
+        ) {
+            return;
+        }
+
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
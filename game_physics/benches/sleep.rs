@@ -0,0 +1,83 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use game_common::components::{
+    Collider, ColliderShape, Cuboid, RigidBody, RigidBodyKind, Transform,
+};
+use game_common::events::EventQueue;
+use game_common::world::hierarchy::update_global_transform;
+use game_common::world::World;
+use game_physics::{Pipeline, PipelineConfig};
+use glam::Vec3;
+
+/// The number of boxes along each axis.
+const GRID_SIZE: u32 = 20;
+
+fn create_collider(hx: f32, hy: f32, hz: f32) -> Collider {
+    Collider {
+        friction: 0.5,
+        restitution: 0.0,
+        shape: ColliderShape::Cuboid(Cuboid { hx, hy, hz }),
+    }
+}
+
+/// Creates a world with `GRID_SIZE * GRID_SIZE` boxes resting on the ground and steps it until
+/// all boxes have settled.
+fn build_settled_world(config: PipelineConfig) -> (World, Pipeline) {
+    let mut world = World::new();
+
+    let ground = world.spawn();
+    world.insert_typed(ground, Transform::IDENTITY);
+    world.insert_typed(ground, RigidBody::new(RigidBodyKind::Fixed));
+    world.insert_typed(ground, create_collider(100.0, 1.0, 100.0));
+
+    for x in 0..GRID_SIZE {
+        for z in 0..GRID_SIZE {
+            let translation = Vec3::new(x as f32 * 3.0 - 30.0, 2.0, z as f32 * 3.0 - 30.0);
+
+            let entity = world.spawn();
+            world.insert_typed(entity, Transform::from_translation(translation));
+            world.insert_typed(entity, RigidBody::new(RigidBodyKind::Dynamic));
+            world.insert_typed(entity, create_collider(1.0, 1.0, 1.0));
+        }
+    }
+
+    let mut pipeline = Pipeline::with_config(config);
+    let mut events = EventQueue::new();
+    for _ in 0..300 {
+        update_global_transform(&mut world);
+        pipeline.step(&mut world, &mut events);
+    }
+
+    (world, pipeline)
+}
+
+fn run_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("settled boxes");
+
+    for (name, config) in [
+        ("sleeping", PipelineConfig::default()),
+        (
+            "awake",
+            PipelineConfig {
+                sleep_linear_threshold: -1.0,
+                sleep_angular_threshold: -1.0,
+                ..Default::default()
+            },
+        ),
+    ] {
+        let (mut world, mut pipeline) = build_settled_world(config);
+        let mut events = EventQueue::new();
+
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                update_global_transform(&mut world);
+                pipeline.step(&mut world, &mut events);
+                while events.pop().is_some() {}
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, run_bench);
+criterion_main!(benches);
@@ -1,19 +1,22 @@
 pub mod data;
 pub mod query;
+pub mod snapshot;
 
 mod convert;
 mod pipeline;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
 use convert::{point, quat, rotation, vec3, vector};
 use game_common::collections::bimap::BiMap;
 use game_common::components::{
-    Axis, Children, ColliderShape, GlobalTransform, RigidBody, RigidBodyKind, Transform,
+    Axis, Children, ColliderShape, CollisionGroups, GlobalTransform, RigidBody, RigidBodyKind,
+    Transform,
 };
 use game_common::entity::EntityId;
 use game_common::events::{self, Event, EventQueue};
+use game_common::world::hierarchy::DEFAULT_MAX_DEPTH;
 use game_common::world::{QueryWrapper, World};
 use game_tracing::trace_span;
 use glam::{Quat, Vec3};
@@ -26,16 +29,45 @@ use rapier3d::parry::query::ShapeCastOptions;
 use rapier3d::parry::shape::{Ball, Capsule, Cuboid};
 use rapier3d::prelude::{
     CCDSolver, Collider, ColliderBuilder, ColliderHandle, ColliderSet, CollisionEvent, ContactPair,
-    EventHandler, ImpulseJointSet, IntegrationParameters, IslandManager, MultibodyJointSet,
-    NarrowPhase, PhysicsPipeline, QueryFilter, QueryPipeline, Ray, RigidBodyBuilder,
-    RigidBodyHandle, RigidBodySet, RigidBodyType, SharedShape, Vector,
+    EventHandler, Group, ImpulseJointSet, IntegrationParameters, InteractionGroups, IslandManager,
+    MultibodyJointSet, NarrowPhase, PhysicsPipeline, QueryFilter, QueryPipeline, Ray,
+    RigidBodyActivation, RigidBodyBuilder, RigidBodyHandle, RigidBodySet, RigidBodyType,
+    SharedShape, Vector,
 };
+use snapshot::PhysicsSnapshot;
 
 const DT: Real = 1.0 / 60.0;
 const MIN_CCD_DT: Real = DT / 100.0;
 const GRAVITY: Vector<Real> = Vector::new(0.0, -9.81, 0.0);
 
+/// The configuration of a [`Pipeline`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PipelineConfig {
+    /// The linear velocity below which a dynamic body can fall asleep.
+    ///
+    /// Sleeping bodies are not simulated until they are woken up again by a collision or a change
+    /// to their components. A negative value prevents bodies from ever falling asleep.
+    pub sleep_linear_threshold: f32,
+    /// The angular velocity below which a dynamic body can fall asleep.
+    ///
+    /// A negative value prevents bodies from ever falling asleep.
+    pub sleep_angular_threshold: f32,
+    /// The time in seconds a body must remain below both sleep thresholds before falling asleep.
+    pub sleep_time: f32,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            sleep_linear_threshold: RigidBodyActivation::default_normalized_linear_threshold(),
+            sleep_angular_threshold: RigidBodyActivation::default_angular_threshold(),
+            sleep_time: RigidBodyActivation::default_time_until_sleep(),
+        }
+    }
+}
+
 pub struct Pipeline {
+    config: PipelineConfig,
     // Physics engine shit
     pipeline: PhysicsPipeline,
     integration_parameters: IntegrationParameters,
@@ -59,10 +91,17 @@ pub struct Pipeline {
     // We need the collider for collision events.
     collider_handles: BiMap<EntityId, ColliderHandle>,
     event_handler: CollisionHandler,
+    /// Set of rigid bodies whose last simulated position was not finite.
+    non_finite_bodies: HashSet<EntityId>,
 }
 
 impl Pipeline {
     pub fn new() -> Self {
+        Self::with_config(PipelineConfig::default())
+    }
+
+    /// Creates a new `Pipeline` using the given [`PipelineConfig`].
+    pub fn with_config(config: PipelineConfig) -> Self {
         let integration_parameters = IntegrationParameters {
             dt: DT,
             min_ccd_dt: MIN_CCD_DT,
@@ -70,6 +109,7 @@ impl Pipeline {
         };
 
         Self {
+            config,
             pipeline: PhysicsPipeline::new(),
             integration_parameters,
             islands: IslandManager::new(),
@@ -85,9 +125,16 @@ impl Pipeline {
             collider_handles: BiMap::new(),
             query_pipeline: QueryPipeline::new(),
             body_children: HashMap::new(),
+            non_finite_bodies: HashSet::new(),
         }
     }
 
+    /// Returns the [`PipelineConfig`] of this `Pipeline`.
+    #[inline]
+    pub fn config(&self) -> &PipelineConfig {
+        &self.config
+    }
+
     /// Returns entities with updated transform.
     pub fn step(&mut self, world: &mut World, events: &mut EventQueue) -> Vec<EntityId> {
         let _span = trace_span!("Pipeline::step").entered();
@@ -139,13 +186,25 @@ impl Pipeline {
                     RigidBodyKind::Kinematic => RigidBodyType::KinematicVelocityBased,
                 };
 
-                let mut builder = RigidBodyBuilder::new(kind);
-                builder = builder.position(Isometry {
-                    translation: translation.into(),
-                    rotation,
-                });
-
-                let body_handle = self.bodies.insert(builder);
+                // Start with the velocities of the component, so that a body
+                // that is recreated (e.g. after being detached from another
+                // body) keeps moving instead of stopping abruptly.
+                let mut body = RigidBodyBuilder::new(kind)
+                    .position(Isometry {
+                        translation: translation.into(),
+                        rotation,
+                    })
+                    .linvel(vector(rigid_body.linvel))
+                    .angvel(vector(rigid_body.angvel))
+                    .build();
+
+                let activation = body.activation_mut();
+                activation.normalized_linear_threshold =
+                    self.config.sleep_linear_threshold / self.integration_parameters.length_unit;
+                activation.angular_threshold = self.config.sleep_angular_threshold;
+                activation.time_until_sleep = self.config.sleep_time;
+
+                let body_handle = self.bodies.insert(body);
                 self.body_handles.insert(entity, body_handle);
                 self.body_children
                     .insert(entity, collect_collider_children(entity, world));
@@ -173,6 +232,8 @@ impl Pipeline {
                 body.set_angvel(angvel, true);
             }
 
+            // The kind is changed in place, so the body keeps its state (e.g.
+            // when a body is attached to a kinematic carrier and dropped again).
             match rigid_body.kind {
                 RigidBodyKind::Fixed => {
                     if body.body_type() != RigidBodyType::Fixed {
@@ -200,6 +261,7 @@ impl Pipeline {
 
         for (entity, handle) in despawned_entities.iter() {
             self.body_children.remove(entity);
+            self.non_finite_bodies.remove(entity);
 
             self.body_handles.remove_left(entity);
             self.bodies.remove(
@@ -246,8 +308,15 @@ impl Pipeline {
                 continue;
             };
 
+            let groups = interaction_groups(
+                world
+                    .get_typed::<CollisionGroups>(entity)
+                    .unwrap_or_default(),
+            );
+
             let Some(handle) = self.collider_handles.get_left(&entity).copied() else {
-                let mut builder = ColliderBuilder::new(build_shape(&collider.shape));
+                let mut builder =
+                    ColliderBuilder::new(build_shape(&collider.shape)).collision_groups(groups);
 
                 builder = builder.position(Isometry {
                     translation: vector(collider_parent.transform.translation).into(),
@@ -284,6 +353,10 @@ impl Pipeline {
                 state.set_restitution(collider.restitution);
             }
 
+            if state.collision_groups() != groups {
+                state.set_collision_groups(groups);
+            }
+
             // TODO: Handle updated collider shape.
 
             self.colliders
@@ -305,6 +378,27 @@ impl Pipeline {
         for (handle, body) in self.bodies.iter() {
             let entity = *self.body_handles.get_right(&handle).unwrap();
 
+            // Write back the simulated velocities, otherwise they are reset
+            // to the previous values of the component in the next step,
+            // which also prevents the body from ever falling asleep.
+            //
+            // Non-finite velocities are reset instead, otherwise they would
+            // be stored in the component and corrupt the body again in every
+            // following step.
+            let mut rigid_body = world.get_typed::<RigidBody>(entity).unwrap();
+            let linvel = finite_or_zero(vec3(*body.linvel()));
+            let angvel = finite_or_zero(vec3(*body.angvel()));
+            if rigid_body.linvel != linvel || rigid_body.angvel != angvel {
+                rigid_body.linvel = linvel;
+                rigid_body.angvel = angvel;
+                world.insert_typed(entity, rigid_body);
+            }
+
+            // Sleeping bodies have not moved since the last step.
+            if body.is_sleeping() {
+                continue;
+            }
+
             // We source the position of the rigid body from the `GlobalTransform` component,
             // but we cannot write back directly to the `GlobalTransform` component since
             // it will be overwritten in the next frame.
@@ -322,6 +416,23 @@ impl Pipeline {
             let new_translation = vec3(*body.translation());
             let new_rotation = quat(*body.rotation());
 
+            // A degenerate body (e.g. an exploded constraint) may end up with
+            // a non-finite position. Writing it back would permanently corrupt
+            // the entity, so we skip it. The body is reset from the unchanged
+            // `Transform` in the next step.
+            if !new_translation.is_finite() || !new_rotation.is_finite() {
+                if self.non_finite_bodies.insert(entity) {
+                    tracing::warn!(
+                        "rigid body of entity {:?} has non-finite position, skipping update",
+                        entity
+                    );
+                }
+
+                continue;
+            }
+
+            self.non_finite_bodies.remove(&entity);
+
             let delta_translation = new_translation - global_transform.0.translation;
             // Note that `delta_rotation` is applied from left.
             let delta_rotation = new_rotation * global_transform.0.rotation.conjugate();
@@ -379,6 +490,127 @@ impl Pipeline {
         }
     }
 
+    /// Takes a snapshot of the internal state of the `Pipeline`.
+    pub fn snapshot(&self) -> PhysicsSnapshot {
+        PhysicsSnapshot {
+            islands: self.islands.clone(),
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            bodies: self.bodies.clone(),
+            colliders: self.colliders.clone(),
+            impulse_joints: self.impulse_joints.clone(),
+            multibody_joints: self.multibody_joints.clone(),
+            body_handles: self
+                .body_handles
+                .iter()
+                .map(|(entity, handle)| (entity.into_raw(), *handle))
+                .collect(),
+            collider_handles: self
+                .collider_handles
+                .iter()
+                .map(|(entity, handle)| (entity.into_raw(), *handle))
+                .collect(),
+        }
+    }
+
+    /// Restores the internal state of the `Pipeline` from a [`PhysicsSnapshot`].
+    ///
+    /// Rigid bodies and colliders of entities that no longer exist in the `world` are removed.
+    /// Entities in the `world` that are not part of the snapshot are added in the next
+    /// [`step`].
+    ///
+    /// [`step`]: Self::step
+    pub fn restore(&mut self, snapshot: PhysicsSnapshot, world: &World) {
+        let _span = trace_span!("Pipeline::restore").entered();
+
+        self.islands = snapshot.islands;
+        self.broad_phase = snapshot.broad_phase;
+        self.narrow_phase = snapshot.narrow_phase;
+        self.bodies = snapshot.bodies;
+        self.colliders = snapshot.colliders;
+        self.impulse_joints = snapshot.impulse_joints;
+        self.multibody_joints = snapshot.multibody_joints;
+        self.ccd_solver = CCDSolver::new();
+        self.event_handler.events.get_mut().clear();
+        self.non_finite_bodies.clear();
+
+        self.body_handles = BiMap::new();
+        self.body_children.clear();
+        for (entity, handle) in snapshot.body_handles {
+            let entity = EntityId::from_raw(entity);
+
+            if world.contains(entity) {
+                self.body_handles.insert(entity, handle);
+                self.body_children
+                    .insert(entity, collect_collider_children(entity, world));
+            } else {
+                self.bodies.remove(
+                    handle,
+                    &mut self.islands,
+                    &mut self.colliders,
+                    &mut self.impulse_joints,
+                    &mut self.multibody_joints,
+                    false,
+                );
+            }
+        }
+
+        self.collider_handles = BiMap::new();
+        for (entity, handle) in snapshot.collider_handles {
+            let entity = EntityId::from_raw(entity);
+
+            if world.contains(entity) {
+                self.collider_handles.insert(entity, handle);
+            } else {
+                self.colliders
+                    .remove(handle, &mut self.islands, &mut self.bodies, true);
+            }
+        }
+
+        self.query_pipeline.update(&self.colliders);
+    }
+
+    /// Returns all entities whose colliders are currently in contact with the collider of
+    /// `entity`.
+    ///
+    /// Colliders whose bounding volumes overlap but that don't have any actual contact points
+    /// are not included. Returns an empty list if `entity` has no collider.
+    pub fn contacts_with(&self, entity: EntityId) -> Vec<EntityId> {
+        let Some(handle) = self.collider_handles.get_left(&entity) else {
+            return Vec::new();
+        };
+
+        self.narrow_phase
+            .contact_pairs_with(*handle)
+            .filter(|pair| pair.has_any_active_contact)
+            .filter_map(|pair| {
+                let other = if pair.collider1 == *handle {
+                    pair.collider2
+                } else {
+                    pair.collider1
+                };
+
+                self.collider_handles.get_right(&other).copied()
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the colliders of the entities `a` and `b` are currently in contact.
+    ///
+    /// Returns `false` if either entity has no collider.
+    pub fn are_in_contact(&self, a: EntityId, b: EntityId) -> bool {
+        let (Some(lhs), Some(rhs)) = (
+            self.collider_handles.get_left(&a),
+            self.collider_handles.get_left(&b),
+        ) else {
+            return false;
+        };
+
+        self.narrow_phase
+            .contact_pair(*lhs, *rhs)
+            .is_some_and(|pair| pair.has_any_active_contact)
+    }
+
     pub fn cast_ray(
         &self,
         ray: game_common::math::Ray,
@@ -396,7 +628,9 @@ impl Pipeline {
             let entity = *self.collider_handles.get_right(&handle).unwrap();
             !filter.exclude_entities.contains(&entity)
         };
-        let filter = QueryFilter::new().predicate(&pred);
+        let filter = QueryFilter::new()
+            .groups(interaction_groups(filter.groups))
+            .predicate(&pred);
 
         match self.query_pipeline.cast_ray(
             &self.bodies,
@@ -435,7 +669,9 @@ impl Pipeline {
             let entity = self.collider_handles.get_right(&handle).unwrap();
             !filter.exclude_entities.contains(&entity)
         };
-        let filter = QueryFilter::new().predicate(&pred);
+        let filter = QueryFilter::new()
+            .groups(interaction_groups(filter.groups))
+            .predicate(&pred);
 
         let options = ShapeCastOptions {
             max_time_of_impact: max_toi,
@@ -523,6 +759,64 @@ impl Pipeline {
             }
         })
     }
+
+    /// Returns all entities whose colliders intersect the sphere at `center` with the given
+    /// `radius`, sorted by distance.
+    ///
+    /// The distance of an entity is the distance from `center` to the closest point on its
+    /// collider, which is `0.0` if `center` is inside the collider. If `max` is `Some` at most
+    /// `max` of the nearest entities are returned.
+    pub fn entities_within(
+        &self,
+        center: Vec3,
+        radius: f32,
+        filter: &query::QueryFilter,
+        max: Option<usize>,
+    ) -> Vec<(EntityId, f32)> {
+        let _span = trace_span!("PhysicsPipeline::entities_within").entered();
+
+        let shape_pos = Isometry {
+            rotation: rotation(Quat::IDENTITY),
+            translation: vector(center).into(),
+        };
+        let shape = Ball::new(radius);
+
+        let pred = |handle, _collider: &Collider| {
+            let entity = self.collider_handles.get_right(&handle).unwrap();
+            !filter.exclude_entities.contains(entity)
+        };
+        let filter = QueryFilter::new()
+            .groups(interaction_groups(filter.groups))
+            .predicate(&pred);
+
+        let center = point(center);
+        let mut entities = Vec::new();
+        self.query_pipeline.intersections_with_shape(
+            &self.bodies,
+            &self.colliders,
+            &shape_pos,
+            &shape,
+            filter,
+            |handle| {
+                let entity = *self.collider_handles.get_right(&handle).unwrap();
+                let collider = &self.colliders[handle];
+                let distance =
+                    collider
+                        .shape()
+                        .distance_to_point(collider.position(), &center, true);
+
+                entities.push((entity, distance));
+                true
+            },
+        );
+
+        entities.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some(max) = max {
+            entities.truncate(max);
+        }
+
+        entities
+    }
 }
 
 impl Default for Pipeline {
@@ -537,10 +831,12 @@ impl Default for Pipeline {
 /// The [`Transform`] value of an entity is the local offset from the root `entity`.
 ///
 /// The branch is interrupted if an entity contains no transform, or if it contains a rigid body
-/// component, signaling the start of a new rigid body.
+/// component, signaling the start of a new rigid body. Children deeper than [`DEFAULT_MAX_DEPTH`]
+/// and entities that were already visited (e.g. in a cyclic hierarchy) are skipped.
 fn collect_collider_children(entity: EntityId, world: &World) -> HashMap<EntityId, Transform> {
     let mut entities = HashMap::new();
-    let mut backlog = vec![(entity, Transform::default())];
+    let mut visited = HashSet::from([entity]);
+    let mut backlog = vec![(entity, Transform::default(), 0)];
 
     // The root entity can also be a collider.
     if world
@@ -550,9 +846,23 @@ fn collect_collider_children(entity: EntityId, world: &World) -> HashMap<EntityI
         entities.insert(entity, Transform::default());
     }
 
-    while let Some((entity, parent_transform)) = backlog.pop() {
+    while let Some((entity, parent_transform, depth)) = backlog.pop() {
         let children = world.get_typed::<Children>(entity).unwrap_or_default();
+        if depth == DEFAULT_MAX_DEPTH && !children.is_empty() {
+            tracing::warn!(
+                "hierarchy exceeds maximum depth of {}, ignoring children of {:?}",
+                DEFAULT_MAX_DEPTH,
+                entity,
+            );
+            continue;
+        }
+
         for child in children.get() {
+            if !visited.insert(*child) {
+                tracing::warn!("entity {:?} has multiple parents", child);
+                continue;
+            }
+
             let Ok(child_transform) = world.get_typed::<Transform>(*child) else {
                 continue;
             };
@@ -573,7 +883,7 @@ fn collect_collider_children(entity: EntityId, world: &World) -> HashMap<EntityI
                 entities.insert(*child, transform);
             }
 
-            backlog.push((*child, transform));
+            backlog.push((*child, transform, depth + 1));
         }
     }
 
@@ -650,6 +960,13 @@ impl Debug for Pipeline {
     }
 }
 
+fn interaction_groups(groups: CollisionGroups) -> InteractionGroups {
+    InteractionGroups::new(
+        Group::from_bits_retain(groups.memberships),
+        Group::from_bits_retain(groups.filter),
+    )
+}
+
 fn build_shape(shape: &ColliderShape) -> SharedShape {
     match shape {
         ColliderShape::Cuboid(cuboid) => SharedShape::cuboid(cuboid.hx, cuboid.hy, cuboid.hz),
@@ -676,19 +993,29 @@ fn build_shape(shape: &ColliderShape) -> SharedShape {
     }
 }
 
+/// Returns `v`, or [`Vec3::ZERO`] if any of its components is not finite.
+fn finite_or_zero(v: Vec3) -> Vec3 {
+    if v.is_finite() {
+        v
+    } else {
+        Vec3::ZERO
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use game_common::components::{
-        Children, Collider, ColliderShape, Cuboid, GlobalTransform, RigidBody, RigidBodyKind,
-        Transform,
+        Children, Collider, ColliderShape, CollisionGroups, Component, Cuboid, GlobalTransform,
+        RigidBody, RigidBodyKind, Transform,
     };
     use game_common::events::EventQueue;
+    use game_common::math::Ray;
     use game_common::world::hierarchy::update_global_transform;
     use game_common::world::World;
     use glam::{Quat, Vec3};
 
     use crate::query::QueryFilter;
-    use crate::Pipeline;
+    use crate::{Pipeline, PipelineConfig};
 
     #[test]
     fn dynamic_rigid_body_with_collider() {
@@ -725,6 +1052,51 @@ mod tests {
         assert_ne!(transform, Transform::IDENTITY);
     }
 
+    #[test]
+    fn write_back_non_finite() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert_typed(entity, RigidBody::new(RigidBodyKind::Dynamic));
+        world.insert_typed(entity, create_test_collider());
+        world.insert_typed(entity, Transform::IDENTITY);
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+        update_global_transform(&mut world);
+
+        // A NaN velocity results in a NaN position after the next step.
+        world.insert_typed(
+            entity,
+            RigidBody {
+                kind: RigidBodyKind::Dynamic,
+                linvel: Vec3::NAN,
+                angvel: Vec3::NAN,
+            },
+        );
+
+        let transform = world.get_typed::<Transform>(entity).unwrap();
+        let updated = pipeline.step(&mut world, &mut events);
+        update_global_transform(&mut world);
+
+        assert!(updated.is_empty());
+        assert_eq!(world.get_typed::<Transform>(entity).unwrap(), transform);
+
+        // The non-finite velocities are never written back.
+        let rigid_body = world.get_typed::<RigidBody>(entity).unwrap();
+        assert!(rigid_body.linvel.is_finite());
+        assert!(rigid_body.angvel.is_finite());
+
+        // The body recovers in the next step without any intervention.
+        pipeline.step(&mut world, &mut events);
+
+        let new_transform = world.get_typed::<Transform>(entity).unwrap();
+        assert!(new_transform.translation.is_finite());
+        assert!(new_transform.rotation.is_finite());
+        assert_ne!(new_transform, transform);
+    }
+
     #[test]
     fn pipeline_cast_shape_cuboid() {
         let mut world = World::new();
@@ -775,6 +1147,223 @@ mod tests {
         assert_eq!(res.toi, 3.0);
     }
 
+    #[test]
+    fn pipeline_cast_ray_collision_groups() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert_typed(entity, RigidBody::new(RigidBodyKind::Fixed));
+        world.insert_typed(entity, create_test_collider());
+        world.insert_typed(entity, CollisionGroups::new(0b01, u32::MAX));
+        world.insert_typed(entity, Transform::IDENTITY);
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+
+        let ray = Ray {
+            origin: Vec3::new(5.0, 0.0, 0.0),
+            direction: Vec3::new(-1.0, 0.0, 0.0),
+        };
+
+        let res = pipeline.cast_ray(
+            ray,
+            10.0,
+            &QueryFilter {
+                groups: CollisionGroups::new(u32::MAX, 0b01),
+                ..Default::default()
+            },
+        );
+        assert_eq!(res.unwrap().entity, entity);
+
+        let res = pipeline.cast_ray(
+            ray,
+            10.0,
+            &QueryFilter {
+                groups: CollisionGroups::new(u32::MAX, 0b10),
+                ..Default::default()
+            },
+        );
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn pipeline_entities_within() {
+        let mut world = World::new();
+
+        let entities: Vec<_> = [10.0, 5.0, 0.0]
+            .into_iter()
+            .map(|x| {
+                let entity = world.spawn();
+                world.insert_typed(entity, RigidBody::new(RigidBodyKind::Fixed));
+                world.insert_typed(entity, create_test_collider());
+                world.insert_typed(entity, Transform::from_translation(Vec3::new(x, 0.0, 0.0)));
+                entity
+            })
+            .collect();
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+
+        let center = Vec3::new(-3.0, 0.0, 0.0);
+
+        let res = pipeline.entities_within(center, 10.0, &QueryFilter::default(), None);
+        assert_eq!(res, [(entities[2], 2.0), (entities[1], 7.0)]);
+
+        let res = pipeline.entities_within(center, 10.0, &QueryFilter::default(), Some(1));
+        assert_eq!(res, [(entities[2], 2.0)]);
+
+        let res = pipeline.entities_within(
+            center,
+            10.0,
+            &QueryFilter {
+                exclude_entities: vec![entities[2]],
+                ..Default::default()
+            },
+            None,
+        );
+        assert_eq!(res, [(entities[1], 7.0)]);
+    }
+
+    #[test]
+    fn pipeline_resting_body_sleeps() {
+        for (config, sleeping) in [
+            (PipelineConfig::default(), true),
+            (
+                PipelineConfig {
+                    sleep_linear_threshold: -1.0,
+                    sleep_angular_threshold: -1.0,
+                    ..Default::default()
+                },
+                false,
+            ),
+        ] {
+            let mut world = World::new();
+
+            let ground = world.spawn();
+            world.insert_typed(ground, Transform::IDENTITY);
+            world.insert_typed(ground, RigidBody::new(RigidBodyKind::Fixed));
+            world.insert_typed(ground, create_test_collider());
+
+            let body = world.spawn();
+            world.insert_typed(body, Transform::from_translation(Vec3::new(0.0, 2.0, 0.0)));
+            world.insert_typed(body, RigidBody::new(RigidBodyKind::Dynamic));
+            world.insert_typed(body, create_test_collider());
+
+            let mut events = EventQueue::new();
+            let mut pipeline = Pipeline::with_config(config);
+
+            // Long enough for the body to settle and stay below the
+            // thresholds for `sleep_time`.
+            for _ in 0..300 {
+                update_global_transform(&mut world);
+                pipeline.step(&mut world, &mut events);
+            }
+
+            let handle = pipeline.body_handles.get_left(&body).unwrap();
+            assert_eq!(pipeline.bodies[*handle].is_sleeping(), sleeping);
+        }
+    }
+
+    #[test]
+    fn pipeline_contacts_with() {
+        let mut world = World::new();
+
+        let ground = world.spawn();
+        world.insert_typed(ground, Transform::IDENTITY);
+        world.insert_typed(ground, RigidBody::new(RigidBodyKind::Fixed));
+        world.insert_typed(ground, create_test_collider());
+
+        // Slightly overlaps with `ground`.
+        let body = world.spawn();
+        world.insert_typed(body, Transform::from_translation(Vec3::new(0.0, 1.99, 0.0)));
+        world.insert_typed(body, RigidBody::new(RigidBodyKind::Dynamic));
+        world.insert_typed(body, create_test_collider());
+
+        let other = world.spawn();
+        world.insert_typed(
+            other,
+            Transform::from_translation(Vec3::new(10.0, 0.0, 0.0)),
+        );
+        world.insert_typed(other, RigidBody::new(RigidBodyKind::Fixed));
+        world.insert_typed(other, create_test_collider());
+
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+
+        assert_eq!(pipeline.contacts_with(ground), [body]);
+        assert_eq!(pipeline.contacts_with(body), [ground]);
+        assert!(pipeline.contacts_with(other).is_empty());
+
+        assert!(pipeline.are_in_contact(ground, body));
+        assert!(pipeline.are_in_contact(body, ground));
+        assert!(!pipeline.are_in_contact(ground, other));
+    }
+
+    #[test]
+    fn pipeline_snapshot_restore() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert_typed(entity, Transform::IDENTITY);
+        world.insert_typed(entity, RigidBody::new(RigidBodyKind::Dynamic));
+        world.insert_typed(entity, create_test_collider());
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        for _ in 0..10 {
+            pipeline.step(&mut world, &mut events);
+            update_global_transform(&mut world);
+        }
+
+        let snapshot = bincode::serialize(&pipeline.snapshot()).unwrap();
+        let snapshot = bincode::deserialize(&snapshot).unwrap();
+
+        let mut restored_world = world.clone();
+        let mut restored = Pipeline::new();
+        restored.restore(snapshot, &restored_world);
+
+        pipeline.step(&mut world, &mut events);
+        restored.step(&mut restored_world, &mut events);
+
+        assert_eq!(
+            world.get_typed::<Transform>(entity).unwrap(),
+            restored_world.get_typed::<Transform>(entity).unwrap(),
+        );
+    }
+
+    #[test]
+    fn pipeline_restore_despawned_entity() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert_typed(entity, Transform::IDENTITY);
+        world.insert_typed(entity, RigidBody::new(RigidBodyKind::Dynamic));
+        world.insert_typed(entity, create_test_collider());
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+
+        let snapshot = pipeline.snapshot();
+        world.despawn(entity);
+
+        let mut restored = Pipeline::new();
+        restored.restore(snapshot, &world);
+
+        assert!(restored.body_handles.is_empty());
+        assert!(restored.collider_handles.is_empty());
+        assert_eq!(restored.bodies.len(), 0);
+        assert_eq!(restored.colliders.len(), 0);
+
+        restored.step(&mut world, &mut events);
+    }
+
     #[test]
     fn get_collider_parent_direct() {
         let mut world = World::new();
@@ -841,6 +1430,146 @@ mod tests {
         assert_eq!(handle, *pipeline.body_handles.get_left(&root).unwrap());
     }
 
+    #[test]
+    fn get_collider_parent_cyclic() {
+        let mut world = World::new();
+        let root = world.spawn();
+        let child1 = world.spawn();
+        let child2 = world.spawn();
+        world.insert_typed(root, Transform::default());
+        world.insert_typed(root, GlobalTransform::default());
+        world.insert_typed(root, RigidBody::new(RigidBodyKind::Fixed));
+        world.insert_typed(root, Children::from_iter([child1]));
+
+        // root -> child1 -> child2 -> child1
+        world.insert_typed(child1, Transform::default());
+        world.insert_typed(child1, GlobalTransform::default());
+        world.insert_typed(child1, Children::from_iter([child2]));
+
+        world.insert_typed(child2, Transform::default());
+        world.insert_typed(child2, GlobalTransform::default());
+        world.insert_typed(child2, Children::from_iter([child1]));
+        world.insert_typed(child2, create_test_collider());
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+
+        let handle = pipeline.get_collider_parent(child2).unwrap().body;
+        assert_eq!(handle, *pipeline.body_handles.get_left(&root).unwrap());
+    }
+
+    #[test]
+    fn pipeline_reparent_moving_body() {
+        let mut world = World::new();
+        let carrier = world.spawn();
+        world.insert_typed(
+            carrier,
+            Transform::from_translation(Vec3::new(0.0, 10.0, 0.0)),
+        );
+        world.insert_typed(carrier, RigidBody::new(RigidBodyKind::Kinematic));
+
+        let entity = world.spawn();
+        world.insert_typed(entity, Transform::IDENTITY);
+        world.insert_typed(
+            entity,
+            RigidBody {
+                kind: RigidBodyKind::Dynamic,
+                linvel: Vec3::new(5.0, 0.0, 0.0),
+                angvel: Vec3::ZERO,
+            },
+        );
+        world.insert_typed(entity, create_test_collider());
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+        update_global_transform(&mut world);
+
+        let body = *pipeline.body_handles.get_left(&entity).unwrap();
+        let collider = *pipeline.collider_handles.get_left(&entity).unwrap();
+        assert_eq!(world.get_typed::<RigidBody>(entity).unwrap().linvel.x, 5.0);
+
+        // Attach the body to the carrier without changing its global transform.
+        let mut transform = world.get_typed::<Transform>(entity).unwrap();
+        transform.translation.y -= 10.0;
+        world.insert_typed(entity, transform);
+        world.insert_typed(carrier, Children::from_iter([entity]));
+        world.insert_typed(
+            entity,
+            RigidBody {
+                kind: RigidBodyKind::Kinematic,
+                ..world.get_typed::<RigidBody>(entity).unwrap()
+            },
+        );
+        update_global_transform(&mut world);
+
+        pipeline.step(&mut world, &mut events);
+        update_global_transform(&mut world);
+
+        assert_eq!(*pipeline.body_handles.get_left(&entity).unwrap(), body);
+        assert_eq!(
+            *pipeline.collider_handles.get_left(&entity).unwrap(),
+            collider
+        );
+        assert_eq!(world.get_typed::<RigidBody>(entity).unwrap().linvel.x, 5.0);
+
+        // Detach the body from the carrier again.
+        let GlobalTransform(transform) = world.get_typed(entity).unwrap();
+        world.insert_typed(entity, transform);
+        world.insert_typed(carrier, Children::new());
+        world.insert_typed(
+            entity,
+            RigidBody {
+                kind: RigidBodyKind::Dynamic,
+                ..world.get_typed::<RigidBody>(entity).unwrap()
+            },
+        );
+        update_global_transform(&mut world);
+
+        pipeline.step(&mut world, &mut events);
+
+        assert_eq!(*pipeline.body_handles.get_left(&entity).unwrap(), body);
+        assert_eq!(
+            *pipeline.collider_handles.get_left(&entity).unwrap(),
+            collider
+        );
+        assert_eq!(world.get_typed::<RigidBody>(entity).unwrap().linvel.x, 5.0);
+
+        let transform = world.get_typed::<Transform>(entity).unwrap();
+        assert!(transform.translation.x > 0.0);
+    }
+
+    #[test]
+    fn pipeline_recreated_body_keeps_velocity() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert_typed(entity, Transform::IDENTITY);
+        world.insert_typed(entity, RigidBody::new(RigidBodyKind::Dynamic));
+        update_global_transform(&mut world);
+
+        let mut events = EventQueue::new();
+        let mut pipeline = Pipeline::new();
+        pipeline.step(&mut world, &mut events);
+
+        world.remove(entity, RigidBody::ID);
+        pipeline.step(&mut world, &mut events);
+        assert!(pipeline.body_handles.get_left(&entity).is_none());
+
+        world.insert_typed(
+            entity,
+            RigidBody {
+                kind: RigidBodyKind::Dynamic,
+                linvel: Vec3::new(5.0, 0.0, 0.0),
+                angvel: Vec3::ZERO,
+            },
+        );
+        pipeline.step(&mut world, &mut events);
+
+        assert_eq!(world.get_typed::<RigidBody>(entity).unwrap().linvel.x, 5.0);
+    }
+
     fn create_test_collider() -> Collider {
         Collider {
             friction: 0.0,
@@ -1,8 +1,11 @@
+use game_common::components::CollisionGroups;
 use game_common::entity::EntityId;
 
 #[derive(Clone, Debug, Default)]
 pub struct QueryFilter {
     pub exclude_entities: Vec<EntityId>,
+    /// Only include colliders whose [`CollisionGroups`] interact with these groups.
+    pub groups: CollisionGroups,
 }
 
 #[derive(Copy, Clone, Debug)]
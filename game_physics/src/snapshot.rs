@@ -0,0 +1,31 @@
+use rapier3d::geometry::BroadPhaseMultiSap;
+use rapier3d::prelude::{
+    ColliderHandle, ColliderSet, ImpulseJointSet, IslandManager, MultibodyJointSet, NarrowPhase,
+    RigidBodyHandle, RigidBodySet,
+};
+use serde::{Deserialize, Serialize};
+
+/// A snapshot of the internal state of a [`Pipeline`].
+///
+/// A `PhysicsSnapshot` contains all state that cannot be rebuilt from the components of the
+/// [`World`], like sleeping bodies and the contacts between colliders. Restoring a snapshot with
+/// [`Pipeline::restore`] resumes the simulation exactly where the snapshot was taken.
+///
+/// Entities are stored by their raw id, so a snapshot should only be restored together with the
+/// [`World`] it was taken from.
+///
+/// [`Pipeline`]: crate::Pipeline
+/// [`Pipeline::restore`]: crate::Pipeline::restore
+/// [`World`]: game_common::world::World
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PhysicsSnapshot {
+    pub(crate) islands: IslandManager,
+    pub(crate) broad_phase: BroadPhaseMultiSap,
+    pub(crate) narrow_phase: NarrowPhase,
+    pub(crate) bodies: RigidBodySet,
+    pub(crate) colliders: ColliderSet,
+    pub(crate) impulse_joints: ImpulseJointSet,
+    pub(crate) multibody_joints: MultibodyJointSet,
+    pub(crate) body_handles: Vec<(u64, RigidBodyHandle)>,
+    pub(crate) collider_handles: Vec<(u64, ColliderHandle)>,
+}
@@ -1,13 +1,15 @@
 //! Binary data format for the [`Prefab`] strucuture.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display, Formatter};
 use std::ops::Range;
 
 use bytes::{Buf, BufMut};
+use game_common::components::components::RawComponent;
+use game_wasm::encoding::{decode_fields, encode_fields_into};
 use game_wasm::world::RecordReference;
 
-use crate::{ComponentRef, Prefab};
+use crate::Prefab;
 
 pub(crate) fn encode(prefab: &Prefab) -> Vec<u8> {
     let num_entities = prefab.entities.len() as u64;
@@ -19,20 +21,23 @@ pub(crate) fn encode(prefab: &Prefab) -> Vec<u8> {
     buf.put_u64_le(num_children);
     buf.put_u64_le(num_root);
 
+    // The data of all components is appended after all other sections.
+    let mut data = Vec::new();
+
     for components in &prefab.entities {
         buf.put_u64_le(components.len() as u64);
 
-        for data_ref in components {
-            if cfg!(debug_assertions) {
-                let _ = &prefab.data[data_ref.data.clone()];
-                let _ = &prefab.data[data_ref.fields.clone()];
-            }
-
-            buf.put_slice(&data_ref.id.into_bytes());
-            buf.put_u64_le(data_ref.data.start as u64);
-            buf.put_u64_le(data_ref.data.len() as u64);
-            buf.put_u64_le(data_ref.fields.start as u64);
-            buf.put_u64_le(data_ref.fields.len() as u64);
+        for (id, component) in components {
+            let data_start = data.len();
+            data.extend_from_slice(component.as_bytes());
+            let fields_start = data.len();
+            encode_fields_into(component.fields(), &mut data);
+
+            buf.put_slice(&id.into_bytes());
+            buf.put_u64_le(data_start as u64);
+            buf.put_u64_le((fields_start - data_start) as u64);
+            buf.put_u64_le(fields_start as u64);
+            buf.put_u64_le((data.len() - fields_start) as u64);
         }
     }
 
@@ -49,7 +54,7 @@ pub(crate) fn encode(prefab: &Prefab) -> Vec<u8> {
         buf.put_u64_le(*index);
     }
 
-    buf.extend_from_slice(&prefab.data);
+    buf.extend_from_slice(&data);
 
     buf
 }
@@ -67,6 +72,10 @@ pub enum DecodeError {
         range: Range<usize>,
         data_len: usize,
     },
+    /// The entity has multiple parents or is part of a cycle.
+    InvalidHierarchy {
+        entity: usize,
+    },
 }
 
 impl Display for DecodeError {
@@ -88,6 +97,9 @@ impl Display for DecodeError {
                     component, entity, range.start, range.end, data_len
                 )
             }
+            Self::InvalidHierarchy { entity } => {
+                write!(f, "entity {} has multiple parents", entity)
+            }
         }
     }
 }
@@ -177,7 +189,7 @@ pub(crate) fn decode(mut buf: &[u8]) -> Result<Prefab, DecodeError> {
             let fields_start = buf.get_u64_le();
             let fields_len = buf.get_u64_le();
 
-            components.push(ComponentRef {
+            components.push(ComponentRange {
                 id: RecordReference::from_bytes(id),
                 data: Range {
                     start: data_start as usize,
@@ -251,46 +263,122 @@ pub(crate) fn decode(mut buf: &[u8]) -> Result<Prefab, DecodeError> {
         root.push(index);
     }
 
-    let data = buf.to_vec();
+    // Every entity must have at most one parent, where root entities
+    // have an implicit parent. This guarantees that the hierarchy
+    // contains no cycles that would never finish instantiating.
+    let mut has_parent = HashSet::new();
+    for index in children.values().flatten().chain(&root) {
+        if !has_parent.insert(*index) {
+            return Err(DecodeError::InvalidHierarchy {
+                entity: *index as usize,
+            });
+        }
+    }
+
+    let data = buf;
+
+    let mut decoded_entities = Vec::with_capacity(entities.len());
+    for (index, components) in entities.into_iter().enumerate() {
+        let mut decoded_components = Vec::with_capacity(components.len());
 
-    for (index, components) in entities.iter().enumerate() {
         for component in components {
-            if data.get(component.data.clone()).is_none() {
+            let Some(bytes) = data.get(component.data.clone()) else {
                 return Err(DecodeError::InvalidComponentReference {
                     entity: index,
                     component: component.id,
-                    range: component.data.clone(),
+                    range: component.data,
                     data_len: data.len(),
                 });
-            }
+            };
 
-            if data.get(component.fields.clone()).is_none() {
+            let Some(fields) = data.get(component.fields.clone()) else {
                 return Err(DecodeError::InvalidComponentReference {
                     entity: index,
                     component: component.id,
-                    range: component.fields.clone(),
+                    range: component.fields,
                     data_len: data.len(),
                 });
-            }
+            };
+
+            decoded_components.push((
+                component.id,
+                RawComponent::new(bytes, decode_fields(fields)),
+            ));
         }
+
+        decoded_entities.push(decoded_components);
     }
 
     Ok(Prefab {
-        entities,
+        entities: decoded_entities,
         children,
         root,
-        data,
     })
 }
 
+/// The location of an encoded component in the data section.
+struct ComponentRange {
+    id: RecordReference,
+    data: Range<usize>,
+    fields: Range<usize>,
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use game_common::components::components::RawComponent;
+    use game_wasm::encoding::{Field, Primitive};
+    use game_wasm::world::RecordReference;
+
     use crate::Prefab;
 
+    use super::DecodeError;
+
     #[test]
     fn encode_and_decode_empty() {
         let prefab = Prefab::new();
         let buf = super::encode(&prefab);
         super::decode(&buf).unwrap();
     }
+
+    #[test]
+    fn encode_and_decode_components() {
+        let fields = [Field {
+            primitive: Primitive::EntityId,
+            offset: 0,
+        }];
+        let components = vec![
+            (
+                RecordReference::STUB,
+                RawComponent::new(vec![1; 8], &fields[..]),
+            ),
+            (RecordReference::STUB, RawComponent::new(vec![2, 3], [])),
+        ];
+
+        let prefab = Prefab {
+            entities: vec![components.clone()],
+            children: HashMap::new(),
+            root: vec![0],
+        };
+
+        let buf = super::encode(&prefab);
+        let prefab = super::decode(&buf).unwrap();
+        assert_eq!(prefab.entities, [components]);
+    }
+
+    #[test]
+    fn decode_cyclic_hierarchy() {
+        let prefab = Prefab {
+            entities: vec![Vec::new(), Vec::new(), Vec::new()],
+            children: [(0, vec![1]), (1, vec![2]), (2, vec![1])].into(),
+            root: vec![0],
+        };
+
+        let buf = super::encode(&prefab);
+        match super::decode(&buf) {
+            Err(DecodeError::InvalidHierarchy { entity: 1 }) => (),
+            res => panic!("expected invalid hierarchy error, got {:?}", res),
+        }
+    }
 }
@@ -1,24 +1,31 @@
 mod format;
 
-use std::collections::HashMap;
-use std::ops::Range;
+pub mod pool;
+pub mod save;
+
+use std::collections::{HashMap, HashSet};
 
 use game_common::components::components::RawComponent;
 use game_common::components::{Children, Component};
 use game_common::entity::EntityId;
 use game_common::record::RecordReference;
+use game_common::world::hierarchy::DEFAULT_MAX_DEPTH;
 use game_common::world::World;
 use game_tracing::trace_span;
-use game_wasm::encoding::{decode_fields, encode_fields, BinaryWriter};
+use game_wasm::encoding::BinaryWriter;
+use pool::BufferPool;
 
 pub use format::DecodeError;
 
 #[derive(Clone, Debug, Default)]
 pub struct Prefab {
-    entities: Vec<Vec<ComponentRef>>,
+    /// The components of all entities.
+    ///
+    /// The components share their data with the [`World`] they were added from or were decoded
+    /// once, so instantiating the `Prefab` does not copy them.
+    entities: Vec<Vec<(RecordReference, RawComponent)>>,
     children: HashMap<u64, Vec<u64>>,
     root: Vec<u64>,
-    data: Vec<u8>,
 }
 
 impl Prefab {
@@ -27,27 +34,61 @@ impl Prefab {
         Self {
             entities: Vec::new(),
             children: HashMap::new(),
-            data: Vec::new(),
             root: Vec::new(),
         }
     }
 
+    /// Adds the entity `id` and all its children to the `Prefab`.
+    ///
+    /// Children nested deeper than [`DEFAULT_MAX_DEPTH`] are ignored, see [`add_with_max_depth`].
+    ///
+    /// [`add_with_max_depth`]: Self::add_with_max_depth
     pub fn add(&mut self, id: EntityId, world: &World) {
+        self.add_with_max_depth(id, world, DEFAULT_MAX_DEPTH);
+    }
+
+    /// Adds the entity `id` and all its children up to `max_depth` levels below `id` to the
+    /// `Prefab`.
+    ///
+    /// Children nested deeper than `max_depth` are ignored with a warning. Every entity is only
+    /// added once, even if it is reachable through multiple parents. This means that cycles in
+    /// the hierarchy are broken up.
+    pub fn add_with_max_depth(&mut self, id: EntityId, world: &World, max_depth: usize) {
         let _span = trace_span!("Prefab::add").entered();
 
         let mut entities = Vec::new();
+        let mut visited = HashSet::new();
 
         // Collect all recursive children of `id` in the stack
         // `entities` "bottom-up". This means popping from
         // `entities` will always yield entities whose children
         // have already been yielded.
-        let mut stack = vec![id];
-        while let Some(id) = stack.pop() {
+        let mut stack = vec![(id, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            if !visited.insert(id) {
+                tracing::warn!("entity {:?} has multiple parents", id);
+                continue;
+            }
+
             entities.push(id);
 
-            if let Ok(children) = world.get_typed::<Children>(id) {
-                stack.extend(children.get());
+            let Ok(children) = world.get_typed::<Children>(id) else {
+                continue;
+            };
+
+            if depth == max_depth {
+                if !children.is_empty() {
+                    tracing::warn!(
+                        "hierarchy exceeds maximum depth of {}, ignoring children of {:?}",
+                        max_depth,
+                        id,
+                    );
+                }
+
+                continue;
             }
+
+            stack.extend(children.get().iter().map(|child| (*child, depth + 1)));
         }
 
         let mut spawned_entities = HashMap::new();
@@ -63,28 +104,7 @@ impl Prefab {
                     continue;
                 }
 
-                let data = component.as_bytes().to_vec();
-                let fields = encode_fields(component.fields());
-
-                let data_start = self.data.len();
-                self.data.extend(data);
-                let data_end = self.data.len();
-
-                let fields_start = self.data.len();
-                self.data.extend(fields);
-                let fields_end = self.data.len();
-
-                components.push(ComponentRef {
-                    id: component_id,
-                    data: Range {
-                        start: data_start,
-                        end: data_end,
-                    },
-                    fields: Range {
-                        start: fields_start,
-                        end: fields_end,
-                    },
-                });
+                components.push((component_id, component.clone()));
             }
 
             self.entities.push(components);
@@ -92,10 +112,11 @@ impl Prefab {
             if let Ok(children) = world.get_typed::<Children>(entity) {
                 // `entities` is order so that all entities that are children
                 // of the current entity have already been processed.
+                // Children that were ignored are missing.
                 let children_list = children
                     .get()
                     .iter()
-                    .map(|id| *spawned_entities.get(id).unwrap())
+                    .filter_map(|id| spawned_entities.get(id).copied())
                     .collect();
 
                 self.children.insert(index as u64, children_list);
@@ -108,12 +129,45 @@ impl Prefab {
 
     /// Instantiate the `Prefab` using the given [`Spawner`] and returns the [`EntityId`] of the
     /// spawned prefab.
-    pub fn instantiate<S>(self, mut spawner: S) -> EntityId
+    pub fn instantiate<S>(self, spawner: S) -> EntityId
+    where
+        S: Spawner,
+    {
+        self.instantiate_with(spawner, &mut BufferPool::new())
+    }
+
+    /// Instantiate the `Prefab` like [`instantiate`], reusing the temporary buffers from the
+    /// given [`BufferPool`].
+    ///
+    /// [`instantiate`]: Self::instantiate
+    pub fn instantiate_with<S>(self, mut spawner: S, pool: &mut BufferPool) -> EntityId
     where
         S: Spawner,
     {
         let _span = trace_span!("Prefab::instantiate").entered();
 
+        let roots = self.spawn_roots(&mut spawner, pool);
+
+        let root_entity = spawner.spawn();
+
+        let mut children_component = Children::new();
+        for entity in roots {
+            children_component.insert(entity);
+        }
+
+        if !children_component.is_empty() {
+            insert_pooled(&mut spawner, pool, root_entity, children_component);
+        }
+
+        root_entity
+    }
+
+    /// Spawns all entities of the `Prefab` and returns the [`EntityId`]s of the top-level
+    /// entities in the order they were added.
+    fn spawn_roots<S>(&self, mut spawner: S, pool: &mut BufferPool) -> Vec<EntityId>
+    where
+        S: Spawner,
+    {
         let mut entities = Vec::new();
 
         let mut stack = self.root.clone();
@@ -131,10 +185,8 @@ impl Prefab {
             let entity = spawner.spawn();
             spawned_entities.insert(index, entity);
 
-            let component_refs = &self.entities[index as usize];
-            for component_ref in component_refs {
-                let component = component_ref.load(&self.data);
-                spawner.insert(entity, component_ref.id, component);
+            for (component_id, component) in &self.entities[index as usize] {
+                spawner.insert(entity, *component_id, component.clone());
             }
 
             if let Some(children) = self.children.get(&index) {
@@ -146,24 +198,15 @@ impl Prefab {
                 }
 
                 if !children_component.is_empty() {
-                    spawner.insert_typed(entity, children_component);
+                    insert_pooled(&mut spawner, pool, entity, children_component);
                 }
             }
         }
 
-        let root_entity = spawner.spawn();
-
-        let mut children_component = Children::new();
-        for index in &self.root {
-            let children_entity = spawned_entities.get(index).unwrap();
-            children_component.insert(*children_entity);
-        }
-
-        if !children_component.is_empty() {
-            spawner.insert_typed(root_entity, children_component);
-        }
-
-        root_entity
+        self.root
+            .iter()
+            .map(|index| *spawned_entities.get(index).unwrap())
+            .collect()
     }
 
     /// Serializes the `Prefab` into bytes.
@@ -181,20 +224,17 @@ impl Prefab {
     }
 }
 
-#[derive(Clone, Debug)]
-struct ComponentRef {
-    id: RecordReference,
-    data: Range<usize>,
-    fields: Range<usize>,
-}
-
-impl ComponentRef {
-    fn load(&self, buf: &[u8]) -> RawComponent {
-        let data = &buf[self.data.clone()];
-        let fields = &buf[self.fields.clone()];
-        let fields = decode_fields(fields);
-        RawComponent::new(data, fields)
-    }
+/// Inserts the typed `component` like [`Spawner::insert_typed`], encoding it into buffers from
+/// the `pool`.
+fn insert_pooled<S, T>(spawner: &mut S, pool: &mut BufferPool, entity: EntityId, component: T)
+where
+    S: Spawner,
+    T: Component,
+{
+    let (fields, data) = pool.encode(&component);
+    spawner.insert(entity, T::ID, RawComponent::new(&data[..], &fields[..]));
+    pool.recycle_fields(fields);
+    pool.recycle_bytes(data);
 }
 
 pub trait Spawner {
@@ -233,12 +273,58 @@ impl Spawner for World {
 
 #[cfg(test)]
 mod tests {
+    use std::alloc::System;
+
+    use game_common::components::components::RawComponent;
+    use game_common::entity::EntityId;
     use game_common::world::World;
+    use game_tracing::counting::{allocations, CountingAllocator};
+    use game_wasm::encoding::{Field, Primitive};
     use game_wasm::hierarchy::Children;
     use game_wasm::record::{ModuleId, RecordId};
     use game_wasm::world::RecordReference;
 
-    use crate::{ComponentRef, Prefab};
+    use crate::pool::BufferPool;
+    use crate::{Prefab, Spawner};
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator<System> = CountingAllocator::new(System);
+
+    /// Returns the number of allocations made by `f` on the current thread.
+    fn count_allocations<F>(f: F) -> usize
+    where
+        F: FnOnce(),
+    {
+        let start = allocations();
+        f();
+        allocations() - start
+    }
+
+    /// A [`Spawner`] that only stores the inserted components.
+    struct NullSpawner {
+        next: u64,
+        components: Vec<RawComponent>,
+    }
+
+    impl NullSpawner {
+        fn new(capacity: usize) -> Self {
+            Self {
+                next: 0,
+                components: Vec::with_capacity(capacity),
+            }
+        }
+    }
+
+    impl Spawner for NullSpawner {
+        fn spawn(&mut self) -> EntityId {
+            self.next += 1;
+            EntityId::from_raw(self.next)
+        }
+
+        fn insert(&mut self, _: EntityId, _: RecordReference, component: RawComponent) {
+            self.components.push(component);
+        }
+    }
 
     #[test]
     fn prefab_instantiate_with_children() {
@@ -260,27 +346,14 @@ mod tests {
         let prefab = Prefab {
             entities: vec![
                 // Top level parent
-                vec![ComponentRef {
-                    id: MARKER_COMPONENTS[0],
-                    data: 0..0,
-                    fields: 0..0,
-                }],
+                vec![(MARKER_COMPONENTS[0], RawComponent::default())],
                 // First children
-                vec![ComponentRef {
-                    id: MARKER_COMPONENTS[1],
-                    data: 0..0,
-                    fields: 0..0,
-                }],
+                vec![(MARKER_COMPONENTS[1], RawComponent::default())],
                 // Second children
-                vec![ComponentRef {
-                    id: MARKER_COMPONENTS[2],
-                    data: 0..0,
-                    fields: 0..0,
-                }],
+                vec![(MARKER_COMPONENTS[2], RawComponent::default())],
             ],
             children: [(0, vec![1]), (1, vec![2])].into(),
             root: vec![0],
-            data: Vec::new(),
         };
 
         let mut world = World::new();
@@ -312,4 +385,138 @@ mod tests {
             .get_typed::<Children>(children1.get()[0].into())
             .is_err());
     }
+
+    #[test]
+    fn prefab_add_cyclic_hierarchy() {
+        let mut world = World::new();
+        let root = world.spawn();
+        let child0 = world.spawn();
+        let child1 = world.spawn();
+
+        // root -> child0 -> child1 -> child0
+        world.insert_typed(root, Children::from_iter([child0]));
+        world.insert_typed(child0, Children::from_iter([child1]));
+        world.insert_typed(child1, Children::from_iter([child0]));
+
+        let mut prefab = Prefab::new();
+        prefab.add(root, &world);
+
+        assert_eq!(prefab.entities.len(), 3);
+        assert_eq!(prefab.root.len(), 1);
+
+        // The cycle must be broken, otherwise instantiating the
+        // prefab would never finish.
+        let bytes = prefab.to_bytes();
+        let prefab = Prefab::from_bytes(&bytes).unwrap();
+        prefab.instantiate(&mut World::new());
+    }
+
+    #[test]
+    fn prefab_add_max_depth() {
+        let mut world = World::new();
+        let root = world.spawn();
+        let mut parent = root;
+        for _ in 0..8 {
+            let child = world.spawn();
+            world.insert_typed(parent, Children::from_iter([child]));
+            parent = child;
+        }
+
+        let mut prefab = Prefab::new();
+        prefab.add_with_max_depth(root, &world, 4);
+
+        // The root and 4 levels of children.
+        assert_eq!(prefab.entities.len(), 5);
+    }
+
+    /// Returns a `Prefab` of `ROOTS` entities with `CHILDREN` children each, where every entity
+    /// has `components` components.
+    fn build_prefab(components: u32) -> Prefab {
+        let mut world = World::new();
+        let mut prefab = Prefab::new();
+
+        let fields = [
+            Field {
+                primitive: Primitive::Bytes,
+                offset: 0,
+            },
+            Field {
+                primitive: Primitive::Bytes,
+                offset: 4,
+            },
+        ];
+
+        for _ in 0..ROOTS {
+            let root = world.spawn();
+            let children: Vec<_> = (0..CHILDREN).map(|_| world.spawn()).collect();
+
+            for &entity in std::iter::once(&root).chain(&children) {
+                for id in 0..components {
+                    world.insert(
+                        entity,
+                        RecordReference {
+                            module: ModuleId::CORE,
+                            record: RecordId(id),
+                        },
+                        RawComponent::new(vec![id as u8; 8], &fields[..]),
+                    );
+                }
+            }
+
+            world.insert_typed(root, Children::from_iter(children));
+            prefab.add(root, &world);
+        }
+
+        prefab
+    }
+
+    const ROOTS: usize = 100;
+    const CHILDREN: usize = 9;
+
+    /// Instantiates the `prefab` using the `pool` and returns the number of allocations.
+    fn instantiate_with(prefab: &Prefab, components: u32, pool: &mut BufferPool) -> usize {
+        // Every root and the prefab root have a `Children` component.
+        let num_components = ROOTS * (CHILDREN + 1) * components as usize + ROOTS + 1;
+
+        let prefab = prefab.clone();
+        let mut spawner = NullSpawner::new(num_components);
+        let allocations = count_allocations(|| {
+            prefab.instantiate_with(&mut spawner, pool);
+        });
+        assert_eq!(spawner.components.len(), num_components);
+
+        allocations
+    }
+
+    #[test]
+    fn prefab_instantiate_reuses_buffers() {
+        const COMPONENTS: u32 = 4;
+
+        let prefab = build_prefab(COMPONENTS);
+
+        let fresh = instantiate_with(&prefab, COMPONENTS, &mut BufferPool::new());
+
+        let mut pool = BufferPool::new();
+        instantiate_with(&prefab, COMPONENTS, &mut pool);
+        let reused = instantiate_with(&prefab, COMPONENTS, &mut pool);
+
+        // The temporary buffers for the `Children` components are taken from
+        // the pool instead of being allocated again.
+        assert!(reused < fresh, "reused: {}, fresh: {}", reused, fresh);
+    }
+
+    #[test]
+    fn prefab_instantiate_shares_components() {
+        let mut pool = BufferPool::new();
+
+        let allocations = [4, 8].map(|components| {
+            let prefab = build_prefab(components);
+            instantiate_with(&prefab, components, &mut pool);
+            instantiate_with(&prefab, components, &mut pool)
+        });
+
+        // Components share their data with the prefab, so the number of
+        // allocations does not depend on the number of components.
+        assert_eq!(allocations[0], allocations[1]);
+    }
 }
@@ -0,0 +1,64 @@
+//! Reusable buffers for encoding and decoding components.
+
+use game_wasm::encoding::{BinaryWriter, Encode, Field};
+
+/// The maximum number of buffers of each kind retained by a [`BufferPool`].
+const MAX_POOLED: usize = 8;
+
+/// A pool of buffers reused when encoding and decoding many components.
+///
+/// Components are only ever encoded/decoded into a temporary buffer before being copied into
+/// their final storage. Reusing these temporary buffers avoids allocating a new buffer for every
+/// component.
+#[derive(Clone, Debug, Default)]
+pub struct BufferPool {
+    bytes: Vec<Vec<u8>>,
+    fields: Vec<Vec<Field>>,
+}
+
+impl BufferPool {
+    /// Creates a new, empty `BufferPool`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns an empty byte buffer, reusing a previously recycled buffer if possible.
+    pub fn bytes(&mut self) -> Vec<u8> {
+        self.bytes.pop().unwrap_or_default()
+    }
+
+    /// Returns an empty field buffer, reusing a previously recycled buffer if possible.
+    pub fn fields(&mut self) -> Vec<Field> {
+        self.fields.pop().unwrap_or_default()
+    }
+
+    /// Returns the byte buffer `buf` to the pool.
+    pub fn recycle_bytes(&mut self, mut buf: Vec<u8>) {
+        if self.bytes.len() < MAX_POOLED {
+            buf.clear();
+            self.bytes.push(buf);
+        }
+    }
+
+    /// Returns the field buffer `buf` to the pool.
+    pub fn recycle_fields(&mut self, mut buf: Vec<Field>) {
+        if self.fields.len() < MAX_POOLED {
+            buf.clear();
+            self.fields.push(buf);
+        }
+    }
+
+    /// Encodes `value` using buffers from the pool.
+    ///
+    /// The returned buffers should be passed to [`recycle_fields`] and [`recycle_bytes`] once
+    /// they are no longer needed.
+    ///
+    /// [`recycle_fields`]: Self::recycle_fields
+    /// [`recycle_bytes`]: Self::recycle_bytes
+    pub fn encode<T>(&mut self, value: &T) -> (Vec<Field>, Vec<u8>)
+    where
+        T: Encode,
+    {
+        BinaryWriter::with_buffers(self.fields(), self.bytes()).encoded(value)
+    }
+}
@@ -0,0 +1,405 @@
+//! Cell-chunked world saves.
+//!
+//! Instead of saving the whole world in a single [`Prefab`], the entities of every cell are saved
+//! in a separate [`CellChunk`]. This allows loading and unloading the entities of a cell
+//! independently of all other cells.
+//!
+//! Every chunk is encoded as a header followed by the encoded [`Prefab`] containing the entities
+//! of the cell:
+//!
+//! | Field    | Type      | Description                                 |
+//! | -------- | --------- | ------------------------------------------- |
+//! | magic    | `[u8; 4]` | Always [`MAGIC`].                           |
+//! | version  | `u32`     | The [`CHUNK_VERSION`] of the chunk format.  |
+//! | cell     | `[u32; 3]`| The parts of the [`CellId`] of the chunk.   |
+//! | checksum | `u32`     | The CRC32 checksum of the payload.          |
+//! | len      | `u64`     | The length of the payload in bytes.         |
+//!
+//! All integers are encoded in the byte order defined by [`game_common::utils::endian`].
+
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use bytes::{Buf, BufMut};
+use game_common::entity::EntityId;
+use game_common::utils::endian::Primitive;
+use game_common::world::World;
+use game_tracing::trace_span;
+use game_wasm::cell::CellId;
+
+use crate::pool::BufferPool;
+use crate::{DecodeError, Prefab, Spawner};
+
+/// The magic bytes at the start of every [`CellChunk`].
+pub const MAGIC: [u8; 4] = *b"CELL";
+
+/// The current version of the [`CellChunk`] format.
+///
+/// Chunks with a different version are rejected when decoding.
+pub const CHUNK_VERSION: u32 = 1;
+
+const HEADER_LEN: usize = 4 + 4 + 4 * 3 + 4 + 8;
+
+/// The entities of a single cell.
+#[derive(Clone, Debug)]
+pub struct CellChunk {
+    cell: CellId,
+    prefab: Prefab,
+}
+
+impl CellChunk {
+    /// Creates a new, empty `CellChunk` for the given [`CellId`].
+    pub fn new(cell: CellId) -> Self {
+        Self {
+            cell,
+            prefab: Prefab::new(),
+        }
+    }
+
+    /// Returns the [`CellId`] of the cell of this `CellChunk`.
+    #[inline]
+    pub fn cell(&self) -> CellId {
+        self.cell
+    }
+
+    /// Returns `true` if the `CellChunk` contains no entities.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.prefab.root.is_empty()
+    }
+
+    /// Adds the entity with the given `id` and all of its children to the `CellChunk`.
+    pub fn add(&mut self, id: EntityId, world: &World) {
+        self.prefab.add(id, world);
+    }
+
+    /// Spawns all entities of the `CellChunk` using the given [`Spawner`] and returns the
+    /// [`EntityId`]s of the top-level entities.
+    ///
+    /// Unlike [`Prefab::instantiate`] no additional parent entity is spawned.
+    pub fn instantiate<S>(self, spawner: S) -> Vec<EntityId>
+    where
+        S: Spawner,
+    {
+        let _span = trace_span!("CellChunk::instantiate").entered();
+
+        self.prefab.spawn_roots(spawner, &mut BufferPool::new())
+    }
+
+    /// Serializes the `CellChunk` into bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload = self.prefab.to_bytes();
+        let (x, y, z) = self.cell.as_parts();
+
+        let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+        buf.put_slice(&MAGIC);
+        put(&mut buf, CHUNK_VERSION);
+        put(&mut buf, x);
+        put(&mut buf, y);
+        put(&mut buf, z);
+        put(&mut buf, crc32fast::hash(&payload));
+        put(&mut buf, payload.len() as u64);
+        buf.put_slice(&payload);
+        buf
+    }
+
+    /// Deserializes the `CellChunk` from the given `bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChunkError`] if `bytes` does not contain a valid `CellChunk` of the current
+    /// [`CHUNK_VERSION`].
+    pub fn from_bytes(mut buf: &[u8]) -> Result<Self, ChunkError> {
+        if buf.remaining() < HEADER_LEN {
+            return Err(ChunkError::Eof {
+                expected_len: HEADER_LEN,
+                got_len: buf.remaining(),
+            });
+        }
+
+        let mut magic = [0; 4];
+        buf.copy_to_slice(&mut magic);
+        if magic != MAGIC {
+            return Err(ChunkError::InvalidMagic(magic));
+        }
+
+        let version: u32 = get(&mut buf);
+        if version != CHUNK_VERSION {
+            return Err(ChunkError::UnsupportedVersion(version));
+        }
+
+        let x = get(&mut buf);
+        let y = get(&mut buf);
+        let z = get(&mut buf);
+        let checksum: u32 = get(&mut buf);
+        let len: u64 = get(&mut buf);
+
+        if (buf.remaining() as u64) < len {
+            return Err(ChunkError::Eof {
+                expected_len: len as usize,
+                got_len: buf.remaining(),
+            });
+        }
+
+        let payload = &buf[..len as usize];
+        let actual = crc32fast::hash(payload);
+        if actual != checksum {
+            return Err(ChunkError::ChecksumMismatch {
+                expected: checksum,
+                actual,
+            });
+        }
+
+        let prefab = Prefab::from_bytes(payload).map_err(ChunkError::Prefab)?;
+
+        Ok(Self {
+            cell: CellId::from_parts(x, y, z),
+            prefab,
+        })
+    }
+}
+
+/// Writes the `value` to the `buf`.
+fn put<T>(buf: &mut Vec<u8>, value: T)
+where
+    T: Primitive,
+{
+    buf.put_slice(value.to_bytes().as_ref());
+}
+
+/// Reads a `T` from the `buf`.
+///
+/// The caller must ensure that `buf` contains at least `N` bytes.
+fn get<T, const N: usize>(buf: &mut &[u8]) -> T
+where
+    T: Primitive<Bytes = [u8; N]>,
+{
+    let mut bytes = [0; N];
+    buf.copy_to_slice(&mut bytes);
+    T::from_bytes(bytes)
+}
+
+#[derive(Clone, Debug)]
+pub enum ChunkError {
+    Eof { expected_len: usize, got_len: usize },
+    InvalidMagic([u8; 4]),
+    UnsupportedVersion(u32),
+    ChecksumMismatch { expected: u32, actual: u32 },
+    Prefab(DecodeError),
+}
+
+impl Display for ChunkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Eof {
+                expected_len,
+                got_len,
+            } => {
+                write!(
+                    f,
+                    "unexpected eof: expected {} bytes, got {} bytes",
+                    expected_len, got_len
+                )
+            }
+            Self::InvalidMagic(magic) => write!(f, "invalid magic: {:?}", magic),
+            Self::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "unsupported chunk version {} (expected {})",
+                    version, CHUNK_VERSION
+                )
+            }
+            Self::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "checksum mismatch: expected {:08x}, got {:08x}",
+                    expected, actual
+                )
+            }
+            Self::Prefab(err) => Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+/// A world save stored as one file per [`CellChunk`] in a directory.
+#[derive(Clone, Debug)]
+pub struct WorldSave {
+    root: PathBuf,
+}
+
+impl WorldSave {
+    /// Creates a new `WorldSave` stored in the directory at `root`.
+    ///
+    /// The directory is created when the first chunk is stored.
+    pub fn new<P>(root: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self { root: root.into() }
+    }
+
+    /// Returns the directory of the `WorldSave`.
+    #[inline]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Stores the `chunk`, replacing any previously stored chunk of the same cell.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::Error`] if the chunk could not be written.
+    pub fn store(&self, chunk: &CellChunk) -> io::Result<()> {
+        let _span = trace_span!("WorldSave::store").entered();
+
+        fs::create_dir_all(&self.root)?;
+
+        // Write to a temporary file first, so that a partially written chunk never
+        // replaces a valid chunk.
+        let path = self.path(chunk.cell);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, chunk.to_bytes())?;
+        fs::rename(&tmp, &path)
+    }
+
+    /// Loads the chunk of the given [`CellId`].
+    ///
+    /// Returns `None` if no chunk is stored for the cell.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LoadChunkError`] if the chunk could not be read or is invalid.
+    pub fn load(&self, cell: CellId) -> Result<Option<CellChunk>, LoadChunkError> {
+        let _span = trace_span!("WorldSave::load").entered();
+
+        let bytes = match fs::read(self.path(cell)) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(LoadChunkError::Io(err)),
+        };
+
+        let chunk = CellChunk::from_bytes(&bytes).map_err(LoadChunkError::Chunk)?;
+        if chunk.cell != cell {
+            return Err(LoadChunkError::CellMismatch {
+                expected: cell,
+                actual: chunk.cell,
+            });
+        }
+
+        Ok(Some(chunk))
+    }
+
+    fn path(&self, cell: CellId) -> PathBuf {
+        let (x, y, z) = cell.as_parts();
+        self.root
+            .join(format!("{:08x}_{:08x}_{:08x}.cell", x, y, z))
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadChunkError {
+    Io(io::Error),
+    Chunk(ChunkError),
+    CellMismatch { expected: CellId, actual: CellId },
+}
+
+impl Display for LoadChunkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => Display::fmt(err, f),
+            Self::Chunk(err) => Display::fmt(err, f),
+            Self::CellMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "chunk contains cell {:?}, expected {:?}",
+                    actual, expected
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadChunkError {}
+
+#[cfg(test)]
+mod tests {
+    use game_common::components::components::RawComponent;
+    use game_common::world::World;
+    use game_wasm::cell::CellId;
+    use game_wasm::record::{ModuleId, RecordId};
+    use game_wasm::world::RecordReference;
+
+    use super::{CellChunk, ChunkError, WorldSave, CHUNK_VERSION};
+
+    const COMPONENT: RecordReference = RecordReference {
+        module: ModuleId::CORE,
+        record: RecordId(0x01),
+    };
+
+    fn chunk(cell: CellId) -> CellChunk {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, COMPONENT, RawComponent::new(vec![1, 2, 3], []));
+
+        let mut chunk = CellChunk::new(cell);
+        chunk.add(entity, &world);
+        chunk
+    }
+
+    #[test]
+    fn cell_chunk_roundtrip() {
+        let cell = CellId::from_parts(u32::MAX, 2, 3);
+        let bytes = chunk(cell).to_bytes();
+
+        let chunk = CellChunk::from_bytes(&bytes).unwrap();
+        assert_eq!(chunk.cell(), cell);
+
+        let mut world = World::new();
+        let entities = chunk.instantiate(&mut world);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(
+            world.get(entities[0], COMPONENT).unwrap().as_bytes(),
+            &[1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn cell_chunk_checksum_mismatch() {
+        let mut bytes = chunk(CellId::ZERO).to_bytes();
+        *bytes.last_mut().unwrap() ^= 0xFF;
+
+        assert!(matches!(
+            CellChunk::from_bytes(&bytes),
+            Err(ChunkError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn cell_chunk_unsupported_version() {
+        let mut bytes = chunk(CellId::ZERO).to_bytes();
+        bytes[4..8].copy_from_slice(&(CHUNK_VERSION + 1).to_le_bytes());
+
+        assert!(matches!(
+            CellChunk::from_bytes(&bytes),
+            Err(ChunkError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn world_save_store_and_load() {
+        let root = tempfile::tempdir().unwrap();
+        let save = WorldSave::new(root.path());
+
+        let cell = CellId::from_parts(1, 0, 4);
+        assert!(save.load(cell).unwrap().is_none());
+
+        save.store(&chunk(cell)).unwrap();
+        let chunk = save.load(cell).unwrap().unwrap();
+        assert_eq!(chunk.cell(), cell);
+        assert!(!chunk.is_empty());
+    }
+}
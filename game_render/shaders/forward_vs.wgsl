@@ -59,7 +59,10 @@ fn vs_main(in: VertexInput) -> VertexOutput {
 
     out.world_position = (model.transform * vec4<f32>(position, 1.0)).xyz;
     out.world_normal = model.normal * normal;
-    out.world_tangent = vec4((model.normal * tangent.xyz), tangent.w);
+    // Tangents lie in the surface, so unlike normals they are transformed
+    // by the model matrix.
+    let model_3x3 = mat3x3(model.transform[0].xyz, model.transform[1].xyz, model.transform[2].xyz);
+    out.world_tangent = vec4((model_3x3 * tangent.xyz), tangent.w);
 
     return out;
 }
@@ -0,0 +1,53 @@
+// Renders the world-space bounding boxes of objects for occlusion queries.
+// Nothing is written, the boxes are only tested against the depth buffer.
+
+struct Camera {
+    position: vec3<f32>,
+    view_proj: mat4x4<f32>,
+}
+
+struct Bounds {
+    // The `w` component is unused.
+    min: vec4<f32>,
+    max: vec4<f32>,
+}
+
+var<push_constant> camera: Camera;
+
+@group(0) @binding(0)
+var<storage> bounds: array<Bounds>;
+
+// The corners of the 12 triangles of a box. Bit 0, 1 and 2 select the
+// max component of the x, y and z axis respectively.
+const CORNERS = array<u32, 36>(
+    0u, 2u, 1u, 1u, 2u, 3u,
+    4u, 5u, 6u, 5u, 7u, 6u,
+    0u, 1u, 4u, 1u, 5u, 4u,
+    2u, 6u, 3u, 3u, 6u, 7u,
+    0u, 4u, 2u, 2u, 4u, 6u,
+    1u, 3u, 5u, 3u, 7u, 5u,
+);
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32,
+) -> @builtin(position) vec4<f32> {
+    var corners = CORNERS;
+    let corner = corners[vertex_index];
+    let aabb = bounds[instance_index];
+
+    let factor = vec3<f32>(
+        f32(corner & 1u),
+        f32((corner >> 1u) & 1u),
+        f32((corner >> 2u) & 1u),
+    );
+    let position = mix(aabb.min.xyz, aabb.max.xyz, factor);
+
+    return camera.view_proj * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0);
+}
@@ -27,6 +27,7 @@ var t_texture: texture_2d<f32>;
 @group(0) @binding(1)
 var t_sampler: sampler;
 
+// Entry point for non-sRGB UNORM output textures.
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var color = textureSample(t_texture, t_sampler, in.uv).rgb;
@@ -42,6 +43,31 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     return vec4(color, 1.0);
 }
 
+// Entry point for sRGB output textures.
+@fragment
+fn fs_main_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(t_texture, t_sampler, in.uv).rgb;
+
+    color = tonemap(color);
+
+    // The GPU converts the output to sRGB when storing it, but dithering
+    // must still happen in gamma space.
+    color = gamma_correct(color) + screen_space_dither(in.clip_position.xy);
+    color = gamma_decode(color);
+
+    return vec4(color, 1.0);
+}
+
+// Entry point for floating point output textures which store linear values.
+@fragment
+fn fs_main_linear(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = textureSample(t_texture, t_sampler, in.uv).rgb;
+
+    color = tonemap(color);
+
+    return vec4(color, 1.0);
+}
+
 fn tonemap(color: vec3<f32>) -> vec3<f32> {
     return color / (color + vec3(1.0));
 }
@@ -51,7 +77,15 @@ fn gamma_correct(color: vec3<f32>) -> vec3<f32> {
     out_color.r = linear_to_srgb(color.r);
     out_color.g = linear_to_srgb(color.g);
     out_color.b = linear_to_srgb(color.b);
-    return color;
+    return out_color;
+}
+
+fn gamma_decode(color: vec3<f32>) -> vec3<f32> {
+    var out_color = vec3(0.0);
+    out_color.r = srgb_to_linear(color.r);
+    out_color.g = srgb_to_linear(color.g);
+    out_color.b = srgb_to_linear(color.b);
+    return out_color;
 }
 
 // sRGB transfer functions
@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::{Mat4, Vec3};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Aabb {
@@ -24,4 +24,56 @@ impl Aabb {
             half_extents,
         }
     }
+
+    /// Returns `true` if the `point` is inside the `Aabb`.
+    pub fn contains(&self, point: Vec3) -> bool {
+        (point - self.center).abs().cmple(self.half_extents).all()
+    }
+
+    /// Returns the smallest `Aabb` containing this `Aabb` transformed by the affine `transform`.
+    pub fn transform(&self, transform: Mat4) -> Self {
+        let center = transform.transform_point3(self.center);
+
+        // The extent along every axis is the sum of the absolute projections
+        // of the transformed half extents.
+        let half_extents = transform.x_axis.truncate().abs() * self.half_extents.x
+            + transform.y_axis.truncate().abs() * self.half_extents.y
+            + transform.z_axis.truncate().abs() * self.half_extents.z;
+
+        Self {
+            center,
+            half_extents,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use glam::{Mat4, Quat, Vec3};
+
+    use super::Aabb;
+
+    #[test]
+    fn aabb_transform() {
+        let aabb = Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0));
+
+        let transform = Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 1.0),
+            Quat::from_rotation_y(FRAC_PI_4),
+            Vec3::new(0.0, 5.0, 0.0),
+        );
+        let output = aabb.transform(transform);
+
+        assert!((output.center - Vec3::new(0.0, 5.0, 0.0)).length() < 1e-5);
+        assert!((output.half_extents.y - 1.0).abs() < 1e-5);
+
+        let extent = 2.0 * FRAC_PI_4.cos() + FRAC_PI_4.sin();
+        assert!((output.half_extents.x - extent).abs() < 1e-5);
+        assert!((output.half_extents.z - extent).abs() < 1e-5);
+
+        assert!(output.contains(Vec3::new(0.0, 5.0, 0.0)));
+        assert!(!output.contains(Vec3::ZERO));
+    }
 }
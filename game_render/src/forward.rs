@@ -4,31 +4,43 @@ use game_common::cell::UnsafeRefCell;
 use wgpu::{
     AddressMode, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
     BlendState, BufferBindingType, ColorTargetState, ColorWrites, CompareFunction, DepthBiasState,
-    DepthStencilState, Device, FilterMode, FragmentState, FrontFace, MultisampleState,
-    PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology, PushConstantRange,
-    RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor,
-    ShaderModuleDescriptor, ShaderSource, ShaderStages, StencilState, TextureFormat,
-    TextureSampleType, TextureViewDimension, VertexState,
+    DepthStencilState, Device, Face, FilterMode, FragmentState, FrontFace, MultisampleState,
+    PipelineLayout, PipelineLayoutDescriptor, PolygonMode, PrimitiveState, PrimitiveTopology,
+    PushConstantRange, RenderPipeline, RenderPipelineDescriptor, Sampler, SamplerBindingType,
+    SamplerDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages,
+    StencilState, TextureFormat, TextureSampleType, TextureViewDimension, VertexState,
 };
 
 use crate::depth_stencil::DEPTH_TEXTURE_FORMAT;
 use crate::entities::{Event, Resources};
+use crate::occlusion::OcclusionPipeline;
 
 #[derive(Debug)]
 pub struct ForwardPipeline {
-    pub pipeline: RenderPipeline,
+    /// The pipeline for single-sided materials, culling back faces.
+    pub single_sided_pipeline: RenderPipeline,
+    /// The pipeline for double-sided materials, culling no faces.
+    pub double_sided_pipeline: RenderPipeline,
     pub vs_bind_group_layout: BindGroupLayout,
     pub fs_bind_group_layout: BindGroupLayout,
     pub mesh_bind_group_layout: BindGroupLayout,
     pub material_bind_group_layout: BindGroupLayout,
     pub lights_bind_group_layout: BindGroupLayout,
-    pub sampler: Sampler,
     pub resources: Arc<Resources>,
     pub events: UnsafeRefCell<Vec<Event>>,
+    /// The occlusion culling pipeline, `None` if occlusion culling is not supported.
+    pub(crate) occlusion: Option<OcclusionPipeline>,
+    /// The highest level of anisotropic filtering supported by the adapter.
+    pub(crate) max_anisotropy: u16,
 }
 
 impl ForwardPipeline {
-    pub fn new(device: &Device, resources: Arc<Resources>) -> Self {
+    pub fn new(
+        device: &Device,
+        resources: Arc<Resources>,
+        occlusion_culling: bool,
+        max_anisotropy: u16,
+    ) -> Self {
         let vs_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("vs_bind_group_layout"),
             entries: &[
@@ -222,70 +234,111 @@ impl ForwardPipeline {
             }],
         });
 
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("forward_pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: VertexState {
-                module: &vs_shader,
-                entry_point: "vs_main",
-                buffers: &[],
-            },
-            fragment: Some(FragmentState {
-                module: &fs_shader,
-                entry_point: "fs_main",
-                targets: &[Some(ColorTargetState {
-                    format: TextureFormat::Rgba16Float,
-                    blend: Some(BlendState::ALPHA_BLENDING),
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            primitive: PrimitiveState {
-                topology: PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: FrontFace::Ccw,
-                cull_mode: None,
-                polygon_mode: PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(DepthStencilState {
-                format: DEPTH_TEXTURE_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: CompareFunction::Less,
-                stencil: StencilState::default(),
-                bias: DepthBiasState::default(),
-            }),
-            multisample: MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-        });
-
-        let sampler = device.create_sampler(&SamplerDescriptor {
-            label: Some("default_sampler"),
-            address_mode_u: AddressMode::Repeat,
-            address_mode_v: AddressMode::Repeat,
-            address_mode_w: AddressMode::Repeat,
-            mag_filter: FilterMode::Linear,
-            min_filter: FilterMode::Linear,
-            mipmap_filter: FilterMode::Linear,
-            lod_min_clamp: 0.0,
-            lod_max_clamp: 100.0,
-            ..Default::default()
-        });
+        let single_sided_pipeline = create_pipeline(
+            device,
+            &pipeline_layout,
+            &vs_shader,
+            &fs_shader,
+            Some(Face::Back),
+        );
+        let double_sided_pipeline =
+            create_pipeline(device, &pipeline_layout, &vs_shader, &fs_shader, None);
 
         Self {
-            pipeline,
+            single_sided_pipeline,
+            double_sided_pipeline,
             vs_bind_group_layout,
             fs_bind_group_layout,
             mesh_bind_group_layout,
             material_bind_group_layout,
             lights_bind_group_layout,
-            sampler,
             resources,
             events: UnsafeRefCell::new(Vec::new()),
+            occlusion: occlusion_culling.then(|| OcclusionPipeline::new(device)),
+            max_anisotropy,
+        }
+    }
+
+    /// Returns the pipeline for materials that are [`double_sided`] or single-sided.
+    ///
+    /// [`double_sided`]: crate::pbr::PbrMaterial::double_sided
+    pub fn pipeline(&self, double_sided: bool) -> &RenderPipeline {
+        if double_sided {
+            &self.double_sided_pipeline
+        } else {
+            &self.single_sided_pipeline
         }
     }
 }
+
+/// Creates the forward pipeline culling the faces given by `cull_mode`.
+///
+/// Faces with a counter-clockwise winding order are front-facing, as in glTF.
+fn create_pipeline(
+    device: &Device,
+    layout: &PipelineLayout,
+    vs_shader: &ShaderModule,
+    fs_shader: &ShaderModule,
+    cull_mode: Option<Face>,
+) -> RenderPipeline {
+    device.create_render_pipeline(&RenderPipelineDescriptor {
+        label: Some("forward_pipeline"),
+        layout: Some(layout),
+        vertex: VertexState {
+            module: vs_shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(FragmentState {
+            module: fs_shader,
+            entry_point: "fs_main",
+            targets: &[Some(ColorTargetState {
+                format: TextureFormat::Rgba16Float,
+                blend: Some(BlendState::ALPHA_BLENDING),
+                write_mask: ColorWrites::ALL,
+            })],
+        }),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FrontFace::Ccw,
+            cull_mode,
+            polygon_mode: PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(DepthStencilState {
+            format: DEPTH_TEXTURE_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Less,
+            stencil: StencilState::default(),
+            bias: DepthBiasState::default(),
+        }),
+        multisample: MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+/// Creates the sampler used for all material textures.
+///
+/// `anisotropy_clamp` must be in range of `1..=16`.
+pub(crate) fn create_material_sampler(device: &Device, anisotropy_clamp: u16) -> Sampler {
+    device.create_sampler(&SamplerDescriptor {
+        label: Some("default_sampler"),
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        address_mode_w: AddressMode::Repeat,
+        // Anisotropic filtering requires all filters to be linear.
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        mipmap_filter: FilterMode::Linear,
+        lod_min_clamp: 0.0,
+        lod_max_clamp: 100.0,
+        anisotropy_clamp,
+        ..Default::default()
+    })
+}
@@ -32,6 +32,7 @@ use wgpu::{Buffer, CommandEncoder, Device, Queue, Texture, TextureFormat, Textur
 
 use crate::camera::RenderTarget;
 use crate::mipmap::MipMapGenerator;
+use crate::statistics::FrameStatistics;
 
 pub trait Node: Send + Sync + 'static {
     /// Renders the node.
@@ -50,6 +51,7 @@ pub struct RenderContext<'a, 'b> {
     pub mipmap: &'b mut MipMapGenerator,
     pub(crate) resource_permissions: &'a HashMap<SlotLabel, SlotFlags>,
     pub(crate) resources: &'b mut HashMap<SlotLabel, SlotValueInner<'a>>,
+    pub(crate) statistics: &'b mut FrameStatistics,
 }
 
 impl<'a, 'b> RenderContext<'a, 'b> {
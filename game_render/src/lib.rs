@@ -9,14 +9,17 @@ pub mod mesh;
 pub mod metrics;
 pub mod mipmap;
 pub mod options;
+pub mod pass;
 pub mod pbr;
 pub mod shape;
+pub mod statistics;
 pub mod surface;
 pub mod texture;
 
 mod debug;
 mod depth_stencil;
 mod fps_limiter;
+mod occlusion;
 mod passes;
 mod pipeline_cache;
 mod pipelined_rendering;
@@ -30,6 +33,7 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use futures_lite::FutureExt;
 use game_tasks::TaskPool;
@@ -39,14 +43,17 @@ use camera::RenderTarget;
 use forward::ForwardPipeline;
 use game_window::windows::{WindowId, WindowState};
 use glam::UVec2;
-use graph::RenderGraph;
+use graph::{NodeLabel, RenderGraph};
+use pass::{DepthStencils, PassNode, RenderPass};
 use pipelined_rendering::{Pipeline, RenderImageGpu};
+use statistics::Statistics;
 use texture::{RenderImageId, RenderTexture, RenderTextureEvent, RenderTextures};
 use thiserror::Error;
 use tokio::sync::oneshot;
 use wgpu::{
-    Backends, Device, DeviceDescriptor, Features, Gles3MinorVersion, Instance, InstanceDescriptor,
-    InstanceFlags, Limits, PowerPreference, Queue, RequestAdapterOptions, RequestDeviceError,
+    Backends, Device, DeviceDescriptor, DownlevelFlags, Features, Gles3MinorVersion, Instance,
+    InstanceDescriptor, InstanceFlags, Limits, PowerPreference, Queue, RequestAdapterOptions,
+    RequestDeviceError, TextureFormat,
 };
 
 pub use passes::FINAL_RENDER_PASS;
@@ -70,6 +77,7 @@ pub struct Renderer {
 
     render_textures: RenderTextures,
     jobs: VecDeque<Job>,
+    depth_stencils: DepthStencils,
 }
 
 impl Renderer {
@@ -116,15 +124,38 @@ impl Renderer {
 
         let resources = Arc::new(Resources::default());
 
-        let forward = Arc::new(ForwardPipeline::new(&device, resources.clone()));
+        let occlusion_culling = occlusion::is_supported(&adapter);
+        if !occlusion_culling {
+            tracing::info!("occlusion culling is not supported by the adapter");
+        }
+
+        let max_anisotropy = if adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::ANISOTROPIC_FILTERING)
+        {
+            options::MAX_ANISOTROPY
+        } else {
+            tracing::info!("anisotropic filtering is not supported by the adapter");
+            1
+        };
+
+        let forward = Arc::new(ForwardPipeline::new(
+            &device,
+            resources.clone(),
+            occlusion_culling,
+            max_anisotropy,
+        ));
 
         let pipeline = Pipeline::new(instance, adapter, device, queue);
+        let depth_stencils = DepthStencils::default();
 
         {
             let mut graph = unsafe { pipeline.shared.graph.borrow_mut() };
             passes::init(
                 &mut graph,
                 forward.clone(),
+                depth_stencils.clone(),
                 &pipeline.shared.device,
                 &pipeline.shared.queue,
             );
@@ -138,6 +169,7 @@ impl Renderer {
             forward,
             resources,
             events: Vec::new(),
+            depth_stencils,
         })
     }
 
@@ -145,9 +177,19 @@ impl Renderer {
         unsafe { ResourcesMut::new(&self.resources, &mut self.events) }
     }
 
+    /// Reads back the contents of the render texture with the given `id`.
+    ///
+    /// The returned [`ReadTexture`] resolves to `None` if the texture is destroyed before it
+    /// was read.
     pub fn read_gpu_texture(&mut self, id: RenderImageId) -> ReadTexture {
         let (tx, rx) = oneshot::channel();
-        self.jobs.push_back(Job::TextureToBuffer(id, tx));
+
+        // Dropping `tx` for a destroyed texture immediately resolves the
+        // `ReadTexture` to `None`.
+        if self.render_textures.contains(id) {
+            self.jobs.push_back(Job::TextureToBuffer(id, tx));
+        }
+
         ReadTexture { rx }
     }
 
@@ -159,17 +201,56 @@ impl Renderer {
         &self.pipeline.shared.queue
     }
 
+    /// Returns the [`Statistics`] recorded by the render thread.
+    pub fn statistics(&self) -> Arc<Statistics> {
+        self.pipeline.shared.statistics.clone()
+    }
+
     pub fn graph_mut(&mut self) -> RefMut<'_, RenderGraph> {
         self.pipeline.wait_idle();
         unsafe { self.pipeline.shared.graph.borrow_mut() }
     }
 
+    /// Registers a new [`RenderPass`] in the render graph.
+    ///
+    /// The pass runs after the main pass and after all passes in `after`. Refer to the
+    /// [`pass`] module for the resources available to the pass.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any node in `after` does not exist.
+    pub fn add_pass<T>(&mut self, label: NodeLabel, pass: T, after: &[NodeLabel])
+    where
+        T: RenderPass,
+    {
+        let depth_stencils = self.depth_stencils.clone();
+
+        let mut graph = self.graph_mut();
+        graph.add_node(
+            label,
+            PassNode {
+                pass,
+                depth_stencils,
+            },
+        );
+        graph.add_node_dependency(label, passes::MAIN_PASS);
+        for node in after {
+            graph.add_node_dependency(label, *node);
+        }
+    }
+
     pub fn create_render_texture(&mut self, texture: RenderTexture) -> RenderImageId {
         let id = self.render_textures.insert(texture);
         id
     }
 
-    pub fn destory_render_texture(&mut self, id: RenderImageId) {}
+    /// Destroys the render texture with the given `id`.
+    ///
+    /// The `id` becomes invalid and all further uses of it are ignored. Does nothing if the
+    /// texture was already destroyed.
+    pub fn destroy_render_texture(&mut self, id: RenderImageId) {
+        self.render_textures.remove(id);
+    }
 
     /// Create a new renderer for the window.
     pub fn create(&mut self, id: WindowId, window: WindowState) {
@@ -184,6 +265,39 @@ impl Renderer {
         self.backlog.push_back(SurfaceEvent::Destroy(id));
     }
 
+    /// Requests the surface of the window with the given `id` to be recreated using `format`.
+    ///
+    /// The `format` must be one of the [`supported_surface_formats`] of the window, otherwise the
+    /// request is ignored and an error is logged. The final render pass adapts to the color space
+    /// of the new format.
+    ///
+    /// [`supported_surface_formats`]: Self::supported_surface_formats
+    pub fn set_surface_format(&mut self, id: WindowId, format: TextureFormat) {
+        self.backlog.push_back(SurfaceEvent::SetFormat(id, format));
+    }
+
+    /// Returns all formats supported by the surface of the window with the given `id`.
+    ///
+    /// Returns an empty list if the window has no surface. Note that surfaces are only created
+    /// once the next frame is rendered after calling [`create`].
+    ///
+    /// [`create`]: Self::create
+    pub fn supported_surface_formats(&self, id: WindowId) -> Vec<TextureFormat> {
+        let surfaces = unsafe { self.pipeline.shared.surfaces.borrow() };
+        surfaces
+            .get(id)
+            .map(|surface| surface.supported_formats().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Returns the format currently used by the surface of the window with the given `id`.
+    ///
+    /// Returns `None` if the window has no surface.
+    pub fn surface_format(&self, id: WindowId) -> Option<TextureFormat> {
+        let surfaces = unsafe { self.pipeline.shared.surfaces.borrow() };
+        surfaces.get(id).map(|surface| surface.config.format)
+    }
+
     // TODO: Get rid of this shit.
     pub fn get_surface_size(&self, target: RenderTarget) -> Option<UVec2> {
         match target {
@@ -240,6 +354,10 @@ impl Renderer {
         }
 
         {
+            // Drop all jobs that were cancelled before they were submitted
+            // to the render thread.
+            self.jobs.retain(|job| !job.is_cancelled());
+
             let mut jobs = unsafe { self.pipeline.shared.jobs.borrow_mut() };
             std::mem::swap(&mut self.jobs, &mut jobs);
         }
@@ -275,6 +393,9 @@ impl Renderer {
                         }
                     }
                 }
+                SurfaceEvent::SetFormat(id, format) => {
+                    surfaces.set_format(id, device, format);
+                }
                 SurfaceEvent::Destroy(id) => {
                     surfaces.destroy(id);
                 }
@@ -285,6 +406,15 @@ impl Renderer {
     pub fn set_fps_limit(&mut self, limit: FpsLimit) {
         self.jobs.push_back(Job::SetFpsLimit(limit));
     }
+
+    /// Sets the frame time above which a frame is reported as a stall.
+    ///
+    /// A stalled frame emits a warning and is recorded in the [`Statistics`], see
+    /// [`Statistics::recent_stalls`]. Time spent waiting for the [`FpsLimit`] does not count
+    /// towards the frame time. `None` disables stall detection, which is the default.
+    pub fn set_stall_threshold(&mut self, threshold: Option<Duration>) {
+        self.jobs.push_back(Job::SetStallThreshold(threshold));
+    }
 }
 
 impl Drop for Renderer {
@@ -297,6 +427,7 @@ impl Drop for Renderer {
 enum SurfaceEvent {
     Create(WindowId, WindowState),
     Resize(WindowId, UVec2),
+    SetFormat(WindowId, TextureFormat),
     Destroy(WindowId),
 }
 
@@ -304,16 +435,46 @@ enum SurfaceEvent {
 enum Job {
     TextureToBuffer(RenderImageId, tokio::sync::oneshot::Sender<Vec<u8>>),
     SetFpsLimit(FpsLimit),
+    SetStallThreshold(Option<Duration>),
 }
 
+impl Job {
+    /// Returns `true` if the result of this `Job` is no longer awaited.
+    fn is_cancelled(&self) -> bool {
+        match self {
+            Self::TextureToBuffer(_, tx) => tx.is_closed(),
+            Self::SetFpsLimit(_) => false,
+            Self::SetStallThreshold(_) => false,
+        }
+    }
+}
+
+/// A pending readback of a render texture.
+///
+/// Resolves to the raw bytes of the texture or `None` if the texture was destroyed before it was
+/// read.
+///
+/// Dropping the `ReadTexture` cancels the readback. A readback that was not yet submitted to the
+/// render thread is removed without ever being executed. A readback that is already in flight will
+/// still complete, but its result is discarded.
 pub struct ReadTexture {
     rx: oneshot::Receiver<Vec<u8>>,
 }
 
+impl ReadTexture {
+    /// Cancels the readback.
+    ///
+    /// This is equivalent to dropping the `ReadTexture`.
+    #[inline]
+    pub fn cancel(self) {
+        drop(self);
+    }
+}
+
 impl Future for ReadTexture {
-    type Output = Vec<u8>;
+    type Output = Option<Vec<u8>>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        self.get_mut().rx.poll(cx).map(|res| res.unwrap())
+        self.get_mut().rx.poll(cx).map(Result::ok)
     }
 }
@@ -1,3 +1,4 @@
+use game_common::math::compute_aabb;
 use game_tracing::trace_span;
 use glam::{Vec2, Vec3, Vec4};
 use mikktspace::Geometry;
@@ -14,6 +15,7 @@ pub struct Mesh {
     uvs: Vec<Vec2>,
     tangents: Vec<Vec4>,
     tangents_set: bool,
+    aabb: Option<Aabb>,
 }
 
 impl Mesh {
@@ -25,6 +27,7 @@ impl Mesh {
             uvs: vec![],
             tangents: vec![],
             tangents_set: false,
+            aabb: None,
         }
     }
 
@@ -34,6 +37,7 @@ impl Mesh {
 
     pub fn set_positions(&mut self, positions: Vec<Vec3>) {
         self.positions = positions;
+        self.aabb = None;
     }
 
     pub fn positions(&self) -> &[Vec3] {
@@ -94,20 +98,35 @@ impl Mesh {
         self.tangents_set
     }
 
+    /// Sets the precomputed `(min, max)` bounding box of the positions.
+    ///
+    /// The bounding box is reset when the positions are changed.
+    pub fn set_aabb(&mut self, (min, max): (Vec3, Vec3)) {
+        // An empty bounding box means that the mesh has no positions.
+        self.aabb = if min.cmple(max).all() {
+            Some(Aabb::from_min_max(min, max))
+        } else {
+            None
+        };
+    }
+
+    /// Returns the [`Aabb`] of the mesh.
+    ///
+    /// Uses the bounding box set by [`set_aabb`] if any, otherwise it is computed from the
+    /// positions. Returns `None` if the mesh has no positions.
+    ///
+    /// [`set_aabb`]: Self::set_aabb
     pub fn compute_aabb(&self) -> Option<Aabb> {
+        if let Some(aabb) = self.aabb {
+            return Some(aabb);
+        }
+
         // We need at least one vertex to determine an AABB.
         if self.positions.is_empty() {
             return None;
         }
 
-        let mut min = Vec3::splat(f32::MAX);
-        let mut max = Vec3::splat(f32::MIN);
-
-        for pos in &self.positions {
-            min = Vec3::min(min, *pos);
-            max = Vec3::max(max, *pos);
-        }
-
+        let (min, max) = compute_aabb(&self.positions);
         Some(Aabb::from_min_max(min, max))
     }
 }
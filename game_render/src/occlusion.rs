@@ -0,0 +1,300 @@
+//! GPU occlusion culling.
+//!
+//! After all objects are drawn the world-space bounding boxes of all objects are rendered
+//! against the depth buffer with an occlusion query per object. The query results are read back
+//! asynchronously and objects whose bounding box had no visible samples are skipped in the next
+//! frames.
+//!
+//! Since the results are always at least one frame old, the bounding boxes of skipped objects
+//! are still tested every time new queries are issued. This re-tests the visibility of the
+//! previous frames with the current camera and depth buffer, causing objects to reappear as
+//! soon as they become visible.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use parking_lot::Mutex;
+use wgpu::{
+    Adapter, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, Buffer,
+    BufferAddress, BufferBindingType, BufferDescriptor, BufferUsages, ColorTargetState,
+    ColorWrites, CommandEncoder, CompareFunction, DepthBiasState, DepthStencilState, Device,
+    DownlevelFlags, FragmentState, FrontFace, MapMode, MultisampleState, PipelineLayoutDescriptor,
+    PolygonMode, PrimitiveState, PrimitiveTopology, PushConstantRange, QuerySet,
+    QuerySetDescriptor, QueryType, RenderPipeline, RenderPipelineDescriptor,
+    ShaderModuleDescriptor, ShaderSource, ShaderStages, StencilState, TextureFormat, VertexState,
+};
+
+use crate::aabb::Aabb;
+use crate::depth_stencil::DEPTH_TEXTURE_FORMAT;
+use crate::entities::ObjectId;
+
+/// The size of a single occlusion query result, a `u64`.
+const QUERY_SIZE: BufferAddress = 8;
+
+/// Returns `true` if the `adapter` supports reading back occlusion queries without stalling.
+pub(crate) fn is_supported(adapter: &Adapter) -> bool {
+    adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(DownlevelFlags::NONBLOCKING_QUERY_RESOLVE)
+}
+
+/// The pipeline rendering the bounding boxes of objects.
+#[derive(Debug)]
+pub(crate) struct OcclusionPipeline {
+    pub pipeline: RenderPipeline,
+    pub bounds_bind_group_layout: BindGroupLayout,
+}
+
+impl OcclusionPipeline {
+    pub(crate) fn new(device: &Device) -> Self {
+        let bounds_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("occlusion_bounds_bind_group_layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("occlusion"),
+            source: ShaderSource::Wgsl(include_str!("../shaders/occlusion.wgsl").into()),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("occlusion_pipeline_layout"),
+            bind_group_layouts: &[&bounds_bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: ShaderStages::VERTEX,
+                range: 0..80,
+            }],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("occlusion_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(ColorTargetState {
+                    format: TextureFormat::Rgba16Float,
+                    blend: None,
+                    write_mask: ColorWrites::empty(),
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                // The camera may be located inside of a bounding box.
+                cull_mode: None,
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_TEXTURE_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::LessEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            bounds_bind_group_layout,
+        }
+    }
+}
+
+/// The world-space bounding box of an object as laid out in `occlusion.wgsl`.
+#[derive(Copy, Clone, Debug, Zeroable, Pod)]
+#[repr(C)]
+pub(crate) struct BoundsUniform {
+    min: [f32; 4],
+    max: [f32; 4],
+}
+
+impl From<Aabb> for BoundsUniform {
+    fn from(aabb: Aabb) -> Self {
+        Self {
+            min: aabb.min().extend(0.0).to_array(),
+            max: aabb.max().extend(0.0).to_array(),
+        }
+    }
+}
+
+/// The occlusion queries of a single render target.
+#[derive(Debug)]
+pub(crate) struct OcclusionQueries {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    capacity: u32,
+    /// The objects of the in-flight queries, in query order.
+    in_flight: Vec<ObjectId>,
+    readback: Arc<Mutex<Readback>>,
+    /// The objects that were occluded in the most recent results.
+    occluded: HashSet<ObjectId>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Readback {
+    /// No queries are in flight, new queries can be issued.
+    Idle,
+    /// The queries were recorded, but not yet submitted.
+    Recorded,
+    /// The queries were submitted and the readback buffer is being mapped.
+    Mapping,
+    /// The readback buffer is mapped and contains the results.
+    Ready,
+}
+
+impl OcclusionQueries {
+    pub(crate) fn new(device: &Device, capacity: u32) -> Self {
+        // Creating a `QuerySet` with 0 queries is not allowed.
+        let capacity = capacity.max(1);
+
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: Some("occlusion_query_set"),
+            ty: QueryType::Occlusion,
+            count: capacity,
+        });
+
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("occlusion_resolve_buffer"),
+            size: QUERY_SIZE * BufferAddress::from(capacity),
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("occlusion_readback_buffer"),
+            size: QUERY_SIZE * BufferAddress::from(capacity),
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            capacity,
+            in_flight: Vec::new(),
+            readback: Arc::new(Mutex::new(Readback::Idle)),
+            occluded: HashSet::new(),
+        }
+    }
+
+    /// Advances the readback of the queries recorded in previous frames.
+    ///
+    /// This must be called once per frame before any new queries are recorded. The command
+    /// buffer of the previous frame must already be submitted.
+    pub(crate) fn update(&mut self) {
+        let state = *self.readback.lock();
+        match state {
+            Readback::Idle | Readback::Mapping => (),
+            Readback::Recorded => {
+                *self.readback.lock() = Readback::Mapping;
+
+                let readback = self.readback.clone();
+                let size = QUERY_SIZE * self.in_flight.len() as BufferAddress;
+                self.readback_buffer
+                    .slice(..size)
+                    .map_async(MapMode::Read, move |res| {
+                        *readback.lock() = match res {
+                            Ok(()) => Readback::Ready,
+                            Err(err) => {
+                                tracing::error!("failed to read occlusion queries: {}", err);
+                                Readback::Idle
+                            }
+                        };
+                    });
+            }
+            Readback::Ready => {
+                self.occluded.clear();
+
+                {
+                    let size = QUERY_SIZE * self.in_flight.len() as BufferAddress;
+                    let data = self.readback_buffer.slice(..size).get_mapped_range();
+                    let samples: &[u64] = bytemuck::cast_slice(&data);
+
+                    for (id, samples) in self.in_flight.iter().zip(samples) {
+                        if *samples == 0 {
+                            self.occluded.insert(*id);
+                        }
+                    }
+                }
+
+                self.readback_buffer.unmap();
+                self.in_flight.clear();
+                *self.readback.lock() = Readback::Idle;
+            }
+        }
+    }
+
+    /// Returns `true` if new queries can be recorded in this frame.
+    pub(crate) fn is_idle(&self) -> bool {
+        *self.readback.lock() == Readback::Idle
+    }
+
+    /// Returns `true` if the object was occluded in the most recent results.
+    pub(crate) fn is_occluded(&self, id: ObjectId) -> bool {
+        self.occluded.contains(&id)
+    }
+
+    /// Returns the maximum number of queries that can be recorded in a single frame.
+    pub(crate) fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub(crate) fn query_set(&self) -> &QuerySet {
+        &self.query_set
+    }
+
+    /// Records the resolve and readback of the queries for the given `objects`.
+    ///
+    /// Query `i` must be the query of `objects[i]`.
+    pub(crate) fn resolve(&mut self, encoder: &mut CommandEncoder, objects: Vec<ObjectId>) {
+        debug_assert!(self.is_idle());
+        debug_assert!(objects.len() <= self.capacity as usize);
+
+        if objects.is_empty() {
+            self.occluded.clear();
+            return;
+        }
+
+        let count = objects.len() as u32;
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            QUERY_SIZE * BufferAddress::from(count),
+        );
+
+        self.in_flight = objects;
+        *self.readback.lock() = Readback::Recorded;
+    }
+}
@@ -1,8 +1,57 @@
 use bytemuck::{Pod, Zeroable};
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct MainPassOptions {
     pub shading: ShadingMode,
+    /// Skip objects that are hidden behind other objects using GPU occlusion queries.
+    ///
+    /// Occlusion culling is only performed if the adapter supports it, otherwise this option
+    /// is ignored. Since the visibility of objects is only known after a frame has been
+    /// rendered, objects may appear up to a few frames late when they become visible.
+    pub occlusion_culling: bool,
+    /// The number of samples used for anisotropic filtering of material textures.
+    ///
+    /// Valid values are `1`, `2`, `4`, `8` and `16`, where `1` disables anisotropic filtering.
+    /// Other values are rounded down to the next valid value. If the adapter does not support
+    /// the requested level the highest supported level is used instead.
+    pub anisotropy: u8,
+}
+
+impl Default for MainPassOptions {
+    fn default() -> Self {
+        Self {
+            shading: ShadingMode::default(),
+            occlusion_culling: false,
+            anisotropy: 1,
+        }
+    }
+}
+
+/// The highest level of anisotropic filtering supported by wgpu.
+pub(crate) const MAX_ANISOTROPY: u16 = 16;
+
+/// Returns the anisotropy clamp value for samplers with the requested `anisotropy` level.
+///
+/// `max` is the highest level supported by the adapter.
+pub(crate) fn anisotropy_clamp(anisotropy: u8, max: u16) -> u16 {
+    // Round down to the next power of two.
+    let requested: u16 = match anisotropy {
+        0 => 1,
+        n => 1 << n.ilog2(),
+    };
+    let requested = requested.min(MAX_ANISOTROPY);
+
+    if requested > max {
+        tracing::warn!(
+            "anisotropic filtering with {} samples is not supported by the adapter, falling back to {}",
+            requested,
+            max,
+        );
+
+        max
+    } else {
+        requested
+    }
 }
 
 /// The shading mode of the main pipeline.
@@ -39,3 +88,29 @@ impl MainPassOptionsEncoded {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::anisotropy_clamp;
+
+    #[test]
+    fn anisotropy_clamp_valid() {
+        for level in [1, 2, 4, 8, 16] {
+            assert_eq!(anisotropy_clamp(level, 16), u16::from(level));
+        }
+    }
+
+    #[test]
+    fn anisotropy_clamp_round_down() {
+        assert_eq!(anisotropy_clamp(0, 16), 1);
+        assert_eq!(anisotropy_clamp(3, 16), 2);
+        assert_eq!(anisotropy_clamp(15, 16), 8);
+        assert_eq!(anisotropy_clamp(u8::MAX, 16), 16);
+    }
+
+    #[test]
+    fn anisotropy_clamp_unsupported() {
+        assert_eq!(anisotropy_clamp(16, 1), 1);
+        assert_eq!(anisotropy_clamp(8, 4), 4);
+    }
+}
@@ -0,0 +1,152 @@
+//! # Custom render passes
+//!
+//! Crates outside of the renderer can inject their own passes (e.g. post-processing or debug
+//! overlays) into the render graph by implementing [`RenderPass`] and registering it with
+//! [`Renderer::add_pass`]. Unlike a raw [`Node`] a [`RenderPass`] does not need to know about
+//! slots, all resources that are commonly needed are provided by the [`PassContext`].
+//!
+//! # Resource access
+//!
+//! A [`RenderPass`] is executed on the render thread once for every render target in every frame.
+//! The renderer guarantees that no frame is in progress while the render graph is modified, but
+//! passes themselves may be called concurrently with the main thread. Any state shared with the
+//! main thread must therefore be synchronized by the pass.
+//!
+//! All passes registered with [`Renderer::add_pass`] run after the main pass. The resources
+//! provided by the [`PassContext`] follow these rules:
+//!
+//! - The command encoder is shared by all passes of the frame. Commands recorded by a pass are
+//!   submitted together with all other passes at the end of the frame, in the order the passes
+//!   were scheduled. A pass must not submit command buffers that depend on the recorded commands
+//!   itself.
+//! - The color [`target`] contains the image of all previously scheduled passes. A pass drawing
+//!   to it should use [`LoadOp::Load`] to retain the existing contents.
+//! - The [`depth`] buffer contains the depth of the main pass of the current frame. It is
+//!   read-only: it may be sampled or used as depth attachment with depth writes disabled, but
+//!   must never be written to, since other passes rely on its contents.
+//!
+//! [`Renderer::add_pass`]: crate::Renderer::add_pass
+//! [`target`]: PassContext::target
+//! [`depth`]: PassContext::depth
+//! [`LoadOp::Load`]: wgpu::LoadOp::Load
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use glam::UVec2;
+use parking_lot::Mutex;
+use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
+
+use crate::camera::RenderTarget;
+use crate::depth_stencil::{DepthData, DEPTH_TEXTURE_FORMAT};
+use crate::graph::{Node, RenderContext};
+
+/// The [`TextureFormat`] of the depth buffer provided by [`PassContext::depth`].
+pub const DEPTH_FORMAT: TextureFormat = DEPTH_TEXTURE_FORMAT;
+
+/// A render pass that can be registered with [`Renderer::add_pass`].
+///
+/// Refer to the [module documentation](self) for more details.
+///
+/// [`Renderer::add_pass`]: crate::Renderer::add_pass
+pub trait RenderPass: Send + Sync + 'static {
+    /// Records the commands of the pass for the render target of the given [`PassContext`].
+    fn render(&self, ctx: &mut PassContext<'_>);
+}
+
+/// Context provided to render a [`RenderPass`].
+pub struct PassContext<'a> {
+    render_target: RenderTarget,
+    encoder: &'a mut CommandEncoder,
+    target: &'a TextureView,
+    size: UVec2,
+    format: TextureFormat,
+    device: &'a Device,
+    queue: &'a Queue,
+    depth: Option<&'a TextureView>,
+}
+
+impl<'a> PassContext<'a> {
+    /// Returns the [`RenderTarget`] that is currently being rendered.
+    #[inline]
+    pub fn render_target(&self) -> RenderTarget {
+        self.render_target
+    }
+
+    /// Returns the [`CommandEncoder`] shared by all passes of the frame.
+    #[inline]
+    pub fn encoder(&mut self) -> &mut CommandEncoder {
+        self.encoder
+    }
+
+    /// Returns the final color target.
+    #[inline]
+    pub fn target(&self) -> &'a TextureView {
+        self.target
+    }
+
+    /// Returns the size of the color target and depth buffer in pixels.
+    #[inline]
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Returns the [`TextureFormat`] of the color target.
+    #[inline]
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Returns the [`Device`] of the renderer.
+    #[inline]
+    pub fn device(&self) -> &'a Device {
+        self.device
+    }
+
+    /// Returns the [`Queue`] used to submit the frame.
+    #[inline]
+    pub fn queue(&self) -> &'a Queue {
+        self.queue
+    }
+
+    /// Returns the depth buffer of the main pass in the [`DEPTH_FORMAT`].
+    ///
+    /// Returns `None` if the render target has no camera, in which case no depth was rendered.
+    #[inline]
+    pub fn depth(&self) -> Option<&'a TextureView> {
+        self.depth
+    }
+}
+
+/// The depth buffers of all render targets, written by the main pass.
+pub(crate) type DepthStencils = Arc<Mutex<HashMap<RenderTarget, DepthData>>>;
+
+/// Adapter to run a [`RenderPass`] as a [`Node`].
+pub(crate) struct PassNode<T> {
+    pub(crate) pass: T,
+    pub(crate) depth_stencils: DepthStencils,
+}
+
+impl<T> Node for PassNode<T>
+where
+    T: RenderPass,
+{
+    fn render(&self, ctx: &mut RenderContext<'_, '_>) {
+        let depth_stencils = self.depth_stencils.lock();
+
+        let mut ctx = PassContext {
+            render_target: ctx.render_target,
+            encoder: ctx.encoder,
+            target: ctx.target,
+            size: ctx.size,
+            format: ctx.format,
+            device: ctx.device,
+            queue: ctx.queue,
+            depth: depth_stencils
+                .get(&ctx.render_target)
+                .map(|depth| &depth.view),
+        };
+
+        self.pass.render(&mut ctx);
+    }
+}
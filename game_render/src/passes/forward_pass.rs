@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use game_tracing::trace_span;
-use glam::UVec2;
+use glam::{Mat4, UVec2};
 use parking_lot::Mutex;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::{
@@ -14,6 +14,7 @@ use wgpu::{
     TextureViewDescriptor,
 };
 
+use crate::aabb::Aabb;
 use crate::buffer::{DynamicBuffer, IndexBuffer};
 use crate::camera::{Camera, CameraUniform, RenderTarget};
 use crate::depth_stencil::DepthData;
@@ -22,12 +23,14 @@ use crate::entities::{
     CameraId, DirectionalLightId, Event, ImageId, MaterialId, MeshId, ObjectId, PointLightId,
     Resources, SceneId, SpotLightId,
 };
-use crate::forward::ForwardPipeline;
+use crate::forward::{create_material_sampler, ForwardPipeline};
 use crate::graph::{Node, RenderContext, SlotLabel};
 use crate::light::pipeline::{DirectionalLightUniform, PointLightUniform, SpotLightUniform};
 use crate::mesh::{Indices, Mesh};
 use crate::mipmap::MipMapGenerator;
-use crate::options::{MainPassOptions, MainPassOptionsEncoded};
+use crate::occlusion::{BoundsUniform, OcclusionQueries};
+use crate::options::{anisotropy_clamp, MainPassOptions, MainPassOptionsEncoded};
+use crate::pass::DepthStencils;
 use crate::pbr::material::MaterialConstants;
 use crate::pbr::mesh::TransformUniform;
 use crate::pbr::PbrMaterial;
@@ -36,7 +39,8 @@ use crate::texture::Image;
 pub(super) struct ForwardPass {
     pub state: Mutex<ForwardState>,
     pub forward: Arc<ForwardPipeline>,
-    pub depth_stencils: Mutex<HashMap<RenderTarget, DepthData>>,
+    pub depth_stencils: DepthStencils,
+    pub occlusion_queries: Mutex<HashMap<RenderTarget, OcclusionQueries>>,
     pub dst: SlotLabel,
 }
 
@@ -45,12 +49,14 @@ impl ForwardPass {
         device: &Device,
         queue: &Queue,
         forward: Arc<ForwardPipeline>,
+        depth_stencils: DepthStencils,
         dst: SlotLabel,
     ) -> Self {
         Self {
-            state: Mutex::new(ForwardState::new(device, queue)),
+            state: Mutex::new(ForwardState::new(device, queue, &forward)),
             forward,
-            depth_stencils: Mutex::default(),
+            depth_stencils,
+            occlusion_queries: Mutex::default(),
             dst,
         }
     }
@@ -69,7 +75,7 @@ impl Node for ForwardPass {
                 &self.forward.mesh_bind_group_layout,
                 &self.forward.material_bind_group_layout,
                 &self.forward.vs_bind_group_layout,
-                &self.forward.sampler,
+                self.forward.max_anisotropy,
                 ctx.mipmap,
             );
         }
@@ -84,6 +90,10 @@ impl Node for ForwardPass {
             }
         }
 
+        // Without a camera no depth is rendered. Drop the depth of
+        // previous frames so that other passes don't observe it.
+        self.depth_stencils.lock().remove(&ctx.render_target);
+
         // Some APIs don't play nicely when not submitting any work
         // for the surface, so we just clear the surface color.
         clear_pass(ctx, self.dst);
@@ -155,6 +165,78 @@ impl ForwardPass {
         });
         let target_view = render_target.create_view(&TextureViewDescriptor::default());
 
+        let mut occlusion_queries = self.occlusion_queries.lock();
+        let mut occlusion = match (&pipeline.occlusion, state.options.occlusion_culling) {
+            (Some(occlusion_pipeline), true) => {
+                let queries = occlusion_queries
+                    .entry(ctx.render_target)
+                    .or_insert_with(|| OcclusionQueries::new(device, scene.objects.len() as u32));
+                queries.update();
+
+                // The queries can only be replaced while no results are pending.
+                let num_objects = scene.objects.len() as u32;
+                if queries.is_idle() && queries.capacity() < num_objects {
+                    *queries = OcclusionQueries::new(device, num_objects.next_power_of_two());
+                }
+
+                Some((occlusion_pipeline, queries))
+            }
+            _ => {
+                occlusion_queries.remove(&ctx.render_target);
+                None
+            }
+        };
+
+        // Only issue new queries once the results of the previous queries
+        // have been read back.
+        let occlusion_pass = match &occlusion {
+            Some((occlusion_pipeline, queries)) if queries.is_idle() => {
+                let mut queried = Vec::new();
+                let mut bounds = Vec::new();
+                for id in scene.objects.iter() {
+                    if queried.len() == queries.capacity() as usize {
+                        break;
+                    }
+
+                    let (_, _, _, aabb) = state.objects.get(id).unwrap();
+
+                    // Objects without bounds are always drawn. If the camera
+                    // is inside the bounds the object is always visible.
+                    let Some(aabb) = aabb else {
+                        continue;
+                    };
+                    if aabb.contains(camera.transform.translation) {
+                        continue;
+                    }
+
+                    queried.push(*id);
+                    bounds.push(BoundsUniform::from(*aabb));
+                }
+
+                if bounds.is_empty() {
+                    None
+                } else {
+                    let bounds_buffer = device.create_buffer_init(&BufferInitDescriptor {
+                        label: Some("occlusion_bounds_buffer"),
+                        contents: bytemuck::cast_slice(&bounds),
+                        usage: BufferUsages::STORAGE,
+                    });
+
+                    let bounds_bind_group = device.create_bind_group(&BindGroupDescriptor {
+                        label: Some("occlusion_bounds_bind_group"),
+                        layout: &occlusion_pipeline.bounds_bind_group_layout,
+                        entries: &[BindGroupEntry {
+                            binding: 0,
+                            resource: bounds_buffer.as_entire_binding(),
+                        }],
+                    });
+
+                    Some((queried, bounds_bind_group))
+                }
+            }
+            _ => None,
+        };
+
         let mut render_pass = ctx.encoder.begin_render_pass(&RenderPassDescriptor {
             label: Some("render_pass"),
             color_attachments: &[Some(RenderPassColorAttachment {
@@ -174,7 +256,10 @@ impl ForwardPass {
                 stencil_ops: None,
             }),
             timestamp_writes: None,
-            occlusion_query_set: None,
+            occlusion_query_set: match (&occlusion, &occlusion_pass) {
+                (Some((_, queries)), Some(_)) => Some(queries.query_set()),
+                _ => None,
+            },
         });
 
         let mut push_constants = [0; 84];
@@ -186,7 +271,10 @@ impl ForwardPass {
             &state.options,
         )));
 
-        render_pass.set_pipeline(&pipeline.pipeline);
+        // The pipeline is switched whenever the material changes between
+        // single- and double-sided. All pipelines share the same layout.
+        let mut double_sided = false;
+        render_pass.set_pipeline(pipeline.pipeline(double_sided));
         render_pass.set_push_constants(
             ShaderStages::VERTEX | ShaderStages::FRAGMENT,
             0,
@@ -194,10 +282,25 @@ impl ForwardPass {
         );
 
         for id in scene.objects.iter() {
-            let (mesh, material, transform_bg) = state.objects.get(id).unwrap();
+            let (mesh, material, transform_bg, aabb) = state.objects.get(id).unwrap();
+
+            if let Some((_, queries)) = &occlusion {
+                let camera_inside =
+                    aabb.is_some_and(|aabb| aabb.contains(camera.transform.translation));
+
+                if queries.is_occluded(*id) && !camera_inside {
+                    ctx.statistics.occluded_objects += 1;
+                    continue;
+                }
+            }
+
+            let (mesh_bg, index_buffer, _) = state.meshes.get(mesh).unwrap();
+            let (material_bg, material_double_sided) = state.materials.get(material).unwrap();
 
-            let (mesh_bg, index_buffer) = state.meshes.get(mesh).unwrap();
-            let material_bg = state.materials.get(material).unwrap();
+            if *material_double_sided != double_sided {
+                double_sided = *material_double_sided;
+                render_pass.set_pipeline(pipeline.pipeline(double_sided));
+            }
 
             render_pass.set_bind_group(0, transform_bg, &[]);
             render_pass.set_bind_group(1, mesh_bg, &[]);
@@ -206,9 +309,31 @@ impl ForwardPass {
 
             render_pass.set_index_buffer(index_buffer.buffer.slice(..), index_buffer.format);
             render_pass.draw_indexed(0..index_buffer.len, 0, 0..1);
+
+            ctx.statistics.draw_calls += 1;
+            ctx.statistics.triangles += u64::from(index_buffer.len / 3);
+        }
+
+        if let (Some((occlusion_pipeline, _)), Some((queried, bounds_bind_group))) =
+            (&occlusion, &occlusion_pass)
+        {
+            render_pass.set_pipeline(&occlusion_pipeline.pipeline);
+            render_pass.set_push_constants(ShaderStages::VERTEX, 0, &push_constants[0..80]);
+            render_pass.set_bind_group(0, bounds_bind_group, &[]);
+
+            for index in 0..queried.len() as u32 {
+                render_pass.begin_occlusion_query(index);
+                render_pass.draw(0..36, index..index + 1);
+                render_pass.end_occlusion_query();
+            }
         }
 
         drop(render_pass);
+
+        if let (Some((_, queries)), Some((queried, _))) = (&mut occlusion, occlusion_pass) {
+            queries.resolve(ctx.encoder, queried);
+        }
+
         ctx.write(self.dst, render_target).unwrap();
     }
 }
@@ -317,12 +442,16 @@ impl DefaultTextures {
 struct ForwardState {
     default_textures: DefaultTextures,
 
-    meshes: HashMap<MeshId, (BindGroup, IndexBuffer)>,
+    meshes: HashMap<MeshId, (BindGroup, IndexBuffer, Option<Aabb>)>,
     images: HashMap<ImageId, Texture>,
-    materials: HashMap<MaterialId, BindGroup>,
+    /// The material bind groups and whether the material is double-sided.
+    materials: HashMap<MaterialId, (BindGroup, bool)>,
+    /// The sampler used for all material textures.
+    material_sampler: Sampler,
 
     cameras: HashMap<CameraId, Camera>,
-    objects: HashMap<ObjectId, (MeshId, MaterialId, BindGroup)>,
+    /// The objects with their world-space bounds.
+    objects: HashMap<ObjectId, (MeshId, MaterialId, BindGroup, Option<Aabb>)>,
 
     scenes: HashMap<SceneId, Scene>,
     options: MainPassOptions,
@@ -383,16 +512,20 @@ impl Scene {
 }
 
 impl ForwardState {
-    fn new(device: &Device, queue: &Queue) -> Self {
+    fn new(device: &Device, queue: &Queue, forward: &ForwardPipeline) -> Self {
+        let options = MainPassOptions::default();
+        let anisotropy = anisotropy_clamp(options.anisotropy, forward.max_anisotropy);
+
         Self {
             default_textures: DefaultTextures::new(device, queue),
             meshes: HashMap::new(),
             images: HashMap::new(),
             materials: HashMap::new(),
+            material_sampler: create_material_sampler(device, anisotropy),
             cameras: HashMap::new(),
             objects: HashMap::new(),
             scenes: HashMap::new(),
-            options: MainPassOptions::default(),
+            options,
         }
     }
 
@@ -405,7 +538,7 @@ impl ForwardState {
         mesh_bind_group_layout: &BindGroupLayout,
         material_bind_group_layout: &BindGroupLayout,
         object_bind_group_layout: &BindGroupLayout,
-        material_sampler: &Sampler,
+        max_anisotropy: u16,
         mipmap_generator: &mut MipMapGenerator,
     ) {
         let meshes = unsafe { resources.meshes.viewer() };
@@ -450,7 +583,7 @@ impl ForwardState {
 
                     self.materials.entry(object.material).or_insert_with(|| {
                         let material = materials.get(object.material.0).unwrap();
-                        create_material(
+                        let bind_group = create_material(
                             device,
                             queue,
                             mipmap_generator,
@@ -459,8 +592,10 @@ impl ForwardState {
                             &mut self.images,
                             &images,
                             material,
-                            material_sampler,
-                        )
+                            &self.material_sampler,
+                        );
+
+                        (bind_group, material.double_sided)
                     });
 
                     let transform_buffer = device.create_buffer_init(&BufferInitDescriptor {
@@ -478,8 +613,17 @@ impl ForwardState {
                         }],
                     });
 
+                    let (_, _, mesh_aabb) = self.meshes.get(&object.mesh).unwrap();
+                    let aabb = mesh_aabb.map(|aabb| {
+                        aabb.transform(Mat4::from_scale_rotation_translation(
+                            object.transform.scale,
+                            object.transform.rotation,
+                            object.transform.translation,
+                        ))
+                    });
+
                     self.objects
-                        .insert(id, (object.mesh, object.material, object_bind_group));
+                        .insert(id, (object.mesh, object.material, object_bind_group, aabb));
 
                     let scene = self
                         .scenes
@@ -659,6 +803,34 @@ impl ForwardState {
                     }
                 }
                 Event::UpdateMainPassOptions(options) => {
+                    if options.anisotropy != self.options.anisotropy {
+                        let anisotropy = anisotropy_clamp(options.anisotropy, max_anisotropy);
+                        self.material_sampler = create_material_sampler(device, anisotropy);
+
+                        // The sampler is part of the material bind groups, so all
+                        // materials must be recreated with the new sampler.
+                        let ids: Vec<_> = self.materials.keys().copied().collect();
+                        for id in ids {
+                            let Some(material) = materials.get(id.0) else {
+                                continue;
+                            };
+
+                            let bind_group = create_material(
+                                device,
+                                queue,
+                                mipmap_generator,
+                                material_bind_group_layout,
+                                &self.default_textures,
+                                &mut self.images,
+                                &images,
+                                material,
+                                &self.material_sampler,
+                            );
+                            self.materials
+                                .insert(id, (bind_group, material.double_sided));
+                        }
+                    }
+
                     self.options = options;
                 }
             }
@@ -674,7 +846,7 @@ fn upload_mesh(
     device: &Device,
     mesh: &Mesh,
     bind_group_layout: &BindGroupLayout,
-) -> (BindGroup, IndexBuffer) {
+) -> (BindGroup, IndexBuffer, Option<Aabb>) {
     let _span = trace_span!("upload_mesh").entered();
     // FIXME: Since meshes are user controlled, we might not catch invalid
     // meshes with a panic and simply ignore them.
@@ -761,7 +933,7 @@ fn upload_mesh(
         ],
     });
 
-    (bind_group, indices)
+    (bind_group, indices, mesh.compute_aabb())
 }
 
 fn create_material(
@@ -6,6 +6,7 @@ use wgpu::{Device, Queue};
 
 use crate::forward::ForwardPipeline;
 use crate::graph::{Node, NodeLabel, RenderGraph, SlotFlags, SlotKind, SlotLabel};
+use crate::pass::DepthStencils;
 
 pub mod forward_pass;
 pub mod post_process;
@@ -17,6 +18,9 @@ const SURFACE_INJECTOR: NodeLabel = NodeLabel::new("SURFACE_INJECTOR");
 const FORWARD_PASS: NodeLabel = NodeLabel::new("FORWARD_PASS");
 const POST_PROCESS_PASS: NodeLabel = NodeLabel::new("POST_PROCESS_PASS");
 
+/// The label of the node that renders the scene and writes the depth buffer.
+pub(crate) const MAIN_PASS: NodeLabel = FORWARD_PASS;
+
 /// The label of the node that finalizes the rendering process and writes the final image to the
 /// surface texture.
 pub const FINAL_RENDER_PASS: NodeLabel = POST_PROCESS_PASS;
@@ -24,10 +28,11 @@ pub const FINAL_RENDER_PASS: NodeLabel = POST_PROCESS_PASS;
 pub fn init(
     graph: &mut RenderGraph,
     forward: Arc<ForwardPipeline>,
+    depth_stencils: DepthStencils,
     device: &Device,
     queue: &Queue,
 ) {
-    let forward_pass = ForwardPass::new(device, queue, forward, HDR_TEXTURE);
+    let forward_pass = ForwardPass::new(device, queue, forward, depth_stencils, HDR_TEXTURE);
     let post_process = PostProcessPass::new(device, HDR_TEXTURE, SlotLabel::SURFACE);
 
     // `SurfaceInjector` is dummy node that only exists to
@@ -131,6 +131,27 @@ impl Node for PostProcessPass {
     }
 }
 
+/// Returns the fragment shader entry point that writes the correct color space for the output
+/// `format`.
+///
+/// - sRGB formats are converted to sRGB by the GPU when stored, so the shader outputs linear
+///   values.
+/// - Floating point formats (e.g. HDR surfaces) store linear values.
+/// - All other formats store the values as-is, so the shader performs the gamma correction.
+fn fragment_entry_point(format: TextureFormat) -> &'static str {
+    match format {
+        format if format.is_srgb() => "fs_main_srgb",
+        TextureFormat::R16Float
+        | TextureFormat::Rg16Float
+        | TextureFormat::Rgba16Float
+        | TextureFormat::R32Float
+        | TextureFormat::Rg32Float
+        | TextureFormat::Rgba32Float
+        | TextureFormat::Rg11b10Float => "fs_main_linear",
+        _ => "fs_main",
+    }
+}
+
 #[derive(Debug)]
 struct PostProcessPipelineBuilder {
     pipeline_layout: PipelineLayout,
@@ -151,7 +172,7 @@ impl PipelineBuilder for PostProcessPipelineBuilder {
             },
             fragment: Some(FragmentState {
                 module: &self.shader,
-                entry_point: "fs_main",
+                entry_point: fragment_entry_point(format),
                 targets: &[Some(ColorTargetState {
                     format,
                     blend: None,
@@ -173,3 +194,24 @@ impl PipelineBuilder for PostProcessPipelineBuilder {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat;
+
+    use super::fragment_entry_point;
+
+    #[test]
+    fn fragment_entry_point_color_space() {
+        assert_eq!(fragment_entry_point(TextureFormat::Bgra8Unorm), "fs_main");
+        assert_eq!(fragment_entry_point(TextureFormat::Rgb10a2Unorm), "fs_main");
+        assert_eq!(
+            fragment_entry_point(TextureFormat::Bgra8UnormSrgb),
+            "fs_main_srgb"
+        );
+        assert_eq!(
+            fragment_entry_point(TextureFormat::Rgba16Float),
+            "fs_main_linear"
+        );
+    }
+}
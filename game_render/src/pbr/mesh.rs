@@ -1,6 +1,6 @@
 use bytemuck::{Pod, Zeroable};
 use game_common::components::Transform;
-use glam::{Mat3, Mat4, Vec4};
+use glam::{Mat4, Vec4};
 use slotmap::{DefaultKey, SlotMap};
 
 use crate::mesh::Mesh;
@@ -9,7 +9,7 @@ use crate::mesh::Mesh;
 #[repr(C)]
 pub struct TransformUniform {
     transform: [[f32; 4]; 4],
-    // rotation matrix for normals/tangents
+    // Inverse transpose matrix for normals.
     // Note that we can't use the transform matrix for non-uniform
     // scaling values.
     normal: [[f32; 4]; 3],
@@ -17,7 +17,15 @@ pub struct TransformUniform {
 
 impl From<Transform> for TransformUniform {
     fn from(value: Transform) -> Self {
-        let normal = Mat3::from_quat(value.rotation);
+        let mut normal = value.normal_matrix();
+
+        // A mirrored transform flips the winding order, so the fragment shader
+        // considers the outside of the mesh to be back-facing and inverts the
+        // normal. Invert the normal here too so that both cancel out.
+        if value.is_mirrored() {
+            normal = -normal;
+        }
+
         let normal_x = Vec4::new(normal.x_axis.x, normal.x_axis.y, normal.x_axis.z, 0.0);
         let normal_y = Vec4::new(normal.y_axis.x, normal.y_axis.y, normal.y_axis.z, 0.0);
         let normal_z = Vec4::new(normal.z_axis.x, normal.z_axis.y, normal.z_axis.z, 0.0);
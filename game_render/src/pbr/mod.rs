@@ -25,6 +25,14 @@ pub struct PbrMaterial {
     ///
     /// Defaults to `0.5`, which corresponds to 0.04 in the shader.
     pub reflectance: f32,
+
+    /// Whether both sides of the faces are rendered.
+    ///
+    /// If `false` back faces are culled. Faces with a counter-clockwise winding order are
+    /// front-facing.
+    ///
+    /// Defaults to `false`.
+    pub double_sided: bool,
 }
 
 impl Default for PbrMaterial {
@@ -38,6 +46,7 @@ impl Default for PbrMaterial {
             metallic: 0.0,
             metallic_roughness_texture: None,
             reflectance: 0.5,
+            double_sided: false,
         }
     }
 }
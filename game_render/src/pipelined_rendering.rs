@@ -2,6 +2,8 @@ use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use game_common::cell::UnsafeRefCell;
 use game_tasks::park::Parker;
@@ -9,8 +11,8 @@ use game_tracing::trace_span;
 use glam::UVec2;
 use wgpu::{
     Adapter, BufferAddress, BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Device,
-    Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Instance, MapMode, Origin3d,
-    Queue, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+    Extent3d, ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Instance, Maintain, MapMode,
+    Origin3d, Queue, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
     TextureUsages, TextureViewDescriptor, COPY_BYTES_PER_ROW_ALIGNMENT,
 };
 
@@ -19,6 +21,7 @@ use crate::fps_limiter::{FpsLimit, FpsLimiter};
 use crate::graph::scheduler::RenderGraphScheduler;
 use crate::graph::{NodeLabel, RenderContext, RenderGraph, SlotLabel, SlotValueInner};
 use crate::mipmap::MipMapGenerator;
+use crate::statistics::{FrameStatistics, Statistics};
 use crate::surface::RenderSurfaces;
 use crate::texture::RenderImageId;
 use crate::Job;
@@ -41,7 +44,10 @@ pub struct SharedState {
     main_unparker: Arc<Parker>,
     pub jobs: UnsafeRefCell<VecDeque<Job>>,
     fps_limiter: UnsafeRefCell<FpsLimiter>,
+    /// Frames taking longer than this are recorded as stalls.
+    stall_threshold: UnsafeRefCell<Option<Duration>>,
     shutdown: AtomicBool,
+    pub statistics: Arc<Statistics>,
 }
 
 struct State {
@@ -54,6 +60,8 @@ pub struct Pipeline {
     main_parker: Arc<Parker>,
     /// Unparker for the render thread.
     render_unparker: Arc<Parker>,
+    /// The render thread, `None` once it was shut down.
+    render_thread: Option<JoinHandle<()>>,
     // While `Pipeline` is not directly thread-unsafe, we make no guarantees
     // whether atomic operations hold up when dispatching renders from multiple
     // threads.
@@ -78,14 +86,17 @@ impl Pipeline {
             render_textures: UnsafeRefCell::new(HashMap::new()),
             jobs: UnsafeRefCell::new(VecDeque::new()),
             fps_limiter: UnsafeRefCell::new(FpsLimiter::new(FpsLimit::UNLIMITED)),
+            stall_threshold: UnsafeRefCell::new(None),
             shutdown: AtomicBool::new(false),
+            statistics: Arc::new(Statistics::new()),
         });
 
-        let render_unparker = start_render_thread(shared.clone());
+        let (render_unparker, render_thread) = start_render_thread(shared.clone());
 
         Self {
             shared,
             render_unparker,
+            render_thread: Some(render_thread),
             main_parker,
             _marker: PhantomData,
         }
@@ -116,17 +127,39 @@ impl Pipeline {
         self.render_unparker.unpark();
     }
 
+    /// Stops the render thread and blocks until it has exited.
+    ///
+    /// A frame that is currently being rendered or was already dispatched is completed first.
+    /// Afterwards the render thread waits for the GPU to finish all submitted work and destroys
+    /// all surfaces and render resources it owns. Calling `shutdown` more than once has no
+    /// effect.
     pub fn shutdown(&mut self) {
+        let _span = trace_span!("Pipeline::shutdown").entered();
+
+        let Some(render_thread) = self.render_thread.take() else {
+            return;
+        };
+
         self.shared.shutdown.store(true, Ordering::Release);
         self.render_unparker.unpark();
+
+        if render_thread.join().is_err() {
+            tracing::error!("render thread panicked");
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
 
-fn start_render_thread(shared: Arc<SharedState>) -> Arc<Parker> {
+fn start_render_thread(shared: Arc<SharedState>) -> (Arc<Parker>, JoinHandle<()>) {
     let parker = Arc::new(Parker::new());
     let unparker = parker.clone();
 
-    std::thread::spawn(move || {
+    let handle = std::thread::spawn(move || {
         let _span = trace_span!("render_thread").entered();
 
         let mut state = State {
@@ -135,14 +168,22 @@ fn start_render_thread(shared: Arc<SharedState>) -> Arc<Parker> {
         };
 
         loop {
-            if state.shared.shutdown.load(Ordering::Acquire) {
-                return;
-            }
-
             // FIXME: If it is guaranteed that the parker will never yield
             // before being signaled, there is not need to watch for the atomic
             // to change.
             while state.shared.state.load(Ordering::Acquire) != PIPELINE_STATE_RENDERING {
+                // Only exit once no frame is dispatched, a frame that was
+                // dispatched before the shutdown is still rendered.
+                if state.shared.shutdown.load(Ordering::Acquire) {
+                    // SAFETY: The pipeline is idle and the main thread is
+                    // blocked until the render thread exits.
+                    unsafe {
+                        destroy_resources(&mut state);
+                    }
+
+                    return;
+                }
+
                 parker.park();
             }
 
@@ -160,16 +201,46 @@ fn start_render_thread(shared: Arc<SharedState>) -> Arc<Parker> {
         }
     });
 
-    unparker
+    (unparker, handle)
+}
+
+/// Destroys all resources owned by the render thread.
+///
+/// # Safety
+///
+/// The render thread must have full access to the state.
+unsafe fn destroy_resources(state: &mut State) {
+    let _span = trace_span!("destroy_resources").entered();
+
+    // Drop all jobs that were never executed. Pending readbacks
+    // resolve to `None`.
+    unsafe { state.shared.jobs.borrow_mut() }.clear();
+
+    // Wait for all submitted work to complete before destroying the
+    // resources that it may still be using.
+    state.shared.device.poll(Maintain::Wait);
+
+    // The graph holds the pipelines of all render passes.
+    *unsafe { state.shared.graph.borrow_mut() } = RenderGraph::default();
+    state.schedule.clear();
+
+    unsafe { state.shared.render_textures.borrow_mut() }.clear();
+
+    // Surfaces must be destroyed while the windows are still alive.
+    *unsafe { state.shared.surfaces.borrow_mut() } = RenderSurfaces::new();
 }
 
 unsafe fn execute_render(state: &mut State) {
     let _span = trace_span!("render_frame").entered();
 
+    let frame_start = Instant::now();
+    let mut statistics = FrameStatistics::default();
+
     let surfaces = unsafe { state.shared.surfaces.borrow() };
     let mut graph = unsafe { state.shared.graph.borrow_mut() };
     let mut mipmap = unsafe { state.shared.mipmap_generator.borrow_mut() };
     let mut fps_limiter = unsafe { state.shared.fps_limiter.borrow_mut() };
+    let mut stall_threshold = unsafe { state.shared.stall_threshold.borrow_mut() };
 
     let mut encoder = state
         .shared
@@ -205,8 +276,9 @@ unsafe fn execute_render(state: &mut State) {
             SlotValueInner::TextureRef(&output.texture),
         );
 
-        for node in &state.schedule {
-            let node = graph.get(*node).unwrap();
+        for label in &state.schedule {
+            let node = graph.get(*label).unwrap();
+            let pass_start = Instant::now();
 
             let mut ctx = RenderContext {
                 render_target: RenderTarget::Window(*window),
@@ -219,9 +291,11 @@ unsafe fn execute_render(state: &mut State) {
                 mipmap: &mut mipmap,
                 resources: &mut resources,
                 resource_permissions: &node.permissions,
+                statistics: &mut statistics,
             };
 
             node.node.render(&mut ctx);
+            statistics.record_pass(*label, pass_start.elapsed());
         }
 
         outputs.push((surface, output));
@@ -251,8 +325,9 @@ unsafe fn execute_render(state: &mut State) {
         let mut resources = HashMap::new();
         resources.insert(SlotLabel::SURFACE, SlotValueInner::TextureRef(texture));
 
-        for node in &state.schedule {
-            let node = graph.get(*node).unwrap();
+        for label in &state.schedule {
+            let node = graph.get(*label).unwrap();
+            let pass_start = Instant::now();
 
             let mut ctx = RenderContext {
                 render_target: RenderTarget::Image(*id),
@@ -265,9 +340,11 @@ unsafe fn execute_render(state: &mut State) {
                 mipmap: &mut mipmap,
                 resources: &mut resources,
                 resource_permissions: &node.permissions,
+                statistics: &mut statistics,
             };
 
             node.node.render(&mut ctx);
+            statistics.record_pass(*label, pass_start.elapsed());
         }
     }
 
@@ -279,8 +356,20 @@ unsafe fn execute_render(state: &mut State) {
             Job::SetFpsLimit(limit) => {
                 *fps_limiter = FpsLimiter::new(limit);
             }
+            Job::SetStallThreshold(threshold) => {
+                *stall_threshold = threshold;
+            }
             Job::TextureToBuffer(id, tx) => {
-                let texture = render_textures.get(&id).unwrap();
+                // The `ReadTexture` was dropped after the job was queued.
+                if tx.is_closed() {
+                    continue;
+                }
+
+                // The texture may have been destroyed after the job was queued.
+                // Dropping `tx` resolves the `ReadTexture` to `None`.
+                let Some(texture) = render_textures.get(&id) else {
+                    continue;
+                };
 
                 // bytes_per_row must be aligned as required by wgpu.
                 // 4 for RGBA8
@@ -328,13 +417,32 @@ unsafe fn execute_render(state: &mut State) {
 
     state.shared.queue.submit(std::iter::once(encoder.finish()));
 
+    let idle_start = Instant::now();
     fps_limiter.block_until_ready();
+    statistics.idle_time = idle_start.elapsed();
 
     for (surface, output) in outputs {
         surface.window().pre_present_notify();
         output.present();
     }
 
+    statistics.frame_time = frame_start.elapsed();
+    state.shared.statistics.push_frame(&statistics);
+
+    if let Some(stall) = stall_threshold.and_then(|threshold| statistics.stall(threshold)) {
+        match stall.dominant_pass {
+            Some(pass) => tracing::warn!(
+                "frame stalled for {:?} (dominant pass {:?} took {:?})",
+                stall.frame_time,
+                pass.label,
+                pass.time,
+            ),
+            None => tracing::warn!("frame stalled for {:?}", stall.frame_time),
+        }
+
+        state.shared.statistics.push_stall(stall);
+    }
+
     for (buffer, tx) in mapping_buffers {
         // Unfortunately we need to wrap `Buffer` in `Arc` to be able
         // to call `map_async` on the same value that takes a closure
@@ -0,0 +1,282 @@
+//! Per-frame rendering statistics.
+//!
+//! The render thread records the values of every frame into a [`Statistics`] instance shared with
+//! the [`Renderer`]. The values of a frame are only committed once the frame has been fully
+//! rendered, so a reader never observes a partially recorded frame.
+//!
+//! Frames that take longer than the threshold set with [`Renderer::set_stall_threshold`] are
+//! additionally recorded as a [`FrameStall`], see [`Statistics::recent_stalls`].
+//!
+//! [`Renderer`]: crate::Renderer
+//! [`Renderer::set_stall_threshold`]: crate::Renderer::set_stall_threshold
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::graph::NodeLabel;
+
+/// Rendering statistics accumulated by the render thread.
+///
+/// Use [`snapshot_and_reset`] to take the values accumulated since the last call.
+///
+/// [`snapshot_and_reset`]: Self::snapshot_and_reset
+#[derive(Debug, Default)]
+pub struct Statistics {
+    state: Mutex<StatisticsSnapshot>,
+    /// The most recent stalls, oldest first.
+    stalls: Mutex<VecDeque<FrameStall>>,
+}
+
+/// The maximum number of stalls kept by [`Statistics::recent_stalls`].
+pub const MAX_RECENT_STALLS: usize = 32;
+
+impl Statistics {
+    /// Creates a new, empty `Statistics`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns all values accumulated since the last call and resets them.
+    ///
+    /// Taking the snapshot and resetting happens atomically with respect to the render thread,
+    /// meaning no frame is lost or counted twice between two calls.
+    pub fn snapshot_and_reset(&self) -> StatisticsSnapshot {
+        core::mem::take(&mut *self.state.lock())
+    }
+
+    /// Returns the most recent frame stalls, oldest first.
+    ///
+    /// At most [`MAX_RECENT_STALLS`] stalls are kept. Unlike the other values the stalls are not
+    /// reset by [`snapshot_and_reset`].
+    ///
+    /// [`snapshot_and_reset`]: Self::snapshot_and_reset
+    pub fn recent_stalls(&self) -> Vec<FrameStall> {
+        self.stalls.lock().iter().copied().collect()
+    }
+
+    /// Commits the values recorded for a single frame.
+    pub(crate) fn push_frame(&self, frame: &FrameStatistics) {
+        let mut state = self.state.lock();
+        state.frames += 1;
+        state.frame_time += frame.frame_time;
+        state.draw_calls += frame.draw_calls;
+        state.triangles += frame.triangles;
+        state.occluded_objects += frame.occluded_objects;
+
+        for (label, time) in &frame.passes {
+            match state.passes.iter_mut().find(|pass| pass.label == *label) {
+                Some(pass) => pass.time += *time,
+                None => state.passes.push(PassTiming {
+                    label: *label,
+                    time: *time,
+                }),
+            }
+        }
+    }
+
+    /// Records a frame that exceeded the stall threshold.
+    pub(crate) fn push_stall(&self, stall: FrameStall) {
+        self.state.lock().stalls += 1;
+
+        let mut stalls = self.stalls.lock();
+        if stalls.len() == MAX_RECENT_STALLS {
+            stalls.pop_front();
+        }
+        stalls.push_back(stall);
+    }
+}
+
+/// A plain copy of the values accumulated in [`Statistics`].
+///
+/// If more than a single frame was rendered between two snapshots all values are the sum over
+/// all rendered frames. Use [`frames`] to get the number of frames contained.
+///
+/// [`frames`]: Self::frames
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatisticsSnapshot {
+    /// The number of frames rendered.
+    pub frames: u64,
+    /// The total time spent rendering frames.
+    pub frame_time: Duration,
+    /// The number of issued draw calls.
+    pub draw_calls: u64,
+    /// The number of submitted triangles.
+    pub triangles: u64,
+    /// The number of objects skipped by occlusion culling.
+    pub occluded_objects: u64,
+    /// The time spent in each render graph pass.
+    pub passes: Vec<PassTiming>,
+    /// The number of frames that exceeded the stall threshold.
+    pub stalls: u64,
+}
+
+impl StatisticsSnapshot {
+    /// Returns the average time spent rendering a single frame.
+    ///
+    /// Returns [`Duration::ZERO`] if no frames were rendered.
+    pub fn average_frame_time(&self) -> Duration {
+        match u32::try_from(self.frames) {
+            Ok(0) => Duration::ZERO,
+            Ok(frames) => self.frame_time / frames,
+            Err(_) => Duration::from_secs_f64(self.frame_time.as_secs_f64() / self.frames as f64),
+        }
+    }
+}
+
+/// The time spent in a single render graph pass.
+///
+/// Note that this is the time spent by the render thread recording commands for the pass, not
+/// the time spent executing the commands on the GPU.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PassTiming {
+    pub label: NodeLabel,
+    pub time: Duration,
+}
+
+/// A single frame that took longer than the stall threshold.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameStall {
+    /// The time spent rendering the frame, excluding the time waiting for the FPS limit.
+    pub frame_time: Duration,
+    /// The render graph pass that took the longest time in the frame.
+    ///
+    /// `None` if the frame did not run any passes. If the pass only took a small part of
+    /// `frame_time` the stall happened outside of the render graph, e.g. while waiting for the
+    /// next surface texture.
+    pub dominant_pass: Option<PassTiming>,
+}
+
+/// The values recorded while rendering a single frame.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct FrameStatistics {
+    pub frame_time: Duration,
+    /// The time spent waiting for the FPS limit. Included in `frame_time`.
+    pub idle_time: Duration,
+    pub draw_calls: u64,
+    pub triangles: u64,
+    pub occluded_objects: u64,
+    pub passes: Vec<(NodeLabel, Duration)>,
+}
+
+impl FrameStatistics {
+    pub fn record_pass(&mut self, label: NodeLabel, time: Duration) {
+        match self.passes.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, t)) => *t += time,
+            None => self.passes.push((label, time)),
+        }
+    }
+
+    /// Returns the [`FrameStall`] of this frame if it took longer than `threshold`.
+    pub fn stall(&self, threshold: Duration) -> Option<FrameStall> {
+        let frame_time = self.frame_time.saturating_sub(self.idle_time);
+        if frame_time <= threshold {
+            return None;
+        }
+
+        let dominant_pass =
+            self.passes
+                .iter()
+                .max_by_key(|(_, time)| *time)
+                .map(|(label, time)| PassTiming {
+                    label: *label,
+                    time: *time,
+                });
+
+        Some(FrameStall {
+            frame_time,
+            dominant_pass,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::graph::NodeLabel;
+
+    use super::{FrameStatistics, PassTiming, Statistics, MAX_RECENT_STALLS};
+
+    #[test]
+    fn statistics_snapshot_and_reset() {
+        let stats = Statistics::new();
+
+        let mut frame = FrameStatistics {
+            frame_time: Duration::from_millis(10),
+            draw_calls: 5,
+            triangles: 100,
+            occluded_objects: 3,
+            passes: Vec::new(),
+            ..Default::default()
+        };
+        frame.record_pass(NodeLabel::new("A"), Duration::from_millis(2));
+        frame.record_pass(NodeLabel::new("A"), Duration::from_millis(1));
+
+        stats.push_frame(&frame);
+        stats.push_frame(&frame);
+
+        let snapshot = stats.snapshot_and_reset();
+        assert_eq!(snapshot.frames, 2);
+        assert_eq!(snapshot.frame_time, Duration::from_millis(20));
+        assert_eq!(snapshot.average_frame_time(), Duration::from_millis(10));
+        assert_eq!(snapshot.draw_calls, 10);
+        assert_eq!(snapshot.triangles, 200);
+        assert_eq!(snapshot.occluded_objects, 6);
+        assert_eq!(snapshot.passes.len(), 1);
+        assert_eq!(snapshot.passes[0].time, Duration::from_millis(6));
+
+        let snapshot = stats.snapshot_and_reset();
+        assert_eq!(snapshot, Default::default());
+    }
+
+    #[test]
+    fn statistics_frame_stall() {
+        let mut frame = FrameStatistics {
+            frame_time: Duration::from_millis(50),
+            idle_time: Duration::from_millis(20),
+            ..Default::default()
+        };
+        frame.record_pass(NodeLabel::new("A"), Duration::from_millis(2));
+        frame.record_pass(NodeLabel::new("B"), Duration::from_millis(25));
+
+        // Waiting for the FPS limit is not a stall.
+        assert_eq!(frame.stall(Duration::from_millis(30)), None);
+
+        let stall = frame.stall(Duration::from_millis(20)).unwrap();
+        assert_eq!(stall.frame_time, Duration::from_millis(30));
+        assert_eq!(
+            stall.dominant_pass,
+            Some(PassTiming {
+                label: NodeLabel::new("B"),
+                time: Duration::from_millis(25),
+            })
+        );
+    }
+
+    #[test]
+    fn statistics_recent_stalls() {
+        let stats = Statistics::new();
+
+        for millis in 0..MAX_RECENT_STALLS as u64 + 2 {
+            let frame = FrameStatistics {
+                frame_time: Duration::from_millis(millis + 1),
+                ..Default::default()
+            };
+            stats.push_stall(frame.stall(Duration::ZERO).unwrap());
+        }
+
+        let stalls = stats.recent_stalls();
+        assert_eq!(stalls.len(), MAX_RECENT_STALLS);
+        assert_eq!(stalls[0].frame_time, Duration::from_millis(3));
+        assert_eq!(stalls[0].dominant_pass, None);
+
+        // Recent stalls are kept across snapshots.
+        assert_eq!(
+            stats.snapshot_and_reset().stalls,
+            MAX_RECENT_STALLS as u64 + 2
+        );
+        assert_eq!(stats.recent_stalls().len(), MAX_RECENT_STALLS);
+    }
+}
@@ -42,6 +42,31 @@ impl RenderSurfaces {
         resize_surface(surface, device, size);
     }
 
+    /// Reconfigures the surface of the window with the given `id` to use a new `format`.
+    ///
+    /// The format must be one of the [`supported_formats`] of the surface, otherwise the surface
+    /// is left unchanged.
+    ///
+    /// [`supported_formats`]: SurfaceData::supported_formats
+    pub fn set_format(&mut self, id: WindowId, device: &Device, format: TextureFormat) {
+        let Some(surface) = self.windows.get_mut(&id) else {
+            return;
+        };
+
+        if !surface.formats.contains(&format) {
+            tracing::error!(
+                "surface format {:?} is not supported by window {:?}, supported formats: {:?}",
+                format,
+                id,
+                surface.formats,
+            );
+            return;
+        }
+
+        surface.config.format = format;
+        surface.surface.configure(device, &surface.config);
+    }
+
     pub fn get(&self, id: WindowId) -> Option<&SurfaceData> {
         self.windows.get(&id)
     }
@@ -63,6 +88,8 @@ impl RenderSurfaces {
 pub struct SurfaceData {
     pub surface: Surface<'static>,
     pub config: SurfaceConfiguration,
+    /// The formats supported by the `surface`.
+    formats: Vec<TextureFormat>,
     /// A handle to the window underlying the `surface`.
     ///
     /// NOTE: The surface MUST be dropped before the handle to the window is dropped.
@@ -79,6 +106,12 @@ impl SurfaceData {
     pub fn window(&self) -> &WindowState {
         &self.window
     }
+
+    /// Returns all formats that the surface can be configured with.
+    #[inline]
+    pub fn supported_formats(&self) -> &[TextureFormat] {
+        &self.formats
+    }
 }
 
 fn create_surface(
@@ -138,6 +171,7 @@ fn create_surface(
     Ok(SurfaceData {
         surface,
         config,
+        formats: caps.formats,
         window,
     })
 }
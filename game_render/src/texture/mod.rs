@@ -7,6 +7,12 @@ use slotmap::{DefaultKey, SlotMap};
 
 pub use self::image::{Image, ImageFormat, TextureFormat};
 
+/// A handle to a [`RenderTexture`].
+///
+/// A `RenderImageId` contains the generation of the slot it refers to. Once the texture is
+/// destroyed the slot may be reused by a new texture with a new generation, so a stale
+/// `RenderImageId` never refers to a different texture. All accesses using a stale
+/// `RenderImageId` return `None`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct RenderImageId(DefaultKey);
 
@@ -39,6 +45,11 @@ impl RenderTextures {
     pub(crate) fn get(&self, id: RenderImageId) -> Option<&RenderTexture> {
         self.textures.get(id.0)
     }
+
+    /// Returns `true` if the texture referred to by `id` has not been destroyed.
+    pub fn contains(&self, id: RenderImageId) -> bool {
+        self.textures.contains_key(id.0)
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -50,3 +61,33 @@ pub(crate) enum RenderTextureEvent {
     Create(RenderImageId, RenderTexture),
     Destroy(RenderImageId),
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec2;
+
+    use super::{RenderTexture, RenderTextures};
+
+    #[test]
+    fn render_textures_stale_id() {
+        let mut textures = RenderTextures::new();
+
+        let old = textures.insert(RenderTexture {
+            size: UVec2::new(1, 1),
+        });
+        assert!(textures.remove(old).is_some());
+
+        // The slot of `old` is reused with a new generation.
+        let new = textures.insert(RenderTexture {
+            size: UVec2::new(2, 2),
+        });
+        assert_ne!(old, new);
+
+        assert!(!textures.contains(old));
+        assert!(textures.get(old).is_none());
+        assert!(textures.remove(old).is_none());
+
+        assert!(textures.contains(new));
+        assert_eq!(textures.get(new).unwrap().size, UVec2::new(2, 2));
+    }
+}
@@ -75,7 +75,7 @@ impl Harness {
         let (tx, mut rx) = oneshot::channel();
 
         std::thread::spawn(move || {
-            let data = futures_lite::future::block_on(fut);
+            let data = futures_lite::future::block_on(fut).unwrap();
 
             let mut buffer = ImageBuffer::new(size.x, size.y);
             assert_eq!(data.len() as u32, size.x * size.y * 4);
@@ -0,0 +1,63 @@
+use game_common::components::{Color, Transform};
+use game_common::math::RotationExt;
+use game_render::camera::{Camera, Projection};
+use game_render::entities::Object;
+use game_render::light::DirectionalLight;
+use game_render::mesh::Mesh;
+use game_render::pbr::PbrMaterial;
+use game_render::shape::Plane;
+use glam::{Quat, Vec3};
+
+use crate::Harness;
+
+/// Renders two planes from below, i.e. looking at their back faces.
+///
+/// The left plane uses a single-sided material and must be culled. The right plane uses a
+/// double-sided material and must be rendered.
+pub(super) fn double_sided() -> Harness {
+    Harness::new(stringify!(double_sided), |renderer, scene, target| {
+        renderer.resources().cameras().insert(Camera {
+            transform: Transform {
+                translation: Vec3::new(0.0, -1.0, 0.0),
+                rotation: Quat::TOP,
+                ..Default::default()
+            },
+            target,
+            projection: Projection {
+                aspect_ratio: 1.0,
+                fov: 90.0,
+                near: 0.1,
+                far: 1000.0,
+            },
+            scene,
+        });
+
+        let plane = Mesh::from(Plane { size: 0.8 });
+        let mesh = renderer.resources().meshes().insert(plane);
+
+        for (x, double_sided) in [(-0.5, false), (0.5, true)] {
+            let material = renderer.resources().materials().insert(PbrMaterial {
+                base_color: Color::WHITE,
+                double_sided,
+                ..Default::default()
+            });
+
+            renderer.resources().objects().insert(Object {
+                transform: Transform::from_translation(Vec3::new(x, 0.0, 0.0)),
+                mesh,
+                material,
+                scene,
+            });
+        }
+
+        renderer
+            .resources()
+            .directional_lights()
+            .insert(DirectionalLight {
+                transform: Transform::from_rotation(Quat::TOP),
+                color: Color::WHITE,
+                illuminance: 100_000.0,
+                scene,
+            });
+    })
+}
@@ -1,4 +1,5 @@
 mod directional_light;
+mod double_sided;
 mod point_light;
 mod spot_light;
 
@@ -7,6 +8,7 @@ use glam::UVec2;
 use crate::{load_sample, store_sample, Command};
 
 use self::directional_light::directional_light;
+use self::double_sided::double_sided;
 use self::point_light::point_light;
 use self::spot_light::spot_light;
 
@@ -21,6 +23,7 @@ pub(crate) fn run_tests(options: Options) {
     tests.push(directional_light());
     tests.push(point_light());
     tests.push(spot_light());
+    tests.push(double_sided());
 
     let mut result = TestResult {
         passed: 0,
@@ -279,9 +279,11 @@ pub fn from_slice(buf: &[u8]) -> Result<SceneRoot, Box<dyn std::error::Error>> {
 }
 
 fn convert_mesh(input: GltfMesh) -> Mesh {
+    let aabb = input.aabb();
     let mut mesh = Mesh::new();
 
     mesh.set_positions(input.positions);
+    mesh.set_aabb(aabb);
     mesh.set_normals(input.normals);
     mesh.set_uvs(input.uvs);
     mesh.set_indices(Indices::U32(input.indices));
@@ -317,6 +319,7 @@ fn create_material(
         roughness: material.roughness,
         metallic: material.metallic,
         metallic_roughness_texture,
+        double_sided: material.double_sided,
         ..Default::default()
     }
 }
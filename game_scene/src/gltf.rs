@@ -19,61 +19,50 @@ impl LoadScene for GltfData {
 
         let mut mesh_indices = HashMap::new();
         let mut material_indices = HashMap::new();
-
-        scene.nodes = self
-            .scenes
-            .iter()
-            .enumerate()
-            .filter_map(|(index, scene)| {
-                if Some(index) == self.default_scene {
-                    Some(scene)
-                } else {
-                    None
+        let mut image_indices = HashMap::new();
+
+        let index = self.default_scene_index().unwrap();
+        scene.nodes = self.scenes[index].nodes.convert(|node| {
+            // Mesh and material always come together as one or nothing.
+            // There is no such thing as a mesh without material.
+            if let (Some(mesh), Some(material)) = (node.mesh, node.material) {
+                let mesh_index = mesh_indices.entry(mesh).or_insert_with(|| {
+                    let mesh_index = scene.meshes.len();
+                    scene
+                        .meshes
+                        .insert(mesh_index, convert_mesh(self.meshes[&mesh].clone()));
+
+                    mesh_index
+                });
+
+                let material_index = material_indices.entry(material).or_insert_with(|| {
+                    let material_index = scene.materials.len();
+                    scene.materials.push(create_material(
+                        self.materials[&material],
+                        &mut scene.images,
+                        &mut image_indices,
+                        &mut self.images,
+                    ));
+                    material_index
+                });
+
+                Node {
+                    transform: node.transform,
+                    body: NodeBody::Object(ObjectNode {
+                        mesh: *mesh_index,
+                        material: *material_index,
+                    }),
                 }
-            })
-            .nth(0)
-            .unwrap()
-            .nodes
-            .convert(|node| {
-                // Mesh and material always come together as one or nothing.
-                // There is no such thing as a mesh without material.
-                if let (Some(mesh), Some(material)) = (node.mesh, node.material) {
-                    let mesh_index = mesh_indices.entry(mesh).or_insert_with(|| {
-                        let mesh_index = scene.meshes.len();
-                        scene
-                            .meshes
-                            .insert(mesh_index, convert_mesh(self.meshes[&mesh].clone()));
-
-                        mesh_index
-                    });
-
-                    let material_index = material_indices.entry(material).or_insert_with(|| {
-                        let material_index = scene.materials.len();
-                        scene.materials.push(create_material(
-                            self.materials[&material],
-                            &mut scene.images,
-                            &mut self.images,
-                        ));
-                        material_index
-                    });
-
-                    Node {
-                        transform: node.transform,
-                        body: NodeBody::Object(ObjectNode {
-                            mesh: *mesh_index,
-                            material: *material_index,
-                        }),
-                    }
-                } else {
-                    debug_assert!(node.mesh.is_none());
-                    debug_assert!(node.material.is_none());
-
-                    Node {
-                        transform: node.transform,
-                        body: NodeBody::Empty,
-                    }
+            } else {
+                debug_assert!(node.mesh.is_none());
+                debug_assert!(node.material.is_none());
+
+                Node {
+                    transform: node.transform,
+                    body: NodeBody::Empty,
                 }
-            });
+            }
+        });
 
         scene
     }
@@ -82,26 +71,22 @@ impl LoadScene for GltfData {
 fn create_material(
     material: GltfMaterial,
     images: &mut Vec<Image>,
+    image_indices: &mut HashMap<TextureIndex, usize>,
     gltf_images: &mut HashMap<TextureIndex, Image>,
 ) -> Material {
-    let base_color_texture = material.base_color_texture.map(|index| {
-        let img_index = images.len();
-        let img = gltf_images[&index].clone();
-        images.push(img);
-        img_index
-    });
-    let normal_texture = material.normal_texture.map(|index| {
-        let img_index = images.len();
-        let img = gltf_images[&index].clone();
-        images.push(img);
-        img_index
-    });
-    let metallic_roughness_texture = material.metallic_roughness_texture.map(|index| {
-        let img_index = images.len();
-        let img = gltf_images[&index].clone();
-        images.push(img);
-        img_index
-    });
+    // Maps the texture index local to the glTF file to the index in the
+    // scene. Textures used by multiple materials are only added once.
+    let mut map_texture = |index: TextureIndex| {
+        *image_indices.entry(index).or_insert_with(|| {
+            let img_index = images.len();
+            images.push(gltf_images[&index].clone());
+            img_index
+        })
+    };
+
+    let base_color_texture = material.base_color_texture.map(&mut map_texture);
+    let normal_texture = material.normal_texture.map(&mut map_texture);
+    let metallic_roughness_texture = material.metallic_roughness_texture.map(&mut map_texture);
 
     Material {
         alpha_mode: convert_alpha_mode(material.alpha_mode),
@@ -111,14 +96,17 @@ fn create_material(
         roughness: material.roughness,
         metallic: material.metallic,
         metallic_roughness_texture,
+        double_sided: material.double_sided,
         ..Default::default()
     }
 }
 
 fn convert_mesh(input: GltfMesh) -> Mesh {
+    let aabb = input.aabb();
     let mut mesh = Mesh::new();
 
     mesh.set_positions(input.positions);
+    mesh.set_aabb(aabb);
     mesh.set_normals(input.normals);
     mesh.set_uvs(input.uvs);
     mesh.set_indices(Indices::U32(input.indices));
@@ -2,6 +2,7 @@ pub mod debug;
 pub mod format;
 mod loader;
 mod model;
+pub mod resource;
 pub mod scene2;
 mod spawner;
 
@@ -17,6 +17,7 @@ impl LoadScene for Model {
         let mut scene = Scene::default();
 
         for mesh in self.meshes {
+            let aabb = mesh.aabb();
             let positions = self.buffers[mesh.positions as usize].as_positions();
             let normals = self.buffers[mesh.normals as usize].as_normals();
             let tangents = self.buffers[mesh.tangents as usize].as_tangents();
@@ -29,6 +30,7 @@ impl LoadScene for Model {
             mesh.set_tangents(tangents.to_vec());
             mesh.set_uvs(uvs.to_vec());
             mesh.set_indices(Indices::U32(indices.to_vec()));
+            mesh.set_aabb(aabb);
 
             scene.meshes.push(mesh);
         }
@@ -88,6 +90,7 @@ fn create_material(material: Material) -> crate::scene::Material {
         metallic_roughness_texture: metallic_roughness_texture,
         roughness: material.roughness as f32 / 255.0,
         metallic: material.metallic as f32 / 255.0,
+        double_sided: material.double_sided,
         ..Default::default()
     }
 }
@@ -0,0 +1,206 @@
+//! Resources shared between all scenes.
+
+use std::collections::HashMap;
+
+use game_render::texture::{Image, TextureFormat};
+use sha2::{Digest, Sha256};
+
+/// A global handle to a resource.
+///
+/// Unlike the per-file indices used by the scene formats, a `ResourceId` is unique across all
+/// loaded scenes. Resources with identical contents share the same `ResourceId`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResourceId(u64);
+
+/// A set of [`Image`]s that are deduplicated by their contents.
+///
+/// Every image is only uploaded once, no matter how many scenes reference it. The uploaded image
+/// is dropped once the last reference to it is removed.
+///
+/// Images are compared by a SHA-256 hash of their contents, so no copy of the image data is kept
+/// after it was uploaded.
+#[derive(Debug)]
+pub(crate) struct SharedImages<T> {
+    entries: HashMap<ResourceId, Entry<T>>,
+    /// Lookup table from the contents of an image to its [`ResourceId`].
+    keys: HashMap<ImageKey, ResourceId>,
+    next_id: u64,
+}
+
+impl<T> SharedImages<T>
+where
+    T: Copy,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            keys: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Inserts a new reference to `image` and returns its [`ResourceId`].
+    ///
+    /// If an identical image already exists a new reference to it is returned. Otherwise the image
+    /// is uploaded using `upload`.
+    pub(crate) fn insert_with<F>(&mut self, image: Image, upload: F) -> ResourceId
+    where
+        F: FnOnce(Image) -> T,
+    {
+        let key = ImageKey::new(&image);
+
+        if let Some(id) = self.keys.get(&key) {
+            self.entries.get_mut(id).unwrap().ref_count += 1;
+            return *id;
+        }
+
+        let id = ResourceId(self.next_id);
+        self.next_id += 1;
+
+        self.keys.insert(key, id);
+        self.entries.insert(
+            id,
+            Entry {
+                value: upload(image),
+                key,
+                ref_count: 1,
+            },
+        );
+
+        id
+    }
+
+    pub(crate) fn get(&self, id: ResourceId) -> Option<T> {
+        self.entries.get(&id).map(|entry| entry.value)
+    }
+
+    /// Removes a reference to the image with the given `id`.
+    ///
+    /// Returns the uploaded value if this was the last reference to the image.
+    pub(crate) fn remove(&mut self, id: ResourceId) -> Option<T> {
+        let entry = self.entries.get_mut(&id)?;
+        entry.ref_count -= 1;
+        if entry.ref_count != 0 {
+            return None;
+        }
+
+        let entry = self.entries.remove(&id).unwrap();
+        self.keys.remove(&entry.key);
+        Some(entry.value)
+    }
+}
+
+impl<T> Default for SharedImages<T>
+where
+    T: Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    value: T,
+    key: ImageKey,
+    ref_count: usize,
+}
+
+/// Identifies an [`Image`] by its contents.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct ImageKey {
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+    /// The SHA-256 hash of the image data.
+    hash: [u8; 32],
+}
+
+impl ImageKey {
+    fn new(image: &Image) -> Self {
+        Self {
+            format: image.format(),
+            width: image.width(),
+            height: image.height(),
+            hash: Sha256::digest(image.as_bytes()).into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use game_render::texture::{Image, TextureFormat};
+    use glam::UVec2;
+
+    use super::SharedImages;
+
+    fn image(color: [u8; 4]) -> Image {
+        Image::new(
+            UVec2::new(1, 1),
+            TextureFormat::Rgba8UnormSrgb,
+            color.to_vec(),
+        )
+    }
+
+    #[test]
+    fn shared_images_dedup() {
+        let mut images = SharedImages::new();
+        let mut uploads = 0;
+
+        let a = images.insert_with(image([255, 0, 0, 255]), |_| {
+            uploads += 1;
+            uploads
+        });
+        let b = images.insert_with(image([255, 0, 0, 255]), |_| {
+            uploads += 1;
+            uploads
+        });
+        let c = images.insert_with(image([0, 255, 0, 255]), |_| {
+            uploads += 1;
+            uploads
+        });
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(uploads, 2);
+        assert_eq!(images.get(a), Some(1));
+        assert_eq!(images.get(c), Some(2));
+    }
+
+    #[test]
+    fn shared_images_remove_last_reference() {
+        let mut images = SharedImages::new();
+
+        let a = images.insert_with(image([255, 0, 0, 255]), |_| 0);
+        let b = images.insert_with(image([255, 0, 0, 255]), |_| 1);
+        assert_eq!(a, b);
+
+        assert_eq!(images.remove(a), None);
+        assert_eq!(images.get(a), Some(0));
+
+        assert_eq!(images.remove(b), Some(0));
+        assert_eq!(images.get(a), None);
+
+        // A previously removed image is uploaded again.
+        let c = images.insert_with(image([255, 0, 0, 255]), |_| 2);
+        assert_ne!(a, c);
+        assert_eq!(images.get(c), Some(2));
+    }
+
+    #[test]
+    fn shared_images_same_bytes_different_format() {
+        let mut images = SharedImages::new();
+
+        let a = images.insert_with(image([255, 0, 0, 255]), |_| 0);
+        let b = images.insert_with(
+            Image::new(
+                UVec2::new(1, 1),
+                TextureFormat::Rgba8Unorm,
+                vec![255, 0, 0, 255],
+            ),
+            |_| 1,
+        );
+
+        assert_ne!(a, b);
+    }
+}
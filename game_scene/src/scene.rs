@@ -2,13 +2,14 @@ use std::collections::HashMap;
 
 use game_common::components::{Color, Transform};
 use game_core::hierarchy::Hierarchy;
-use game_render::entities::{Object, SceneId};
+use game_render::entities::{ImageId, Object, SceneId};
 use game_render::mesh::Mesh;
 use game_render::pbr::{AlphaMode, PbrMaterial};
 use game_render::texture::Image;
 use game_render::{shape, Renderer};
 use game_tracing::trace_span;
 
+use crate::resource::SharedImages;
 use crate::scene2::{Key, ObjectWithId, SceneResources, SpawnedScene};
 
 #[derive(Clone, Debug, Default)]
@@ -41,17 +42,30 @@ pub struct ObjectNode {
 }
 
 impl Scene {
-    pub(crate) fn setup_materials(&mut self, renderer: &mut Renderer) -> SceneResources {
+    pub(crate) fn setup_materials(
+        &mut self,
+        renderer: &mut Renderer,
+        shared_images: &mut SharedImages<ImageId>,
+    ) -> SceneResources {
         let meshes = self
             .meshes
             .drain(..)
             .map(|mesh| renderer.resources().meshes().insert(mesh))
             .collect();
 
+        // Images are shared with all other scenes, identical images
+        // are only uploaded once.
         let images = self
             .images
             .drain(..)
-            .map(|image| renderer.resources().images().insert(image))
+            .map(|image| {
+                shared_images
+                    .insert_with(image, |image| renderer.resources().images().insert(image))
+            })
+            .collect::<Vec<_>>();
+        let image_ids = images
+            .iter()
+            .map(|id| shared_images.get(*id).unwrap())
             .collect::<Vec<_>>();
 
         let materials = self
@@ -62,14 +76,17 @@ impl Scene {
                     PbrMaterial {
                         alpha_mode: material.alpha_mode,
                         base_color: material.base_color,
-                        base_color_texture: material.base_color_texture.map(|index| images[index]),
-                        normal_texture: material.normal_texture.map(|index| images[index]),
+                        base_color_texture: material
+                            .base_color_texture
+                            .map(|index| image_ids[index]),
+                        normal_texture: material.normal_texture.map(|index| image_ids[index]),
                         roughness: material.roughness,
                         metallic: material.metallic,
                         metallic_roughness_texture: material
                             .metallic_roughness_texture
-                            .map(|index| images[index]),
+                            .map(|index| image_ids[index]),
                         reflectance: material.reflectance,
+                        double_sided: material.double_sided,
                     }
                 })
             })
@@ -216,6 +233,7 @@ pub struct Material {
     pub metallic: f32,
     pub metallic_roughness_texture: Option<usize>,
     pub reflectance: f32,
+    pub double_sided: bool,
 }
 
 impl Default for Material {
@@ -229,6 +247,7 @@ impl Default for Material {
             metallic: 0.0,
             metallic_roughness_texture: None,
             reflectance: 0.5,
+            double_sided: false,
         }
     }
 }
@@ -8,6 +8,8 @@ use game_render::Renderer;
 use game_tracing::trace_span;
 use glam::Vec3;
 
+use crate::resource::{ResourceId, SharedImages};
+
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct ObjectWithId {
     pub(crate) object: Object,
@@ -30,7 +32,32 @@ pub struct SpawnedScene {
 pub(crate) struct SceneResources {
     pub(crate) meshes: Vec<MeshId>,
     pub(crate) materials: Vec<MaterialId>,
-    pub(crate) images: Vec<ImageId>,
+    pub(crate) images: Vec<ResourceId>,
+}
+
+impl SceneResources {
+    /// Removes all resources of the scene from the renderer.
+    ///
+    /// Shared images are only removed if no other scene references them.
+    pub(crate) fn destroy(
+        self,
+        renderer: &mut Renderer,
+        shared_images: &mut SharedImages<ImageId>,
+    ) {
+        for mesh in self.meshes {
+            renderer.resources().meshes().remove(mesh);
+        }
+
+        for material in self.materials {
+            renderer.resources().materials().remove(material);
+        }
+
+        for image in self.images {
+            if let Some(id) = shared_images.remove(image) {
+                renderer.resources().images().remove(id);
+            }
+        }
+    }
 }
 
 impl SpawnedScene {
@@ -2,11 +2,13 @@ use std::collections::{HashMap, VecDeque};
 
 use game_common::collections::arena::{self, Arena};
 use game_common::components::Transform;
+use game_render::entities::ImageId;
 use game_render::Renderer;
 use game_tasks::{Task, TaskPool};
 use game_tracing::trace_span;
 
 use crate::load_from_bytes;
+use crate::resource::SharedImages;
 use crate::scene::Scene;
 use crate::scene2::{SceneResources, SpawnedScene};
 
@@ -19,6 +21,8 @@ pub struct SceneSpawner {
     scenes: Arena<SceneData>,
     events: VecDeque<Event>,
     tasks: HashMap<SceneId, SceneLoadState>,
+    /// Images shared between all scenes.
+    images: SharedImages<ImageId>,
 }
 
 impl SceneSpawner {
@@ -145,7 +149,9 @@ impl SceneSpawner {
                         }
                     }
 
-                    self.scenes.remove(scene.0);
+                    if let Some(SceneData::Loaded(_, resources)) = self.scenes.remove(scene.0) {
+                        resources.destroy(renderer, &mut self.images);
+                    }
                     self.tasks.remove(&scene);
                 }
                 Event::DestroyInstance(instance) => {
@@ -196,7 +202,7 @@ impl SceneSpawner {
         self.tasks
             .retain(|scene, state| match state.task.get_output() {
                 Some(Some(mut output)) => {
-                    let res = output.setup_materials(renderer);
+                    let res = output.setup_materials(renderer, &mut self.images);
                     *self.scenes.get_mut(scene.0).unwrap() = SceneData::Loaded(output, res);
 
                     for instance in state.deferred_instances.drain(..) {
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{self, Debug, Formatter};
+
+use game_common::collections::arena::Arena;
+use game_tracing::trace_span;
+use wasmtime::{
+    Config, Engine, InstanceAllocationStrategy, OptLevel, PoolingAllocationConfig,
+    WasmBacktraceDetails,
+};
+
+use crate::instance::{HostBufferPool, InstancePool, StoreOptions};
+use crate::{
+    Executor, HostBufferListPool, DEFAULT_MAX_DEFERRED_INVOCATIONS, DEFAULT_MAX_INVOCATIONS,
+    HOST_BUFFERS_PER_INVOCATION,
+};
+
+/// A builder for an [`Executor`].
+///
+/// The defaults are the same as those of [`Executor::new`].
+#[derive(Clone, Debug)]
+pub struct ExecutorBuilder {
+    opt_level: OptLevel,
+    backtrace: bool,
+    fuel: Option<u64>,
+    epoch_interruption: bool,
+    pooling_allocator: bool,
+    max_memory_size: Option<usize>,
+    max_invocations: usize,
+    max_deferred_invocations: usize,
+}
+
+impl ExecutorBuilder {
+    /// Creates a new `ExecutorBuilder` with the default configuration.
+    pub fn new() -> Self {
+        Self {
+            opt_level: OptLevel::SpeedAndSize,
+            backtrace: true,
+            fuel: None,
+            epoch_interruption: false,
+            pooling_allocator: false,
+            max_memory_size: None,
+            max_invocations: DEFAULT_MAX_INVOCATIONS,
+            max_deferred_invocations: DEFAULT_MAX_DEFERRED_INVOCATIONS,
+        }
+    }
+
+    /// Sets the optimization level used when compiling scripts.
+    ///
+    /// Defaults to [`OptLevel::SpeedAndSize`].
+    pub fn opt_level(mut self, level: OptLevel) -> Self {
+        self.opt_level = level;
+        self
+    }
+
+    /// Sets whether errors in scripts should capture a backtrace.
+    ///
+    /// Defaults to `true`.
+    pub fn backtrace(mut self, enable: bool) -> Self {
+        self.backtrace = enable;
+        self
+    }
+
+    /// Sets the amount of fuel available to a single script invocation.
+    ///
+    /// An invocation that runs out of fuel is aborted. If `None` fuel is not consumed and
+    /// invocations can run for an unlimited time. Defaults to `None`.
+    pub fn fuel(mut self, fuel: Option<u64>) -> Self {
+        self.fuel = fuel;
+        self
+    }
+
+    /// Sets whether script invocations can be interrupted using an [`EpochHandle`].
+    ///
+    /// If enabled, an invocation is aborted once the epoch is incremented while it is running.
+    /// Defaults to `false`.
+    pub fn epoch_interruption(mut self, enable: bool) -> Self {
+        self.epoch_interruption = enable;
+        self
+    }
+
+    /// Sets whether the memory of scripts should be allocated from a preallocated pool.
+    ///
+    /// The pooling allocator makes loading scripts cheaper, but reserves a large amount of
+    /// virtual memory upfront. Defaults to `false`.
+    pub fn pooling_allocator(mut self, enable: bool) -> Self {
+        self.pooling_allocator = enable;
+        self
+    }
+
+    /// Sets the maximum size of a linear memory of a script in bytes.
+    ///
+    /// The limit is enforced by the pooling allocator. Setting a limit enables the pooling
+    /// allocator. Defaults to `None`.
+    pub fn max_memory_size(mut self, bytes: Option<usize>) -> Self {
+        self.max_memory_size = bytes;
+        self
+    }
+
+    /// Sets the maximum number of script invocations processed in a single call to
+    /// [`Executor::update`].
+    ///
+    /// Defaults to [`DEFAULT_MAX_INVOCATIONS`]. See [`Executor::set_max_invocations`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is `0`.
+    pub fn max_invocations(mut self, max: usize) -> Self {
+        assert!(max != 0, "max_invocations must not be 0");
+        self.max_invocations = max;
+        self
+    }
+
+    /// Sets the maximum number of script invocations deferred to the next call to
+    /// [`Executor::update`].
+    ///
+    /// Defaults to [`DEFAULT_MAX_DEFERRED_INVOCATIONS`]. See
+    /// [`Executor::set_max_deferred_invocations`] for details.
+    pub fn max_deferred_invocations(mut self, max: usize) -> Self {
+        self.max_deferred_invocations = max;
+        self
+    }
+
+    /// Builds the [`Executor`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the wasmtime [`Engine`] cannot be created with the configured options,
+    /// e.g. if the pooling allocator fails to reserve its memory.
+    pub fn build(self) -> Result<Executor, wasmtime::Error> {
+        let _span = trace_span!("ExecutorBuilder::build").entered();
+
+        let mut config = Config::new();
+        config.wasm_backtrace(self.backtrace);
+        config.wasm_backtrace_details(if self.backtrace {
+            WasmBacktraceDetails::Enable
+        } else {
+            WasmBacktraceDetails::Disable
+        });
+        config.cranelift_opt_level(self.opt_level);
+        config.consume_fuel(self.fuel.is_some());
+        config.epoch_interruption(self.epoch_interruption);
+
+        if self.pooling_allocator || self.max_memory_size.is_some() {
+            let mut pooling = PoolingAllocationConfig::default();
+            if let Some(bytes) = self.max_memory_size {
+                pooling.max_memory_size(bytes);
+            }
+
+            config.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+        }
+
+        let engine = Engine::new(&config)?;
+
+        let options = StoreOptions {
+            fuel: self.fuel,
+            epoch_interruption: self.epoch_interruption,
+        };
+
+        Ok(Executor {
+            instances: InstancePool::new(&engine, options),
+            engine,
+            scripts: Arena::new(),
+            systems: vec![],
+            action_handlers: HashMap::new(),
+            event_handlers: HashMap::new(),
+            invocations: VecDeque::with_capacity(32),
+            host_buffer_pool: HostBufferPool::new(
+                self.max_invocations
+                    .saturating_mul(HOST_BUFFERS_PER_INVOCATION),
+            ),
+            host_buffer_lists: HostBufferListPool::new(self.max_invocations),
+            prev_num_invocations: 0,
+            max_invocations: self.max_invocations,
+            max_deferred_invocations: self.max_deferred_invocations,
+        })
+    }
+}
+
+impl Default for ExecutorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A handle to interrupt running scripts of an [`Executor`].
+///
+/// Only has an effect if the [`Executor`] was created with
+/// [`epoch_interruption`](ExecutorBuilder::epoch_interruption) enabled.
+#[derive(Clone)]
+pub struct EpochHandle {
+    pub(crate) engine: Engine,
+}
+
+impl EpochHandle {
+    /// Increments the epoch, aborting all currently running script invocations.
+    ///
+    /// This can be called from any thread.
+    pub fn increment(&self) {
+        self.engine.increment_epoch();
+    }
+}
+
+impl Debug for EpochHandle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EpochHandle").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::time::{Duration, Instant};
+
+    use super::ExecutorBuilder;
+    use crate::Executor;
+
+    /// A script that never returns from `on_init`.
+    const INFINITE_INIT: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "on_init")
+                (loop $loop (br $loop)))
+            (func (export "__wasm_fn_trampoline") (param i32 i64))
+        )
+    "#;
+
+    /// A script with a memory of a single page.
+    const SMALL_MEMORY: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "on_init"))
+            (func (export "__wasm_fn_trampoline") (param i32 i64))
+        )
+    "#;
+
+    /// A script with a memory of two pages.
+    const LARGE_MEMORY: &str = r#"
+        (module
+            (memory (export "memory") 2)
+            (func (export "on_init"))
+            (func (export "__wasm_fn_trampoline") (param i32 i64))
+        )
+    "#;
+
+    #[test]
+    fn executor_builder_default() {
+        let mut executor = ExecutorBuilder::new().build().unwrap();
+        executor.load(LARGE_MEMORY.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn executor_builder_fuel() {
+        let mut executor = Executor::builder().fuel(Some(10_000)).build().unwrap();
+        executor.load(SMALL_MEMORY.as_bytes()).unwrap();
+        assert!(executor.load(INFINITE_INIT.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn executor_builder_epoch_interruption() {
+        let mut executor = Executor::builder()
+            .epoch_interruption(true)
+            .build()
+            .unwrap();
+
+        executor.load(SMALL_MEMORY.as_bytes()).unwrap();
+
+        let handle = executor.epoch_handle();
+        let (tx, rx) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let res = executor.load(INFINITE_INIT.as_bytes());
+            tx.send(res.is_err()).unwrap();
+        });
+
+        // The script may only start running after an increment, so keep
+        // incrementing the epoch until it is interrupted.
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let is_err = loop {
+            handle.increment();
+
+            match rx.recv_timeout(Duration::from_millis(10)) {
+                Ok(is_err) => break is_err,
+                Err(RecvTimeoutError::Timeout) => {
+                    assert!(Instant::now() < deadline, "script was not interrupted");
+                }
+                Err(RecvTimeoutError::Disconnected) => panic!("loading thread panicked"),
+            }
+        };
+
+        assert!(is_err);
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn executor_builder_max_memory_size() {
+        let mut executor = Executor::builder()
+            .max_memory_size(Some(65536))
+            .build()
+            .unwrap();
+
+        executor.load(SMALL_MEMORY.as_bytes()).unwrap();
+        assert!(executor.load(LARGE_MEMORY.as_bytes()).is_err());
+    }
+}
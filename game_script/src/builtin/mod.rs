@@ -51,7 +51,9 @@ pub fn register_host_fns(store: &mut Linker<State>) {
         player_set_active,
         register_system,
         register_event_handler,
+        register_event_handler_with_priority,
         register_action_handler,
+        register_action_handler_with_priority,
         event_dispatch,
         host_buffer_len,
         host_buffer_get,
@@ -1,5 +1,7 @@
 use bytemuck::{Pod, Zeroable};
-use game_common::components::{Axis, Ball, Capsule, ColliderShape, Cuboid, TriMesh};
+use game_common::components::{
+    Axis, Ball, Capsule, ColliderShape, CollisionGroups, Cuboid, TriMesh,
+};
 use game_common::entity::EntityId;
 use game_common::math::Ray;
 use game_physics::query::QueryFilter;
@@ -200,7 +202,10 @@ fn read_query_filter(caller: &mut Caller<'_, State>, ptr: u32) -> wasmtime::Resu
         exclude_entities.push(entity);
     }
 
-    Ok(QueryFilter { exclude_entities })
+    Ok(QueryFilter {
+        exclude_entities,
+        groups: CollisionGroups::new(filter.groups_memberships, filter.groups_filter),
+    })
 }
 
 #[derive(Copy, Clone, Debug, Zeroable, Pod)]
@@ -208,6 +213,8 @@ fn read_query_filter(caller: &mut Caller<'_, State>, ptr: u32) -> wasmtime::Resu
 struct RawQueryFilter {
     exclude_entities_ptr: u32,
     exclude_entities_len: u32,
+    groups_memberships: u32,
+    groups_filter: u32,
 }
 
 #[derive(Copy, Clone, Debug, Zeroable, Pod)]
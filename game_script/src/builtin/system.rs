@@ -4,7 +4,7 @@ use game_wasm::world::RecordReference;
 use wasmtime::{Caller, Result};
 
 use crate::instance::State;
-use crate::{Entry, Pointer, System, SystemQuery};
+use crate::{Entry, Pointer, System, SystemQuery, DEFAULT_HANDLER_PRIORITY};
 
 use super::AsMemory;
 
@@ -27,7 +27,16 @@ pub fn register_system(mut caller: Caller<'_, State>, params: u32, fn_ptr: u32)
     Ok(())
 }
 
-pub fn register_event_handler(mut caller: Caller<'_, State>, id: u32, fn_ptr: u32) -> Result<()> {
+pub fn register_event_handler(caller: Caller<'_, State>, id: u32, fn_ptr: u32) -> Result<()> {
+    register_event_handler_with_priority(caller, id, fn_ptr, DEFAULT_HANDLER_PRIORITY)
+}
+
+pub fn register_event_handler_with_priority(
+    mut caller: Caller<'_, State>,
+    id: u32,
+    fn_ptr: u32,
+    priority: i32,
+) -> Result<()> {
     let _span = trace_span!("register_event_handler").entered();
 
     let id: RecordReference = caller.read(id)?;
@@ -36,12 +45,22 @@ pub fn register_event_handler(mut caller: Caller<'_, State>, id: u32, fn_ptr: u3
     state.event_handlers.entry(id).or_default().push(Entry {
         script: state.script,
         fn_ptr: Pointer(fn_ptr),
+        priority,
     });
 
     Ok(())
 }
 
-pub fn register_action_handler(mut caller: Caller<'_, State>, id: u32, fn_ptr: u32) -> Result<()> {
+pub fn register_action_handler(caller: Caller<'_, State>, id: u32, fn_ptr: u32) -> Result<()> {
+    register_action_handler_with_priority(caller, id, fn_ptr, DEFAULT_HANDLER_PRIORITY)
+}
+
+pub fn register_action_handler_with_priority(
+    mut caller: Caller<'_, State>,
+    id: u32,
+    fn_ptr: u32,
+    priority: i32,
+) -> Result<()> {
     let _span = trace_span!("register_action_handler").entered();
 
     let id: RecordReference = caller.read(id)?;
@@ -50,6 +69,7 @@ pub fn register_action_handler(mut caller: Caller<'_, State>, id: u32, fn_ptr: u
     state.actions.entry(id).or_default().push(Entry {
         script: state.script,
         fn_ptr: Pointer(fn_ptr),
+        priority,
     });
 
     Ok(())
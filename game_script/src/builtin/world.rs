@@ -1,16 +1,19 @@
 use std::sync::Arc;
 
+use bytemuck::{Pod, Zeroable};
 use game_common::components::components::RawComponent;
+use game_common::components::{Component, Transform};
 use game_common::entity::EntityId;
 use game_common::record::RecordReference;
 use game_prefab::{Prefab, Spawner};
 use game_tracing::trace_span;
-use game_wasm::encoding::{decode_fields, encode_fields, Field};
+use game_wasm::encoding::{decode_fields, encode_fields, BinaryWriter, Field};
 use game_wasm::raw::{RESULT_NO_ENTITY, RESULT_NO_RECORD, RESULT_OK};
 use game_wasm::resource::RuntimeResourceId;
+use glam::{Quat, Vec3};
 use wasmtime::{Caller, Result};
 
-use crate::builtin::CallerExt;
+use crate::builtin::{assert_caller_precondition, CallerExt};
 use crate::instance::{RunState, State};
 
 use super::AsMemory;
@@ -178,8 +181,28 @@ pub fn world_entity_component_remove(
     }
 }
 
-pub fn prefab_spawn(mut caller: Caller<'_, State>, id: u32, out: u32) -> Result<u32> {
+pub fn prefab_spawn(
+    mut caller: Caller<'_, State>,
+    id: u32,
+    transform: u32,
+    out: u32,
+) -> Result<u32> {
     let _span = trace_span!("prefab_spawn").entered();
+    tracing::trace!(
+        "prefab_spawn(id = {}, transform = {}, out = {})",
+        id,
+        transform,
+        out
+    );
+
+    let transform: RawTransform = caller.read(transform)?;
+    let transform = Transform {
+        translation: Vec3::from_array(transform.translation),
+        rotation: Quat::from_array(transform.rotation),
+        scale: Vec3::from_array(transform.scale),
+    };
+
+    assert_caller_precondition!(stringify!(prefab_spawn), transform.is_valid());
 
     let id: RecordReference = caller.read(id)?;
     let data = caller.data_mut().as_run_mut()?;
@@ -199,12 +222,25 @@ pub fn prefab_spawn(mut caller: Caller<'_, State>, id: u32, out: u32) -> Result<
         }
     };
 
-    let root = prefab.instantiate(PrefabSpawner { state: data });
+    let mut spawner = PrefabSpawner { state: data };
+    let root = prefab.instantiate(&mut spawner);
+
+    let (fields, bytes) = BinaryWriter::new().encoded(&transform);
+    spawner.insert(root, Transform::ID, RawComponent::new(bytes, fields));
+
     caller.write(out, &root)?;
 
     Ok(RESULT_OK)
 }
 
+#[derive(Copy, Clone, Debug, Zeroable, Pod)]
+#[repr(C)]
+struct RawTransform {
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+}
+
 struct PrefabSpawner<'a> {
     state: &'a mut RunState,
 }
@@ -24,16 +24,38 @@ pub(crate) struct InstancePool {
     /// Linker for instantiating new instances.
     linker: Linker<State>,
     instances: HashMap<Handle, Runnable>,
+    options: StoreOptions,
+}
+
+/// Limits applied to every call into a script.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct StoreOptions {
+    /// The fuel available to a single call, if fuel consumption is enabled.
+    pub(crate) fuel: Option<u64>,
+    pub(crate) epoch_interruption: bool,
+}
+
+impl StoreOptions {
+    fn apply(&self, store: &mut Store<State>) {
+        if let Some(fuel) = self.fuel {
+            store.set_fuel(fuel).unwrap();
+        }
+
+        if self.epoch_interruption {
+            store.set_epoch_deadline(1);
+        }
+    }
 }
 
 impl InstancePool {
-    pub(crate) fn new(engine: &Engine) -> Self {
+    pub(crate) fn new(engine: &Engine, options: StoreOptions) -> Self {
         let mut linker = Linker::<State>::new(engine);
         register_host_fns(&mut linker);
 
         Self {
             instances: HashMap::new(),
             linker,
+            options,
         }
     }
 
@@ -51,7 +73,8 @@ impl InstancePool {
         });
 
         let mut store = Store::new(engine, state);
-        let instance = self.linker.instantiate(&mut store, module).unwrap();
+        self.options.apply(&mut store);
+        let instance = self.linker.instantiate(&mut store, module)?;
         let mut runnable = Runnable { store, instance };
 
         runnable.init()?;
@@ -73,6 +96,7 @@ impl InstancePool {
     pub fn get(&mut self, state: State, handle: Handle) -> &mut Runnable {
         let runnable = self.instances.get_mut(&handle).unwrap();
         *runnable.store.data_mut() = state;
+        self.options.apply(&mut runnable.store);
         runnable
     }
 }
@@ -399,12 +423,49 @@ impl RunState {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+/// The maximum capacity of a buffer that is retained by a [`HostBufferPool`] for reuse.
+///
+/// Larger buffers are freed instead of being reused, so that a single large buffer does not
+/// permanently increase the memory usage of the pool.
+const MAX_FREE_BUFFER_CAPACITY: usize = 64 * 1024;
+
+/// The buffers that are exposed to the guest as host buffers.
+///
+/// Buffers are recycled once the pool is cleared and reused by [`alloc`].
+///
+/// [`alloc`]: Self::alloc
+#[derive(Clone, Debug)]
 pub struct HostBufferPool {
     buffers: Vec<Vec<u8>>,
+    /// Empty buffers that can be reused.
+    free: Vec<Vec<u8>>,
+    /// The maximum number of buffers retained in `free`.
+    max_free: usize,
+    /// Scratch space for [`retain`], reused across calls.
+    ///
+    /// [`retain`]: Self::retain
+    retained: Vec<Vec<u8>>,
+    remap: Vec<usize>,
 }
 
 impl HostBufferPool {
+    /// Creates a new, empty `HostBufferPool` retaining at most `max_free` unused buffers.
+    pub fn new(max_free: usize) -> Self {
+        Self {
+            buffers: Vec::new(),
+            free: Vec::new(),
+            max_free,
+            retained: Vec::new(),
+            remap: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of unused buffers retained, dropping all buffers exceeding it.
+    pub fn set_max_free(&mut self, max_free: usize) {
+        self.max_free = max_free;
+        self.free.truncate(max_free);
+    }
+
     pub fn get(&self, index: usize) -> Option<&[u8]> {
         self.buffers.get(index).map(Vec::as_slice)
     }
@@ -415,7 +476,135 @@ impl HostBufferPool {
         index
     }
 
+    /// Returns an empty buffer, reusing a previously recycled buffer if possible.
+    pub fn alloc(&mut self) -> Vec<u8> {
+        self.free.pop().unwrap_or_default()
+    }
+
+    /// Returns the buffer `buf` to the pool without inserting it.
+    pub fn recycle(&mut self, mut buf: Vec<u8>) {
+        if buf.capacity() == 0
+            || buf.capacity() > MAX_FREE_BUFFER_CAPACITY
+            || self.free.len() >= self.max_free
+        {
+            return;
+        }
+
+        buf.clear();
+        self.free.push(buf);
+    }
+
+    /// Removes all buffers from the pool, recycling them for future calls to [`alloc`].
+    ///
+    /// [`alloc`]: Self::alloc
     pub fn clear(&mut self) {
-        self.buffers.clear();
+        while let Some(buf) = self.buffers.pop() {
+            self.recycle(buf);
+        }
+    }
+
+    /// Removes all buffers that are not referenced by any of the index `lists`, recycling them
+    /// for future calls to [`alloc`].
+    ///
+    /// The retained buffers are moved to the front of the pool and the indices in `lists` are
+    /// updated to their new positions.
+    ///
+    /// [`alloc`]: Self::alloc
+    pub fn retain<'a, I>(&mut self, lists: I)
+    where
+        I: IntoIterator<Item = &'a mut Vec<usize>>,
+    {
+        self.remap.clear();
+        self.remap.resize(self.buffers.len(), usize::MAX);
+
+        for list in lists {
+            for index in list {
+                let new_index = &mut self.remap[*index];
+                if *new_index == usize::MAX {
+                    *new_index = self.retained.len();
+                    self.retained
+                        .push(std::mem::take(&mut self.buffers[*index]));
+                }
+
+                *index = *new_index;
+            }
+        }
+
+        std::mem::swap(&mut self.buffers, &mut self.retained);
+        while let Some(buf) = self.retained.pop() {
+            self.recycle(buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HostBufferPool, MAX_FREE_BUFFER_CAPACITY};
+
+    const MAX_FREE_BUFFERS: usize = 64;
+
+    #[test]
+    fn host_buffer_pool_reuse() {
+        let mut pool = HostBufferPool::new(MAX_FREE_BUFFERS);
+
+        let mut buf = pool.alloc();
+        buf.extend_from_slice(&[1, 2, 3, 4]);
+        let ptr = buf.as_ptr();
+        let index = pool.insert(buf);
+        assert_eq!(pool.get(index), Some(&[1, 2, 3, 4][..]));
+
+        pool.clear();
+        assert_eq!(pool.get(index), None);
+
+        let buf = pool.alloc();
+        assert!(buf.is_empty());
+        assert_eq!(buf.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn host_buffer_pool_max_capacity() {
+        let mut pool = HostBufferPool::new(MAX_FREE_BUFFERS);
+
+        pool.insert(vec![0; MAX_FREE_BUFFER_CAPACITY + 1]);
+        pool.clear();
+
+        assert_eq!(pool.alloc().capacity(), 0);
+    }
+
+    #[test]
+    fn host_buffer_pool_max_buffers() {
+        let mut pool = HostBufferPool::new(MAX_FREE_BUFFERS);
+
+        for _ in 0..MAX_FREE_BUFFERS * 2 {
+            pool.insert(vec![0; 16]);
+        }
+        pool.clear();
+
+        assert_eq!(pool.free.len(), MAX_FREE_BUFFERS);
+
+        pool.set_max_free(MAX_FREE_BUFFERS / 2);
+        assert_eq!(pool.free.len(), MAX_FREE_BUFFERS / 2);
+    }
+
+    #[test]
+    fn host_buffer_pool_retain() {
+        let mut pool = HostBufferPool::new(MAX_FREE_BUFFERS);
+
+        for byte in 0..8 {
+            pool.insert(vec![byte]);
+        }
+
+        let mut lists = vec![vec![5, 2], vec![2, 7]];
+        pool.retain(&mut lists);
+
+        assert_eq!(lists, [[0, 1], [1, 2]]);
+        assert_eq!(pool.get(0), Some(&[5][..]));
+        assert_eq!(pool.get(1), Some(&[2][..]));
+        assert_eq!(pool.get(2), Some(&[7][..]));
+        assert_eq!(pool.get(3), None);
+        assert_eq!(pool.free.len(), 5);
+
+        pool.retain([]);
+        assert_eq!(pool.get(0), None);
     }
 }
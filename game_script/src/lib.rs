@@ -1,33 +1,66 @@
 //! Game (dynamic) scripting
 
-use std::collections::{HashMap, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{self, Debug, Formatter};
 
 use effect::Effects;
 use events::DispatchEvent;
 use game_common::collections::arena::{Arena, Key};
+use game_common::components::Children;
 use game_common::entity::EntityId;
 use game_common::events::{Event, EventQueue};
 use game_common::record::RecordReference;
 use game_common::world::World;
 use game_data::record::Record;
 use game_tracing::trace_span;
-use game_wasm::encoding::{encode_fields, BinaryWriter};
+use game_wasm::encoding::{encode_fields_into, BinaryWriter, Encode};
 use game_wasm::events::{CELL_LOAD, CELL_UNLOAD, PLAYER_CONNECT, PLAYER_DISCONNECT};
 use game_wasm::player::PlayerId;
 use game_wasm::record::ModuleId;
 use instance::{HostBufferPool, InstancePool, RunState, State};
 use script::{Script, ScriptLoadError};
 use thiserror::Error;
-use wasmtime::{Config, Engine, OptLevel, WasmBacktraceDetails};
+use wasmtime::Engine;
 
 pub mod effect;
 
+mod builder;
 mod builtin;
 mod events;
 mod instance;
 mod script;
 
+#[cfg(test)]
+mod test_support;
+
+pub use builder::{EpochHandle, ExecutorBuilder};
+pub use wasmtime::OptLevel;
+
+/// The default maximum number of script invocations processed in a single
+/// [`Executor::update`] call.
+pub const DEFAULT_MAX_INVOCATIONS: usize = 8192;
+
+/// The default maximum number of script invocations deferred to the next [`Executor::update`]
+/// call.
+pub const DEFAULT_MAX_DEFERRED_INVOCATIONS: usize = 65536;
+
+/// The priority of action and event handlers registered without an explicit priority.
+///
+/// Handlers for the same action or event are run in descending order of their priority. Handlers
+/// with the same priority are run in ascending order of the id of the script that registered
+/// them, independent of the order in which the scripts were loaded (see
+/// [`Executor::load_record`]). Scripts without an id run after all scripts with an id in the order
+/// they were loaded. Handlers of the same script are run in the order they were registered.
+pub const DEFAULT_HANDLER_PRIORITY: i32 = 0;
+
+/// The maximum number of host buffers referenced by a single [`Invocation`].
+///
+/// The host buffer pools retain enough buffers and lists for all invocations of a single
+/// [`Executor::update`] call, so their limits are derived from the maximum number of
+/// invocations.
+const HOST_BUFFERS_PER_INVOCATION: usize = 2;
+
 pub struct Executor {
     engine: Engine,
     scripts: Arena<Script>,
@@ -38,31 +71,70 @@ pub struct Executor {
     // Reuse memory for invocations across `update` calls.
     invocations: VecDeque<Invocation>,
     host_buffer_pool: HostBufferPool,
+    host_buffer_lists: HostBufferListPool,
+    /// The number of invocations processed in the previous `update` call.
+    prev_num_invocations: usize,
+    max_invocations: usize,
+    max_deferred_invocations: usize,
 }
 
 impl Executor {
+    /// Creates a new `Executor` with the default configuration.
+    ///
+    /// Use [`ExecutorBuilder`] to configure the `Executor`.
     pub fn new() -> Self {
         let _span = trace_span!("Executor::new").entered();
 
-        let mut config = Config::new();
-        config.wasm_backtrace(true);
-        config.wasm_backtrace_details(WasmBacktraceDetails::Enable);
-        config.cranelift_opt_level(OptLevel::SpeedAndSize);
-        let engine = Engine::new(&config).unwrap();
+        ExecutorBuilder::new().build().unwrap()
+    }
 
-        Self {
-            instances: InstancePool::new(&engine),
-            engine,
-            scripts: Arena::new(),
-            systems: vec![],
-            action_handlers: HashMap::new(),
-            event_handlers: HashMap::new(),
-            invocations: VecDeque::with_capacity(32),
-            host_buffer_pool: HostBufferPool::default(),
+    /// Returns a new [`ExecutorBuilder`].
+    pub fn builder() -> ExecutorBuilder {
+        ExecutorBuilder::new()
+    }
+
+    /// Returns an [`EpochHandle`] that can be used to interrupt running scripts.
+    pub fn epoch_handle(&self) -> EpochHandle {
+        EpochHandle {
+            engine: self.engine.clone(),
         }
     }
 
-    /// Loads a script.
+    /// Sets the maximum number of script invocations processed in a single call to [`update`].
+    ///
+    /// Invocations exceeding the limit are deferred to the next call to [`update`] in the order
+    /// they were scheduled. This prevents a cascade of events from stalling a single `update`
+    /// call. Defaults to [`DEFAULT_MAX_INVOCATIONS`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is `0`.
+    ///
+    /// [`update`]: Self::update
+    pub fn set_max_invocations(&mut self, max: usize) {
+        assert!(max != 0, "max_invocations must not be 0");
+        self.max_invocations = max;
+        self.host_buffer_pool
+            .set_max_free(max.saturating_mul(HOST_BUFFERS_PER_INVOCATION));
+        self.host_buffer_lists.set_max_free(max);
+    }
+
+    /// Sets the maximum number of script invocations deferred to the next call to [`update`].
+    ///
+    /// Invocations exceeding the limit are dropped, starting with the invocations that were
+    /// scheduled last. This prevents scripts that keep scheduling new invocations, e.g. by
+    /// triggering their own events, from growing the queue without bound. Defaults to
+    /// [`DEFAULT_MAX_DEFERRED_INVOCATIONS`].
+    ///
+    /// [`update`]: Self::update
+    pub fn set_max_deferred_invocations(&mut self, max: usize) {
+        self.max_deferred_invocations = max;
+    }
+
+    /// Loads a script without an id.
+    ///
+    /// Handlers of the script run after the handlers of all scripts with an id that have the
+    /// same priority. See [`DEFAULT_HANDLER_PRIORITY`] for details.
     ///
     /// # Errors
     ///
@@ -71,9 +143,35 @@ impl Executor {
     /// - The script imports or exports unknown symbols.
     /// - Script initialization fails.
     pub fn load(&mut self, bytes: &[u8]) -> Result<Handle, ScriptLoadError> {
+        self.load_inner(bytes, None)
+    }
+
+    /// Loads the script of the record with the given `id`.
+    ///
+    /// Handlers with the same priority are ordered by the `id` of their script. See
+    /// [`DEFAULT_HANDLER_PRIORITY`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ScriptLoadError`] in the same cases as [`load`].
+    ///
+    /// [`load`]: Self::load
+    pub fn load_record(
+        &mut self,
+        id: RecordReference,
+        bytes: &[u8],
+    ) -> Result<Handle, ScriptLoadError> {
+        self.load_inner(bytes, Some(id))
+    }
+
+    fn load_inner(
+        &mut self,
+        bytes: &[u8],
+        id: Option<RecordReference>,
+    ) -> Result<Handle, ScriptLoadError> {
         let _span = trace_span!("Executor::load").entered();
 
-        let script = Script::new(bytes, &self.engine)?;
+        let script = Script::new(bytes, &self.engine, id)?;
 
         let entry = self.scripts.allocate();
         let handle = Handle(entry.key());
@@ -83,17 +181,24 @@ impl Executor {
             .init(&self.engine, &script.module, handle)
             .map_err(ScriptLoadError::Init)?;
 
+        // The script must be inserted before the handlers are sorted, since
+        // they are ordered by the id of their script.
+        entry.write(script);
+
         self.systems.extend(state.systems);
 
         for (id, entries) in state.actions {
-            self.action_handlers.entry(id).or_default().extend(entries);
+            let handlers = self.action_handlers.entry(id).or_default();
+            handlers.extend(entries);
+            sort_handlers(handlers, &self.scripts);
         }
 
         for (id, entries) in state.event_handlers {
-            self.event_handlers.entry(id).or_default().extend(entries);
+            let handlers = self.event_handlers.entry(id).or_default();
+            handlers.extend(entries);
+            sort_handlers(handlers, &self.scripts);
         }
 
-        entry.write(script);
         Ok(handle)
     }
 
@@ -132,16 +237,12 @@ impl Executor {
 
         let world = ctx.world.world();
 
-        for system in &self.systems {
-            'entities: for entity in world.entities() {
-                let components = world.components(entity);
-
-                for component in &system.query.components {
-                    if components.get(*component).is_none() {
-                        continue 'entities;
-                    }
-                }
+        // Most ticks schedule a similar number of invocations, reserving the
+        // capacity upfront avoids growing the queue while scheduling.
+        self.invocations.reserve(self.prev_num_invocations);
 
+        for system in &self.systems {
+            for entity in world.entities_with(&system.query.components) {
                 self.invocations.push_back(Invocation {
                     script: system.script,
                     fn_ptr: system.ptr,
@@ -158,47 +259,19 @@ impl Executor {
                     None => continue,
                 },
                 Event::PlayerConnect(event) => {
-                    let (fields, data) = BinaryWriter::new().encoded(&event);
-                    let fields = encode_fields(&fields);
-
-                    self.schedule_event(DispatchEvent {
-                        id: PLAYER_CONNECT,
-                        data,
-                        fields,
-                    });
+                    self.schedule_encoded_event(PLAYER_CONNECT, &event);
                     continue;
                 }
                 Event::PlayerDisconnect(event) => {
-                    let (fields, data) = BinaryWriter::new().encoded(&event);
-                    let fields = encode_fields(&fields);
-
-                    self.schedule_event(DispatchEvent {
-                        id: PLAYER_DISCONNECT,
-                        data,
-                        fields,
-                    });
+                    self.schedule_encoded_event(PLAYER_DISCONNECT, &event);
                     continue;
                 }
                 Event::CellLoad(event) => {
-                    let (fields, data) = BinaryWriter::new().encoded(&event);
-                    let fields = encode_fields(&fields);
-
-                    self.schedule_event(DispatchEvent {
-                        id: CELL_LOAD,
-                        data,
-                        fields,
-                    });
+                    self.schedule_encoded_event(CELL_LOAD, &event);
                     continue;
                 }
                 Event::CellUnload(event) => {
-                    let (fields, data) = BinaryWriter::new().encoded(&event);
-                    let fields = encode_fields(&fields);
-
-                    self.schedule_event(DispatchEvent {
-                        id: CELL_UNLOAD,
-                        data,
-                        fields,
-                    });
+                    self.schedule_encoded_event(CELL_UNLOAD, &event);
                     continue;
                 }
                 _ => continue,
@@ -211,7 +284,7 @@ impl Executor {
                 self.invocations.push_back(Invocation {
                     script: entry.script,
                     fn_ptr: entry.fn_ptr,
-                    host_buffers: vec![action_buffer, empty_buffer],
+                    host_buffers: self.host_buffer_lists.alloc(&[action_buffer, empty_buffer]),
                     entity: Some(entity),
                 });
             }
@@ -234,12 +307,20 @@ impl Executor {
         );
 
         // TODO: Right now if two event handlers call each other unconditionally we will
-        // never stop scheduling more invocations and deadlock. We should implement some
-        // sort of cycle checks and stop when an event schedules an event from which the
-        // the event was dispatched from.
+        // never stop scheduling more invocations. The invocation limits prevent this from
+        // stalling the caller and from growing the queue without bound, but we should implement
+        // some sort of cycle checks and stop when an event schedules an event from which the the
+        // event was dispatched from.
 
-        while let Some(invocation) = self.invocations.pop_front() {
-            state.host_buffers = invocation.host_buffers;
+        let mut num_invocations = 0;
+        while num_invocations < self.max_invocations {
+            let Some(invocation) = self.invocations.pop_front() else {
+                break;
+            };
+            num_invocations += 1;
+
+            let host_buffers = std::mem::replace(&mut state.host_buffers, invocation.host_buffers);
+            self.host_buffer_lists.recycle(host_buffers);
 
             let runnable = self.instances.get(State::Run(state), invocation.script);
 
@@ -254,22 +335,73 @@ impl Executor {
             }
         }
 
-        self.host_buffer_pool.clear();
+        self.host_buffer_lists
+            .recycle(std::mem::take(&mut state.host_buffers));
+        self.prev_num_invocations = num_invocations;
+
+        if self.invocations.len() > self.max_deferred_invocations {
+            tracing::error!(
+                "reached limit of {} deferred script invocations, dropping {} invocations",
+                self.max_deferred_invocations,
+                self.invocations.len() - self.max_deferred_invocations,
+            );
+
+            for invocation in self.invocations.drain(self.max_deferred_invocations..) {
+                self.host_buffer_lists.recycle(invocation.host_buffers);
+            }
+        }
+
+        // Deferred invocations still reference their host buffers, so only
+        // the buffers of invocations that have already run can be recycled.
+        if self.invocations.is_empty() {
+            self.host_buffer_pool.clear();
+        } else {
+            tracing::warn!(
+                "reached limit of {} script invocations, deferring {} invocations to next update",
+                self.max_invocations,
+                self.invocations.len(),
+            );
+
+            self.host_buffer_pool.retain(
+                self.invocations
+                    .iter_mut()
+                    .map(|invocation| &mut invocation.host_buffers),
+            );
+        }
 
         effects
     }
 
+    /// Encodes `event` into buffers from the host buffer pool and schedules it.
+    fn schedule_encoded_event<T>(&mut self, id: RecordReference, event: &T)
+    where
+        T: Encode,
+    {
+        let (fields, data) =
+            BinaryWriter::with_buffers(Vec::new(), self.host_buffer_pool.alloc()).encoded(event);
+
+        let mut encoded_fields = self.host_buffer_pool.alloc();
+        encode_fields_into(&fields, &mut encoded_fields);
+
+        self.schedule_event(DispatchEvent {
+            id,
+            data,
+            fields: encoded_fields,
+        });
+    }
+
     fn schedule_event(&mut self, event: DispatchEvent) {
         tracing::debug!("scheduling event {:?}", event);
 
-        let Some(handlers) = self.event_handlers.get(&event.id) else {
-            return;
+        let handlers = match self.event_handlers.get(&event.id) {
+            Some(handlers) if !handlers.is_empty() => handlers,
+            _ => {
+                self.host_buffer_pool.recycle(event.data);
+                self.host_buffer_pool.recycle(event.fields);
+                return;
+            }
         };
 
-        if handlers.is_empty() {
-            return;
-        }
-
         let data = self.host_buffer_pool.insert(event.data);
         let fields = self.host_buffer_pool.insert(event.fields);
 
@@ -279,7 +411,7 @@ impl Executor {
             self.invocations.push_back(Invocation {
                 script: handler.script,
                 fn_ptr: handler.fn_ptr,
-                host_buffers: vec![data, fields],
+                host_buffers: self.host_buffer_lists.alloc(&[data, fields]),
                 entity: None,
             });
         }
@@ -306,6 +438,47 @@ struct Invocation {
     entity: Option<EntityId>,
 }
 
+/// A pool of the lists of host buffer indices used by [`Invocation`]s.
+#[derive(Clone, Debug)]
+struct HostBufferListPool {
+    free: Vec<Vec<usize>>,
+    /// The maximum number of recycled lists retained in `free`.
+    max_free: usize,
+}
+
+impl HostBufferListPool {
+    /// Creates a new, empty `HostBufferListPool` retaining at most `max_free` recycled lists.
+    fn new(max_free: usize) -> Self {
+        Self {
+            free: Vec::new(),
+            max_free,
+        }
+    }
+
+    /// Sets the maximum number of recycled lists retained, dropping all lists exceeding it.
+    fn set_max_free(&mut self, max_free: usize) {
+        self.max_free = max_free;
+        self.free.truncate(max_free);
+    }
+
+    /// Returns a list containing `indices`, reusing a previously recycled list if possible.
+    fn alloc(&mut self, indices: &[usize]) -> Vec<usize> {
+        let mut list = self.free.pop().unwrap_or_default();
+        list.extend_from_slice(indices);
+        list
+    }
+
+    /// Returns the list `list` to the pool.
+    fn recycle(&mut self, mut list: Vec<usize>) {
+        if list.capacity() == 0 || self.free.len() >= self.max_free {
+            return;
+        }
+
+        list.clear();
+        self.free.push(list);
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Handle(Key);
 
@@ -319,6 +492,54 @@ pub struct Context<'a> {
 pub trait WorldProvider: 'static {
     fn world(&self) -> &World;
     fn player(&self, id: EntityId) -> Option<PlayerId>;
+
+    /// Returns all entities controlled by the given `player`.
+    ///
+    /// The default implementation checks the [`player`] of every entity in the [`world`].
+    /// Implementors that keep an index of the entities of every player should override it.
+    ///
+    /// [`player`]: Self::player
+    /// [`world`]: Self::world
+    fn entities_controlled_by(&self, player: PlayerId) -> Vec<EntityId> {
+        self.world()
+            .entities()
+            .filter(|entity| self.player(*entity) == Some(player))
+            .collect()
+    }
+
+    /// Returns all entities owned by the given `player`.
+    ///
+    /// A player owns the entities it controls and all their children. Entities controlled by
+    /// another player are shared and are never owned by `player`, together with all their
+    /// children.
+    fn entities_owned_by(&self, player: PlayerId) -> Vec<EntityId> {
+        let world = self.world();
+
+        let mut entities = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = self.entities_controlled_by(player);
+
+        while let Some(entity) = queue.pop() {
+            // Children may be shared between multiple parents or form a cycle.
+            if !visited.insert(entity) {
+                continue;
+            }
+
+            entities.push(entity);
+
+            let Ok(children) = world.get_typed::<Children>(entity) else {
+                continue;
+            };
+
+            for child in children.get() {
+                if world.contains(*child) && self.player(*child).is_none() {
+                    queue.push(*child);
+                }
+            }
+        }
+
+        entities
+    }
 }
 
 pub trait RecordProvider: 'static {
@@ -331,6 +552,24 @@ pub trait RecordProvider: 'static {
 struct Entry {
     script: Handle,
     fn_ptr: Pointer,
+    priority: i32,
+}
+
+/// Sorts the `handlers` of an action or event in the order they are run.
+///
+/// See [`DEFAULT_HANDLER_PRIORITY`] for the ordering contract.
+fn sort_handlers(handlers: &mut [Entry], scripts: &Arena<Script>) {
+    // Handlers of newly loaded scripts are appended and the sort is
+    // stable, which keeps handlers of scripts without an id in load order
+    // and handlers of the same script in registration order.
+    handlers.sort_by_cached_key(|entry| {
+        let id = scripts.get(entry.script.0).and_then(|script| script.id);
+        (
+            Reverse(entry.priority),
+            id.is_none(),
+            id.map(|id| (id.module.into_bytes(), id.record.0)),
+        )
+    });
 }
 
 #[derive(Clone, Debug)]
@@ -359,3 +598,560 @@ impl Debug for Pointer {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Error)]
 #[error("no script with the given handle")]
 pub struct InvalidHandle;
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use game_common::components::actions::ActionId;
+    use game_common::components::components::RawComponent;
+    use game_common::components::{Component, Transform};
+    use game_common::entity::EntityId;
+    use game_common::events::{ActionEvent, Event, EventQueue, PlayerConnect};
+    use game_common::record::{ModuleId, RecordId, RecordReference};
+    use game_common::world::World;
+    use game_data::record::{Record, RecordKind};
+    use game_physics::Pipeline;
+    use game_prefab::Prefab;
+    use game_wasm::encoding::{BinaryReader, Decode};
+    use game_wasm::events::PLAYER_CONNECT;
+    use game_wasm::player::PlayerId;
+    use game_wasm::record::ModuleId as WasmModuleId;
+    use glam::{Quat, Vec3};
+
+    use crate::effect::Effect;
+    use crate::test_support::{
+        allocations, wat_bytes, wat_record_reference, TestRecords, TestWorld,
+    };
+    use crate::{Context, Executor, RecordProvider};
+
+    const ACTION: RecordReference = RecordReference::STUB;
+    const PREFAB: RecordReference = RecordReference {
+        module: ModuleId::CORE,
+        record: RecordId(1),
+    };
+
+    /// Returns a script that registers an empty handler for the `PLAYER_CONNECT` event.
+    fn player_connect_script() -> String {
+        let id = wat_record_reference(PLAYER_CONNECT);
+
+        format!(
+            r#"
+            (module
+                (import "host" "register_event_handler" (func $register (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{id}")
+                (func (export "on_init")
+                    (call $register (i32.const 16) (i32.const 1)))
+                (func (export "__wasm_fn_trampoline") (param i32 i64))
+            )
+            "#
+        )
+    }
+
+    /// Returns the number of allocations made by a single [`Executor::update`] call that
+    /// dispatches `num_events` `PLAYER_CONNECT` events.
+    fn update_player_connect(executor: &mut Executor, num_events: u64) -> usize {
+        let world = TestWorld(World::new());
+        let physics = Pipeline::new();
+        let mut events = EventQueue::new();
+        for index in 0..num_events {
+            events.push(Event::PlayerConnect(PlayerConnect {
+                player: PlayerId::from_raw(index),
+            }));
+        }
+
+        let start = allocations();
+        executor.update(Context {
+            world: &world,
+            physics: &physics,
+            events: &mut events,
+            records: &TestRecords,
+        });
+        allocations() - start
+    }
+
+    /// Asserts that updates dispatching `num_events` events reuse the host buffers of the
+    /// previous update.
+    fn assert_host_buffers_reused(executor: &mut Executor, num_events: u64) {
+        let first = update_player_connect(executor, num_events);
+        let second = update_player_connect(executor, num_events);
+
+        // All buffers from the previous update are recycled, so following updates
+        // must not allocate the event buffers again.
+        for _ in 0..4 {
+            let allocations = update_player_connect(executor, num_events);
+            assert_eq!(allocations, second);
+            assert!(
+                allocations + 2 * num_events as usize <= first,
+                "update allocated {} times, first update allocated {} times",
+                allocations,
+                first,
+            );
+        }
+    }
+
+    #[test]
+    fn host_buffers_reused_across_updates() {
+        let mut executor = Executor::new();
+        executor.load(player_connect_script().as_bytes()).unwrap();
+
+        assert_host_buffers_reused(&mut executor, 32);
+        // The pools retain the buffers of as many events as can be
+        // processed in a single update.
+        assert_host_buffers_reused(&mut executor, 4096);
+    }
+
+    #[test]
+    fn host_buffers_bounded_by_max_invocations() {
+        const MAX_INVOCATIONS: usize = 64;
+
+        let mut executor = Executor::builder()
+            .max_invocations(MAX_INVOCATIONS)
+            .build()
+            .unwrap();
+        executor.load(player_connect_script().as_bytes()).unwrap();
+
+        assert_host_buffers_reused(&mut executor, MAX_INVOCATIONS as u64);
+        let steady = update_player_connect(&mut executor, MAX_INVOCATIONS as u64);
+
+        // Events exceeding the limit are deferred to the following updates,
+        // which never need more buffers than the pools retain.
+        update_player_connect(&mut executor, MAX_INVOCATIONS as u64 * 4);
+        for _ in 0..4 {
+            assert_eq!(update_player_connect(&mut executor, 0), 0);
+        }
+
+        for _ in 0..4 {
+            let allocations = update_player_connect(&mut executor, MAX_INVOCATIONS as u64);
+            assert_eq!(allocations, steady);
+        }
+    }
+
+    /// Returns a script that registers an empty handler for the `ACTION` action.
+    fn action_script() -> String {
+        let id = wat_record_reference(ACTION);
+
+        format!(
+            r#"
+            (module
+                (import "host" "register_action_handler" (func $register (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{id}")
+                (func (export "on_init")
+                    (call $register (i32.const 16) (i32.const 1)))
+                (func (export "__wasm_fn_trampoline") (param i32 i64))
+            )
+            "#
+        )
+    }
+
+    /// Returns the number of allocations made by a single [`Executor::update`] call that
+    /// dispatches `num_events` `ACTION` actions.
+    fn update_action(executor: &mut Executor, world: &TestWorld, num_events: u64) -> usize {
+        let physics = Pipeline::new();
+        let mut events = EventQueue::new();
+        for index in 0..num_events {
+            events.push(Event::Action(ActionEvent {
+                entity: EntityId::from_raw(index),
+                invoker: EntityId::from_raw(index),
+                action: ActionId(ACTION),
+                data: Vec::new(),
+            }));
+        }
+
+        let start = allocations();
+        executor.update(Context {
+            world,
+            physics: &physics,
+            events: &mut events,
+            records: &TestRecords,
+        });
+        allocations() - start
+    }
+
+    #[test]
+    fn invocations_warm_update_does_not_allocate() {
+        const NUM_EVENTS: u64 = 32;
+
+        let world = TestWorld(World::new());
+
+        let mut executor = Executor::new();
+        executor.load(action_script().as_bytes()).unwrap();
+
+        // The first updates populate the pools.
+        update_action(&mut executor, &world, NUM_EVENTS);
+        update_action(&mut executor, &world, NUM_EVENTS);
+
+        for _ in 0..4 {
+            let allocations = update_action(&mut executor, &world, NUM_EVENTS);
+            assert_eq!(allocations, 0);
+        }
+    }
+
+    /// Returns a script that registers a handler for the `ACTION` action for every
+    /// `(fn_ptr, priority)` pair.
+    ///
+    /// Every handler inserts a component containing its `fn_ptr` on the entity of the action.
+    fn action_priority_script(handlers: &[(u32, i32)]) -> String {
+        let id = wat_record_reference(ACTION);
+
+        let mut register = String::new();
+        for (fn_ptr, priority) in handlers {
+            write!(
+                register,
+                "(call $register (i32.const 16) (i32.const {fn_ptr}) (i32.const {priority}))"
+            )
+            .unwrap();
+        }
+
+        format!(
+            r#"
+            (module
+                (import "host" "register_action_handler_with_priority" (func $register (param i32 i32 i32)))
+                (import "host" "world_entity_component_insert" (func $insert (param i64 i32 i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{id}")
+                (func (export "on_init")
+                    {register})
+                (func (export "__wasm_fn_trampoline") (param i32 i64)
+                    (i32.store8 (i32.const 64) (local.get 0))
+                    (drop (call $insert (local.get 1) (i32.const 16) (i32.const 64) (i32.const 1) (i32.const 0) (i32.const 0))))
+            )
+            "#
+        )
+    }
+
+    /// Dispatches a single `ACTION` action and returns the `fn_ptr`s of the handlers in the
+    /// order they were run.
+    fn dispatch_action(executor: &mut Executor) -> Vec<u8> {
+        let mut world = World::new();
+        let entity = world.spawn();
+        let world = TestWorld(world);
+
+        let physics = Pipeline::new();
+        let mut events = EventQueue::new();
+        events.push(Event::Action(ActionEvent {
+            entity,
+            invoker: entity,
+            action: ActionId(ACTION),
+            data: Vec::new(),
+        }));
+
+        let effects = executor.update(Context {
+            world: &world,
+            physics: &physics,
+            events: &mut events,
+            records: &TestRecords,
+        });
+
+        effects
+            .into_iter()
+            .map(|effect| match effect {
+                Effect::EntityComponentInsert(insert) => insert.component.as_bytes()[0],
+                _ => panic!("unexpected effect: {:?}", effect),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn handler_priority_order() {
+        let mut executor = Executor::new();
+        executor
+            .load(action_priority_script(&[(1, 0)]).as_bytes())
+            .unwrap();
+        executor
+            .load(action_priority_script(&[(2, -5), (3, 10), (4, 10)]).as_bytes())
+            .unwrap();
+        executor
+            .load(action_priority_script(&[(5, 0)]).as_bytes())
+            .unwrap();
+
+        // Higher priorities run first, handlers with the same priority in the
+        // order of their script and registration.
+        assert_eq!(dispatch_action(&mut executor), [3, 4, 1, 5, 2]);
+    }
+
+    #[test]
+    fn handler_priority_load_order() {
+        let scripts = [
+            action_priority_script(&[(1, 0)]),
+            action_priority_script(&[(2, 0)]),
+            action_priority_script(&[(3, 5), (4, -5)]),
+        ];
+
+        let mut forward = Executor::new();
+        for script in &scripts {
+            forward.load(script.as_bytes()).unwrap();
+        }
+
+        let mut reverse = Executor::new();
+        for script in scripts.iter().rev() {
+            reverse.load(script.as_bytes()).unwrap();
+        }
+
+        // Different priorities run in the same order, handlers with the same
+        // priority in the order their scripts were loaded.
+        assert_eq!(dispatch_action(&mut forward), [3, 1, 2, 4]);
+        assert_eq!(dispatch_action(&mut reverse), [3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn handler_priority_record_id_order() {
+        let script_id = |record| RecordReference {
+            module: ModuleId::CORE,
+            record: RecordId(record),
+        };
+        let scripts = [
+            (script_id(2), action_priority_script(&[(1, 0)])),
+            (script_id(1), action_priority_script(&[(2, 0), (3, 0)])),
+            (script_id(3), action_priority_script(&[(4, 0)])),
+        ];
+
+        let mut forward = Executor::new();
+        for (id, script) in &scripts {
+            forward.load_record(*id, script.as_bytes()).unwrap();
+        }
+
+        let mut reverse = Executor::new();
+        reverse
+            .load(action_priority_script(&[(5, 0)]).as_bytes())
+            .unwrap();
+        for (id, script) in scripts.iter().rev() {
+            reverse.load_record(*id, script.as_bytes()).unwrap();
+        }
+
+        // Handlers with the same priority run in the order of their script
+        // ids independent of the load order. Scripts without an id run last.
+        assert_eq!(dispatch_action(&mut forward), [2, 3, 1, 4]);
+        assert_eq!(dispatch_action(&mut reverse), [2, 3, 1, 4, 5]);
+    }
+
+    #[test]
+    fn handler_priority_load_order_after_unload() {
+        let mut executor = Executor::new();
+        let first = executor
+            .load(action_priority_script(&[(1, 0)]).as_bytes())
+            .unwrap();
+        executor
+            .load(action_priority_script(&[(2, 0)]).as_bytes())
+            .unwrap();
+        executor.unload(first).unwrap();
+
+        // The script may reuse the slot of the unloaded script, but still runs
+        // after all scripts loaded before it.
+        executor
+            .load(action_priority_script(&[(3, 0)]).as_bytes())
+            .unwrap();
+
+        assert_eq!(dispatch_action(&mut executor), [2, 3]);
+    }
+
+    /// Returns a script that registers a handler for the `ACTION` action that inserts a
+    /// component containing the first byte of the action data on the entity of the action.
+    fn action_data_script() -> String {
+        let id = wat_record_reference(ACTION);
+
+        format!(
+            r#"
+            (module
+                (import "host" "register_action_handler" (func $register (param i32 i32)))
+                (import "host" "host_buffer_get" (func $host_buffer_get (param i32 i32)))
+                (import "host" "world_entity_component_insert" (func $insert (param i64 i32 i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{id}")
+                (func (export "on_init")
+                    (call $register (i32.const 16) (i32.const 1)))
+                (func (export "__wasm_fn_trampoline") (param i32 i64)
+                    (call $host_buffer_get (i32.const 0) (i32.const 64))
+                    (drop (call $insert (local.get 1) (i32.const 16) (i32.const 64) (i32.const 1) (i32.const 0) (i32.const 0))))
+            )
+            "#
+        )
+    }
+
+    /// Dispatches an `ACTION` action for every byte in `data` and returns the first data byte of
+    /// the actions whose handlers were run.
+    fn dispatch_action_data(executor: &mut Executor, world: &TestWorld, data: &[u8]) -> Vec<u8> {
+        let physics = Pipeline::new();
+        let mut events = EventQueue::new();
+        for byte in data {
+            events.push(Event::Action(ActionEvent {
+                entity: EntityId::from_raw(0),
+                invoker: EntityId::from_raw(0),
+                action: ActionId(ACTION),
+                data: vec![*byte],
+            }));
+        }
+
+        let effects = executor.update(Context {
+            world,
+            physics: &physics,
+            events: &mut events,
+            records: &TestRecords,
+        });
+
+        effects
+            .into_iter()
+            .map(|effect| match effect {
+                Effect::EntityComponentInsert(insert) => insert.component.as_bytes()[0],
+                _ => panic!("unexpected effect: {:?}", effect),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn invocations_deferred_in_order() {
+        let mut world = World::new();
+        world.spawn();
+        let world = TestWorld(world);
+
+        let mut executor = Executor::builder().max_invocations(2).build().unwrap();
+        executor.load(action_data_script().as_bytes()).unwrap();
+
+        assert_eq!(
+            dispatch_action_data(&mut executor, &world, &[0, 1, 2, 3, 4]),
+            [0, 1]
+        );
+
+        // Deferred invocations run before invocations scheduled in a later
+        // update and still see their own host buffers.
+        assert_eq!(dispatch_action_data(&mut executor, &world, &[5]), [2, 3]);
+        assert_eq!(dispatch_action_data(&mut executor, &world, &[]), [4, 5]);
+        assert_eq!(dispatch_action_data(&mut executor, &world, &[]), []);
+    }
+
+    #[test]
+    fn invocations_deferred_limit() {
+        let mut world = World::new();
+        world.spawn();
+        let world = TestWorld(world);
+
+        let mut executor = Executor::builder()
+            .max_invocations(2)
+            .max_deferred_invocations(2)
+            .build()
+            .unwrap();
+        executor.load(action_data_script().as_bytes()).unwrap();
+
+        // The invocations scheduled last are dropped once the deferred
+        // invocations exceed the limit.
+        assert_eq!(
+            dispatch_action_data(&mut executor, &world, &[0, 1, 2, 3, 4, 5]),
+            [0, 1]
+        );
+        assert_eq!(dispatch_action_data(&mut executor, &world, &[6]), [2, 3]);
+        assert_eq!(dispatch_action_data(&mut executor, &world, &[]), [6]);
+        assert_eq!(dispatch_action_data(&mut executor, &world, &[]), []);
+    }
+
+    /// Provides a single prefab record with the id `PREFAB`.
+    struct PrefabRecords(Record);
+
+    impl RecordProvider for PrefabRecords {
+        fn get(&self, id: RecordReference) -> Option<&Record> {
+            (id == PREFAB).then_some(&self.0)
+        }
+
+        fn iter(&self) -> Box<dyn Iterator<Item = (WasmModuleId, &Record)> + '_> {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    #[test]
+    fn prefab_spawn_inserts_transform() {
+        let mut prefab_world = World::new();
+        let entity = prefab_world.spawn();
+        prefab_world.insert(entity, ACTION, RawComponent::new(vec![1], []));
+
+        let mut prefab = Prefab::new();
+        prefab.add(entity, &prefab_world);
+        let records = PrefabRecords(Record {
+            id: PREFAB.record,
+            kind: RecordKind::PREFAB,
+            name: String::new(),
+            description: String::new(),
+            data: prefab.to_bytes(),
+        });
+
+        let transform = Transform {
+            translation: Vec3::new(1.0, 2.0, 3.0),
+            rotation: Quat::from_rotation_y(1.0),
+            scale: Vec3::splat(2.0),
+        };
+        let mut raw_transform = [0.0; 10];
+        raw_transform[0..3].copy_from_slice(&transform.translation.to_array());
+        raw_transform[3..7].copy_from_slice(&transform.rotation.to_array());
+        raw_transform[7..10].copy_from_slice(&transform.scale.to_array());
+
+        let script = format!(
+            r#"
+            (module
+                (import "host" "register_action_handler" (func $register (param i32 i32)))
+                (import "host" "prefab_spawn" (func $spawn (param i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 16) "{action}")
+                (data (i32.const 48) "{prefab}")
+                (data (i32.const 80) "{transform}")
+                (func (export "on_init")
+                    (call $register (i32.const 16) (i32.const 1)))
+                (func (export "__wasm_fn_trampoline") (param i32 i64)
+                    (drop (call $spawn (i32.const 48) (i32.const 80) (i32.const 128))))
+            )
+            "#,
+            action = wat_record_reference(ACTION),
+            prefab = wat_record_reference(PREFAB),
+            transform = wat_bytes(bytemuck::bytes_of(&raw_transform)),
+        );
+
+        let mut executor = Executor::new();
+        executor.load(script.as_bytes()).unwrap();
+
+        let world = TestWorld(World::new());
+        let physics = Pipeline::new();
+        let mut events = EventQueue::new();
+        events.push(Event::Action(ActionEvent {
+            entity: EntityId::from_raw(0),
+            invoker: EntityId::from_raw(0),
+            action: ActionId(ACTION),
+            data: Vec::new(),
+        }));
+
+        let effects = executor.update(Context {
+            world: &world,
+            physics: &physics,
+            events: &mut events,
+            records: &records,
+        });
+
+        let mut spawned = Vec::new();
+        let mut inserted = Vec::new();
+        for effect in effects.into_iter() {
+            match effect {
+                Effect::EntitySpawn(entity) => spawned.push(entity),
+                Effect::EntityComponentInsert(insert) => inserted.push(insert),
+                _ => panic!("unexpected effect: {:?}", effect),
+            }
+        }
+
+        // The prefab entity and the root entity of the prefab.
+        assert_eq!(spawned.len(), 2);
+        let root = spawned[1];
+
+        assert_eq!(inserted[0].entity, spawned[0]);
+        assert_eq!(inserted[0].component_id, ACTION);
+        assert_eq!(inserted[0].component.as_bytes(), &[1]);
+
+        let insert = inserted
+            .iter()
+            .find(|insert| insert.component_id == Transform::ID)
+            .unwrap();
+        assert_eq!(insert.entity, root);
+
+        let reader = BinaryReader::new(
+            insert.component.as_bytes().to_vec(),
+            insert.component.fields().to_vec().into(),
+        );
+        assert_eq!(Transform::decode(reader).unwrap(), transform);
+    }
+}
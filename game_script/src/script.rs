@@ -1,5 +1,6 @@
 use std::fmt::{self, Debug, Display, Formatter, Write};
 
+use game_common::record::RecordReference;
 use thiserror::Error;
 use wasmtime::{Engine, ExternType, Module, ValType};
 
@@ -33,10 +34,16 @@ pub enum ScriptLoadError {
 
 pub(crate) struct Script {
     pub module: Module,
+    /// The id of the record of the script, if any.
+    pub id: Option<RecordReference>,
 }
 
 impl Script {
-    pub fn new(bytes: &[u8], engine: &Engine) -> Result<Self, ScriptLoadError> {
+    pub fn new(
+        bytes: &[u8],
+        engine: &Engine,
+        id: Option<RecordReference>,
+    ) -> Result<Self, ScriptLoadError> {
         let module = Module::new(engine, bytes).map_err(ScriptLoadError::Module)?;
 
         for fn_sig in EXPORT_FUNCTIONS {
@@ -77,7 +84,7 @@ impl Script {
             }
         }
 
-        Ok(Self { module })
+        Ok(Self { module, id })
     }
 }
 
@@ -0,0 +1,57 @@
+//! Fixtures shared by the tests of this crate.
+
+use std::alloc::System;
+use std::fmt::Write;
+
+use game_common::entity::EntityId;
+use game_common::record::RecordReference;
+use game_common::world::World;
+use game_data::record::Record;
+use game_tracing::counting::CountingAllocator;
+use game_wasm::player::PlayerId;
+use game_wasm::record::ModuleId;
+
+use crate::{RecordProvider, WorldProvider};
+
+pub use game_tracing::counting::allocations;
+
+#[global_allocator]
+static GLOBAL: CountingAllocator<System> = CountingAllocator::new(System);
+
+pub struct TestWorld(pub World);
+
+impl WorldProvider for TestWorld {
+    fn world(&self) -> &World {
+        &self.0
+    }
+
+    fn player(&self, _: EntityId) -> Option<PlayerId> {
+        None
+    }
+}
+
+pub struct TestRecords;
+
+impl RecordProvider for TestRecords {
+    fn get(&self, _: RecordReference) -> Option<&Record> {
+        None
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = (ModuleId, &Record)> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
+
+/// Returns the bytes of `id` escaped for use in a WAT data segment.
+pub fn wat_record_reference(id: RecordReference) -> String {
+    wat_bytes(bytemuck::bytes_of(&id))
+}
+
+/// Returns the `bytes` escaped for use in a WAT data segment.
+pub fn wat_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::new();
+    for byte in bytes {
+        write!(escaped, "\\{:02x}", byte).unwrap();
+    }
+    escaped
+}
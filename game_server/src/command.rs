@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::fmt::Write;
 
 use game_core::command::{
@@ -12,6 +13,19 @@ pub enum Command {
 }
 
 impl Command {
+    /// Returns the [`Priority`] with which the command is processed.
+    pub fn priority(&self) -> Priority {
+        match self {
+            Self::Server(ServerCommand::Pause)
+            | Self::Server(ServerCommand::Resume)
+            | Self::Server(ServerCommand::Step(_)) => Priority::High,
+            Self::Server(ServerCommand::Uptime)
+            | Self::Server(ServerCommand::Clients)
+            | Self::Game(_)
+            | Self::Empty => Priority::Low,
+        }
+    }
+
     pub fn parse(tokens: &[Token<'_>]) -> Result<Self, ParseError> {
         match tokens.get(0) {
             Some(Token::Ident("help")) => {
@@ -19,12 +33,14 @@ impl Command {
             }
             Some(Token::Ident("server")) => match tokens.get(1) {
                 Some(Token::Dot) => match tokens.get(2) {
-                    Some(Token::Ident("uptime")) => Ok(Self::Server(ServerCommand::Uptime)),
-                    Some(Token::Ident("clients")) => Ok(Self::Server(ServerCommand::Clients)),
-                    Some(Token::Ident(ident)) => Err(ParseError::Msg(format!(
-                        "unknown command {} in server namesapce",
-                        ident
-                    ))),
+                    Some(Token::Ident(ident)) => match ServerCommand::parse(&tokens[2..]) {
+                        Ok(cmd) => Ok(Self::Server(cmd)),
+                        Err(ParseError::Empty) => Err(ParseError::Msg(format!(
+                            "unknown command {} in server namesapce",
+                            ident
+                        ))),
+                        Err(err) => Err(err),
+                    },
                     Some(token) => Err(ParseError::Msg(format!(
                         "expected {}, found {}",
                         TokenKind::Ident,
@@ -91,6 +107,65 @@ impl Command {
     }
 }
 
+/// The priority class of a [`Command`].
+///
+/// Commands with a higher priority are processed before commands with a lower priority.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Informational queries.
+    Low,
+    /// Commands controlling the server.
+    High,
+}
+
+/// A queue of pending commands ordered by [`Priority`].
+///
+/// Commands of the same priority are yielded in the order they were pushed.
+#[derive(Clone, Debug)]
+pub struct CommandQueue<T> {
+    low: VecDeque<T>,
+    high: VecDeque<T>,
+}
+
+impl<T> CommandQueue<T> {
+    /// Creates a new, empty `CommandQueue`.
+    pub fn new() -> Self {
+        Self {
+            low: VecDeque::new(),
+            high: VecDeque::new(),
+        }
+    }
+
+    /// Returns the number of pending commands.
+    pub fn len(&self) -> usize {
+        self.low.len() + self.high.len()
+    }
+
+    /// Returns `true` if no commands are pending.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pushes a new command with the given [`Priority`] to the back of its priority class.
+    pub fn push(&mut self, priority: Priority, cmd: T) {
+        match priority {
+            Priority::Low => self.low.push_back(cmd),
+            Priority::High => self.high.push_back(cmd),
+        }
+    }
+
+    /// Removes the oldest command of the highest non-empty priority class.
+    pub fn pop(&mut self) -> Option<T> {
+        self.high.pop_front().or_else(|| self.low.pop_front())
+    }
+}
+
+impl<T> Default for CommandQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 fn display_command_list(namespace: Option<&str>, cmds: &[CommandDescriptor]) -> String {
     let mut buf = String::new();
     match namespace {
@@ -104,3 +179,25 @@ fn display_command_list(namespace: Option<&str>, cmds: &[CommandDescriptor]) ->
 
     buf
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandQueue, Priority};
+
+    #[test]
+    fn command_queue_priority_order() {
+        let mut queue = CommandQueue::new();
+        queue.push(Priority::Low, 0);
+        queue.push(Priority::High, 1);
+        queue.push(Priority::Low, 2);
+        queue.push(Priority::High, 3);
+        assert_eq!(queue.len(), 4);
+
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+}
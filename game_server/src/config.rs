@@ -1,8 +1,9 @@
 use std::fs::File;
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::Utf8Error;
 
+use game_net::conn::DEFAULT_IDLE_TIMEOUT;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -10,6 +11,15 @@ use thiserror::Error;
 pub struct Config {
     pub timestep: u32,
     pub player_streaming_source_distance: u32,
+    /// The directory in which the entities of unloaded cells are saved.
+    ///
+    /// If `None` the entities of unloaded cells are discarded.
+    #[serde(default)]
+    pub save_directory: Option<PathBuf>,
+    /// The number of seconds without receiving any packets after which a client is
+    /// disconnected.
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: u64,
 }
 
 impl Config {
@@ -47,10 +57,16 @@ impl Default for Config {
         Self {
             timestep: 60,
             player_streaming_source_distance: 2,
+            save_directory: None,
+            idle_timeout: default_idle_timeout(),
         }
     }
 }
 
+fn default_idle_timeout() -> u64 {
+    DEFAULT_IDLE_TIMEOUT.as_secs()
+}
+
 #[derive(Debug, Error)]
 pub enum LoadConfigError {
     #[error(transparent)]
@@ -1,15 +1,22 @@
 use std::borrow::Borrow;
+use std::collections::VecDeque;
 use std::iter::FusedIterator;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
 use ahash::HashMap;
 use game_net::conn::ConnectionHandle;
-use game_net::message::MessageId;
+use game_net::message::{ControlMessage, Message, MessageId};
 use parking_lot::{Mutex, RwLock};
 
 use crate::net::state::ConnectionState;
 
+/// The maximum number of inputs buffered per connection while the server tick is paused.
+///
+/// Further inputs are dropped, otherwise all of them would be applied in the first tick after
+/// the server is resumed.
+const MAX_BUFFERED_INPUTS: usize = 64;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ConnectionKey {
     pub local_addr: SocketAddr,
@@ -35,6 +42,7 @@ impl Connections {
                     state: RwLock::new(ConnectionState::new()),
                     handle,
                     messages_in_frame: Mutex::new(vec![]),
+                    backlog: Mutex::new(VecDeque::new()),
                 }),
             },
         );
@@ -128,6 +136,43 @@ impl Connection {
     pub fn take_messages_in_frame(&self) -> Vec<MessageId> {
         std::mem::take(&mut *self.inner.messages_in_frame.lock())
     }
+
+    /// Receives the next message from the connection, including buffered messages.
+    pub fn recv(&self) -> Option<Message> {
+        if let Some(msg) = self.inner.backlog.lock().pop_front() {
+            return Some(msg);
+        }
+
+        self.handle().recv()
+    }
+
+    /// Moves all messages received from the connection into the backlog.
+    ///
+    /// ACKs are dropped since they are only meaningful in the frame they are received. Inputs
+    /// exceeding [`MAX_BUFFERED_INPUTS`] are dropped, but still acknowledged in the next frame
+    /// so that the peer stops predicting them.
+    pub fn buffer_messages(&self) {
+        let mut backlog = self.inner.backlog.lock();
+
+        let mut num_inputs = backlog
+            .iter()
+            .filter(|msg| matches!(msg, Message::Data(_)))
+            .count();
+
+        while let Some(msg) = self.handle().recv() {
+            match msg {
+                Message::Control(ControlMessage::Ack(_)) => continue,
+                Message::Data(msg) if num_inputs >= MAX_BUFFERED_INPUTS => {
+                    self.push_message_in_frame(msg.id);
+                    continue;
+                }
+                Message::Data(_) => num_inputs += 1,
+                Message::Control(_) => (),
+            }
+
+            backlog.push_back(msg);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -136,4 +181,6 @@ struct ConnectionInner {
     handle: Arc<ConnectionHandle>,
     state: RwLock<ConnectionState>,
     messages_in_frame: Mutex<Vec<MessageId>>,
+    /// Messages received while the server tick was paused.
+    backlog: Mutex<VecDeque<Message>>,
 }
@@ -11,11 +11,12 @@ pub mod world;
 use std::fmt::Write;
 use std::time::{Duration, Instant};
 
-use command::Command;
+use command::{Command, CommandQueue};
 use game_common::events::EventQueue;
 use game_core::command::{GameCommand, ServerCommand};
 use game_core::counter::{Interval, UpdateCounter};
 use game_core::modules::Modules;
+use game_prefab::save::WorldSave;
 use game_script::Executor;
 use game_tasks::TaskPool;
 use server::ConnectionPool;
@@ -60,8 +61,15 @@ pub async fn run(mut state: ServerState) {
         interval.wait(now).await;
 
         process_commands(&mut state);
-        tick(&mut state);
+        if !state.advance_tick() {
+            buffer_messages(&state);
+            continue;
+        }
+
+        tick(&mut state).await;
 
+        // The control frame must only be incremented once the full tick
+        // has completed.
         state.state.control_frame.inc();
         let cf = state.state.control_frame.get();
 
@@ -71,10 +79,19 @@ pub async fn run(mut state: ServerState) {
     }
 }
 
+/// The maximum number of commands processed at once.
+///
+/// Commands are processed before every tick and between the phases of a tick. Remaining commands
+/// stay queued until commands are processed the next time, so a flood of commands can't starve
+/// the simulation.
+pub const MAX_COMMANDS_PER_TICK: usize = 16;
+
 pub struct ServerState {
     /// Start time of the server.
     pub start: Instant,
     pub command_queue: mpsc::Receiver<(Command, oneshot::Sender<String>)>,
+    /// Commands received but not yet processed.
+    pub pending_commands: CommandQueue<(Command, oneshot::Sender<String>)>,
     pub world: WorldState,
     pub level: world::level::Level,
     pub pipeline: game_physics::Pipeline,
@@ -84,6 +101,10 @@ pub struct ServerState {
     pub script_executor: Executor,
     pub pool: TaskPool,
     pub next_player: u64,
+    /// Whether the server tick is paused.
+    pub paused: bool,
+    /// The number of control frames to advance while `paused`.
+    pub pending_steps: u32,
 }
 
 impl ServerState {
@@ -93,11 +114,17 @@ impl ServerState {
         config: Config,
         executor: Executor,
     ) -> Self {
+        let mut level = world::level::Level::new();
+        if let Some(path) = &config.save_directory {
+            level.set_save(WorldSave::new(path));
+        }
+
         Self {
             start: Instant::now(),
             command_queue: command_handler,
+            pending_commands: CommandQueue::new(),
             world: WorldState::new(),
-            level: world::level::Level::new(),
+            level,
             pipeline: game_physics::Pipeline::new(),
             event_queue: EventQueue::new(),
             modules,
@@ -105,7 +132,25 @@ impl ServerState {
             script_executor: executor,
             pool: TaskPool::new(8),
             next_player: 0,
+            paused: false,
+            pending_steps: 0,
+        }
+    }
+
+    /// Returns `true` if the next tick should run.
+    ///
+    /// While the server is paused this only returns `true` for pending steps.
+    fn advance_tick(&mut self) -> bool {
+        if !self.paused {
+            return true;
+        }
+
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            return true;
         }
+
+        false
     }
 
     pub fn connections(&self) -> ConnectionPool {
@@ -127,7 +172,12 @@ impl ServerState {
             interval.wait(now).await;
 
             process_commands(&mut self);
-            tick(&mut self);
+            if !self.advance_tick() {
+                buffer_messages(&self);
+                continue;
+            }
+
+            tick(&mut self).await;
 
             self.state.control_frame.inc();
             let cf = self.state.control_frame.get();
@@ -142,7 +192,21 @@ impl ServerState {
 fn process_commands(state: &mut ServerState) {
     let _span = trace_span!("process_commands").entered();
 
-    while let Ok((cmd, tx)) = state.command_queue.try_recv() {
+    // Only pull as many commands as can be processed in this tick. The rest
+    // stays in the bounded channel so senders still see backpressure.
+    while state.pending_commands.len() < MAX_COMMANDS_PER_TICK {
+        let Ok((cmd, tx)) = state.command_queue.try_recv() else {
+            break;
+        };
+
+        state.pending_commands.push(cmd.priority(), (cmd, tx));
+    }
+
+    for _ in 0..MAX_COMMANDS_PER_TICK {
+        let Some((cmd, tx)) = state.pending_commands.pop() else {
+            break;
+        };
+
         match cmd {
             Command::Server(ServerCommand::Uptime) => {
                 let elapsed = state.start.elapsed();
@@ -199,7 +263,37 @@ fn process_commands(state: &mut ServerState) {
 
                 tx.send(resp).unwrap();
             }
+            Command::Server(ServerCommand::Pause) => {
+                state.paused = true;
+                state.pending_steps = 0;
+
+                let cf = state.state.control_frame.get();
+                tx.send(format!("Paused at {:?}", cf)).unwrap();
+            }
+            Command::Server(ServerCommand::Resume) => {
+                state.paused = false;
+                state.pending_steps = 0;
+
+                tx.send("Resumed".to_owned()).unwrap();
+            }
+            Command::Server(ServerCommand::Step(n)) => {
+                state.paused = true;
+                state.pending_steps = state.pending_steps.saturating_add(n);
+
+                let cf = state.state.control_frame.get();
+                tx.send(format!("Stepping {} control frames from {:?}", n, cf))
+                    .unwrap();
+            }
             Command::Empty => {}
         }
     }
 }
+
+/// Buffers the messages of all connections while the tick is paused.
+///
+/// The connections must still be drained, otherwise they are closed once their buffers are full.
+fn buffer_messages(state: &ServerState) {
+    for conn in state.state.conns.iter() {
+        conn.buffer_messages();
+    }
+}
@@ -57,6 +57,11 @@ impl Entities {
         id
     }
 
+    /// Returns an iterator over all entities and their [`ServerEntity`] ids.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, ServerEntity)> + '_ {
+        self.server.iter().map(|(local, id)| (*local, *id))
+    }
+
     pub fn clear(&mut self) {
         self.server.clear();
         self.client.clear();
@@ -2,24 +2,26 @@ use ahash::HashSet;
 use game_common::components::components::RawComponent;
 use game_common::components::{Global, GlobalTransform};
 use game_common::entity::EntityId;
-use game_common::net::ServerResource;
+use game_common::world::control_frame::ControlFrame;
 use game_common::world::{CellId, World};
-use game_net::message::{
-    DataMessageBody, EntityComponentAdd, EntityComponentRemove, EntityDestroy, ResourceCreate,
-    ResourceDestroy,
-};
+use game_net::message::{DataMessageBody, EntityDestroy};
 use tracing::trace_span;
 
 use crate::plugins::TickEvent;
 use crate::world::state::WorldState;
 
 use self::entities::Entities;
+use self::snapshot::Snapshot;
 use self::state::ConnectionState;
 
 pub mod entities;
+pub mod snapshot;
 pub mod state;
 
-/// Synchronize a player to the current `world`.
+/// Updates the entities known to a player from the events of the current tick.
+///
+/// Returns the messages for entities that are no longer known to the player. The state of all
+/// known entities is sent by [`update_snapshot`].
 pub(crate) fn sync_player(
     world: &WorldState,
     state: &mut ConnectionState,
@@ -66,22 +68,8 @@ pub(crate) fn sync_player(
             for entity in world.cell(*cell).entities() {
                 // Entities referencing this entity may have already caused it
                 // to be spawned.
-                let server_entity = match state.entities.get(entity) {
-                    Some(server_entity) => server_entity,
-                    None => state.entities.insert(entity),
-                };
-
-                for (id, component) in world.world.components(entity).iter() {
-                    let Some(component) = remap_component(&mut state.entities, component.clone())
-                    else {
-                        continue;
-                    };
-
-                    events.push(DataMessageBody::EntityComponentAdd(EntityComponentAdd {
-                        entity: server_entity,
-                        component_id: id,
-                        component,
-                    }));
+                if !state.entities.contains(entity) {
+                    state.entities.insert(entity);
                 }
             }
         }
@@ -128,74 +116,27 @@ pub(crate) fn sync_player(
                     entity: server_entity,
                 }));
             }
-            TickEvent::EntityComponentInsert(entity, id) => {
-                let Some(server_entity) = state.entities.get(*entity) else {
-                    continue;
-                };
-
-                let Some(component) = world.world.get(*entity, *id) else {
-                    continue;
-                };
-
-                let Some(component) = remap_component(&mut state.entities, component.clone())
-                else {
-                    continue;
-                };
-
-                events.push(DataMessageBody::EntityComponentAdd(EntityComponentAdd {
-                    entity: server_entity,
-                    component_id: *id,
-                    component,
-                }));
-            }
-            TickEvent::EntityComponentRemove(entity, id) => {
-                let Some(server_entity) = state.entities.get(*entity) else {
-                    continue;
-                };
-
-                events.push(DataMessageBody::EntityComponentRemove(
-                    EntityComponentRemove {
-                        entity: server_entity,
-                        component: *id,
-                    },
-                ));
-            }
-            TickEvent::ResourceCreate(id) | TickEvent::ResourceUpdate(id) => {
-                let Some(data) = world.world.get_resource(*id) else {
-                    continue;
-                };
-
-                events.push(DataMessageBody::ResourceCreate(ResourceCreate {
-                    id: ServerResource(id.to_bits()),
-                    data: data.to_vec(),
-                }));
-            }
-            TickEvent::ResourceDestroy(id) => {
-                events.push(DataMessageBody::ResourceDestroy(ResourceDestroy {
-                    id: ServerResource(id.to_bits()),
-                }));
-            }
+            // Component and resource changes are part of the snapshot delta.
+            TickEvent::EntityComponentInsert(_, _)
+            | TickEvent::EntityComponentRemove(_, _)
+            | TickEvent::ResourceCreate(_)
+            | TickEvent::ResourceUpdate(_)
+            | TickEvent::ResourceDestroy(_) => (),
         }
     }
 
     events
 }
 
-pub fn full_update(state: &mut ConnectionState, world: &World) -> Vec<DataMessageBody> {
+/// Resets the entities known to a player to all entities visible from the current cells.
+///
+/// This discards all previous snapshots, causing the full state to be sent by
+/// [`update_snapshot`].
+pub fn full_update(state: &mut ConnectionState, world: &World) {
     let _span = trace_span!("full_update").entered();
 
     state.entities.clear();
-    // state.known_entities.clear();
-    // state.known_resources.clear();
-
-    let mut events = Vec::new();
-
-    for (id, data) in world.iter_resources() {
-        events.push(DataMessageBody::ResourceCreate(ResourceCreate {
-            id: ServerResource(id.to_bits()),
-            data: data.to_vec(),
-        }));
-    }
+    state.snapshots.clear();
 
     for entity in world.entities() {
         let mut should_sync = false;
@@ -214,24 +155,33 @@ pub fn full_update(state: &mut ConnectionState, world: &World) -> Vec<DataMessag
 
         // Entities referencing this entity may have already caused it
         // to be spawned.
-        let server_entity = match state.entities.get(entity) {
-            Some(server_entity) => server_entity,
-            None => state.entities.insert(entity),
-        };
-
-        for (id, component) in world.components(entity).iter() {
-            let Some(component) = remap_component(&mut state.entities, component.clone()) else {
-                continue;
-            };
-
-            events.push(DataMessageBody::EntityComponentAdd(EntityComponentAdd {
-                entity: server_entity,
-                component_id: id,
-                component,
-            }));
+        if !state.entities.contains(entity) {
+            state.entities.insert(entity);
         }
     }
+}
+
+/// Captures the state of all entities known to a player in the given [`ControlFrame`].
+///
+/// Returns the messages required to update the player from the snapshot of the `acked_cf`
+/// baseline. If the `acked_cf` is unknown or no longer retained the previous baseline is used.
+/// Without any baseline the full state is returned.
+pub(crate) fn update_snapshot(
+    state: &mut ConnectionState,
+    world: &World,
+    cf: ControlFrame,
+    acked_cf: Option<ControlFrame>,
+) -> Vec<DataMessageBody> {
+    let _span = trace_span!("update_snapshot").entered();
+
+    let snapshot = Snapshot::capture(world, &mut state.entities);
+
+    if let Some(acked_cf) = acked_cf {
+        state.snapshots.acknowledge(acked_cf);
+    }
+    let events = state.snapshots.delta(&snapshot);
 
+    state.snapshots.push(cf, snapshot);
     events
 }
 
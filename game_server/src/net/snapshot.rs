@@ -0,0 +1,653 @@
+//! Delta compression of the state sent to clients.
+//!
+//! Every tick the state of all entities known to a client is captured in a [`Snapshot`]. Instead
+//! of sending the full state the server only sends the difference between the current snapshot
+//! and the snapshot of the newest control frame that the client has acknowledged. If no such
+//! baseline exists (the client has not acknowledged any frame yet) the difference to an empty
+//! snapshot, i.e. the full state, is sent.
+//!
+//! Since the client may not have received any changes sent after the acknowledged frame, these
+//! changes are sent again until a newer frame is acknowledged. Applying the same change twice
+//! has no effect on the client. The client may however have received components that were
+//! added and removed again after the baseline, so the removal of every component sent since the
+//! baseline is repeated as well.
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use game_common::components::components::RawComponent;
+use game_common::net::{ServerEntity, ServerResource};
+use game_common::record::RecordReference;
+use game_common::world::control_frame::ControlFrame;
+use game_common::world::World;
+use game_net::message::{
+    DataMessageBody, EntityComponentAdd, EntityComponentRemove, ResourceCreate, ResourceDestroy,
+};
+
+use super::entities::Entities;
+use super::remap_component;
+
+/// The maximum number of snapshots retained per connection in addition to the acknowledged
+/// baseline.
+///
+/// Snapshots older than the acknowledged baseline are dropped, so this is only reached if a
+/// client does not acknowledge a frame within this many frames. The oldest unacknowledged
+/// snapshots are dropped then and can no longer become the baseline, but the baseline itself is
+/// always retained.
+pub const MAX_SNAPSHOTS: usize = 64;
+
+/// The state of the world as known to a client.
+///
+/// Components share their data with the [`World`], so a snapshot only costs a few words per
+/// component.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    /// All captured entities, sorted by id.
+    entities: Vec<ServerEntity>,
+    /// The components of all captured entities, sorted by [`cmp_component`].
+    components: Vec<(ServerEntity, RecordReference, RawComponent)>,
+    /// All resources, sorted by id.
+    resources: Vec<(ServerResource, Arc<[u8]>)>,
+}
+
+impl Snapshot {
+    /// Creates a new, empty `Snapshot`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the components of all `entities` known to the client and all resources.
+    ///
+    /// Entities referenced by components are added to `entities`, but are only captured in the
+    /// next snapshot.
+    pub fn capture(world: &World, entities: &mut Entities) -> Self {
+        let mut snapshot = Self::new();
+
+        let known: Vec<_> = entities.iter().collect();
+        snapshot.entities.reserve(known.len());
+        for (entity, server_entity) in known {
+            if !world.contains(entity) {
+                continue;
+            }
+
+            snapshot.entities.push(server_entity);
+            for (id, component) in world.components(entity).iter() {
+                let Some(component) = remap_component(entities, component.clone()) else {
+                    continue;
+                };
+
+                snapshot.components.push((server_entity, id, component));
+            }
+        }
+
+        snapshot.entities.sort_unstable_by_key(|entity| entity.0);
+        snapshot.components.sort_unstable_by(cmp_component);
+
+        snapshot.resources.extend(
+            world
+                .iter_resources()
+                .map(|(id, data)| (ServerResource(id.to_bits()), data.clone())),
+        );
+        snapshot.resources.sort_unstable_by_key(|(id, _)| id.0);
+
+        snapshot
+    }
+
+    /// Returns the messages required to update a client from the `baseline` to `self`.
+    ///
+    /// Entities that are only contained in the `baseline` are ignored. Destroying entities is
+    /// not part of the delta.
+    pub fn delta(&self, baseline: &Snapshot) -> Vec<DataMessageBody> {
+        let mut events = Vec::new();
+
+        let mut prev = baseline.components.iter().peekable();
+        for next in &self.components {
+            // Components that only exist in the baseline were removed.
+            while let Some(old) = prev.next_if(|old| cmp_component(old, next).is_lt()) {
+                self.push_remove(&mut events, old);
+            }
+
+            if prev
+                .next_if(|old| cmp_component(old, next).is_eq())
+                .is_some_and(|old| old.2 == next.2)
+            {
+                continue;
+            }
+
+            events.push(DataMessageBody::EntityComponentAdd(EntityComponentAdd {
+                entity: next.0,
+                component_id: next.1,
+                component: next.2.clone(),
+            }));
+        }
+
+        for old in prev {
+            self.push_remove(&mut events, old);
+        }
+
+        let mut prev = baseline.resources.iter().peekable();
+        for (id, data) in &self.resources {
+            while let Some((old, _)) = prev.next_if(|(old, _)| old.0 < id.0) {
+                events.push(DataMessageBody::ResourceDestroy(ResourceDestroy {
+                    id: *old,
+                }));
+            }
+
+            if prev
+                .next_if(|(old, _)| old == id)
+                .is_some_and(|(_, old)| old == data)
+            {
+                continue;
+            }
+
+            events.push(DataMessageBody::ResourceCreate(ResourceCreate {
+                id: *id,
+                data: data.to_vec(),
+            }));
+        }
+
+        for (old, _) in prev {
+            events.push(DataMessageBody::ResourceDestroy(ResourceDestroy {
+                id: *old,
+            }));
+        }
+
+        events
+    }
+
+    /// Pushes the removal of all `sent` components and resources that exist neither in `self`
+    /// nor in the `baseline`.
+    ///
+    /// Components and resources that only exist in the `baseline` are already removed by
+    /// [`delta`].
+    ///
+    /// [`delta`]: Self::delta
+    fn push_sent_removals(
+        &self,
+        events: &mut Vec<DataMessageBody>,
+        baseline: &Snapshot,
+        sent: &SentKeys,
+    ) {
+        for (entity, id) in &sent.components {
+            let key = (entity.0, id.into_bytes());
+            let contains = |snapshot: &Snapshot| {
+                snapshot
+                    .components
+                    .binary_search_by(|(entity, id, _)| (entity.0, id.into_bytes()).cmp(&key))
+                    .is_ok()
+            };
+
+            if contains(self) || contains(baseline) {
+                continue;
+            }
+
+            if self
+                .entities
+                .binary_search_by_key(&entity.0, |entity| entity.0)
+                .is_ok()
+            {
+                events.push(DataMessageBody::EntityComponentRemove(
+                    EntityComponentRemove {
+                        entity: *entity,
+                        component: *id,
+                    },
+                ));
+            }
+        }
+
+        for id in &sent.resources {
+            let contains = |snapshot: &Snapshot| {
+                snapshot
+                    .resources
+                    .binary_search_by_key(&id.0, |(id, _)| id.0)
+                    .is_ok()
+            };
+
+            if contains(self) || contains(baseline) {
+                continue;
+            }
+
+            events.push(DataMessageBody::ResourceDestroy(ResourceDestroy {
+                id: *id,
+            }));
+        }
+    }
+
+    /// Pushes the removal of the `old` component if the entity still exists.
+    fn push_remove(
+        &self,
+        events: &mut Vec<DataMessageBody>,
+        old: &(ServerEntity, RecordReference, RawComponent),
+    ) {
+        if self
+            .entities
+            .binary_search_by_key(&old.0 .0, |entity| entity.0)
+            .is_ok()
+        {
+            events.push(DataMessageBody::EntityComponentRemove(
+                EntityComponentRemove {
+                    entity: old.0,
+                    component: old.1,
+                },
+            ));
+        }
+    }
+}
+
+/// The order of components within a [`Snapshot`].
+fn cmp_component(
+    lhs: &(ServerEntity, RecordReference, RawComponent),
+    rhs: &(ServerEntity, RecordReference, RawComponent),
+) -> Ordering {
+    (lhs.0 .0, lhs.1.into_bytes()).cmp(&(rhs.0 .0, rhs.1.into_bytes()))
+}
+
+/// The keys of all components and resources sent to a client.
+#[derive(Clone, Debug, Default)]
+struct SentKeys {
+    /// Sorted by [`cmp_component`].
+    components: Vec<(ServerEntity, RecordReference)>,
+    /// Sorted by id.
+    resources: Vec<ServerResource>,
+}
+
+impl SentKeys {
+    fn extend(&mut self, snapshot: &Snapshot) {
+        self.components.extend(
+            snapshot
+                .components
+                .iter()
+                .map(|(entity, id, _)| (*entity, *id)),
+        );
+        self.components
+            .sort_unstable_by_key(|(entity, id)| (entity.0, id.into_bytes()));
+        self.components
+            .dedup_by_key(|(entity, id)| (entity.0, id.into_bytes()));
+
+        self.resources
+            .extend(snapshot.resources.iter().map(|(id, _)| *id));
+        self.resources.sort_unstable_by_key(|id| id.0);
+        self.resources.dedup_by_key(|id| id.0);
+    }
+
+    fn clear(&mut self) {
+        self.components.clear();
+        self.resources.clear();
+    }
+}
+
+/// The most recent [`Snapshot`]s sent to a client.
+#[derive(Clone, Debug)]
+pub struct SnapshotHistory {
+    /// The newest snapshot acknowledged by the client.
+    baseline: Option<(ControlFrame, Snapshot)>,
+    /// The snapshots sent after the baseline, oldest first.
+    snapshots: VecDeque<(ControlFrame, Snapshot)>,
+    /// The keys of everything sent after the baseline, including dropped snapshots.
+    sent: SentKeys,
+    capacity: usize,
+}
+
+impl SnapshotHistory {
+    /// Creates a new, empty `SnapshotHistory` retaining at most `capacity` snapshots in addition
+    /// to the acknowledged baseline.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            baseline: None,
+            snapshots: VecDeque::with_capacity(capacity),
+            sent: SentKeys::default(),
+            capacity,
+        }
+    }
+
+    /// Returns the number of retained snapshots, including the baseline.
+    pub fn len(&self) -> usize {
+        self.snapshots.len() + usize::from(self.baseline.is_some())
+    }
+
+    /// Returns `true` if no snapshots are retained.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the [`ControlFrame`] of the acknowledged baseline.
+    pub fn baseline(&self) -> Option<ControlFrame> {
+        self.baseline.as_ref().map(|(cf, _)| *cf)
+    }
+
+    /// Pushes the `snapshot` sent in the given [`ControlFrame`], dropping the oldest
+    /// unacknowledged snapshot if the history is full.
+    pub fn push(&mut self, control_frame: ControlFrame, snapshot: Snapshot) {
+        self.sent.extend(&snapshot);
+
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back((control_frame, snapshot));
+    }
+
+    /// Makes the snapshot sent in the given [`ControlFrame`] the new baseline.
+    ///
+    /// Returns `false` and keeps the current baseline if the snapshot is not retained, i.e. the
+    /// acknowledgement is older than the baseline or the snapshot was dropped.
+    ///
+    /// All snapshots sent before the given [`ControlFrame`] are dropped, since the client has
+    /// already received a newer state and they can no longer become a baseline.
+    pub fn acknowledge(&mut self, control_frame: ControlFrame) -> bool {
+        let Some(index) = self
+            .snapshots
+            .iter()
+            .position(|(cf, _)| *cf == control_frame)
+        else {
+            return false;
+        };
+
+        self.snapshots.drain(..index);
+        self.baseline = self.snapshots.pop_front();
+
+        // The client has the exact state of the baseline, so only
+        // snapshots sent after it can have left anything behind.
+        self.sent.clear();
+        for (_, snapshot) in &self.snapshots {
+            self.sent.extend(snapshot);
+        }
+
+        true
+    }
+
+    /// Returns the messages required to update a client from the acknowledged baseline to the
+    /// `snapshot`.
+    ///
+    /// Without a baseline the full state is returned. In both cases the messages remove all
+    /// components and resources that were sent since the baseline but no longer exist.
+    pub fn delta(&self, snapshot: &Snapshot) -> Vec<DataMessageBody> {
+        let empty;
+        let baseline = match &self.baseline {
+            Some((_, baseline)) => baseline,
+            None => {
+                empty = Snapshot::new();
+                &empty
+            }
+        };
+
+        let mut events = snapshot.delta(baseline);
+        snapshot.push_sent_removals(&mut events, baseline, &self.sent);
+        events
+    }
+
+    /// Drops all snapshots.
+    ///
+    /// Components and resources sent in the dropped snapshots are still removed by [`delta`]
+    /// if they no longer exist.
+    ///
+    /// [`delta`]: Self::delta
+    pub fn clear(&mut self) {
+        if let Some((_, baseline)) = self.baseline.take() {
+            self.sent.extend(&baseline);
+        }
+
+        self.snapshots.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ahash::HashMap;
+    use game_common::components::components::RawComponent;
+    use game_common::net::ServerEntity;
+    use game_common::record::{ModuleId, RecordId, RecordReference};
+    use game_common::world::control_frame::ControlFrame;
+    use game_common::world::World;
+    use game_net::message::DataMessageBody;
+
+    use super::{Snapshot, SnapshotHistory, MAX_SNAPSHOTS};
+    use crate::net::entities::Entities;
+    use crate::net::state::ConnectionState;
+    use crate::net::update_snapshot;
+
+    const COMPONENT_A: RecordReference = RecordReference::STUB;
+    const COMPONENT_B: RecordReference = RecordReference {
+        module: ModuleId::CORE,
+        record: RecordId(1),
+    };
+
+    fn component(bytes: &[u8]) -> RawComponent {
+        RawComponent::new(bytes.to_vec(), [])
+    }
+
+    #[test]
+    fn snapshot_delta_only_contains_changes() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, COMPONENT_A, component(&[1]));
+
+        let mut entities = Entities::new();
+        entities.insert(entity);
+
+        let baseline = Snapshot::capture(&world, &mut entities);
+
+        // Without a baseline the full state is sent.
+        let events = baseline.delta(&Snapshot::new());
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], DataMessageBody::EntityComponentAdd(_)));
+
+        let snapshot = Snapshot::capture(&world, &mut entities);
+        assert!(snapshot.delta(&baseline).is_empty());
+
+        world.insert(entity, COMPONENT_A, component(&[2]));
+        let snapshot = Snapshot::capture(&world, &mut entities);
+        let events = snapshot.delta(&baseline);
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            DataMessageBody::EntityComponentAdd(msg) => {
+                assert_eq!(msg.component.as_bytes(), &[2]);
+            }
+            _ => panic!("expected EntityComponentAdd"),
+        }
+
+        world.remove(entity, COMPONENT_A);
+        let snapshot = Snapshot::capture(&world, &mut entities);
+        let events = snapshot.delta(&baseline);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            DataMessageBody::EntityComponentRemove(_)
+        ));
+    }
+
+    #[test]
+    fn snapshot_history_bounded() {
+        let mut history = SnapshotHistory::new(4);
+
+        for cf in 0..8 {
+            history.push(ControlFrame(cf), Snapshot::new());
+        }
+
+        assert_eq!(history.len(), 4);
+        assert!(!history.acknowledge(ControlFrame(3)));
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.baseline(), None);
+
+        // Acknowledging a frame drops all older snapshots.
+        assert!(history.acknowledge(ControlFrame(4)));
+        assert_eq!(history.len(), 4);
+        assert!(history.acknowledge(ControlFrame(6)));
+        assert_eq!(history.len(), 2);
+        assert!(!history.acknowledge(ControlFrame(4)));
+        assert_eq!(history.baseline(), Some(ControlFrame(6)));
+
+        // The baseline is never dropped.
+        for cf in 8..16 {
+            history.push(ControlFrame(cf), Snapshot::new());
+        }
+        assert_eq!(history.len(), 5);
+        assert_eq!(history.baseline(), Some(ControlFrame(6)));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+
+    /// The state of a client that applies the messages of all frames it received.
+    #[derive(Debug, Default)]
+    struct Client {
+        components: HashMap<(ServerEntity, [u8; 20]), Vec<u8>>,
+    }
+
+    impl Client {
+        fn apply(&mut self, events: &[DataMessageBody]) {
+            for event in events {
+                match event {
+                    DataMessageBody::EntityComponentAdd(msg) => {
+                        self.components.insert(
+                            (msg.entity, msg.component_id.into_bytes()),
+                            msg.component.as_bytes().to_vec(),
+                        );
+                    }
+                    DataMessageBody::EntityComponentRemove(msg) => {
+                        self.components
+                            .remove(&(msg.entity, msg.component.into_bytes()));
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        fn get(&self, entity: ServerEntity, id: RecordReference) -> Option<&[u8]> {
+            self.components
+                .get(&(entity, id.into_bytes()))
+                .map(|bytes| &bytes[..])
+        }
+    }
+
+    #[test]
+    fn update_snapshot_lost_and_out_of_order_acks() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, COMPONENT_A, component(&[1]));
+
+        let mut state = ConnectionState::new();
+        let server_entity = state.entities.insert(entity);
+        let mut client = Client::default();
+
+        // The first frame is lost.
+        let events = update_snapshot(&mut state, &world, ControlFrame(1), None);
+        assert_eq!(events.len(), 1);
+
+        world.insert(entity, COMPONENT_A, component(&[2]));
+        let events = update_snapshot(&mut state, &world, ControlFrame(2), None);
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_A), Some(&[2][..]));
+
+        // The third frame is lost, so the client keeps acknowledging the
+        // second frame and the change is sent again.
+        world.insert(entity, COMPONENT_A, component(&[3]));
+        let events = update_snapshot(&mut state, &world, ControlFrame(3), Some(ControlFrame(2)));
+        assert_eq!(events.len(), 1);
+
+        let events = update_snapshot(&mut state, &world, ControlFrame(4), Some(ControlFrame(2)));
+        assert_eq!(events.len(), 1);
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_A), Some(&[3][..]));
+
+        world.remove(entity, COMPONENT_A);
+        world.insert(entity, COMPONENT_B, component(&[4]));
+        let events = update_snapshot(&mut state, &world, ControlFrame(5), Some(ControlFrame(4)));
+        assert_eq!(events.len(), 2);
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_A), None);
+        assert_eq!(client.get(server_entity, COMPONENT_B), Some(&[4][..]));
+
+        // An ACK for a frame older than the newest acknowledged frame arrives
+        // late. Its snapshot was already dropped, so the changes since the
+        // previous baseline are sent again.
+        let events = update_snapshot(&mut state, &world, ControlFrame(6), Some(ControlFrame(2)));
+        assert_eq!(events.len(), 2);
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_A), None);
+        assert_eq!(client.get(server_entity, COMPONENT_B), Some(&[4][..]));
+
+        // Nothing changed since the acknowledged frame.
+        let events = update_snapshot(&mut state, &world, ControlFrame(7), Some(ControlFrame(6)));
+        assert!(events.is_empty());
+        assert!(state.snapshots.len() <= 2);
+    }
+
+    #[test]
+    fn update_snapshot_component_removed_while_unacked() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, COMPONENT_A, component(&[1]));
+
+        let mut state = ConnectionState::new();
+        let server_entity = state.entities.insert(entity);
+        let mut client = Client::default();
+
+        let events = update_snapshot(&mut state, &world, ControlFrame(1), None);
+        client.apply(&events);
+
+        // The client receives the component, but the ACK is lost.
+        world.insert(entity, COMPONENT_B, component(&[2]));
+        let events = update_snapshot(&mut state, &world, ControlFrame(2), Some(ControlFrame(1)));
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_B), Some(&[2][..]));
+
+        // The component was never part of an acknowledged frame, but the
+        // client has it and must remove it.
+        world.remove(entity, COMPONENT_B);
+        let events = update_snapshot(&mut state, &world, ControlFrame(3), Some(ControlFrame(1)));
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_A), Some(&[1][..]));
+        assert_eq!(client.get(server_entity, COMPONENT_B), None);
+
+        // The removal is repeated until a frame containing it is acknowledged.
+        let events = update_snapshot(&mut state, &world, ControlFrame(4), Some(ControlFrame(1)));
+        assert_eq!(events.len(), 1);
+        let events = update_snapshot(&mut state, &world, ControlFrame(5), Some(ControlFrame(3)));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn update_snapshot_baseline_older_than_capacity() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.insert(entity, COMPONENT_A, component(&[1]));
+        world.insert(entity, COMPONENT_B, component(&[2]));
+
+        let mut state = ConnectionState::new();
+        let server_entity = state.entities.insert(entity);
+        let mut client = Client::default();
+
+        let events = update_snapshot(&mut state, &world, ControlFrame(0), None);
+        client.apply(&events);
+
+        // The client acknowledges the first frame, but no frame after it.
+        let frames = MAX_SNAPSHOTS as u16 * 2;
+        for cf in 1..frames {
+            let events =
+                update_snapshot(&mut state, &world, ControlFrame(cf), Some(ControlFrame(0)));
+            assert!(events.is_empty());
+        }
+        assert_eq!(state.snapshots.baseline(), Some(ControlFrame(0)));
+        assert_eq!(state.snapshots.len(), MAX_SNAPSHOTS + 1);
+
+        // The delta is still computed from the baseline and contains the
+        // removal.
+        world.remove(entity, COMPONENT_B);
+        let events = update_snapshot(
+            &mut state,
+            &world,
+            ControlFrame(frames),
+            Some(ControlFrame(0)),
+        );
+        assert_eq!(events.len(), 1);
+        client.apply(&events);
+        assert_eq!(client.get(server_entity, COMPONENT_A), Some(&[1][..]));
+        assert_eq!(client.get(server_entity, COMPONENT_B), None);
+    }
+}
@@ -5,6 +5,7 @@ use game_common::world::control_frame::ControlFrame;
 use game_common::world::CellId;
 
 use super::entities::Entities;
+use super::snapshot::{SnapshotHistory, MAX_SNAPSHOTS};
 
 #[derive(Clone, Debug)]
 pub struct ConnectionState {
@@ -20,6 +21,8 @@ pub struct ConnectionState {
     pub peer_delay: ControlFrame,
 
     pub entities: Entities,
+    /// Snapshots sent to the peer, used as baselines for delta updates.
+    pub snapshots: SnapshotHistory,
 }
 
 impl ConnectionState {
@@ -31,6 +34,7 @@ impl ConnectionState {
             client_cf: ControlFrame(0),
             peer_delay: ControlFrame(0),
             entities: Entities::new(),
+            snapshots: SnapshotHistory::new(MAX_SNAPSHOTS),
         }
     }
 }
@@ -1,8 +1,8 @@
 use std::collections::VecDeque;
 
-use ahash::HashMap;
+use ahash::{HashMap, HashSet};
 use game_common::components::actions::ActionId;
-use game_common::components::{GlobalTransform, PlayerId, Transform};
+use game_common::components::{Children, GlobalTransform, PlayerId, Transform};
 use game_common::entity::EntityId;
 use game_common::events::{ActionEvent, Event, EventQueue, PlayerConnect, PlayerDisconnect};
 use game_common::world::control_frame::ControlFrame;
@@ -23,16 +23,21 @@ use tracing::trace_span;
 
 use crate::config::Config;
 use crate::conn::{Connection, Connections};
-use crate::net::full_update;
 use crate::net::state::Cells;
+use crate::net::{full_update, update_snapshot};
 use crate::world::level::{Level, Streamer};
 use crate::world::state::WorldState;
 use crate::ServerState;
 
 // All systems need to run sequentially.
-pub fn tick(state: &mut ServerState) {
+//
+// A single tick may take a long time if the physics or script phases have a
+// lot of work to do. We yield to the executor and process pending commands
+// between phases so that neither connection tasks nor commands have to wait
+// for the full tick.
+pub async fn tick(state: &mut ServerState) {
     update_client_heads(state);
-    flush_command_queue(state);
+    let disconnected_players = flush_command_queue(state);
 
     for event in crate::world::level::update_level_cells(&mut state.level, &mut state.world.world) {
         state.event_queue.push(event);
@@ -45,6 +50,8 @@ pub fn tick(state: &mut ServerState) {
         records: &state.modules,
     });
 
+    yield_phase(state).await;
+
     let mut events = apply_effects(
         effects,
         &mut state.world,
@@ -52,6 +59,13 @@ pub fn tick(state: &mut ServerState) {
         &state.state.config,
     );
 
+    // The `PlayerDisconnect` events were dispatched to scripts in this tick,
+    // but handlers deferred past the invocation limit only run in a later
+    // tick and no longer see the entities of the player.
+    for player in disconnected_players {
+        despawn_player_entities(&mut state.world, &mut state.level, player, &mut events);
+    }
+
     for entity in update_global_transform(&mut state.world.world) {
         events.push(TickEvent::EntityComponentInsert(entity, Transform::ID));
         events.push(TickEvent::EntityComponentInsert(
@@ -60,6 +74,8 @@ pub fn tick(state: &mut ServerState) {
         ));
     }
 
+    yield_phase(state).await;
+
     if cfg!(feature = "physics") {
         for entity in step_physics(state) {
             events.push(TickEvent::EntityComponentInsert(entity, Transform::ID));
@@ -68,6 +84,8 @@ pub fn tick(state: &mut ServerState) {
                 GlobalTransform::ID,
             ));
         }
+
+        yield_phase(state).await;
     }
 
     // Filter our any duplicate events once.
@@ -79,6 +97,13 @@ pub fn tick(state: &mut ServerState) {
     update_snapshots(&state.state.conns, &state.world, &state.level, cf, &events);
 }
 
+/// Yields to the executor between two phases of a tick and processes the commands received in
+/// the meantime.
+async fn yield_phase(state: &mut ServerState) {
+    tokio::task::yield_now().await;
+    crate::process_commands(state);
+}
+
 fn apply_effects(
     effects: Effects,
     world: &mut WorldState,
@@ -164,7 +189,7 @@ fn apply_effects(
                     .copied()
                     .unwrap_or(effect.entity);
 
-                if let Some(old_entity) = world.players.insert(effect.player, entity) {
+                if let Some(old_entity) = world.set_player_entity(effect.player, entity) {
                     level.destroy_streamer(old_entity);
                 }
 
@@ -228,10 +253,15 @@ fn update_client_heads(state: &mut ServerState) {
     }
 }
 
-fn flush_command_queue(srv_state: &mut ServerState) {
+/// Handles all messages received from connections.
+///
+/// Returns the players that disconnected.
+fn flush_command_queue(srv_state: &mut ServerState) -> Vec<PlayerId> {
+    let mut disconnected_players = Vec::new();
+
     let mut queue = VecDeque::new();
     for conn in srv_state.state.conns.iter() {
-        while let Some(msg) = conn.handle().recv() {
+        while let Some(msg) = conn.recv() {
             queue.push_back((conn.key(), msg));
         }
     }
@@ -266,6 +296,7 @@ fn flush_command_queue(srv_state: &mut ServerState) {
                 srv_state
                     .event_queue
                     .push(Event::PlayerDisconnect(PlayerDisconnect { player }));
+                disconnected_players.push(player);
             }
             Message::Control(ControlMessage::Ack(_)) => {}
             Message::Control(ControlMessage::Acknowledge(_, _)) => {}
@@ -317,6 +348,53 @@ fn flush_command_queue(srv_state: &mut ServerState) {
             }
         }
     }
+
+    disconnected_players
+}
+
+/// Despawns all entities owned by the `player`.
+///
+/// Shared entities that are controlled by other players are detached and kept alive. Physics
+/// bodies and colliders of the despawned entities are removed in the next physics step.
+fn despawn_player_entities(
+    world: &mut WorldState,
+    level: &mut Level,
+    player: PlayerId,
+    events: &mut Vec<TickEvent>,
+) {
+    let entities = world.entities_owned_by(player);
+    let owned: HashSet<_> = entities.iter().copied().collect();
+
+    for entity in &entities {
+        let Ok(mut children) = world.world.get_typed::<Children>(*entity) else {
+            continue;
+        };
+
+        let shared: Vec<_> = children
+            .get()
+            .iter()
+            .filter(|child| !owned.contains(*child))
+            .copied()
+            .collect();
+
+        if !shared.is_empty() {
+            for child in shared {
+                children.remove(child);
+            }
+
+            world.world.insert_typed(*entity, children);
+        }
+    }
+
+    for entity in entities {
+        world.world.despawn_recursive(entity, |entity| {
+            events.push(TickEvent::EntityDespawn(entity));
+        });
+    }
+
+    if let Some(host) = world.remove_player(player) {
+        level.destroy_streamer(host);
+    }
 }
 
 fn queue_action(
@@ -367,7 +445,7 @@ fn update_client(
         return;
     };
 
-    let Some(host_id) = world.players.get(&player_id).copied() else {
+    let Some(host_id) = world.player_entity(player_id) else {
         return;
     };
 
@@ -393,11 +471,19 @@ fn update_client(
         state.cells.set(cell_id, streamer.distance);
 
         state.full_update = false;
-        full_update(&mut state, &world.world)
+        full_update(&mut state, &world.world);
+        Vec::new()
     } else {
         crate::net::sync_player(world, &mut state, tick_events, cell_id, streamer.distance)
     };
 
+    events.extend(update_snapshot(
+        &mut state,
+        &world.world,
+        cf,
+        conn.handle().last_acked_cf(),
+    ));
+
     if active_entity_changed {
         state.host.entity = Some(host_id);
         let id = state.entities.get(host_id).unwrap();
@@ -463,3 +549,155 @@ fn dedup_tick_events(events: &mut Vec<TickEvent>) {
         _ => false,
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::pin::pin;
+
+    use futures::poll;
+    use game_common::components::{Children, PlayerId};
+    use game_core::command::ServerCommand;
+    use game_core::modules::Modules;
+    use game_script::{Executor, WorldProvider};
+    use tokio::sync::{mpsc, oneshot};
+
+    use crate::command::Command;
+    use crate::config::Config;
+    use crate::world::level::{Level, Streamer};
+    use crate::world::state::WorldState;
+    use crate::ServerState;
+
+    use super::{despawn_player_entities, tick, TickEvent};
+
+    #[tokio::test]
+    async fn tick_processes_commands_between_phases() {
+        let (cmd_tx, cmd_rx) = mpsc::channel(1);
+        let mut state =
+            ServerState::new(cmd_rx, Modules::new(), Config::default(), Executor::new());
+
+        let (tx, mut rx) = oneshot::channel();
+        cmd_tx
+            .send((Command::Server(ServerCommand::Uptime), tx))
+            .await
+            .unwrap();
+
+        let mut tick = pin!(tick(&mut state));
+
+        // The first poll runs until the tick yields after the script phase.
+        assert!(poll!(tick.as_mut()).is_pending());
+        assert!(rx.try_recv().is_err());
+
+        // The command is processed once the tick resumes, before the tick
+        // completes.
+        assert!(poll!(tick.as_mut()).is_pending());
+        assert!(rx.try_recv().is_ok());
+
+        tick.await;
+    }
+
+    #[test]
+    fn despawn_player_entities_on_disconnect() {
+        let mut world = WorldState::new();
+        let mut level = Level::new();
+
+        let player = PlayerId::from_raw(0);
+        let other_player = PlayerId::from_raw(1);
+
+        let host = world.spawn();
+        let child = world.spawn();
+        let grandchild = world.spawn();
+        let other_host = world.spawn();
+        let unrelated = world.spawn();
+
+        // The entity of the other player is attached to the disconnecting player.
+        world
+            .world
+            .insert_typed(host, Children::from_iter([child, other_host]));
+        world
+            .world
+            .insert_typed(child, Children::from_iter([grandchild]));
+
+        world.set_player_entity(player, host);
+        world.set_player_entity(other_player, other_host);
+        level.create_streamer(host, Streamer { distance: 1 });
+        level.create_streamer(other_host, Streamer { distance: 1 });
+
+        let mut owned = world.entities_owned_by(player);
+        owned.sort();
+        let mut expected = vec![host, child, grandchild];
+        expected.sort();
+        assert_eq!(owned, expected);
+
+        let mut events = Vec::new();
+        despawn_player_entities(&mut world, &mut level, player, &mut events);
+
+        for entity in [host, child, grandchild] {
+            assert!(!world.world.contains(entity));
+            assert!(events
+                .iter()
+                .any(|event| matches!(event, TickEvent::EntityDespawn(id) if *id == entity)));
+        }
+
+        for entity in [other_host, unrelated] {
+            assert!(world.world.contains(entity));
+            assert!(!events
+                .iter()
+                .any(|event| matches!(event, TickEvent::EntityDespawn(id) if *id == entity)));
+        }
+
+        assert_eq!(world.player_entity(player), None);
+        assert_eq!(world.player(host), None);
+        assert!(level.get_streamer(host).is_none());
+
+        assert_eq!(world.player_entity(other_player), Some(other_host));
+        assert_eq!(world.player(other_host), Some(other_player));
+        assert!(level.get_streamer(other_host).is_some());
+    }
+
+    #[test]
+    fn despawn_player_entities_shared_children() {
+        let mut world = WorldState::new();
+        let mut level = Level::new();
+
+        let player = PlayerId::from_raw(0);
+
+        let host = world.spawn();
+        let child = world.spawn();
+        let shared = world.spawn();
+
+        // `shared` is reachable through two parents and links back to the
+        // host.
+        world
+            .world
+            .insert_typed(host, Children::from_iter([child, shared]));
+        world
+            .world
+            .insert_typed(child, Children::from_iter([shared]));
+        world
+            .world
+            .insert_typed(shared, Children::from_iter([host]));
+
+        world.set_player_entity(player, host);
+        level.create_streamer(host, Streamer { distance: 1 });
+
+        let mut owned = world.entities_owned_by(player);
+        owned.sort();
+        let mut expected = vec![host, child, shared];
+        expected.sort();
+        assert_eq!(owned, expected);
+
+        let mut events = Vec::new();
+        despawn_player_entities(&mut world, &mut level, player, &mut events);
+
+        for entity in [host, child, shared] {
+            assert!(!world.world.contains(entity));
+            assert_eq!(
+                events
+                    .iter()
+                    .filter(|event| matches!(event, TickEvent::EntityDespawn(id) if *id == entity))
+                    .count(),
+                1
+            );
+        }
+    }
+}
@@ -4,6 +4,7 @@ use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use ahash::HashMap;
 use bytes::BytesMut;
@@ -164,13 +165,14 @@ impl ConnectionPool {
         S: ConnectionStream + Send + 'static,
         S::Error: std::error::Error,
     {
-        let (conn, handle) = Connection::<_, Listen>::new(
+        let (mut conn, handle) = Connection::<_, Listen>::new(
             stream,
             self.state.control_frame.get(),
             ControlFrame(0),
             key.local_addr,
             key.remote_addr,
         );
+        conn.set_idle_timeout(Duration::from_secs(self.state.config.idle_timeout));
 
         let state = self.state.clone();
         tokio::task::spawn(async move {
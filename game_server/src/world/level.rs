@@ -1,9 +1,10 @@
 use ahash::{HashMap, HashSet, HashSetExt};
-use game_common::components::{Global, Transform};
+use game_common::components::{Children, Global, GlobalTransform, Transform};
 use game_common::entity::EntityId;
 use game_common::events::{CellLoad, CellUnload, Event};
 use game_common::world::cell::CubeIter;
 use game_common::world::{CellId, World};
+use game_prefab::save::{CellChunk, WorldSave};
 use tracing::trace_span;
 
 #[derive(Copy, Clone, Debug)]
@@ -34,6 +35,7 @@ pub fn update_level_cells(level: &mut Level, world: &mut World) -> Vec<Event> {
         }
 
         level.loaded.insert(*cell);
+        level.restore_cell(*cell, world);
         events.push(Event::CellLoad(CellLoad { id: *cell }));
 
         tracing::info!("loading cell {:?}", cell);
@@ -61,6 +63,8 @@ pub struct Level {
     loaded: HashSet<CellId>,
     streamers: HashMap<EntityId, Streamer>,
     unload_in_next_tick: HashSet<CellId>,
+    /// Where the entities of unloaded cells are persisted.
+    save: Option<WorldSave>,
 }
 
 impl Level {
@@ -69,9 +73,16 @@ impl Level {
             loaded: HashSet::default(),
             streamers: HashMap::default(),
             unload_in_next_tick: HashSet::new(),
+            save: None,
         }
     }
 
+    /// Persists the entities of cells when they are unloaded in the given [`WorldSave`] and
+    /// restores them when the cells are loaded again.
+    pub fn set_save(&mut self, save: WorldSave) {
+        self.save = Some(save);
+    }
+
     pub fn create_streamer(&mut self, id: EntityId, streamer: Streamer) {
         self.streamers.insert(id, streamer);
     }
@@ -101,39 +112,107 @@ impl Level {
             }
         }
 
+        // Children are always unloaded together with their parent, so only
+        // the roots of hierarchies are considered.
+        let mut children = HashSet::new();
+        for entity in world.entities() {
+            if let Ok(c) = world.get_typed::<Children>(entity) {
+                children.extend(c.get().iter().copied());
+            }
+        }
+
+        let mut chunks: HashMap<CellId, CellChunk> = self
+            .unload_in_next_tick
+            .iter()
+            .map(|cell| (*cell, CellChunk::new(*cell)))
+            .collect();
+
         let mut despawn_queue = Vec::new();
         for entity in world.entities() {
+            if children.contains(&entity) {
+                continue;
+            }
+
             // Entities with a `Global` component are always loaded.
             if let Ok(Global) = world.get_typed::<Global>(entity) {
                 continue;
             }
 
-            let Ok(transform) = world.get_typed::<Transform>(entity) else {
-                continue;
+            // The `GlobalTransform` of a root is the same as its `Transform`,
+            // but newly spawned entities may not have it yet.
+            let translation = match world.get_typed::<GlobalTransform>(entity) {
+                Ok(GlobalTransform(transform)) => transform.translation,
+                Err(_) => match world.get_typed::<Transform>(entity) {
+                    Ok(transform) => transform.translation,
+                    Err(_) => continue,
+                },
             };
 
-            let cell = CellId::from(transform.translation);
-            if self.unload_in_next_tick.contains(&cell) {
+            if let Some(chunk) = chunks.get_mut(&CellId::from(translation)) {
+                // Saves the entity together with all its children.
+                chunk.add(entity, world);
                 despawn_queue.push(entity);
             }
         }
 
+        self.persist_cells(chunks);
+
         tracing::debug!("unloading {} entities", despawn_queue.len());
         for entity in despawn_queue {
+            // Despawns all children of the entity.
             world.despawn(entity);
         }
 
         self.unload_in_next_tick.clear();
     }
+
+    /// Saves the `chunks` of the cells scheduled for unloading.
+    ///
+    /// A chunk is stored for every unloaded cell, even if it contains no entities, so that
+    /// entities removed since the cell was last saved are not restored.
+    fn persist_cells(&self, chunks: HashMap<CellId, CellChunk>) {
+        let Some(save) = &self.save else {
+            return;
+        };
+
+        let _span = trace_span!("Level::persist_cells").entered();
+
+        for chunk in chunks.values() {
+            if let Err(err) = save.store(chunk) {
+                tracing::error!("failed to save cell {:?}: {}", chunk.cell(), err);
+            }
+        }
+    }
+
+    /// Spawns the entities saved for the given `cell`.
+    fn restore_cell(&self, cell: CellId, world: &mut World) {
+        let Some(save) = &self.save else {
+            return;
+        };
+
+        let _span = trace_span!("Level::restore_cell").entered();
+
+        match save.load(cell) {
+            Ok(Some(chunk)) => {
+                let entities = chunk.instantiate(world);
+                tracing::debug!("restored {} entities in cell {:?}", entities.len(), cell);
+            }
+            Ok(None) => (),
+            Err(err) => {
+                tracing::error!("failed to restore cell {:?}: {}", cell, err);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use ahash::{HashSet, HashSetExt};
-    use game_common::components::Transform;
+    use game_common::components::{Children, GlobalTransform, Transform};
     use game_common::events::Event;
     use game_common::world::cell::CubeIter;
     use game_common::world::{CellId, World};
+    use game_prefab::save::WorldSave;
     use glam::Vec3;
 
     use super::{update_level_cells, Level, Streamer};
@@ -185,6 +264,117 @@ mod tests {
         assert!(level.loaded.is_empty());
     }
 
+    #[test]
+    fn test_unloaded_cells_are_restored() {
+        let root = tempfile::tempdir().unwrap();
+
+        let mut level = Level::new();
+        level.set_save(WorldSave::new(root.path()));
+        let mut world = World::new();
+
+        let player = world.spawn();
+        world.insert_typed(player, Transform::default());
+        level.create_streamer(player, Streamer { distance: 0 });
+
+        let entity = world.spawn();
+        world.insert_typed(
+            entity,
+            Transform::from_translation(Vec3::new(1.0, 1.0, 1.0)),
+        );
+
+        update_level_cells(&mut level, &mut world);
+
+        // Move the player away, unloading the cell of the entity in the next tick.
+        world.insert_typed(
+            player,
+            Transform::from_translation(Vec3::new(1000.0, 0.0, 0.0)),
+        );
+        update_level_cells(&mut level, &mut world);
+        update_level_cells(&mut level, &mut world);
+        assert!(!world.contains(entity));
+
+        // Move the player back, restoring the entity.
+        world.insert_typed(player, Transform::default());
+        update_level_cells(&mut level, &mut world);
+
+        let restored: Vec<_> = world
+            .entities()
+            .filter(|id| *id != player)
+            .filter_map(|id| world.get_typed::<Transform>(id).ok())
+            .collect();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].translation, Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_unload_hierarchy_across_cells() {
+        let root = tempfile::tempdir().unwrap();
+
+        let mut level = Level::new();
+        level.set_save(WorldSave::new(root.path()));
+        let mut world = World::new();
+
+        let far = Vec3::new(1000.0, 0.0, 0.0);
+
+        let player = world.spawn();
+        world.insert_typed(player, Transform::default());
+        level.create_streamer(player, Streamer { distance: 0 });
+
+        // A parent in the cell that is unloaded with a child whose local
+        // transform is in the cell that stays loaded.
+        let parent = world.spawn();
+        world.insert_typed(parent, Transform::default());
+        world.insert_typed(parent, GlobalTransform(Transform::default()));
+        let child = world.spawn();
+        world.insert_typed(child, Transform::from_translation(far));
+        let mut children = Children::new();
+        children.insert(child);
+        world.insert_typed(parent, children);
+
+        // A parent in the cell that stays loaded with a child whose local
+        // transform is in the cell that is unloaded.
+        let loaded_parent = world.spawn();
+        world.insert_typed(loaded_parent, Transform::from_translation(far));
+        world.insert_typed(
+            loaded_parent,
+            GlobalTransform(Transform::from_translation(far)),
+        );
+        let loaded_child = world.spawn();
+        world.insert_typed(loaded_child, Transform::default());
+        let mut children = Children::new();
+        children.insert(loaded_child);
+        world.insert_typed(loaded_parent, children);
+
+        update_level_cells(&mut level, &mut world);
+
+        world.insert_typed(player, Transform::from_translation(far));
+        update_level_cells(&mut level, &mut world);
+        update_level_cells(&mut level, &mut world);
+
+        assert!(!world.contains(parent));
+        assert!(!world.contains(child));
+        assert!(world.contains(loaded_parent));
+        assert!(world.contains(loaded_child));
+
+        world.insert_typed(player, Transform::default());
+        update_level_cells(&mut level, &mut world);
+
+        // The unloaded hierarchy is restored exactly once and the loaded
+        // hierarchy is not duplicated.
+        let roots: Vec<_> = world
+            .entities()
+            .filter(|id| ![player, loaded_parent, loaded_child].contains(id))
+            .filter(|id| world.get_typed::<Children>(*id).is_ok())
+            .collect();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(world.entities().count(), 5);
+
+        let children = world.get_typed::<Children>(roots[0]).unwrap();
+        assert_eq!(children.len(), 1);
+        let transform = world.get_typed::<Transform>(children.get()[0]).unwrap();
+        assert_eq!(transform.translation, far);
+    }
+
     #[track_caller]
     fn assert_cell_load_events(actual: &[Event], expected: &HashSet<CellId>) {
         // Order does not matter so we collect both
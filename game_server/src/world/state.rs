@@ -13,7 +13,10 @@ use game_wasm::encoding::BinaryWriter;
 #[derive(Clone, Debug)]
 pub struct WorldState {
     pub world: World,
-    pub players: HashMap<PlayerId, EntityId>,
+    /// The entity controlled by every player.
+    players: HashMap<PlayerId, EntityId>,
+    /// The player controlling every entity, the inverse of `players`.
+    controllers: HashMap<EntityId, PlayerId>,
 }
 
 impl WorldState {
@@ -21,6 +24,38 @@ impl WorldState {
         WorldState {
             world: World::new(),
             players: HashMap::new(),
+            controllers: HashMap::new(),
+        }
+    }
+
+    /// Returns the entity controlled by the `player`.
+    pub fn player_entity(&self, player: PlayerId) -> Option<EntityId> {
+        self.players.get(&player).copied()
+    }
+
+    /// Makes the `player` control the `entity`, returning the entity previously controlled by
+    /// the `player`.
+    pub fn set_player_entity(&mut self, player: PlayerId, entity: EntityId) -> Option<EntityId> {
+        let old_entity = self.players.insert(player, entity);
+        if let Some(old_entity) = old_entity {
+            self.remove_controller(old_entity, player);
+        }
+
+        self.controllers.insert(entity, player);
+        old_entity
+    }
+
+    /// Removes the `player`, returning the entity it controlled.
+    pub fn remove_player(&mut self, player: PlayerId) -> Option<EntityId> {
+        let entity = self.players.remove(&player)?;
+        self.remove_controller(entity, player);
+        Some(entity)
+    }
+
+    fn remove_controller(&mut self, entity: EntityId, player: PlayerId) {
+        // Another player may have taken control of the entity since.
+        if self.controllers.get(&entity) == Some(&player) {
+            self.controllers.remove(&entity);
         }
     }
 
@@ -58,10 +93,11 @@ impl WorldProvider for WorldState {
     }
 
     fn player(&self, id: EntityId) -> Option<PlayerId> {
-        self.players
-            .iter()
-            .find(|(player, entity)| **entity == id)
-            .map(|(player, _)| *player)
+        self.controllers.get(&id).copied()
+    }
+
+    fn entities_controlled_by(&self, player: PlayerId) -> Vec<EntityId> {
+        self.player_entity(player).into_iter().collect()
     }
 }
 
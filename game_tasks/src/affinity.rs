@@ -0,0 +1,29 @@
+//! Best-effort pinning of threads to CPU cores.
+
+/// Pins the calling thread to the CPU core with the given index.
+///
+/// Returns `true` if the thread was pinned. Pinning is not supported on all platforms and always
+/// fails on unsupported platforms.
+#[cfg(target_os = "linux")]
+pub(crate) fn pin_current_thread(core: usize) -> bool {
+    use nix::sched::{sched_setaffinity, CpuSet};
+    use nix::unistd::Pid;
+
+    let mut set = CpuSet::new();
+    if set.set(core).is_err() {
+        return false;
+    }
+
+    // A pid of 0 refers to the calling thread.
+    sched_setaffinity(Pid::from_raw(0), &set).is_ok()
+}
+
+/// Pins the calling thread to the CPU core with the given index.
+///
+/// Returns `true` if the thread was pinned. Pinning is not supported on all platforms and always
+/// fails on unsupported platforms.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pin_current_thread(core: usize) -> bool {
+    let _ = core;
+    false
+}
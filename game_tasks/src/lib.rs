@@ -2,20 +2,27 @@ extern crate alloc;
 
 pub mod park;
 
+mod affinity;
 mod loom;
 mod task;
 mod waker;
 
+use std::cell::Cell;
 use std::future::Future;
 use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
+use std::ptr::NonNull;
+use std::sync::atomic::{self, AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::task::{Poll, RawWaker, RawWakerVTable, Waker};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Wake, Waker};
 use std::thread::JoinHandle;
 
 use crossbeam::deque::{Injector, Steal};
+use futures::FutureExt;
 use park::Parker;
+use parking_lot::Mutex;
 use task::RawTaskPtr;
 
 pub use task::Task;
@@ -39,27 +46,19 @@ struct Inner {
 impl TaskPool {
     /// Creates a new `TaskPool` backed by the given number of threads.
     ///
+    /// Use [`TaskPoolBuilder`] to configure the worker threads.
+    ///
     /// # Panics
     ///
     /// Panics if `threads` is `0`.
     pub fn new(threads: usize) -> Self {
-        assert_ne!(threads, 0);
-
-        let inner = Arc::new(Inner {
-            queue: InjectorQueue::new(),
-            shutdown: AtomicBool::new(false),
-        });
-
-        let mut vec = Vec::new();
-        for _ in 0..threads {
-            let inner = inner.clone();
-            vec.push(spawn_worker_thread(inner));
-        }
+        TaskPoolBuilder::new().threads(threads).build()
+    }
 
-        Self {
-            inner,
-            threads: ManuallyDrop::new(vec),
-        }
+    /// Creates a new [`TaskPoolBuilder`].
+    #[inline]
+    pub fn builder() -> TaskPoolBuilder {
+        TaskPoolBuilder::new()
     }
 
     /// Spawns a new future on the `TaskPool`.
@@ -97,12 +96,83 @@ impl TaskPool {
         }
     }
 
-    /// Spawns a future on the `TaskPool` and blocks until the future finishes execution.
+    /// Runs a future to completion, blocking the calling thread until the future finishes
+    /// execution.
+    ///
+    /// The behavior depends on the thread calling `block_on`:
+    /// - If called from a thread outside of this `TaskPool`, the future is spawned on the worker
+    ///   threads of the pool and the calling thread is parked until the future completes.
+    /// - If called from a worker thread of this `TaskPool` (i.e. from within a task spawned on
+    ///   the pool), the future is polled on the calling thread. While the future is pending the
+    ///   worker continues to run other tasks from the pool. This prevents deadlocks when the
+    ///   future depends on other tasks in the pool, even if the pool only has a single thread.
+    ///
+    /// In both cases a panic in the future is propagated to the caller of `block_on`.
     pub fn block_on<T, F>(&self, future: F) -> T
+    where
+        F: Future<Output = T> + Send,
+        T: Send,
+    {
+        if self.is_worker_thread() {
+            return self.block_on_worker(future);
+        }
+
+        // Catch a panic on the worker and resume it on the calling thread,
+        // otherwise the worker dies and the task never completes.
+        let future = AssertUnwindSafe(future).catch_unwind();
+
+        // SAFETY: We block until the task has completed, so all lifetimes
+        // captured by `future` outlive the task.
+        let task = unsafe { self.spawn_unchecked(future) };
+        match block_on_parked(task) {
+            Ok(output) => output,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// Returns `true` if the calling thread is a worker thread of this `TaskPool`.
+    fn is_worker_thread(&self) -> bool {
+        CURRENT_POOL.with(|pool| pool.get() == Arc::as_ptr(&self.inner))
+    }
+
+    fn block_on_worker<T, F>(&self, future: F) -> T
     where
         F: Future<Output = T>,
     {
-        futures::executor::block_on(future)
+        let parker = Arc::new(ParkWaker(Parker::new()));
+        let waker = Waker::from(parker.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        // Register the worker, so that it is woken up when a new task is
+        // scheduled while it is parked. The guard also unregisters the
+        // worker if the future panics.
+        self.inner.queue.register_blocked(parker.clone());
+        let _guard = BlockedGuard {
+            queue: &self.inner.queue,
+            parker: &parker,
+        };
+
+        // Tasks that are currently being polled further up the stack of
+        // this thread may have been woken and queued again. Polling them
+        // here would poll the same future re-entrantly, so they are held
+        // back until we return.
+        let mut deferred = DeferredTasks {
+            queue: &self.inner.queue,
+            tasks: Vec::new(),
+        };
+
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+
+            match self.inner.queue.pop() {
+                Some(task) if is_running_on_current_thread(&task) => deferred.tasks.push(task),
+                Some(task) => run_task(task),
+                None => parker.0.park(),
+            }
+        }
     }
 }
 
@@ -128,35 +198,257 @@ impl Drop for TaskPool {
     }
 }
 
+/// A builder for a [`TaskPool`].
+#[derive(Clone, Debug)]
+pub struct TaskPoolBuilder {
+    threads: usize,
+    thread_name: String,
+    cores: Vec<usize>,
+}
+
+impl TaskPoolBuilder {
+    /// Creates a new `TaskPoolBuilder`.
+    ///
+    /// The default configuration uses a single worker thread named `game-worker-0` without core
+    /// affinity.
+    pub fn new() -> Self {
+        Self {
+            threads: 1,
+            thread_name: String::from("game-worker"),
+            cores: Vec::new(),
+        }
+    }
+
+    /// Sets the number of worker threads.
+    #[inline]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the name prefix of the worker threads.
+    ///
+    /// The worker threads are named `{prefix}-{n}` where `n` is the index of the worker.
+    #[inline]
+    pub fn thread_name<T>(mut self, prefix: T) -> Self
+    where
+        T: Into<String>,
+    {
+        self.thread_name = prefix.into();
+        self
+    }
+
+    /// Pins the worker threads to the given CPU cores.
+    ///
+    /// The worker with index `n` is pinned to the core `cores[n % cores.len()]`. If `cores` is
+    /// empty the worker threads are not pinned.
+    ///
+    /// Pinning is best-effort: it is only supported on Linux and ignored on all other platforms,
+    /// or if the core is not available.
+    #[inline]
+    pub fn core_affinity<I>(mut self, cores: I) -> Self
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        self.cores = cores.into_iter().collect();
+        self
+    }
+
+    /// Consumes this `TaskPoolBuilder`, returning the constructed [`TaskPool`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of threads is `0`.
+    pub fn build(self) -> TaskPool {
+        assert_ne!(self.threads, 0);
+
+        let inner = Arc::new(Inner {
+            queue: InjectorQueue::new(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let mut vec = Vec::with_capacity(self.threads);
+        for index in 0..self.threads {
+            let name = format!("{}-{}", self.thread_name, index);
+            let core = (!self.cores.is_empty()).then(|| self.cores[index % self.cores.len()]);
+
+            vec.push(spawn_worker_thread(inner.clone(), name, core));
+        }
+
+        TaskPool {
+            inner,
+            threads: ManuallyDrop::new(vec),
+        }
+    }
+}
+
+impl Default for TaskPoolBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 unsafe impl Send for Inner {}
 unsafe impl Sync for Inner {}
 
-fn spawn_worker_thread(inner: Arc<Inner>) -> JoinHandle<()> {
-    std::thread::spawn(move || loop {
-        if inner.shutdown.load(Ordering::Acquire) {
-            return;
+thread_local! {
+    /// The `TaskPool` that the current thread is a worker of, or null if the current thread
+    /// is not a worker thread.
+    static CURRENT_POOL: Cell<*const Inner> = const { Cell::new(std::ptr::null()) };
+
+    /// The innermost task that is being polled by the current thread.
+    static RUNNING_TASKS: Cell<*const RunningFrame> = const { Cell::new(std::ptr::null()) };
+}
+
+fn spawn_worker_thread(inner: Arc<Inner>, name: String, core: Option<usize>) -> JoinHandle<()> {
+    let builder = std::thread::Builder::new().name(name);
+
+    let res = builder.spawn(move || {
+        // Pinning is only a hint, the worker is still fully
+        // functional if it fails.
+        if let Some(core) = core {
+            affinity::pin_current_thread(core);
         }
 
-        let Some(task) = inner.queue.pop() else {
-            inner.queue.parker.park();
-            continue;
-        };
+        CURRENT_POOL.with(|pool| pool.set(Arc::as_ptr(&inner)));
 
-        let waker = unsafe { Waker::from_raw(waker_create(task.clone())) };
-        match unsafe { task.poll(&waker) } {
-            Poll::Pending => {}
-            Poll::Ready(()) => {
-                // The `poll` function handles advancing the internal state
-                // when the future yields `Ready`.
+        loop {
+            if inner.shutdown.load(Ordering::Acquire) {
+                return;
             }
+
+            let Some(task) = inner.queue.pop() else {
+                inner.queue.parker.park();
+                continue;
+            };
+
+            run_task(task);
+        }
+    });
+
+    res.expect("failed to spawn worker thread")
+}
+
+fn run_task(task: RawTaskPtr) {
+    let frame = RunningFrame {
+        task: task.as_ptr(),
+        prev: RUNNING_TASKS.with(|tasks| tasks.get()),
+    };
+    RUNNING_TASKS.with(|tasks| tasks.set(&frame));
+    let _guard = RunningGuard { prev: frame.prev };
+
+    let waker = unsafe { Waker::from_raw(waker_create(task.clone())) };
+    match unsafe { task.poll(&waker) } {
+        Poll::Pending => {}
+        Poll::Ready(()) => {
+            // The `poll` function handles advancing the internal state
+            // when the future yields `Ready`.
+        }
+    }
+}
+
+/// Blocks the current thread until the `task` completes.
+fn block_on_parked<T>(task: Task<T>) -> T {
+    let parker = Arc::new(ParkWaker(Parker::new()));
+    let waker = Waker::from(parker.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let mut task = pin!(task);
+    loop {
+        match task.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => parker.0.park(),
+        }
+    }
+}
+
+/// A task that is being polled by the current thread.
+///
+/// Frames live on the stack of [`run_task`] and form a list of all tasks that are polled by the
+/// current thread, the innermost first.
+struct RunningFrame {
+    task: NonNull<()>,
+    prev: *const RunningFrame,
+}
+
+/// Removes the innermost [`RunningFrame`] when dropped.
+struct RunningGuard {
+    prev: *const RunningFrame,
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING_TASKS.with(|tasks| tasks.set(self.prev));
+    }
+}
+
+/// Returns `true` if the `task` is currently being polled by the calling thread.
+fn is_running_on_current_thread(task: &RawTaskPtr) -> bool {
+    let mut frame = RUNNING_TASKS.with(|tasks| tasks.get());
+    while !frame.is_null() {
+        // SAFETY: Frames are removed from the list before they are dropped.
+        let running = unsafe { &*frame };
+        if running.task == task.as_ptr() {
+            return true;
         }
-    })
+
+        frame = running.prev;
+    }
+
+    false
+}
+
+/// Pushes tasks that could not be run back into the [`InjectorQueue`] when dropped.
+struct DeferredTasks<'a> {
+    queue: &'a InjectorQueue,
+    tasks: Vec<RawTaskPtr>,
+}
+
+impl Drop for DeferredTasks<'_> {
+    fn drop(&mut self) {
+        // The tasks are still marked as queued, so they will not be pushed
+        // again if they are woken in the meantime.
+        for task in self.tasks.drain(..) {
+            self.queue.push(task);
+        }
+    }
+}
+
+/// Unregisters a blocked worker from the [`InjectorQueue`] when dropped.
+struct BlockedGuard<'a> {
+    queue: &'a InjectorQueue,
+    parker: &'a Arc<ParkWaker>,
+}
+
+impl Drop for BlockedGuard<'_> {
+    fn drop(&mut self) {
+        self.queue.unregister_blocked(self.parker);
+    }
+}
+
+/// A [`Waker`] that unparks a thread blocking on a future.
+#[derive(Debug)]
+struct ParkWaker(Parker);
+
+impl Wake for ParkWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
 }
 
 #[derive(Debug)]
 struct InjectorQueue {
     inner: Injector<RawTaskPtr>,
     parker: Parker,
+    /// Worker threads that are blocking on a future and must also be woken up when a new task
+    /// is pushed.
+    blocked: Mutex<Vec<Arc<ParkWaker>>>,
+    num_blocked: AtomicUsize,
 }
 
 impl InjectorQueue {
@@ -164,6 +456,8 @@ impl InjectorQueue {
         Self {
             inner: Injector::new(),
             parker: Parker::new(),
+            blocked: Mutex::new(Vec::new()),
+            num_blocked: AtomicUsize::new(0),
         }
     }
 
@@ -172,6 +466,28 @@ impl InjectorQueue {
         // cause unnecessary delay on high contention.
         self.inner.push(task);
         self.parker.unpark();
+
+        // The blocked worker registers itself before checking the queue.
+        // Either it observes the new task or we observe the registration.
+        atomic::fence(Ordering::SeqCst);
+        if self.num_blocked.load(Ordering::SeqCst) != 0 {
+            for parker in self.blocked.lock().iter() {
+                parker.0.unpark();
+            }
+        }
+    }
+
+    fn register_blocked(&self, parker: Arc<ParkWaker>) {
+        let mut blocked = self.blocked.lock();
+        blocked.push(parker);
+        self.num_blocked.store(blocked.len(), Ordering::SeqCst);
+        atomic::fence(Ordering::SeqCst);
+    }
+
+    fn unregister_blocked(&self, parker: &Arc<ParkWaker>) {
+        let mut blocked = self.blocked.lock();
+        blocked.retain(|p| !Arc::ptr_eq(p, parker));
+        self.num_blocked.store(blocked.len(), Ordering::SeqCst);
     }
 
     fn pop(&self) -> Option<RawTaskPtr> {
@@ -195,12 +511,14 @@ fn noop_waker() -> Waker {
 mod tests {
     use std::future::Future;
     use std::hint::black_box;
+    use std::panic::AssertUnwindSafe;
     use std::pin::Pin;
+    use std::sync::Arc;
     use std::task::{Context, Poll};
 
     use futures::future::poll_fn;
 
-    use crate::{noop_waker, TaskPool};
+    use crate::{noop_waker, TaskPool, TaskPoolBuilder};
 
     #[test]
     fn schedule_basic() {
@@ -347,6 +665,66 @@ mod tests {
         while Pin::new(&mut task).poll(&mut cx).is_pending() {}
     }
 
+    #[test]
+    fn block_on_outside_pool() {
+        let executor = TaskPool::new(1);
+        let thread = std::thread::current().id();
+
+        let output = executor.block_on(async {
+            assert_ne!(std::thread::current().id(), thread);
+            1 + 1
+        });
+        assert_eq!(output, 2);
+    }
+
+    #[test]
+    fn block_on_panic() {
+        let executor = TaskPool::new(1);
+
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            executor.block_on(async { panic!("boom") })
+        }));
+        let payload = res.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+
+        // The worker thread must survive the panic.
+        assert_eq!(executor.block_on(async { 1 + 1 }), 2);
+    }
+
+    #[test]
+    fn block_on_inside_pool() {
+        // With a single worker thread, the inner `block_on` must run the
+        // inner task itself, otherwise it would deadlock.
+        let executor = Arc::new(TaskPool::new(1));
+
+        let pool = executor.clone();
+        let output = executor.block_on(async move {
+            let task = pool.spawn(async { 1 + 1 });
+            pool.block_on(task)
+        });
+        assert_eq!(output, 2);
+    }
+
+    #[test]
+    fn block_on_inside_pool_self_wake() {
+        let executor = Arc::new(TaskPool::new(1));
+
+        let pool = executor.clone();
+        let output = executor.block_on(async move {
+            // Queue the running task again, so that the inner `block_on`
+            // finds it in the queue while it is still being polled.
+            poll_fn(|cx| {
+                cx.waker().wake_by_ref();
+                Poll::Ready(())
+            })
+            .await;
+
+            let task = pool.spawn(async { 1 + 1 });
+            pool.block_on(task)
+        });
+        assert_eq!(output, 2);
+    }
+
     #[test]
     fn task_wake_twice() {
         let executor = TaskPool::new(1);
@@ -360,4 +738,35 @@ mod tests {
         let mut cx = Context::from_waker(&waker);
         while Pin::new(&mut task).poll(&mut cx).is_pending() {}
     }
+
+    #[test]
+    fn builder_thread_name() {
+        let executor = TaskPoolBuilder::new()
+            .threads(2)
+            .thread_name("test-worker")
+            .build();
+
+        let name =
+            executor.block_on(async { std::thread::current().name().map(ToOwned::to_owned) });
+
+        let name = name.unwrap();
+        assert!(
+            name == "test-worker-0" || name == "test-worker-1",
+            "unexpected thread name: {}",
+            name
+        );
+    }
+
+    #[test]
+    fn builder_core_affinity() {
+        // Pinning is best-effort and must never prevent the pool from
+        // running tasks, even for cores that do not exist.
+        let executor = TaskPoolBuilder::new()
+            .threads(2)
+            .core_affinity([0, usize::MAX])
+            .build();
+
+        let output = executor.block_on(async { 42 });
+        assert_eq!(output, 42);
+    }
 }
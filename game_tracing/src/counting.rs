@@ -0,0 +1,42 @@
+//! Counting of the allocations made by every thread.
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::cell::Cell;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// An allocator that counts the allocations made by every thread.
+///
+/// The counter is per-thread so that tests running in parallel do not observe the allocations
+/// of each other. The counter of the current thread is returned by [`allocations`].
+pub struct CountingAllocator<T>(T);
+
+impl<T> CountingAllocator<T> {
+    pub const fn new(inner_allocator: T) -> Self {
+        Self(inner_allocator)
+    }
+}
+
+unsafe impl<T> GlobalAlloc for CountingAllocator<T>
+where
+    T: GlobalAlloc,
+{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // The counter may already be destroyed while the thread exits.
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        unsafe { self.0.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.dealloc(ptr, layout) }
+    }
+}
+
+/// Returns the number of allocations made by the current thread.
+///
+/// Only allocations made through a [`CountingAllocator`] are counted.
+pub fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
@@ -1,3 +1,4 @@
+pub mod counting;
 pub mod span;
 
 #[cfg(feature = "tracy")]
@@ -1,10 +1,31 @@
+//! Access to the system clipboard.
+//!
+//! The clipboard supports text and images. Images are exchanged with the system clipboard as
+//! 8-bit RGBA pixels:
+//! - [`set_image`] accepts images in the [`Rgba8Unorm`], [`Rgba8UnormSrgb`], [`Bgra8Unorm`] and
+//!   [`Bgra8UnormSrgb`] formats.
+//! - [`get_image`] always returns images in the [`Rgba8UnormSrgb`] format.
+//!
+//! Image clipboard access is not available on Wayland.
+//!
+//! [`set_image`]: crate::runtime::ClipboardRef::set_image
+//! [`get_image`]: crate::runtime::ClipboardRef::get_image
+//! [`Rgba8Unorm`]: TextureFormat::Rgba8Unorm
+//! [`Rgba8UnormSrgb`]: TextureFormat::Rgba8UnormSrgb
+//! [`Bgra8Unorm`]: TextureFormat::Bgra8Unorm
+//! [`Bgra8UnormSrgb`]: TextureFormat::Bgra8UnormSrgb
+
 #[cfg(unix)]
 mod wayland;
 
+use std::borrow::Cow;
 use std::fmt::{self, Debug, Formatter};
 
+use game_render::texture::{Image, TextureFormat};
 use game_tracing::trace_span;
 use game_window::windows::{WindowId, WindowState};
+use glam::UVec2;
+use thiserror::Error;
 
 #[cfg(unix)]
 use wayland::WaylandBackend;
@@ -87,6 +108,65 @@ impl Clipboard {
             }
         }
     }
+
+    /// Returns the image contained in the clipboard.
+    ///
+    /// The returned image is always in the [`Rgba8UnormSrgb`] format.
+    ///
+    /// [`Rgba8UnormSrgb`]: TextureFormat::Rgba8UnormSrgb
+    pub(crate) fn get_image(&mut self) -> Result<Image, ClipboardError> {
+        let _span = trace_span!("Clipboard::get_image").entered();
+
+        match &mut self.backend {
+            Backend::NotInit | Backend::None => Err(ClipboardError::Unavailable),
+            Backend::Arboard(backend) => {
+                let image = backend.get_image().map_err(|err| match err {
+                    arboard::Error::ContentNotAvailable => ClipboardError::Empty,
+                    err => ClipboardError::Backend(err.to_string()),
+                })?;
+
+                Ok(Image::new(
+                    UVec2::new(image.width as u32, image.height as u32),
+                    TextureFormat::Rgba8UnormSrgb,
+                    image.bytes.into_owned(),
+                ))
+            }
+            #[cfg(unix)]
+            Backend::Wayland(_) => Err(ClipboardError::Unsupported),
+        }
+    }
+
+    /// Sets the contents of the clipboard to an `image`.
+    pub(crate) fn set_image(&mut self, image: &Image) -> Result<(), ClipboardError> {
+        let _span = trace_span!("Clipboard::set_image").entered();
+
+        let bytes = match image.format() {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => {
+                Cow::Borrowed(image.as_bytes())
+            }
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => {
+                let mut bytes = image.as_bytes().to_vec();
+                for pixel in bytes.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                Cow::Owned(bytes)
+            }
+            format => return Err(ClipboardError::UnsupportedFormat(format)),
+        };
+
+        match &mut self.backend {
+            Backend::NotInit | Backend::None => Err(ClipboardError::Unavailable),
+            Backend::Arboard(backend) => backend
+                .set_image(arboard::ImageData {
+                    width: image.width() as usize,
+                    height: image.height() as usize,
+                    bytes,
+                })
+                .map_err(|err| ClipboardError::Backend(err.to_string())),
+            #[cfg(unix)]
+            Backend::Wayland(_) => Err(ClipboardError::Unsupported),
+        }
+    }
 }
 
 impl Debug for Clipboard {
@@ -95,6 +175,26 @@ impl Debug for Clipboard {
     }
 }
 
+/// An error returned by image clipboard operations.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ClipboardError {
+    /// The system clipboard could not be opened.
+    #[error("clipboard unavailable")]
+    Unavailable,
+    /// The clipboard backend of the platform does not support images.
+    #[error("image clipboard not supported on this platform")]
+    Unsupported,
+    /// The clipboard contains no image.
+    #[error("clipboard contains no image")]
+    Empty,
+    /// The [`TextureFormat`] of the image cannot be stored in the clipboard.
+    #[error("unsupported image format: {0:?}")]
+    UnsupportedFormat(TextureFormat),
+    /// The platform clipboard returned an error.
+    #[error("clipboard error: {0}")]
+    Backend(String),
+}
+
 enum Backend {
     NotInit,
     None,
@@ -2,7 +2,7 @@
 
 use std::sync::Arc;
 
-mod clipboard;
+pub mod clipboard;
 pub mod layout;
 pub mod primitive;
 pub mod render;
@@ -18,7 +18,7 @@ use game_window::events::WindowEvent;
 use game_window::windows::WindowState;
 use glam::UVec2;
 
-use render::UiRenderer;
+use render::{DebugOverlay, UiRenderer};
 use runtime::Runtime;
 
 pub struct UiState {
@@ -55,6 +55,16 @@ impl UiState {
             .update_scale_factor(target, scale_factor);
     }
 
+    /// Returns the current [`DebugOverlay`].
+    pub fn debug_overlay(&self) -> DebugOverlay {
+        self.renderer.debug_overlay()
+    }
+
+    /// Sets the [`DebugOverlay`] that visualizes the computed layout of all elements.
+    pub fn set_debug_overlay(&mut self, overlay: DebugOverlay) {
+        self.renderer.set_debug_overlay(overlay);
+    }
+
     pub fn destroy(&mut self, target: RenderTarget) {
         self.renderer.remove(target);
         self.runtime.windows().destroy(target);
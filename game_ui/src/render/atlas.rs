@@ -0,0 +1,394 @@
+//! Packing of many small images into a single texture.
+//!
+//! Rendering many small images (e.g. icons) from separate textures requires a separate texture
+//! binding for every image. An [`ImageAtlas`] packs these images into a single shared image,
+//! allowing all of them to be drawn from the same texture using their [`UvRect`].
+
+use std::cmp::Reverse;
+
+use game_common::collections::arena::{Arena, Key};
+use game_tracing::trace_span;
+use glam::{UVec2, Vec2};
+use image::{ImageBuffer, Rgba};
+use thiserror::Error;
+
+/// The default initial size of an [`ImageAtlas`].
+pub const DEFAULT_ATLAS_SIZE: u32 = 512;
+
+/// The default maximum size of an [`ImageAtlas`].
+pub const DEFAULT_MAX_ATLAS_SIZE: u32 = 4096;
+
+/// The number of transparent pixels between two images in the atlas.
+///
+/// Without padding linear filtering would sample pixels from neighboring images at the edges.
+const PADDING: u32 = 1;
+
+/// A square image containing many smaller images.
+///
+/// Images are placed using a shelf allocator. When the atlas runs out of space all images are
+/// repacked, reclaiming the space of removed images. If repacking is not enough the atlas grows
+/// by doubling its size, up to a configured maximum.
+///
+/// Since images are moved when the atlas is repacked, the [`UvRect`] of an image is only valid
+/// until the [`generation`] of the atlas changes.
+///
+/// [`generation`]: Self::generation
+#[derive(Debug)]
+pub struct ImageAtlas {
+    image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    max_size: u32,
+    images: Arena<AtlasEntry>,
+    allocator: ShelfAllocator,
+    generation: u64,
+}
+
+impl ImageAtlas {
+    /// Creates a new, empty `ImageAtlas` with the given initial `size` and `max_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is `0` or `size` is greater than `max_size`.
+    pub fn new(size: u32, max_size: u32) -> Self {
+        assert!(size != 0, "atlas size must not be 0");
+        assert!(size <= max_size, "atlas size must not exceed max_size");
+
+        Self {
+            image: ImageBuffer::new(size, size),
+            max_size,
+            images: Arena::new(),
+            allocator: ShelfAllocator::new(size),
+            generation: 0,
+        }
+    }
+
+    /// Returns the current width and height of the atlas.
+    #[inline]
+    pub fn size(&self) -> u32 {
+        self.image.width()
+    }
+
+    /// Returns the number of images in the atlas.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Returns `true` if the atlas contains no images.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    /// Returns the generation of the atlas.
+    ///
+    /// The generation changes whenever the contents of the atlas change. When the generation
+    /// changes the atlas image must be uploaded again and all [`UvRect`]s must be queried again.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the image containing all packed images.
+    #[inline]
+    pub fn image(&self) -> &ImageBuffer<Rgba<u8>, Vec<u8>> {
+        &self.image
+    }
+
+    /// Inserts a new image into the atlas.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AtlasFull`] if the image does not fit into the atlas, even after growing the
+    /// atlas to its maximum size.
+    pub fn insert(
+        &mut self,
+        image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    ) -> Result<AtlasImageId, AtlasFull> {
+        let _span = trace_span!("ImageAtlas::insert").entered();
+
+        let size = UVec2::new(image.width(), image.height());
+        if size.x + PADDING > self.max_size || size.y + PADDING > self.max_size {
+            return Err(AtlasFull);
+        }
+
+        if let Some(position) = self.allocator.allocate(size) {
+            image::imageops::replace(
+                &mut self.image,
+                &image,
+                position.x.into(),
+                position.y.into(),
+            );
+            self.generation += 1;
+
+            let key = self.images.insert(AtlasEntry { image, position });
+            return Ok(AtlasImageId(key));
+        }
+
+        // The new image is placed by `repack` together with all existing images.
+        let key = self.images.insert(AtlasEntry {
+            image,
+            position: UVec2::ZERO,
+        });
+
+        // Try to reclaim the space of removed images first before growing the atlas.
+        let mut atlas_size = self.size();
+        loop {
+            if self.repack(atlas_size) {
+                return Ok(AtlasImageId(key));
+            }
+
+            if atlas_size >= self.max_size {
+                self.images.remove(key);
+                return Err(AtlasFull);
+            }
+
+            atlas_size = u32::min(atlas_size.saturating_mul(2), self.max_size);
+        }
+    }
+
+    /// Removes the image with the given `id` from the atlas.
+    ///
+    /// The space of the removed image is reclaimed when the atlas is repacked. Returns the removed
+    /// image or `None` if the `id` is invalid.
+    pub fn remove(&mut self, id: AtlasImageId) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        let entry = self.images.remove(id.0)?;
+
+        for y in entry.position.y..entry.position.y + entry.image.height() {
+            for x in entry.position.x..entry.position.x + entry.image.width() {
+                self.image.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+
+        if self.images.is_empty() {
+            self.allocator = ShelfAllocator::new(self.size());
+        }
+
+        self.generation += 1;
+        Some(entry.image)
+    }
+
+    /// Returns the [`UvRect`] of the image with the given `id`.
+    ///
+    /// Returns `None` if the `id` is invalid.
+    pub fn uv_rect(&self, id: AtlasImageId) -> Option<UvRect> {
+        let entry = self.images.get(id.0)?;
+
+        let size = self.size() as f32;
+        let min = entry.position.as_vec2() / size;
+        let max = (entry.position + UVec2::new(entry.image.width(), entry.image.height()))
+            .as_vec2()
+            / size;
+
+        Some(UvRect { min, max })
+    }
+
+    /// Repacks all images into a new atlas with the given `size`.
+    ///
+    /// Returns `false` and leaves the atlas unchanged if not all images fit.
+    fn repack(&mut self, size: u32) -> bool {
+        let _span = trace_span!("ImageAtlas::repack").entered();
+
+        // Placing the highest images first results in the least amount of
+        // wasted space on every shelf.
+        let mut keys: Vec<Key> = self.images.keys().collect();
+        keys.sort_by_key(|key| Reverse(self.images.get(*key).unwrap().image.height()));
+
+        let mut allocator = ShelfAllocator::new(size);
+        let mut positions = Vec::with_capacity(keys.len());
+        for key in keys {
+            let image = &self.images.get(key).unwrap().image;
+
+            match allocator.allocate(UVec2::new(image.width(), image.height())) {
+                Some(position) => positions.push((key, position)),
+                None => return false,
+            }
+        }
+
+        let mut image = ImageBuffer::new(size, size);
+        for (key, position) in positions {
+            let entry = self.images.get_mut(key).unwrap();
+            entry.position = position;
+            image::imageops::replace(
+                &mut image,
+                &entry.image,
+                position.x.into(),
+                position.y.into(),
+            );
+        }
+
+        self.image = image;
+        self.allocator = allocator;
+        self.generation += 1;
+        true
+    }
+}
+
+impl Default for ImageAtlas {
+    fn default() -> Self {
+        Self::new(DEFAULT_ATLAS_SIZE, DEFAULT_MAX_ATLAS_SIZE)
+    }
+}
+
+/// A handle to an image in an [`ImageAtlas`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AtlasImageId(Key);
+
+/// The normalized texture coordinates of an image in an [`ImageAtlas`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UvRect {
+    /// The top-left corner.
+    pub min: Vec2,
+    /// The bottom-right corner.
+    pub max: Vec2,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Error)]
+#[error("image does not fit into atlas")]
+pub struct AtlasFull;
+
+#[derive(Clone, Debug)]
+struct AtlasEntry {
+    image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    position: UVec2,
+}
+
+/// An allocator placing rectangles in rows ("shelves") from top to bottom.
+#[derive(Clone, Debug)]
+struct ShelfAllocator {
+    size: u32,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// The x position at which the free space of the shelf begins.
+    cursor: u32,
+}
+
+impl ShelfAllocator {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+        }
+    }
+
+    fn allocate(&mut self, size: UVec2) -> Option<UVec2> {
+        let width = size.x + PADDING;
+        let height = size.y + PADDING;
+
+        // Pick the lowest shelf that still fits the rectangle to waste as
+        // little vertical space as possible.
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height && self.size - shelf.cursor >= width)
+            .min_by_key(|shelf| shelf.height);
+
+        if let Some(shelf) = shelf {
+            let position = UVec2::new(shelf.cursor, shelf.y);
+            shelf.cursor += width;
+            return Some(position);
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map(|shelf| shelf.y + shelf.height)
+            .unwrap_or(0);
+        if self.size - y < height || self.size < width {
+            return None;
+        }
+
+        self.shelves.push(Shelf {
+            y,
+            height,
+            cursor: width,
+        });
+        Some(UVec2::new(0, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+    use image::{ImageBuffer, Rgba};
+
+    use super::{AtlasFull, ImageAtlas, UvRect};
+
+    fn image(width: u32, height: u32, value: u8) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_pixel(width, height, Rgba([value; 4]))
+    }
+
+    fn overlaps(a: UvRect, b: UvRect) -> bool {
+        a.min.x < b.max.x && b.min.x < a.max.x && a.min.y < b.max.y && b.min.y < a.max.y
+    }
+
+    #[test]
+    fn atlas_insert() {
+        let mut atlas = ImageAtlas::new(64, 64);
+
+        let ids: Vec<_> = (0..4)
+            .map(|index| atlas.insert(image(16, 16, index + 1)).unwrap())
+            .collect();
+
+        let rects: Vec<_> = ids.iter().map(|id| atlas.uv_rect(*id).unwrap()).collect();
+        for (index, a) in rects.iter().enumerate() {
+            assert!(a.min.cmpge(Vec2::ZERO).all() && a.max.cmple(Vec2::ONE).all());
+
+            for b in &rects[index + 1..] {
+                assert!(!overlaps(*a, *b));
+            }
+        }
+
+        for (index, rect) in rects.iter().enumerate() {
+            let x = (rect.min.x * 64.0) as u32;
+            let y = (rect.min.y * 64.0) as u32;
+            assert_eq!(*atlas.image().get_pixel(x, y), Rgba([index as u8 + 1; 4]));
+        }
+    }
+
+    #[test]
+    fn atlas_grow() {
+        let mut atlas = ImageAtlas::new(32, 128);
+
+        let a = atlas.insert(image(24, 24, 1)).unwrap();
+        let b = atlas.insert(image(24, 24, 2)).unwrap();
+
+        assert_eq!(atlas.size(), 64);
+        assert!(!overlaps(
+            atlas.uv_rect(a).unwrap(),
+            atlas.uv_rect(b).unwrap()
+        ));
+    }
+
+    #[test]
+    fn atlas_repack_reclaims_space() {
+        let mut atlas = ImageAtlas::new(32, 32);
+
+        let a = atlas.insert(image(31, 15, 1)).unwrap();
+        let b = atlas.insert(image(31, 15, 2)).unwrap();
+        assert_eq!(atlas.insert(image(31, 15, 3)), Err(AtlasFull));
+
+        assert!(atlas.remove(a).is_some());
+        let c = atlas.insert(image(31, 15, 3)).unwrap();
+
+        assert_eq!(atlas.size(), 32);
+        assert!(atlas.uv_rect(a).is_none());
+        assert!(!overlaps(
+            atlas.uv_rect(b).unwrap(),
+            atlas.uv_rect(c).unwrap()
+        ));
+    }
+
+    #[test]
+    fn atlas_image_too_large() {
+        let mut atlas = ImageAtlas::new(32, 64);
+
+        assert_eq!(atlas.insert(image(64, 1, 1)), Err(AtlasFull));
+        assert!(atlas.is_empty());
+        assert_eq!(atlas.size(), 32);
+    }
+}
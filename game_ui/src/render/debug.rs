@@ -1,9 +1,14 @@
 use std::ops::{Deref, DerefMut};
 
+use game_common::components::Color;
 use game_tracing::trace_span;
+use glam::UVec2;
 use image::{ImageBuffer, Pixel, Rgba};
 
 use crate::layout::computed_style::ComputedPadding;
+use crate::layout::Layout;
+
+use super::{DrawCommand, Rect};
 
 #[inline]
 pub fn is_debug_render_enabled() -> bool {
@@ -44,7 +49,6 @@ pub fn debug_border<C>(image: &mut ImageBuffer<Rgba<u8>, C>)
 where
     C: Deref<Target = [<Rgba<u8> as Pixel>::Subpixel]> + DerefMut,
 {
-    assert!(is_debug_render_enabled());
     let _span = trace_span!("debug_border").entered();
 
     if image.width() == 0 || image.height() == 0 {
@@ -80,7 +84,6 @@ pub fn debug_padding<C>(image: &mut ImageBuffer<Rgba<u8>, C>, padding: ComputedP
 where
     C: Deref<Target = [<Rgba<u8> as Pixel>::Subpixel]> + DerefMut,
 {
-    assert!(is_debug_render_enabled());
     let _span = trace_span!("debug_padding").entered();
 
     if cfg!(debug_assertions) {
@@ -116,3 +119,147 @@ where
         }
     }
 }
+
+/// An overlay that visualizes the computed layout of all elements.
+///
+/// The overlay is drawn on top of all elements and does not affect the layout. Unlike the
+/// `UI_DEBUG_RENDER` env variable the overlay can be toggled at runtime.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DebugOverlay {
+    /// Draw an outline around the computed rectangle of every element.
+    pub enabled: bool,
+    /// Also highlight the padding of every element.
+    ///
+    /// Has no effect unless the overlay is `enabled`.
+    pub padding: bool,
+}
+
+/// Draws the [`DebugOverlay`] for an element with the given `layout`.
+///
+/// Returns `None` if the element is not visible in the viewport of the given `size`.
+pub(crate) fn draw_debug_overlay(
+    overlay: DebugOverlay,
+    layout: &Layout,
+    size: UVec2,
+) -> Option<DrawCommand> {
+    let _span = trace_span!("draw_debug_overlay").entered();
+
+    if !overlay.enabled || layout.position.x > size.x || layout.position.y > size.y {
+        return None;
+    }
+
+    // Truncate the overlay at the viewport size, the same as containers.
+    let width = u32::min(layout.width, size.x);
+    let height = u32::min(layout.height, size.y);
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut image = ImageBuffer::new(width, height);
+    debug_border(&mut image);
+
+    let padding = layout.style.padding;
+    if overlay.padding
+        && width >= padding.left + padding.right
+        && height >= padding.top + padding.bottom
+    {
+        debug_padding(&mut image, padding);
+    }
+
+    Some(DrawCommand {
+        position: Rect {
+            min: layout.position,
+            max: layout.position + UVec2::new(layout.width, layout.height),
+        },
+        color: Color::WHITE,
+        image,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec2;
+    use image::Rgba;
+
+    use crate::layout::computed_style::ComputedStyle;
+    use crate::layout::Layout;
+    use crate::style::{Padding, Size, Style};
+
+    use super::{draw_debug_overlay, DebugOverlay};
+
+    const VIEWPORT: UVec2 = UVec2::splat(1000);
+
+    fn create_layout(position: UVec2, width: u32, height: u32) -> Layout {
+        let style = Style {
+            padding: Padding {
+                top: Size::Pixels(10),
+                bottom: Size::Pixels(10),
+                left: Size::Pixels(10),
+                right: Size::Pixels(10),
+            },
+            ..Default::default()
+        };
+
+        Layout {
+            style: ComputedStyle::new(style, VIEWPORT, 1.0),
+            position,
+            width,
+            height,
+            has_changed: true,
+        }
+    }
+
+    #[test]
+    fn debug_overlay_disabled() {
+        let layout = create_layout(UVec2::ZERO, 100, 100);
+        assert!(draw_debug_overlay(DebugOverlay::default(), &layout, VIEWPORT).is_none());
+    }
+
+    #[test]
+    fn debug_overlay_outline() {
+        let overlay = DebugOverlay {
+            enabled: true,
+            padding: false,
+        };
+        let layout = create_layout(UVec2::new(20, 30), 100, 50);
+
+        let cmd = draw_debug_overlay(overlay, &layout, VIEWPORT).unwrap();
+        assert_eq!(cmd.position.min, UVec2::new(20, 30));
+        assert_eq!(cmd.position.max, UVec2::new(120, 80));
+        assert_eq!(cmd.image.dimensions(), (100, 50));
+
+        assert_eq!(*cmd.image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+        assert_eq!(*cmd.image.get_pixel(99, 49), Rgba([255, 0, 0, 255]));
+        // The overlay must not cover the element itself.
+        assert_eq!(*cmd.image.get_pixel(5, 5), Rgba([0, 0, 0, 0]));
+        assert_eq!(*cmd.image.get_pixel(50, 25), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn debug_overlay_padding() {
+        let overlay = DebugOverlay {
+            enabled: true,
+            padding: true,
+        };
+        let layout = create_layout(UVec2::ZERO, 100, 50);
+
+        let cmd = draw_debug_overlay(overlay, &layout, VIEWPORT).unwrap();
+        assert_ne!(*cmd.image.get_pixel(50, 5), Rgba([0, 0, 0, 0]));
+        assert_ne!(*cmd.image.get_pixel(5, 25), Rgba([0, 0, 0, 0]));
+        assert_eq!(*cmd.image.get_pixel(50, 25), Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn debug_overlay_outside_viewport() {
+        let overlay = DebugOverlay {
+            enabled: true,
+            padding: true,
+        };
+
+        let layout = create_layout(UVec2::new(1001, 0), 100, 100);
+        assert!(draw_debug_overlay(overlay, &layout, VIEWPORT).is_none());
+
+        let layout = create_layout(UVec2::ZERO, 0, 100);
+        assert!(draw_debug_overlay(overlay, &layout, VIEWPORT).is_none());
+    }
+}
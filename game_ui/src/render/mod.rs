@@ -1,3 +1,4 @@
+pub mod atlas;
 pub(crate) mod container;
 pub(crate) mod debug;
 pub mod image;
@@ -16,12 +17,13 @@ use game_render::{Renderer, FINAL_RENDER_PASS};
 use game_tracing::trace_span;
 use glam::UVec2;
 use parking_lot::RwLock;
-use pipeline::Vertex;
-use wgpu::Texture;
+use pipeline::{ElementTexture, Vertex};
 
 use crate::layout::{Key, Layout};
 use crate::primitive::Primitive;
 
+use self::debug::draw_debug_overlay;
+pub use self::debug::DebugOverlay;
 pub use self::image::Image;
 use self::pipeline::UiPass;
 pub use self::text::Text;
@@ -29,6 +31,9 @@ pub use self::text::Text;
 pub struct UiRenderer {
     targets: HashMap<RenderTarget, SurfaceState>,
     elements: Arc<RwLock<HashMap<RenderTarget, SurfaceDrawCommands>>>,
+    debug_overlay: DebugOverlay,
+    /// Whether the `debug_overlay` changed since the last call to `update`.
+    debug_overlay_changed: bool,
 }
 
 impl UiRenderer {
@@ -53,6 +58,21 @@ impl UiRenderer {
         Self {
             targets: HashMap::new(),
             elements,
+            debug_overlay: DebugOverlay::default(),
+            debug_overlay_changed: false,
+        }
+    }
+
+    /// Returns the current [`DebugOverlay`].
+    pub fn debug_overlay(&self) -> DebugOverlay {
+        self.debug_overlay
+    }
+
+    /// Sets the [`DebugOverlay`] drawn on top of all elements.
+    pub fn set_debug_overlay(&mut self, overlay: DebugOverlay) {
+        if self.debug_overlay != overlay {
+            self.debug_overlay = overlay;
+            self.debug_overlay_changed = true;
         }
     }
 
@@ -103,6 +123,11 @@ impl UiRenderer {
 
             cmds.begin_tracking();
             for (key, layout, elem) in &state.nodes {
+                if layout.has_changed || self.debug_overlay_changed {
+                    let cmd = draw_debug_overlay(self.debug_overlay, layout, state.size);
+                    cmds.insert_overlay(*key, cmd);
+                }
+
                 if !layout.has_changed {
                     cmds.track(*key);
                     continue;
@@ -139,6 +164,8 @@ impl UiRenderer {
 
             cmds.finish_tracking();
         }
+
+        self.debug_overlay_changed = false;
     }
 }
 
@@ -185,6 +212,8 @@ struct SurfaceDrawCommands {
     // not need to be rendered. We can still retain them
     // over frames.
     cmds: BTreeMap<Key, Option<DrawCommandState>>,
+    /// Commands of the [`DebugOverlay`] that are drawn after all other commands.
+    overlay: BTreeMap<Key, DrawCommandState>,
     tracked: HashSet<Key>,
 }
 
@@ -192,6 +221,7 @@ impl SurfaceDrawCommands {
     fn new() -> Self {
         Self {
             cmds: BTreeMap::new(),
+            overlay: BTreeMap::new(),
             tracked: HashSet::new(),
         }
     }
@@ -202,6 +232,7 @@ impl SurfaceDrawCommands {
 
     fn finish_tracking(&mut self) {
         self.cmds.retain(|k, _| self.tracked.contains(k));
+        self.overlay.retain(|k, _| self.tracked.contains(k));
     }
 
     fn track(&mut self, key: Key) {
@@ -221,8 +252,28 @@ impl SurfaceDrawCommands {
         self.tracked.insert(key);
     }
 
+    fn insert_overlay(&mut self, key: Key, cmd: Option<DrawCommand>) {
+        match cmd {
+            Some(cmd) => {
+                self.overlay.insert(
+                    key,
+                    DrawCommandState {
+                        cmd,
+                        gpu_state: None,
+                    },
+                );
+            }
+            None => {
+                self.overlay.remove(&key);
+            }
+        }
+    }
+
     fn commands_mut(&mut self) -> impl Iterator<Item = &mut DrawCommandState> + '_ {
-        self.cmds.values_mut().filter_map(|v| v.as_mut())
+        self.cmds
+            .values_mut()
+            .filter_map(|v| v.as_mut())
+            .chain(self.overlay.values_mut())
     }
 }
 
@@ -242,7 +293,7 @@ struct DrawCommandState {
 #[derive(Debug)]
 struct GpuDrawCommandState {
     vertices: [Vertex; 4],
-    texture: Texture,
+    texture: ElementTexture,
     /// Viewport size for which this draw command is build.
     size: UVec2,
 }
@@ -6,7 +6,7 @@ use bytemuck::{Pod, Zeroable};
 use game_render::camera::RenderTarget;
 use game_render::graph::{Node, RenderContext};
 use game_tracing::trace_span;
-use glam::UVec2;
+use glam::{UVec2, Vec2};
 use parking_lot::{Mutex, RwLock};
 use wgpu::util::{BufferInitDescriptor, DeviceExt, DrawIndexedIndirectArgs};
 use wgpu::{
@@ -17,11 +17,12 @@ use wgpu::{
     Operations, Origin3d, PipelineLayout, PipelineLayoutDescriptor, PolygonMode, PrimitiveState,
     PrimitiveTopology, Queue, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
     RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderModule,
-    ShaderModuleDescriptor, ShaderSource, ShaderStages, StoreOp, TextureAspect, TextureDescriptor,
-    TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
-    TextureViewDescriptor, TextureViewDimension, VertexState,
+    ShaderModuleDescriptor, ShaderSource, ShaderStages, StoreOp, Texture, TextureAspect,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDescriptor, TextureViewDimension, VertexState,
 };
 
+use super::atlas::{AtlasImageId, ImageAtlas};
 use super::remap::remap;
 use super::{DrawCommand, GpuDrawCommandState, SurfaceDrawCommands};
 
@@ -33,6 +34,14 @@ const DEFAULT_TEXTURE_CAPACITY: NonZeroU32 = unsafe { NonZeroU32::new_unchecked(
 /// The factor at which the texture array capacity grows. Must be > 1.
 const CAPACITY_GROWTH_FACTOR: NonZeroU32 = unsafe { NonZeroU32::new_unchecked(2) };
 
+/// The maximum width and height of images that are packed into the [`ImageAtlas`].
+///
+/// Larger images get their own texture.
+const MAX_ATLAS_IMAGE_SIZE: u32 = 256;
+
+/// The index of the [`ImageAtlas`] texture in the texture array.
+const ATLAS_TEXTURE_INDEX: u32 = 0;
+
 #[derive(Debug)]
 struct UiPipeline {
     bind_group_layout: BindGroupLayout,
@@ -160,6 +169,34 @@ pub(super) struct Vertex {
     _pad0: [u32; 2],
 }
 
+/// The texture of a single UI element.
+#[derive(Debug)]
+pub(super) enum ElementTexture {
+    Texture(Texture),
+    Atlas(AtlasAllocation),
+}
+
+/// An image in the [`ImageAtlas`] that is removed from the atlas when dropped.
+#[derive(Debug)]
+pub(super) struct AtlasAllocation {
+    atlas: Arc<Mutex<ImageAtlas>>,
+    id: AtlasImageId,
+}
+
+impl Drop for AtlasAllocation {
+    fn drop(&mut self) {
+        self.atlas.lock().remove(self.id);
+    }
+}
+
+/// The [`ImageAtlas`] texture on the GPU.
+#[derive(Debug)]
+struct AtlasTexture {
+    texture: Texture,
+    /// The [`ImageAtlas::generation`] of the uploaded image.
+    generation: u64,
+}
+
 #[derive(Debug)]
 pub struct UiPass {
     pipeline: Mutex<UiPipeline>,
@@ -168,6 +205,9 @@ pub struct UiPass {
     texture_buffer: Mutex<Vec<TextureView>>,
     instance_count: Mutex<u32>,
     index_buffer: Buffer,
+    /// Small images of all elements packed into a single texture.
+    atlas: Arc<Mutex<ImageAtlas>>,
+    atlas_texture: Mutex<Option<AtlasTexture>>,
 }
 
 impl UiPass {
@@ -188,6 +228,8 @@ impl UiPass {
             texture_buffer: Mutex::new(Vec::new()),
             instance_count: Mutex::new(0),
             index_buffer,
+            atlas: Arc::new(Mutex::new(ImageAtlas::default())),
+            atlas_texture: Mutex::new(None),
         }
     }
 
@@ -212,35 +254,120 @@ impl UiPass {
         };
 
         for cmd in cmds.commands_mut() {
-            let state = match &mut cmd.gpu_state {
+            match &cmd.gpu_state {
                 // For uploaded textures we must ensure that the texture size
                 // matches the size of the current viewport, otherwise textures
                 // will become squashed.
                 // This can happen when the window is rapidly resized and the
                 // ui state and renderer temporarily report different window sizes.
-                Some(state) if state.size != viewport_size => {
-                    let gpu_state = create_element(&cmd.cmd, viewport_size, device, queue);
-                    cmd.gpu_state.insert(gpu_state)
-                }
-                None => {
-                    let gpu_state = create_element(&cmd.cmd, viewport_size, device, queue);
-                    cmd.gpu_state.insert(gpu_state)
-                }
-                Some(state) => state,
-            };
+                Some(state) if state.size != viewport_size => (),
+                None => (),
+                Some(_) => continue,
+            }
+
+            let gpu_state = create_element(&cmd.cmd, viewport_size, device, queue, &self.atlas);
+            cmd.gpu_state = Some(gpu_state);
+        }
+
+        // Elements are only added to or removed from the atlas when they are
+        // created or dropped, so the atlas is final for this frame.
+        let atlas = self.atlas.lock();
+        if !atlas.is_empty() {
+            debug_assert_eq!(texture_buffer.len() as u32, ATLAS_TEXTURE_INDEX);
+            texture_buffer.push(self.upload_atlas(&atlas, device, queue));
+        }
+
+        for cmd in cmds.commands_mut() {
+            let state = cmd.gpu_state.as_ref().unwrap();
+            let mut vertices = state.vertices;
 
-            let texture_index = texture_buffer.len() as u32;
-            texture_buffer.push(state.texture.create_view(&TextureViewDescriptor::default()));
+            match &state.texture {
+                ElementTexture::Texture(texture) => {
+                    let texture_index = texture_buffer.len() as u32;
+                    texture_buffer.push(texture.create_view(&TextureViewDescriptor::default()));
 
-            for vertex in &mut state.vertices {
-                vertex.texture_index = texture_index;
+                    for vertex in &mut vertices {
+                        vertex.texture_index = texture_index;
+                    }
+                }
+                ElementTexture::Atlas(allocation) => {
+                    // The position of the image changes when the atlas is
+                    // repacked, so the UVs are remapped every frame.
+                    let rect = atlas.uv_rect(allocation.id).unwrap();
+
+                    for vertex in &mut vertices {
+                        let uv = rect.min + (rect.max - rect.min) * Vec2::from(vertex.uv);
+                        vertex.uv = uv.to_array();
+                        vertex.texture_index = ATLAS_TEXTURE_INDEX;
+                    }
+                }
             }
 
-            vertex_buffer.extend(bytemuck::bytes_of(&state.vertices));
+            vertex_buffer.extend(bytemuck::bytes_of(&vertices));
 
             *instance_count += 1;
         }
     }
+
+    /// Returns a view of the texture of the `atlas`, uploading the atlas if it changed since the
+    /// last upload.
+    fn upload_atlas(&self, atlas: &ImageAtlas, device: &Device, queue: &Queue) -> TextureView {
+        let _span = trace_span!("UiPass::upload_atlas").entered();
+
+        let mut atlas_texture = self.atlas_texture.lock();
+        if let Some(texture) = &*atlas_texture {
+            if texture.generation == atlas.generation() {
+                return texture
+                    .texture
+                    .create_view(&TextureViewDescriptor::default());
+            }
+        }
+
+        let size = Extent3d {
+            width: atlas.size(),
+            height: atlas.size(),
+            depth_or_array_layers: 1,
+        };
+
+        // The atlas only grows, so the texture only needs to be recreated
+        // when it grew.
+        let texture = match atlas_texture.take() {
+            Some(texture) if texture.texture.size() == size => texture.texture,
+            _ => device.create_texture(&TextureDescriptor {
+                label: Some("ui_atlas"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8UnormSrgb,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            }),
+        };
+
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            atlas.image(),
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * atlas.size()),
+                rows_per_image: Some(atlas.size()),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        *atlas_texture = Some(AtlasTexture {
+            texture,
+            generation: atlas.generation(),
+        });
+        view
+    }
 }
 
 impl Node for UiPass {
@@ -358,6 +485,7 @@ fn create_element(
     viewport_size: UVec2,
     device: &Device,
     queue: &Queue,
+    atlas: &Arc<Mutex<ImageAtlas>>,
 ) -> GpuDrawCommandState {
     let _span = trace_span!("create_element").entered();
 
@@ -369,40 +497,7 @@ fn create_element(
         );
     }
 
-    let texture = device.create_texture(&TextureDescriptor {
-        label: None,
-        size: Extent3d {
-            width: cmd.image.width(),
-            height: cmd.image.height(),
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: TextureDimension::D2,
-        format: TextureFormat::Rgba8UnormSrgb,
-        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-        view_formats: &[],
-    });
-
-    queue.write_texture(
-        ImageCopyTexture {
-            texture: &texture,
-            mip_level: 0,
-            origin: Origin3d::ZERO,
-            aspect: TextureAspect::All,
-        },
-        &cmd.image,
-        ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * cmd.image.width()),
-            rows_per_image: Some(cmd.image.height()),
-        },
-        Extent3d {
-            width: cmd.image.width(),
-            height: cmd.image.height(),
-            depth_or_array_layers: 1,
-        },
-    );
+    let texture = create_texture(cmd, device, queue, atlas);
 
     let min = remap(cmd.position.min.as_vec2(), viewport_size.as_vec2());
     let max = remap(cmd.position.max.as_vec2(), viewport_size.as_vec2());
@@ -444,3 +539,58 @@ fn create_element(
         size: viewport_size,
     }
 }
+
+/// Creates the texture of an element, packing small images into the `atlas`.
+fn create_texture(
+    cmd: &DrawCommand,
+    device: &Device,
+    queue: &Queue,
+    atlas: &Arc<Mutex<ImageAtlas>>,
+) -> ElementTexture {
+    if cmd.image.width() <= MAX_ATLAS_IMAGE_SIZE && cmd.image.height() <= MAX_ATLAS_IMAGE_SIZE {
+        // Fall back to a separate texture if the atlas is full.
+        if let Ok(id) = atlas.lock().insert(cmd.image.clone()) {
+            return ElementTexture::Atlas(AtlasAllocation {
+                atlas: atlas.clone(),
+                id,
+            });
+        }
+    }
+
+    let texture = device.create_texture(&TextureDescriptor {
+        label: None,
+        size: Extent3d {
+            width: cmd.image.width(),
+            height: cmd.image.height(),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8UnormSrgb,
+        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+
+    queue.write_texture(
+        ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        &cmd.image,
+        ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * cmd.image.width()),
+            rows_per_image: Some(cmd.image.height()),
+        },
+        Extent3d {
+            width: cmd.image.width(),
+            height: cmd.image.height(),
+            depth_or_array_layers: 1,
+        },
+    );
+
+    ElementTexture::Texture(texture)
+}
@@ -10,6 +10,7 @@ use std::sync::Arc;
 use events::{Event, EventHandlerId, EventHandlers, NodeDestroyed};
 use game_common::collections::arena::{Arena, Key};
 use game_render::camera::RenderTarget;
+use game_render::texture::Image;
 use game_tasks::TaskPool;
 use game_tracing::trace_span;
 use game_window::cursor::Cursor;
@@ -18,7 +19,7 @@ use glam::UVec2;
 use parking_lot::Mutex;
 use reactive::ReactiveRuntime;
 
-use crate::clipboard::Clipboard;
+use crate::clipboard::{Clipboard, ClipboardError};
 use crate::layout::{self, LayoutTree};
 use crate::primitive::Primitive;
 use crate::render::Rect;
@@ -641,6 +642,42 @@ impl<'a> ClipboardRef<'a> {
         self.rt.clipboard.lock().get(window)
     }
 
+    /// Sets the current value of the system clipboard to an `image`.
+    ///
+    /// See the [`clipboard`] module for the supported image formats.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform does not support image clipboard access or the format of
+    /// the `image` is not supported.
+    ///
+    /// [`clipboard`]: crate::clipboard
+    pub fn set_image(&self, image: Image) -> Result<(), ClipboardError> {
+        if self.window().is_none() {
+            return Err(ClipboardError::Unavailable);
+        }
+
+        self.rt.clipboard.lock().set_image(&image)
+    }
+
+    /// Returns the image contained in the system clipboard.
+    ///
+    /// The returned image is always in the [`Rgba8UnormSrgb`] format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform does not support image clipboard access or the clipboard
+    /// does not contain an image.
+    ///
+    /// [`Rgba8UnormSrgb`]: game_render::texture::TextureFormat::Rgba8UnormSrgb
+    pub fn get_image(&self) -> Result<Image, ClipboardError> {
+        if self.window().is_none() {
+            return Err(ClipboardError::Unavailable);
+        }
+
+        self.rt.clipboard.lock().get_image()
+    }
+
     fn window(&self) -> Option<WindowId> {
         let rt = self.rt.inner.lock();
         let Some(document) = rt.documents.get(self.document.0) else {
@@ -38,6 +38,7 @@ define_id! {
     // Physics
     RIGID_BODY => 6,
     COLLIDER => 7,
+    COLLISION_GROUPS => 13,
 
     // Game
     INVENTORY => 9,
@@ -195,6 +196,61 @@ impl Component for Collider {
     const ID: RecordReference = COLLIDER;
 }
 
+/// The collision groups of a [`Collider`].
+///
+/// Two colliders `a` and `b` can only interact if `a.memberships & b.filter != 0` and
+/// `b.memberships & a.filter != 0`. The same test is applied to physics queries.
+///
+/// A [`Collider`] without a `CollisionGroups` component is a member of all groups and interacts
+/// with all groups.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct CollisionGroups {
+    /// The groups that the collider is a member of.
+    pub memberships: u32,
+    /// The groups that the collider can interact with.
+    pub filter: u32,
+}
+
+impl CollisionGroups {
+    /// The `CollisionGroups` that are a member of and interact with all groups.
+    pub const ALL: Self = Self {
+        memberships: u32::MAX,
+        filter: u32::MAX,
+    };
+
+    /// The `CollisionGroups` that are not a member of and interact with no groups.
+    pub const NONE: Self = Self {
+        memberships: 0,
+        filter: 0,
+    };
+
+    /// Creates a new `CollisionGroups` with the given `memberships` and `filter`.
+    #[inline]
+    pub const fn new(memberships: u32, filter: u32) -> Self {
+        Self {
+            memberships,
+            filter,
+        }
+    }
+
+    /// Returns `true` if `self` can interact with `other`.
+    #[inline]
+    pub const fn test(self, other: Self) -> bool {
+        self.memberships & other.filter != 0 && other.memberships & self.filter != 0
+    }
+}
+
+impl Default for CollisionGroups {
+    #[inline]
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl Component for CollisionGroups {
+    const ID: RecordReference = COLLISION_GROUPS;
+}
+
 #[derive(Clone, Debug)]
 pub enum ColliderShape {
     Cuboid(Cuboid),
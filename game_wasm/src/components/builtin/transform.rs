@@ -59,6 +59,41 @@ impl Transform {
         Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
     }
 
+    /// Returns the matrix used to transform normals.
+    ///
+    /// This is the inverse transpose of the upper 3x3 of [`compute_matrix`] scaled by the
+    /// absolute value of its determinant. Unlike the rotation it remains correct for non-uniform
+    /// scales, but the transformed normals are not normalized. The matrix is always finite, but
+    /// normals collapse to zero along an axis with zero scale.
+    ///
+    /// Normals transformed by this matrix keep their orientation relative to the surface even if
+    /// the transform [`is_mirrored`].
+    ///
+    /// [`compute_matrix`]: Self::compute_matrix
+    /// [`is_mirrored`]: Self::is_mirrored
+    pub fn normal_matrix(self) -> Mat3 {
+        // The cofactor matrix avoids dividing by the scale. It is the inverse
+        // transpose scaled by the determinant, so the sign of the determinant
+        // has to be removed to keep the orientation.
+        let s = self.scale;
+        let cofactor = Mat3::from_quat(self.rotation)
+            * Mat3::from_diagonal(Vec3::new(s.y * s.z, s.x * s.z, s.x * s.y));
+
+        if self.is_mirrored() {
+            -cofactor
+        } else {
+            cofactor
+        }
+    }
+
+    /// Returns `true` if the transform mirrors space, i.e. an odd number of components of the
+    /// scale are negative.
+    ///
+    /// A mirroring transform reverses the winding order of all triangles it is applied to.
+    pub fn is_mirrored(self) -> bool {
+        self.scale.x * self.scale.y * self.scale.z < 0.0
+    }
+
     pub fn mul_transform(self, transform: Transform) -> Self {
         if cfg!(debug_assertions) {
             assert_transform(self);
@@ -132,3 +167,56 @@ fn assert_transform(transform: Transform) {
         transform.scale,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat3, Quat, Vec3};
+
+    use super::Transform;
+
+    #[test]
+    fn transform_normal_matrix() {
+        for scale in [
+            Vec3::ONE,
+            Vec3::new(4.0, 1.0, 0.25),
+            Vec3::new(-2.0, 1.0, 3.0),
+            Vec3::new(-1.0, -1.0, -1.0),
+        ] {
+            let transform = Transform {
+                translation: Vec3::ZERO,
+                rotation: Quat::from_axis_angle(Vec3::Y, 1.0),
+                scale,
+            };
+
+            let inverse_transpose = Mat3::from_mat4(transform.compute_matrix())
+                .inverse()
+                .transpose();
+
+            for normal in [Vec3::X, Vec3::Y, Vec3::Z, Vec3::ONE.normalize()] {
+                let expected = (inverse_transpose * normal).normalize();
+                let actual = (transform.normal_matrix() * normal).normalize();
+                assert!(
+                    actual.abs_diff_eq(expected, 1e-5),
+                    "{} != {} for scale {}",
+                    actual,
+                    expected,
+                    scale,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn transform_normal_matrix_zero_scale() {
+        let transform = Transform {
+            scale: Vec3::new(1.0, 0.0, 1.0),
+            ..Transform::IDENTITY
+        };
+        let matrix = transform.normal_matrix();
+        assert!(matrix.is_finite());
+
+        // A plane flattened onto the XZ plane faces up.
+        assert_eq!(matrix * Vec3::Y, Vec3::Y);
+        assert_eq!(matrix * Vec3::X, Vec3::ZERO);
+    }
+}
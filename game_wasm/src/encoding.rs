@@ -128,6 +128,15 @@ impl BinaryWriter {
         }
     }
 
+    /// Creates a new `BinaryWriter` that writes into the given buffers.
+    ///
+    /// The buffers are cleared, but their allocations are reused.
+    pub fn with_buffers(mut primitives: Vec<Field>, mut buffer: Vec<u8>) -> Self {
+        primitives.clear();
+        buffer.clear();
+        Self { primitives, buffer }
+    }
+
     pub fn encoded<T>(mut self, t: &T) -> (Vec<Field>, Vec<u8>)
     where
         T: Encode,
@@ -400,25 +409,40 @@ where
     (data, encode_fields(&fields))
 }
 
-pub fn decode_fields(mut buf: &[u8]) -> Vec<Field> {
+pub fn decode_fields(buf: &[u8]) -> Vec<Field> {
     let mut fields = Vec::new();
+    decode_fields_into(buf, &mut fields);
+    fields
+}
+
+/// Decodes the fields in `buf` and appends them to `fields`.
+///
+/// Unlike [`decode_fields`] this allows reusing the allocation of `fields`.
+pub fn decode_fields_into(mut buf: &[u8], fields: &mut Vec<Field>) {
+    fields.reserve(buf.len() / 5);
     while !buf.is_empty() {
         let primitive = Primitive::from_u8(buf.get_u8()).unwrap();
         // usize == u32 for wasm32 arch.
         let offset = buf.get_u32_le() as usize;
         fields.push(Field { primitive, offset });
     }
-
-    fields
 }
 
 pub fn encode_fields(fields: &[Field]) -> Vec<u8> {
     let mut fields_encoded = Vec::new();
+    encode_fields_into(fields, &mut fields_encoded);
+    fields_encoded
+}
+
+/// Encodes the `fields` and appends them to `buf`.
+///
+/// Unlike [`encode_fields`] this allows reusing the allocation of `buf`.
+pub fn encode_fields_into(fields: &[Field], buf: &mut Vec<u8>) {
+    buf.reserve(fields.len() * 5);
     for field in fields {
-        fields_encoded.push(field.primitive.to_u8());
-        fields_encoded.extend((field.offset as u32).to_le_bytes());
+        buf.push(field.primitive.to_u8());
+        buf.extend((field.offset as u32).to_le_bytes());
     }
-    fields_encoded
 }
 
 impl<T> Encode for Vec<T>
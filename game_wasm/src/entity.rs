@@ -5,7 +5,7 @@ use crate::encoding::{Decode, DecodeError, Encode, Primitive, Reader, Writer};
 /// A unique identifier for an [`Entity`].
 ///
 /// [`Entity`]: crate::world::Entity
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Zeroable, Pod)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Zeroable, Pod)]
 #[repr(transparent)]
 pub struct EntityId(u64);
 
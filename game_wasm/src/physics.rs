@@ -2,7 +2,7 @@ use core::mem::MaybeUninit;
 
 use glam::{Quat, Vec3};
 
-use crate::components::builtin::{Axis, ColliderShape};
+use crate::components::builtin::{Axis, ColliderShape, CollisionGroups};
 use crate::entity::EntityId;
 use crate::math::Ray;
 use crate::raw::physics::{
@@ -150,11 +150,17 @@ pub struct RayHit {
 #[derive(Clone, Debug, Default)]
 pub struct QueryFilter<'a> {
     pub exclude_entities: &'a [EntityId],
+    /// Only include colliders whose [`CollisionGroups`] interact with these groups.
+    ///
+    /// Defaults to [`CollisionGroups::ALL`], which includes all colliders.
+    pub groups: CollisionGroups,
 }
 
 fn build_raw_query_filter(filter: QueryFilter<'_>) -> RawQueryFilter {
     RawQueryFilter {
         exclude_entities_ptr: filter.exclude_entities.as_ptr(),
         exclude_entities_len: filter.exclude_entities.len(),
+        groups_memberships: filter.groups.memberships,
+        groups_filter: filter.groups.filter,
     }
 }
@@ -1,23 +1,34 @@
 use core::mem::MaybeUninit;
 
+use crate::components::builtin::Transform;
 use crate::entity::EntityId;
-use crate::raw::{prefab_spawn, RESULT_NO_RECORD, RESULT_OK};
+use crate::raw::{prefab_spawn, Transform as RawTransform, RESULT_NO_RECORD, RESULT_OK};
 use crate::record::RecordReference;
 use crate::{unreachable_unchecked, Error, ErrorImpl};
 
-/// Spawns a new prefab with the given `id`.
+/// Spawns a new prefab with the given `id` at the given [`Transform`].
 ///
-/// Returns the [`EntityId`] of the spawned prefab. You should attach the [`EntityId`] as a
-/// children of another entity.
+/// Returns the [`EntityId`] of the root entity of the spawned prefab. The [`Transform`] is
+/// inserted into the root entity. You should attach the [`EntityId`] as a children of another
+/// entity.
 ///
 /// # Errors
 ///
 /// Returns an [`Error`] if spawning the prefab fails because `id` does not refer to a valid
 /// prefab.
-pub fn spawn_prefab(id: RecordReference) -> Result<EntityId, Error> {
+pub fn spawn_prefab(id: RecordReference, transform: Transform) -> Result<EntityId, Error> {
     let mut out = MaybeUninit::<u64>::uninit();
 
-    match unsafe { prefab_spawn(&id, out.as_mut_ptr()) } {
+    // This is a precondition for prefab_spawn.
+    let rotation = transform.rotation.normalize();
+
+    let transform = RawTransform {
+        translation: transform.translation.to_array(),
+        rotation: rotation.to_array(),
+        scale: transform.scale.to_array(),
+    };
+
+    match unsafe { prefab_spawn(&id, &transform, out.as_mut_ptr()) } {
         RESULT_OK => {
             let entity = unsafe { EntityId::from_raw(out.assume_init()) };
             Ok(entity)
@@ -48,6 +48,20 @@ pub fn register_event_handler(id: *const RecordReference, ptr: *const unsafe fn(
 #[guest_only]
 pub fn register_action_handler(id: *const RecordReference, ptr: *const unsafe fn(u64, c_void));
 
+#[guest_only]
+pub fn register_event_handler_with_priority(
+    id: *const RecordReference,
+    ptr: *const unsafe fn(u64, c_void),
+    priority: i32,
+);
+
+#[guest_only]
+pub fn register_action_handler_with_priority(
+    id: *const RecordReference,
+    ptr: *const unsafe fn(u64, c_void),
+    priority: i32,
+);
+
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub struct Query {
@@ -55,8 +69,16 @@ pub struct Query {
     pub components_len: usize,
 }
 
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct Transform {
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+}
+
 #[guest_only]
-pub fn prefab_spawn(id: *const RecordReference, out: *mut u64) -> u32;
+pub fn prefab_spawn(id: *const RecordReference, transform: *const Transform, out: *mut u64) -> u32;
 
 #[guest_only]
 pub fn resource_create_runtime(ptr: *const u8, len: usize, out: *mut u64) -> u32;
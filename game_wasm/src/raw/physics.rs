@@ -48,6 +48,8 @@ pub struct CastRayResult {
 pub struct QueryFilter {
     pub exclude_entities_ptr: *const EntityId,
     pub exclude_entities_len: usize,
+    pub groups_memberships: u32,
+    pub groups_filter: u32,
 }
 
 #[repr(C)]
@@ -39,8 +39,25 @@ pub struct Query {
     pub components: Vec<RecordReference>,
 }
 
+/// The priority of handlers registered without an explicit priority.
+pub const DEFAULT_HANDLER_PRIORITY: i32 = 0;
+
+/// Registers a handler for the event `T` with the [`DEFAULT_HANDLER_PRIORITY`].
 // NOTE: The EntityId does nothing currently.
 pub fn register_event_handler<T>(f: fn(EntityId, T))
+where
+    T: Event,
+{
+    register_event_handler_with_priority(f, DEFAULT_HANDLER_PRIORITY);
+}
+
+/// Registers a handler for the event `T` with the given `priority`.
+///
+/// All handlers for the same event are run in descending order of their priority. Handlers with
+/// the same priority are run in ascending order of the record id of the script that registered
+/// them, independent of the order in which scripts were loaded. Handlers of the same script with
+/// the same priority are run in the order they were registered.
+pub fn register_event_handler_with_priority<T>(f: fn(EntityId, T), priority: i32)
 where
     T: Event,
 {
@@ -66,11 +83,23 @@ where
     SYSTEM_PTRS.insert(fn_ptr as usize, vtable);
 
     unsafe {
-        crate::raw::register_event_handler(&T::ID, fn_ptr.cast());
+        crate::raw::register_event_handler_with_priority(&T::ID, fn_ptr.cast(), priority);
     }
 }
 
+/// Registers a handler for the action `T` with the [`DEFAULT_HANDLER_PRIORITY`].
 pub fn register_action_handler<T>(f: fn(EntityId, T))
+where
+    T: Action,
+{
+    register_action_handler_with_priority(f, DEFAULT_HANDLER_PRIORITY);
+}
+
+/// Registers a handler for the action `T` with the given `priority`.
+///
+/// Handlers are ordered the same way as event handlers, see
+/// [`register_event_handler_with_priority`].
+pub fn register_action_handler_with_priority<T>(f: fn(EntityId, T), priority: i32)
 where
     T: Action,
 {
@@ -96,7 +125,7 @@ where
     SYSTEM_PTRS.insert(fn_ptr as usize, vtable);
 
     unsafe {
-        crate::raw::register_action_handler(&T::ID, fn_ptr.cast());
+        crate::raw::register_action_handler_with_priority(&T::ID, fn_ptr.cast(), priority);
     }
 }
 
@@ -18,9 +18,11 @@ pub enum WindowEvent {
     WindowScaleFactorChanged(WindowScaleFactorChanged),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct WindowCreated {
     pub window: WindowId,
+    /// The initial scale factor of the window.
+    pub scale_factor: f64,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
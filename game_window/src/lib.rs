@@ -1,5 +1,6 @@
 pub mod cursor;
 pub mod events;
+pub mod monitor;
 pub mod windows;
 
 mod backend;
@@ -440,6 +441,7 @@ where
                         map.windows.insert(window.id(), id);
 
                         let size = window.inner_size();
+                        let scale_factor = window.scale_factor();
                         windows.get_mut(id).unwrap().state = Some(WindowState {
                             id,
                             inner: Arc::new(window),
@@ -451,7 +453,10 @@ where
                                 windows: &mut windows,
                                 exit: &mut exit,
                             },
-                            events::WindowEvent::WindowCreated(WindowCreated { window: id }),
+                            events::WindowEvent::WindowCreated(WindowCreated {
+                                window: id,
+                                scale_factor,
+                            }),
                         );
                         // FIXME: Fire a resized event to ensure the window has the correct
                         // window size. This should be sent with the created event.
@@ -0,0 +1,69 @@
+use glam::{IVec2, UVec2};
+
+/// A monitor connected to the system.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Monitor {
+    pub(crate) inner: winit::monitor::MonitorHandle,
+}
+
+impl Monitor {
+    /// Returns a human-readable name of the `Monitor`.
+    ///
+    /// Returns `None` if the monitor doesn't exist anymore.
+    pub fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+
+    /// Returns the resolution of the `Monitor` in physical pixels.
+    pub fn size(&self) -> UVec2 {
+        let size = self.inner.size();
+        UVec2::new(size.width, size.height)
+    }
+
+    /// Returns the position of the top-left corner of the `Monitor` relative to the desktop.
+    pub fn position(&self) -> IVec2 {
+        let position = self.inner.position();
+        IVec2::new(position.x, position.y)
+    }
+
+    /// Returns the scale factor of the `Monitor`.
+    pub fn scale_factor(&self) -> f64 {
+        self.inner.scale_factor()
+    }
+
+    /// Returns the refresh rate of the `Monitor` in millihertz.
+    ///
+    /// Returns `None` if the refresh rate is unknown.
+    pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+        self.inner.refresh_rate_millihertz()
+    }
+
+    /// Returns all [`VideoMode`]s supported by the `Monitor` in exclusive fullscreen.
+    pub fn video_modes(&self) -> impl Iterator<Item = VideoMode> {
+        self.inner.video_modes().map(|inner| VideoMode { inner })
+    }
+}
+
+/// A video mode that can be used for exclusive fullscreen.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VideoMode {
+    pub(crate) inner: winit::monitor::VideoMode,
+}
+
+impl VideoMode {
+    /// Returns the resolution of the `VideoMode` in physical pixels.
+    pub fn size(&self) -> UVec2 {
+        let size = self.inner.size();
+        UVec2::new(size.width, size.height)
+    }
+
+    /// Returns the bit depth of the `VideoMode`.
+    pub fn bit_depth(&self) -> u16 {
+        self.inner.bit_depth()
+    }
+
+    /// Returns the refresh rate of the `VideoMode` in millihertz.
+    pub fn refresh_rate_millihertz(&self) -> u32 {
+        self.inner.refresh_rate_millihertz()
+    }
+}
@@ -10,6 +10,7 @@ use winit::dpi::{LogicalPosition, Position};
 use winit::error::ExternalError;
 
 use crate::cursor::{CursorGrabMode, CursorIcon};
+use crate::monitor::Monitor;
 use crate::Backend;
 
 const DEFAULT_TITLE: &str = "DEFAULT_TITLE";
@@ -151,10 +152,24 @@ impl WindowState {
         }
     }
 
+    /// Returns the current scale factor of the `Window`.
+    ///
+    /// The scale factor is the ratio between physical and logical pixels and depends on the
+    /// DPI of the monitor the window is on. Changes are reported using
+    /// [`WindowScaleFactorChanged`] events.
+    ///
+    /// [`WindowScaleFactorChanged`]: crate::events::WindowScaleFactorChanged
     pub fn scale_factor(&self) -> f64 {
         self.inner.scale_factor()
     }
 
+    /// Returns the [`Monitor`] the `Window` is currently on.
+    ///
+    /// Returns `None` if the monitor cannot be determined.
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        self.inner.current_monitor().map(|inner| Monitor { inner })
+    }
+
     /// Sets the position of the cursor within this `Window`.
     ///
     /// # Errors
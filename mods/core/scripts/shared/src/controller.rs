@@ -16,6 +16,7 @@ pub fn move_shape(
     let max_toi = direction.length();
     let filter = QueryFilter {
         exclude_entities: &[entity],
+        ..Default::default()
     };
 
     let max_distance = max_toi;
@@ -97,6 +98,7 @@ fn apply_gravity(
 
     let filter = QueryFilter {
         exclude_entities: &[entity],
+        ..Default::default()
     };
 
     let (distance, hit) = match cast_shape(
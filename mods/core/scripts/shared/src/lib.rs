@@ -25,6 +25,7 @@ mod world;
 
 use core::f32::consts::PI;
 
+use alloc::collections::BTreeSet;
 use alloc::vec;
 use alloc::vec::Vec;
 use bytemuck::Pod;
@@ -358,17 +359,33 @@ impl Component for LookingDirection {
     const ID: RecordReference = LOOKING_DIRECTION;
 }
 
+/// The maximum depth of children returned by [`collect_children_recursive`].
+const MAX_HIERARCHY_DEPTH: usize = 256;
+
+/// Returns all recursive children of `entity`.
+///
+/// Children nested deeper than [`MAX_HIERARCHY_DEPTH`] are ignored. Every entity is only returned
+/// once, even if the hierarchy contains cycles.
 fn collect_children_recursive(entity: EntityId) -> Vec<EntityId> {
     let mut buf = Vec::new();
-    let mut entities = vec![entity];
+    let mut visited = BTreeSet::from([entity]);
+    let mut entities = vec![(entity, 0)];
+
+    while let Some((entity, depth)) = entities.pop() {
+        if depth == MAX_HIERARCHY_DEPTH {
+            continue;
+        }
 
-    for entity in entities.pop() {
         let Ok(children) = Entity::new(entity).get::<Children>() else {
             continue;
         };
 
-        buf.extend(children.get());
-        entities.extend(children.get());
+        for child in children.get() {
+            if visited.insert(*child) {
+                buf.push(*child);
+                entities.push((*child, depth + 1));
+            }
+        }
     }
 
     buf
@@ -27,6 +27,7 @@ pub fn cast_actor(entity: EntityId, direction: Vec3, max_toi: f32) -> Option<Ray
         max_toi,
         QueryFilter {
             exclude_entities: &exclude_entities,
+            ..Default::default()
         },
     )
 }
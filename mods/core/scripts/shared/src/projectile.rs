@@ -21,6 +21,7 @@ pub fn drive_projectile(entity: EntityId) {
     let max_toi = 10.0;
     let filter = QueryFilter {
         exclude_entities: &[],
+        ..Default::default()
     };
 
     let direction = transform.rotation * -Vec3::Z;
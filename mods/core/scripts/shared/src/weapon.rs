@@ -104,7 +104,10 @@ fn project_camera_transform(
             direction: camera.rotation * -Vec3::Z,
         },
         MAX_TOI,
-        QueryFilter { exclude_entities },
+        QueryFilter {
+            exclude_entities,
+            ..Default::default()
+        },
     ) {
         Some(hit) => hit.toi,
         None => MAX_TOI,
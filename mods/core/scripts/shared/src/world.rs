@@ -112,13 +112,8 @@ fn generate_cell(cell: CellId) {
     });
 
     for data in generator.load(cell) {
-        match spawn_prefab(data.prefab) {
-            Ok(entity) => {
-                Entity::new(entity).insert(data.transform);
-            }
-            Err(err) => {
-                game_wasm::error!("failed to spawn prefab: {}", err);
-            }
+        if let Err(err) = spawn_prefab(data.prefab, data.transform) {
+            game_wasm::error!("failed to spawn prefab: {}", err);
         }
     }
 }